@@ -0,0 +1,110 @@
+//! GitHub Actions output for `scan --gha`
+//!
+//! Workflow commands printed alongside the scan become annotations on the
+//! run, and the Markdown appended to the file `GITHUB_STEP_SUMMARY` names
+//! becomes the job summary. Outside of Actions that variable isn't set, so
+//! only the annotations are printed.
+
+use crate::output::{self, format_size, Format};
+use anyhow::{Context, Result};
+use devjunk_core::ScanResult;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Largest directories listed in the job summary
+const SUMMARY_ITEMS: usize = 20;
+
+/// Annotate the run with what `result` found, and add it to the job summary
+pub fn report(result: &ScanResult, fail_if_over: Option<u64>, format: Format) -> Result<()> {
+    let mut out = output::messages(format);
+    let total = result.total_size_bytes();
+    let found = if result.items.is_empty() {
+        "No junk directories found".to_string()
+    } else {
+        format!(
+            "Found {} junk directories, {}",
+            result.items.len(),
+            format_size(total)
+        )
+    };
+    writeln!(out, "::notice title=devjunk::{}", escape(&found))?;
+    if let Some(limit) = fail_if_over.filter(|&limit| total > limit) {
+        let over = format!(
+            "{} of junk is more than the {} allowed by --fail-if-over",
+            format_size(total),
+            format_size(limit)
+        );
+        writeln!(out, "::error title=devjunk::{}", escape(&over))?;
+    }
+    for error in &result.errors {
+        let message = format!("Could not read {}: {}", error.path.display(), error.cause);
+        writeln!(out, "::warning title=devjunk::{}", escape(&message))?;
+    }
+
+    if let Some(path) = std::env::var_os("GITHUB_STEP_SUMMARY").map(PathBuf::from) {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("Failed to open the job summary {}", path.display()))?;
+        file.write_all(summary(result, &found).as_bytes())
+            .with_context(|| format!("Failed to write the job summary {}", path.display()))?;
+    }
+    Ok(())
+}
+
+/// The job summary: totals per kind, then the largest directories
+fn summary(result: &ScanResult, found: &str) -> String {
+    let mut md = format!("## devjunk\n\n{}.\n\n", found);
+    if result.items.is_empty() {
+        return md;
+    }
+
+    let mut kinds: Vec<(&str, usize, u64)> = result
+        .by_kind()
+        .into_iter()
+        .map(|(kind, items)| {
+            let size = items.iter().map(|item| item.size_bytes).sum();
+            (kind, items.len(), size)
+        })
+        .collect();
+    kinds.sort_by_key(|&(_, _, size)| std::cmp::Reverse(size));
+    md.push_str("| Type | Dirs | Size |\n| --- | ---: | ---: |\n");
+    for (kind, items, size) in kinds {
+        md.push_str(&format!(
+            "| {} | {} | {} |\n",
+            kind,
+            items,
+            format_size(size)
+        ));
+    }
+
+    let mut items: Vec<_> = result.items.iter().collect();
+    items.sort_by_key(|item| std::cmp::Reverse(item.size_bytes));
+    md.push_str("\n| Path | Type | Size |\n| --- | --- | ---: |\n");
+    for item in items.iter().take(SUMMARY_ITEMS) {
+        md.push_str(&format!(
+            "| `{}` | {} | {} |\n",
+            item.path.display().to_string().replace('|', "\\|"),
+            item.kind_name(),
+            format_size(item.size_bytes)
+        ));
+    }
+    if items.len() > SUMMARY_ITEMS {
+        md.push_str(&format!(
+            "\n...and {} smaller directories.\n",
+            items.len() - SUMMARY_ITEMS
+        ));
+    }
+    md.push('\n');
+    md
+}
+
+/// Keep a message from ending its workflow command early
+fn escape(message: &str) -> String {
+    message
+        .replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
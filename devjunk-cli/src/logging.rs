@@ -0,0 +1,39 @@
+//! Diagnostic logging to stderr
+//!
+//! The core logs which directories a scan skipped or couldn't read, and how
+//! long each phase took. Only warnings are shown by default: `-v` adds the
+//! phase timings and unreadable entries, `-vv` each skipped directory and junk
+//! found, and `-vvv` every directory visited. `--quiet` leaves only errors.
+
+use std::io::{self, IsTerminal};
+use std::sync::atomic::{AtomicBool, Ordering};
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::fmt::time;
+
+static SHOW_PROGRESS: AtomicBool = AtomicBool::new(true);
+
+/// Send log lines at the level picked by `-v` and `--quiet` to stderr
+pub fn init(verbose: u8, quiet: bool, no_color: bool) {
+    let level = match (quiet, verbose) {
+        (true, _) => LevelFilter::ERROR,
+        (false, 0) => LevelFilter::WARN,
+        (false, 1) => LevelFilter::INFO,
+        (false, 2) => LevelFilter::DEBUG,
+        (false, _) => LevelFilter::TRACE,
+    };
+    tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_writer(io::stderr)
+        .with_target(false)
+        .with_timer(time::uptime())
+        .with_ansi(crate::style::allowed(no_color) && io::stderr().is_terminal())
+        .init();
+
+    // Log lines would tear through the spinner, and --quiet asks for neither
+    SHOW_PROGRESS.store(!quiet && verbose == 0, Ordering::Relaxed);
+}
+
+/// Whether long operations may show a spinner
+pub fn shows_progress() -> bool {
+    SHOW_PROGRESS.load(Ordering::Relaxed)
+}
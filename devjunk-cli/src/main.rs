@@ -1,282 +1,3038 @@
 //! devjunk CLI - Command-line interface for development junk cleanup
 
-use anyhow::Result;
-use clap::{Parser, Subcommand};
+mod gha;
+mod logging;
+mod output;
+mod progress;
+mod style;
+
+use anyhow::{bail, Context, Result};
+use clap::builder::{PossibleValue, StringValueParser, TypedValueParser};
+use clap::{ArgAction, Args, CommandFactory, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
 use devjunk_core::{
-    build_clean_plan, execute_clean, scan, CleanResult, JunkKind, ScanConfig, ScanResult,
+    apply_retention, build_clean_plan, default_handoff_path, default_history_path,
+    default_journal_dir, diff_plans, diff_scans, enter_background_mode, execute_clean_with_cancel,
+    filter_scan_result, global_caches, install_schedule, interrupted_cleans, list_restorable,
+    list_schedules, load_history, measure_dir, rank_projects, record_history,
+    remeasure_scan_result, restore, save_handoff, scan_with_progress, set_worker_threads,
+    summarize_history, uninstall_schedule, validate_plan, CancelToken, CleanItem, CleanPlan,
+    CleanResult, CleanStrategy, Config, DeleteMode, DiffEntry, DirSize, Frequency, GlobalCache,
+    HistoryEvent, HistoryRecord, HistoryStats, InterruptedClean, JunkKind, KindSafety, ProjectJunk,
+    Restorable, RetentionPolicy, RetentionScope, ScanConfig, ScanConfigBuilder, ScanDiff,
+    ScanError, ScanItem, ScanResult, Schedule, ScheduleStatus, TypedConfirm, OPEN_SCAN_ARG,
 };
-use std::path::PathBuf;
+use indicatif::{ProgressBar, ProgressStyle};
+use output::{format_size, Column, Format};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::env::consts::EXE_SUFFIX;
+use std::ffi::OsStr;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitCode, Stdio};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use style::Style;
 
 /// DevJunk - A tool for scanning and cleaning development build/cache directories
 #[derive(Parser)]
 #[command(name = "devjunk")]
 #[command(author, version, about, long_about = None)]
 struct Cli {
+    /// Run at background CPU and disk priority so other work stays responsive
+    #[arg(long, global = true, default_value = "false")]
+    low_priority: bool,
+
+    /// Print without colors (also when NO_COLOR is set or the output isn't a terminal)
+    #[arg(long, global = true, default_value = "false")]
+    no_color: bool,
+
+    /// Log skipped and unreadable directories and phase timings to stderr (-vv, -vvv for more)
+    #[arg(short, long, global = true, action = ArgAction::Count)]
+    verbose: u8,
+
+    /// Only log errors, and show no spinner
+    #[arg(
+        short,
+        long,
+        global = true,
+        default_value = "false",
+        conflicts_with = "verbose"
+    )]
+    quiet: bool,
+
+    /// Report progress as JSON lines on stderr instead of a spinner, e.g. for a wrapper's progress bar
+    #[arg(long, global = true, default_value = "false")]
+    progress_json: bool,
+
+    /// Show sizes in powers of 1000, as macOS Finder does, instead of 1024
+    #[arg(long, global = true, default_value = "false")]
+    si: bool,
+
+    /// Worker threads for scanning and deleting (defaults to `threads` in the config file, or one per CPU)
+    #[arg(long, global = true, value_name = "N", env = "DEVJUNK_THREADS")]
+    threads: Option<NonZeroUsize>,
+
     #[command(subcommand)]
     command: Commands,
 }
 
+/// Exit status of `scan` when some paths could not be read, as for any other error
+const EXIT_ERROR: u8 = 1;
+/// Exit status of `scan --exit-code` when it found junk
+const EXIT_JUNK_FOUND: u8 = 3;
+/// Exit status of `scan` when the junk adds up to more than --fail-if-over
+const EXIT_OVER_LIMIT: u8 = 4;
+
 #[derive(Subcommand)]
 enum Commands {
     /// Scan directories for development junk
+    #[command(
+        after_help = "Exit status: 0 on success, 1 on errors (including paths that could not be read), \
+                      2 for invalid arguments, 3 with --exit-code if junk was found and 4 if it adds up to \
+                      more than --fail-if-over."
+    )]
     Scan {
-        /// Paths to scan (defaults to current directory)
-        #[arg(default_value = ".")]
+        /// Paths to scan; `-` reads them from stdin, one per line (defaults to the configured roots, or the current directory)
         paths: Vec<PathBuf>,
 
+        /// Scan your whole home directory, skipping photos, mail, browser data and the trash
+        #[arg(long, default_value = "false", conflicts_with = "paths")]
+        home: bool,
+
         /// Maximum depth to scan
         #[arg(short, long)]
         max_depth: Option<usize>,
 
+        /// Only this junk kind, by id or alias, e.g. `rust` or `nm` (can be specified multiple times)
+        #[arg(long, value_parser = KindParser, hide_possible_values = true)]
+        kind: Vec<JunkKind>,
+
         /// Include hidden directories in scan
         #[arg(long, default_value = "false")]
         include_hidden: bool,
 
-        /// Output in JSON format
+        /// Also descend into network shares and removable media
+        #[arg(long, default_value = "false")]
+        include_network_mounts: bool,
+
+        /// Also report the shared Cargo target directory (CARGO_TARGET_DIR)
+        #[arg(long, default_value = "false")]
+        include_shared_target: bool,
+
+        /// Check each project's git repository for uncommitted or unpushed work
         #[arg(long, default_value = "false")]
+        git_status: bool,
+
+        /// Skip paths matching this glob, e.g. `**/work/critical-project/**` (can be specified multiple times)
+        #[arg(long, value_name = "GLOB")]
+        exclude: Vec<String>,
+
+        #[command(flatten)]
+        thresholds: ThresholdArgs,
+
+        /// Only list the N largest directories
+        #[arg(long, value_name = "N")]
+        top: Option<usize>,
+
+        /// Exit with status 4 if the junk adds up to more than this, e.g. `5GB`; less counts as none
+        #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+        fail_if_over: Option<u64>,
+
+        /// Exit with status 3 if any junk was found, like `git diff --exit-code`
+        #[arg(long, default_value = "false")]
+        exit_code: bool,
+
+        /// Annotate the GitHub Actions run and add the junk to its job summary; finding junk then
+        /// only fails the step past --fail-if-over
+        #[arg(long, default_value = "false")]
+        gha: bool,
+
+        /// Order to list directories in
+        #[arg(long, value_enum, default_value_t = SortKey::Size)]
+        sort: SortKey,
+
+        /// Reverse the order given by --sort
+        #[arg(long, default_value = "false")]
+        reverse: bool,
+
+        /// Print totals per project or per junk kind instead of each directory, largest first
+        #[arg(long, value_enum)]
+        group_by: Option<GroupBy>,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t = Format::Table, env = "DEVJUNK_FORMAT")]
+        format: Format,
+
+        /// Same as `--format json`, and wins over it
+        #[arg(long, default_value = "false", hide = true)]
         json: bool,
+
+        /// Also open the result in the GUI, to pick what to delete there
+        #[arg(long, default_value = "false")]
+        open_gui: bool,
+    },
+
+    /// Rank projects by the junk they hold, to see which to clean first
+    ///
+    /// A project is the git checkout a junk directory is in, so every package
+    /// of a monorepo counts towards it, or else the directory holding it.
+    Biggest {
+        /// Paths to scan; `-` reads them from stdin, one per line (defaults to the configured roots, or the current directory)
+        paths: Vec<PathBuf>,
+
+        /// Scan your whole home directory, skipping photos, mail, browser data and the trash
+        #[arg(long, default_value = "false", conflicts_with = "paths")]
+        home: bool,
+
+        /// Maximum depth to scan
+        #[arg(short, long)]
+        max_depth: Option<usize>,
+
+        /// Only this junk kind, by id or alias, e.g. `rust` or `nm` (can be specified multiple times)
+        #[arg(long, value_parser = KindParser, hide_possible_values = true)]
+        kind: Vec<JunkKind>,
+
+        /// Skip paths matching this glob, e.g. `**/work/critical-project/**` (can be specified multiple times)
+        #[arg(long, value_name = "GLOB")]
+        exclude: Vec<String>,
+
+        /// How many projects to list
+        #[arg(long, value_name = "N", default_value_t = 10)]
+        top: usize,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t = Format::Table, env = "DEVJUNK_FORMAT")]
+        format: Format,
     },
 
     /// Clean (delete) development junk directories
     Clean {
-        /// Paths to scan and clean
-        #[arg(default_value = ".")]
-        paths: Vec<PathBuf>,
+        #[command(flatten)]
+        selection: PlanArgs,
 
         /// Perform a dry run (don't actually delete)
         #[arg(long, default_value = "false")]
         dry_run: bool,
 
-        /// Maximum depth to scan
-        #[arg(short, long)]
-        max_depth: Option<usize>,
+        /// Finish a clean that was interrupted instead of scanning again
+        #[arg(long, default_value = "false", conflicts_with_all = ["paths", "home", "from_scan"])]
+        resume: bool,
 
-        /// Filter by junk kind (can be specified multiple times)
-        #[arg(long)]
-        kind: Vec<String>,
+        /// Forget cleans that were interrupted without finishing them
+        #[arg(long, default_value = "false", conflicts_with = "resume")]
+        forget_interrupted: bool,
 
         /// Skip confirmation prompt
         #[arg(short = 'y', long, default_value = "false")]
         yes: bool,
+
+        /// Ask about each directory in turn instead of about all of them at once
+        #[arg(short, long, default_value = "false", conflicts_with_all = ["yes", "resume", "format"])]
+        interactive: bool,
+
+        /// Output format of the result; anything but `table` needs --yes or --dry-run
+        #[arg(long, value_enum, default_value_t = Format::Table, conflicts_with = "resume")]
+        format: Format,
     },
 
-    /// List supported junk types
-    Types,
-}
+    /// Work with saved clean plans
+    Plan {
+        #[command(subcommand)]
+        command: PlanCommands,
+    },
 
-fn main() -> Result<()> {
-    let cli = Cli::parse();
+    /// Execute a clean plan saved with `plan create`
+    Apply {
+        /// The saved plan (JSON, or TOML if it ends in .toml)
+        plan: PathBuf,
 
-    match cli.command {
-        Commands::Scan {
-            paths,
-            max_depth,
-            include_hidden,
-            json,
-        } => {
-            let config = build_scan_config(paths, max_depth, include_hidden, &[]);
-            let result = scan(&config)?;
+        /// Perform a dry run (don't actually delete)
+        #[arg(long, default_value = "false")]
+        dry_run: bool,
 
-            if json {
-                print_json_result(&result)?;
-            } else {
-                print_table_result(&result);
-            }
-        }
+        /// Leave out directories that changed since the plan was saved instead of refusing to apply it
+        #[arg(long, default_value = "false")]
+        skip_changed: bool,
 
-        Commands::Clean {
-            paths,
-            dry_run,
-            max_depth,
-            kind,
-            yes,
-        } => {
-            let config = build_scan_config(paths, max_depth, false, &kind);
-            let result = scan(&config)?;
+        /// Skip confirmation prompt
+        #[arg(short = 'y', long, default_value = "false")]
+        yes: bool,
+    },
 
-            if result.items.is_empty() {
-                println!("No junk directories found.");
-                return Ok(());
-            }
+    /// Compare two saved scans to see which junk grew, shrank, appeared or went away
+    Diff {
+        /// The baseline scan, saved with `scan --format json` (or a saved plan)
+        old: PathBuf,
 
-            print_table_result(&result);
+        /// The scan to compare against the baseline
+        new: PathBuf,
 
-            // Build plan with all items selected
-            let all_paths: Vec<PathBuf> = result.items.iter().map(|i| i.path.clone()).collect();
-            let plan = build_clean_plan(&result, &all_paths, dry_run);
+        /// Number of projects to list by growth
+        #[arg(long, default_value = "10")]
+        projects: usize,
 
-            if !yes && !dry_run {
-                println!();
-                println!(
-                    "⚠️  This will delete {} directories ({}).",
-                    plan.count(),
-                    format_size(result.total_size_bytes())
-                );
-                print!("Continue? [y/N] ");
-                std::io::Write::flush(&mut std::io::stdout())?;
+        /// Output in JSON format
+        #[arg(long, default_value = "false")]
+        json: bool,
+    },
 
-                let mut input = String::new();
-                std::io::stdin().read_line(&mut input)?;
+    /// Rescan on an interval and warn when the junk adds up to more than a threshold
+    Watch {
+        /// Paths to watch (defaults to the configured roots, or the current directory)
+        paths: Vec<PathBuf>,
 
-                if !input.trim().eq_ignore_ascii_case("y") {
-                    println!("Aborted.");
-                    return Ok(());
-                }
-            }
+        /// Watch your whole home directory, skipping photos, mail, browser data and the trash
+        #[arg(long, default_value = "false", conflicts_with = "paths")]
+        home: bool,
 
-            let clean_result = execute_clean(&plan)?;
-            print_clean_result(&clean_result);
-        }
+        /// Maximum depth to scan
+        #[arg(short, long)]
+        max_depth: Option<usize>,
 
-        Commands::Types => {
-            print_junk_types();
-        }
-    }
+        /// Only this junk kind, by id or alias, e.g. `rust` or `nm` (can be specified multiple times)
+        #[arg(long, value_parser = KindParser, hide_possible_values = true)]
+        kind: Vec<JunkKind>,
 
-    Ok(())
+        /// Warn once the junk adds up to at least this much, e.g. `20GB`
+        #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+        threshold: u64,
+
+        /// Time between scans, e.g. `10m` or `1h`
+        #[arg(long, value_name = "INTERVAL", default_value = "10m", value_parser = humantime::parse_duration)]
+        interval: Duration,
+
+        /// Run this shell command each time the threshold is crossed, with DEVJUNK_TOTAL_BYTES set
+        #[arg(long, value_name = "COMMAND")]
+        on_exceed: Option<String>,
+    },
+
+    /// Have the OS scheduler run a clean on a recurring basis
+    Schedule {
+        #[command(subcommand)]
+        command: ScheduleCommands,
+    },
+
+    /// Show how much junk was found and freed over time
+    Stats {
+        /// Only count scans and cleans since then, e.g. `30d` or `2026-01-01`
+        #[arg(long, value_name = "WHEN", value_parser = parse_since)]
+        since: Option<SystemTime>,
+
+        /// Number of projects to list
+        #[arg(long, default_value = "10")]
+        projects: usize,
+
+        /// Output in JSON format
+        #[arg(long, default_value = "false")]
+        json: bool,
+    },
+
+    /// Put a directory a clean archived or moved to the trash back where it was
+    #[command(after_help = "Without a path or --last, lists what can be restored, newest first.")]
+    Restore {
+        /// Where the directory to restore was deleted from
+        path: Option<PathBuf>,
+
+        /// Restore the directory deleted most recently
+        #[arg(long, default_value = "false", conflicts_with = "path")]
+        last: bool,
+
+        /// Also look for archives here, as given to `clean --archive-to` (can be specified multiple times)
+        #[arg(long = "from", value_name = "DIR")]
+        archive_dirs: Vec<PathBuf>,
+    },
+
+    /// Keep directories out of every scan and clean, saved in the config file
+    Ignore {
+        #[command(subcommand)]
+        command: IgnoreCommands,
+    },
+
+    /// Measure the size and file count of any directories, junk or not
+    Size {
+        /// Directories or files to measure; `-` reads them from stdin, one per line
+        /// (defaults to the current directory)
+        paths: Vec<PathBuf>,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t = Format::Table, env = "DEVJUNK_FORMAT")]
+        format: Format,
+    },
+
+    /// Report the size of package manager and build tool caches outside your projects
+    Doctor {
+        /// Output format
+        #[arg(long, value_enum, default_value_t = Format::Table, env = "DEVJUNK_FORMAT")]
+        format: Format,
+    },
+
+    /// List supported junk types
+    Types {
+        /// Output format
+        #[arg(long, value_enum, default_value_t = Format::Table, env = "DEVJUNK_FORMAT")]
+        format: Format,
+
+        /// Same as `--format json`, and wins over it
+        #[arg(long, default_value = "false")]
+        json: bool,
+    },
+
+    /// Print a shell completion script, e.g. `devjunk completions zsh > ~/.zfunc/_devjunk`
+    Completions {
+        /// Shell to generate the script for
+        shell: Shell,
+    },
+
+    /// Write man pages for devjunk and each of its subcommands, e.g. for packaging
+    #[command(hide = true)]
+    Man {
+        /// Directory to write `devjunk.1`, `devjunk-scan.1` and so on into
+        out_dir: PathBuf,
+    },
 }
 
-fn build_scan_config(
+/// Order of the directories listed by `scan`
+#[derive(Clone, Copy, ValueEnum)]
+enum SortKey {
+    /// Largest first
+    Size,
+    /// By path
+    Path,
+    /// By junk kind, largest first within each
+    Kind,
+    /// Least recently modified first
+    Age,
+    /// Most files first
+    Files,
+}
+
+/// What `scan --group-by` adds directories up by
+#[derive(Clone, Copy, ValueEnum)]
+enum GroupBy {
+    /// The directory holding each junk directory
+    Project,
+    /// Junk kind
+    Kind,
+}
+
+/// What to clean and how, shared by `clean` and `plan create`
+#[derive(Args)]
+struct PlanArgs {
+    /// Paths to scan and clean; `-` reads junk directories to clean from stdin, one per line
+    /// (defaults to the configured roots, or the current directory)
     paths: Vec<PathBuf>,
+
+    /// The junk directories read from stdin, the only ones to clean if any
+    #[arg(skip)]
+    targets: Vec<PathBuf>,
+
+    /// Clean your whole home directory, skipping photos, mail, browser data and the trash
+    #[arg(long, default_value = "false", conflicts_with = "paths")]
+    home: bool,
+
+    /// Clean what a scan saved with `scan --format json` found instead of scanning again
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = ["paths", "home", "max_depth", "include_shared_target", "skip_dirty"]
+    )]
+    from_scan: Option<PathBuf>,
+
+    /// Measure the directories of --from-scan again, leaving out those that are gone
+    #[arg(long, default_value = "false", requires = "from_scan")]
+    revalidate: bool,
+
+    /// Maximum depth to scan
+    #[arg(short, long)]
     max_depth: Option<usize>,
-    include_hidden: bool,
-    kind_filters: &[String],
-) -> ScanConfig {
-    let mut config = ScanConfig::new(paths).with_hidden(include_hidden);
 
-    if let Some(depth) = max_depth {
-        config = config.with_max_depth(depth);
-    }
+    /// Only this junk kind, by id or alias, e.g. `rust` or `nm` (can be specified multiple times)
+    #[arg(long, value_parser = KindParser, hide_possible_values = true)]
+    kind: Vec<JunkKind>,
 
-    // Filter by kind if specified
-    if !kind_filters.is_empty() {
-        let patterns: Vec<JunkKind> = JunkKind::all()
-            .into_iter()
-            .filter(|k| {
-                let name = format!("{:?}", k).to_lowercase();
-                kind_filters.iter().any(|f| name.contains(&f.to_lowercase()))
-            })
-            .collect();
+    /// Every junk kind but this one, e.g. `nm` (can be specified multiple times)
+    #[arg(long, value_name = "KIND", value_parser = KindParser, hide_possible_values = true, conflicts_with = "kind")]
+    all_except: Vec<JunkKind>,
 
-        if !patterns.is_empty() {
-            config = config.with_patterns(patterns);
-        }
-    }
+    /// Also clean the shared Cargo target directory (CARGO_TARGET_DIR)
+    #[arg(long, default_value = "false")]
+    include_shared_target: bool,
 
-    config
-}
+    /// Leave paths matching this glob alone, e.g. `**/work/critical-project/**` (can be specified multiple times)
+    #[arg(long, value_name = "GLOB")]
+    exclude: Vec<String>,
 
-fn print_table_result(result: &ScanResult) {
-    if result.items.is_empty() {
-        println!("No junk directories found.");
-        return;
-    }
+    #[command(flatten)]
+    thresholds: ThresholdArgs,
 
-    // Header
-    println!();
-    println!(
-        "{:<60} {:<15} {:>12} {:>10}",
-        "Path", "Type", "Size", "Files"
-    );
-    println!("{}", "-".repeat(100));
+    /// Leave junk alone in repositories with uncommitted or unpushed work
+    #[arg(long, default_value = "false")]
+    skip_dirty: bool,
 
-    // Items
-    for item in &result.items {
-        let path_str = item.path.display().to_string();
-        let truncated_path = if path_str.len() > 58 {
-            format!("...{}", &path_str[path_str.len() - 55..])
-        } else {
-            path_str
-        };
+    /// Keep the N most recently modified directories of a kind, e.g. `rust=2` (can be specified multiple times)
+    #[arg(long, value_name = "KIND=N", value_parser = parse_keep_newest)]
+    keep_newest: Vec<(JunkKind, usize)>,
 
-        println!(
-            "{:<60} {:<15} {:>12} {:>10}",
-            truncated_path,
-            item.kind.display_name(),
-            format_size(item.size_bytes),
-            item.file_count
-        );
-    }
+    /// Apply --keep-newest within each project instead of across each scanned directory
+    #[arg(long, default_value = "false", requires = "keep_newest")]
+    keep_per_project: bool,
 
-    // Summary
-    println!("{}", "-".repeat(100));
-    println!(
-        "Total: {} directories, {}, {} files",
-        result.item_count(),
-        format_size(result.total_size_bytes()),
-        result.total_file_count()
-    );
-    println!();
-}
+    /// In Rust target dirs, keep final binaries and only drop incremental caches, deps and build outputs
+    #[arg(long, default_value = "false")]
+    sweep_rust: bool,
 
-fn print_json_result(result: &ScanResult) -> Result<()> {
-    let json = serde_json::to_string_pretty(result)?;
-    println!("{}", json);
-    Ok(())
-}
+    /// Leave directories alone while a running process (e.g. a dev server) has files open in them
+    #[arg(long, default_value = "false")]
+    skip_in_use: bool,
 
-fn print_clean_result(result: &CleanResult) {
-    println!();
+    /// Keep contents matching this glob inside each directory, e.g. `release/myapp` or `.bin` (can be specified multiple times)
+    #[arg(long, value_name = "GLOB")]
+    keep: Vec<String>,
 
-    if result.was_dry_run {
-        println!("🔍 DRY RUN - No files were deleted");
-        println!();
-    }
+    /// Archive each directory (tar.zst) into this directory before deleting it
+    #[arg(long, value_name = "DIR", group = "delete_mode")]
+    archive_to: Option<PathBuf>,
 
-    if !result.deleted.is_empty() {
-        let action = if result.was_dry_run {
-            "Would delete"
-        } else {
-            "Deleted"
-        };
+    /// Rename each directory aside right away and delete it afterwards
+    #[arg(long, default_value = "false", group = "delete_mode")]
+    background_delete: bool,
 
-        println!(
-            "✅ {}: {} directories ({})",
-            action,
-            result.deleted_count(),
-            format_size(result.bytes_freed)
-        );
+    /// Move directories to the trash instead of deleting them
+    #[arg(long, default_value = "false", group = "delete_mode")]
+    trash: bool,
+}
+
+impl PlanArgs {
+    /// Replace a `-` path with the junk directories read from stdin
+    ///
+    /// Those become the roots of the scan as well as its only targets, so
+    /// nothing else under them is cleaned.
+    fn read_stdin_targets(mut self) -> Result<Self> {
+        if !reads_stdin(&self.paths) {
+            return Ok(self);
+        }
+        if self.paths.len() > 1 {
+            bail!("`-` reads the paths to clean from stdin and can't be mixed with other paths");
+        }
+        self.paths = read_stdin_paths()?;
+        self.targets.clone_from(&self.paths);
+        Ok(self)
     }
+}
 
-    if !result.failed.is_empty() {
-        println!();
-        println!("❌ Failed to delete {} directories:", result.failed_count());
-        for (path, error) in &result.failed {
-            println!("   {} - {}", path.display(), error);
+/// Size and age thresholds, shared by `scan`, `clean` and `plan create`
+#[derive(Args)]
+struct ThresholdArgs {
+    /// Only include directories of at least this size, e.g. `500MB` or `1.5GB`
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    min_size: Option<u64>,
+
+    /// Only include directories untouched for at least this long, e.g. `30d` or `2weeks`
+    #[arg(long, value_name = "AGE", value_parser = humantime::parse_duration)]
+    older_than: Option<Duration>,
+}
+
+impl ThresholdArgs {
+    fn apply(self, mut config: ScanConfigBuilder) -> ScanConfigBuilder {
+        if let Some(bytes) = self.min_size {
+            config = config.with_min_size(bytes);
         }
+        if let Some(age) = self.older_than {
+            config = config.with_min_age(age);
+        }
+        config
     }
+}
 
-    println!();
+// Parsed once per run, so the size of `Create` doesn't matter
+#[allow(clippy::large_enum_variant)]
+#[derive(Subcommand)]
+enum PlanCommands {
+    /// Scan and save a clean plan to review and apply later
+    Create {
+        #[command(flatten)]
+        selection: PlanArgs,
+
+        /// File to write the plan to (JSON, or TOML if it ends in .toml)
+        #[arg(short, long)]
+        output: PathBuf,
+    },
+
+    /// Compare two saved plans or scan results
+    Diff {
+        /// The baseline plan or scan (JSON, or TOML if it ends in .toml)
+        old: PathBuf,
+
+        /// The plan or scan to compare against the baseline
+        new: PathBuf,
+
+        /// Output in JSON format
+        #[arg(long, default_value = "false")]
+        json: bool,
+    },
 }
 
-fn print_junk_types() {
-    println!();
-    println!("Supported junk directory types:");
-    println!();
-    println!("{:<20} {:<30}", "Type", "Patterns");
-    println!("{}", "-".repeat(50));
+/// Name of the schedule used when none is given
+const DEFAULT_SCHEDULE: &str = "clean";
 
-    for kind in JunkKind::all() {
-        let patterns = kind.patterns().join(", ");
-        println!("{:<20} {:<30}", kind.display_name(), patterns);
-    }
+#[derive(Subcommand)]
+enum ScheduleCommands {
+    /// Install a job running `devjunk clean` with the arguments after `--`, replacing one of the same name
+    #[command(
+        after_help = "Example: devjunk schedule install --every weekly -- ~/workspace --older-than 30d"
+    )]
+    Install {
+        /// How often to clean
+        #[arg(long, value_enum, default_value_t = Every::Weekly)]
+        every: Every,
 
-    println!();
+        /// Time of day to clean at, in local time
+        #[arg(long, value_name = "HH:MM", default_value = "03:00", value_parser = parse_time_of_day)]
+        at: (u8, u8),
+
+        /// Name of the schedule, to keep several apart
+        #[arg(long, default_value = DEFAULT_SCHEDULE)]
+        name: String,
+
+        /// Arguments for `devjunk clean`; `--yes` is added unless there is `--dry-run`
+        #[arg(last = true, value_name = "CLEAN_ARGS")]
+        clean_args: Vec<String>,
+    },
+
+    /// Remove a schedule from the OS scheduler
+    Uninstall {
+        /// Name of the schedule
+        #[arg(default_value = DEFAULT_SCHEDULE)]
+        name: String,
+    },
+
+    /// Show the installed schedules and what they run
+    Status {
+        /// Output in JSON format
+        #[arg(long, default_value = "false")]
+        json: bool,
+    },
 }
 
-/// Format bytes into human-readable string
-fn format_size(bytes: u64) -> String {
-    const KB: u64 = 1024;
-    const MB: u64 = KB * 1024;
-    const GB: u64 = MB * 1024;
+#[derive(Subcommand)]
+enum IgnoreCommands {
+    /// Never scan or clean these directories or anything below them
+    Add {
+        /// Directories to ignore
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+    },
 
-    if bytes >= GB {
-        format!("{:.2} GB", bytes as f64 / GB as f64)
-    } else if bytes >= MB {
-        format!("{:.2} MB", bytes as f64 / MB as f64)
-    } else if bytes >= KB {
-        format!("{:.2} KB", bytes as f64 / KB as f64)
-    } else {
-        format!("{} B", bytes)
-    }
+    /// Stop ignoring directories
+    Remove {
+        /// Directories to stop ignoring
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+    },
+
+    /// List the ignored directories
+    List {
+        /// Output in JSON format
+        #[arg(long, default_value = "false")]
+        json: bool,
+    },
+}
+
+/// How often `schedule install` runs the clean
+#[derive(Clone, Copy, ValueEnum)]
+enum Every {
+    /// Every day
+    Daily,
+    /// Every Sunday
+    Weekly,
+    /// On the first of every month
+    Monthly,
+}
+
+impl Every {
+    fn frequency(self) -> Frequency {
+        match self {
+            Self::Daily => Frequency::Daily,
+            Self::Weekly => Frequency::Weekly,
+            Self::Monthly => Frequency::Monthly,
+        }
+    }
+}
+
+/// A saved file accepted by `diff` and `plan diff`
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PlanFile {
+    Scan(ScanResult),
+    Plan(Box<CleanPlan>),
+}
+
+fn main() -> Result<ExitCode> {
+    let cli = Cli::parse();
+    style::init(cli.no_color);
+    logging::init(cli.verbose, cli.quiet, cli.no_color);
+    progress::init(cli.progress_json);
+    output::init_units(cli.si);
+
+    // Before background mode, so the count asked for wins over its single thread;
+    // a broken config file is reported by the command that reads it
+    let threads = cli
+        .threads
+        .map(NonZeroUsize::get)
+        .or_else(|| Config::load_default().ok()?.threads);
+    if let Some(threads) = threads {
+        if let Err(e) = set_worker_threads(threads) {
+            eprintln!("⚠️  Could not start {} worker threads: {}", threads, e);
+        }
+    }
+
+    if cli.low_priority {
+        if let Err(e) = enter_background_mode() {
+            eprintln!("⚠️  Could not lower the process priority: {}", e);
+        }
+    }
+
+    match cli.command {
+        Commands::Scan {
+            paths,
+            home,
+            max_depth,
+            kind,
+            include_hidden,
+            include_network_mounts,
+            include_shared_target,
+            git_status,
+            exclude,
+            thresholds,
+            top,
+            fail_if_over,
+            exit_code,
+            gha,
+            sort,
+            reverse,
+            group_by,
+            format,
+            json,
+            open_gui,
+        } => {
+            let base = base_config(with_stdin_paths(paths)?, home, &load_config()?)?;
+            let config = build_scan_config(base, max_depth, include_hidden, &kind);
+            let mut config = thresholds
+                .apply(config)
+                .with_skip_network_mounts(!include_network_mounts)
+                .with_shared_target_dirs(include_shared_target)
+                .with_git_status(git_status)
+                .with_exclude_globs(exclude);
+            if let Some(top) = top {
+                config = config.with_limit(top);
+            }
+            let mut result = scan_showing_progress(&config.build()?)?;
+            add_to_history(HistoryRecord::from_scan(&result));
+
+            match sort {
+                // Scans come back largest first
+                SortKey::Size => {}
+                SortKey::Path => result.sort_by_path(),
+                SortKey::Kind => result.sort_by_kind(),
+                SortKey::Age => result.sort_by_age(),
+                SortKey::Files => result.sort_by_file_count(),
+            }
+            if reverse {
+                result.items.reverse();
+            }
+
+            let format = if json { Format::Json } else { format };
+            if let Some(by) = group_by {
+                let mut groups = group_totals(&result, by);
+                if reverse {
+                    groups.reverse();
+                }
+                output::print(format, &groups, &groups, GROUP_COLUMNS, || {
+                    print_group_totals(&groups, by)
+                })?;
+            } else {
+                output::print(format, &result, &result.items, SCAN_COLUMNS, || {
+                    print_table_result(&result)
+                })?;
+            }
+            if open_gui {
+                open_in_gui(&result, format)?;
+            }
+            if gha {
+                gha::report(&result, fail_if_over, format)?;
+            }
+            return Ok(scan_exit_code(&result, fail_if_over, exit_code && !gha));
+        }
+
+        Commands::Biggest {
+            paths,
+            home,
+            max_depth,
+            kind,
+            exclude,
+            top,
+            format,
+        } => {
+            let base = base_config(with_stdin_paths(paths)?, home, &load_config()?)?;
+            let config = build_scan_config(base, max_depth, false, &kind)
+                .with_exclude_globs(exclude)
+                .build()?;
+            let result = scan_showing_progress(&config)?;
+            add_to_history(HistoryRecord::from_scan(&result));
+
+            let mut projects = rank_projects(&result);
+            projects.truncate(top);
+            output::print(format, &projects, &projects, PROJECT_COLUMNS, || {
+                print_biggest_projects(&projects)
+            })?;
+        }
+
+        Commands::Clean {
+            selection,
+            dry_run,
+            resume,
+            forget_interrupted,
+            yes,
+            interactive,
+            format,
+        } => {
+            if format != Format::Table && !yes && !dry_run {
+                bail!("--format {} needs --yes or --dry-run", format_name(format));
+            }
+            // Prompts would read their answers from the same stdin as the paths
+            if reads_stdin(&selection.paths) && !yes && !dry_run {
+                bail!("Reading paths from stdin needs --yes or --dry-run");
+            }
+            let journal_dir = default_journal_dir();
+            let interrupted = match &journal_dir {
+                Some(dir) => interrupted_cleans(dir).context("Failed to read the clean journal")?,
+                None => Vec::new(),
+            };
+            if resume {
+                resume_cleans(&interrupted, dry_run, yes)?;
+                return Ok(ExitCode::SUCCESS);
+            }
+            if forget_interrupted {
+                for clean in &interrupted {
+                    clean.discard()?;
+                }
+            } else if !interrupted.is_empty() && format == Format::Table {
+                for clean in &interrupted {
+                    print_interrupted(clean);
+                }
+                println!("   Run `devjunk clean --resume` to finish, or pass --forget-interrupted to drop it.");
+                println!();
+            }
+
+            let selection = selection.read_stdin_targets()?;
+            let user_config = load_config()?;
+            let Some((result, mut plan)) = plan_clean(selection, &user_config, dry_run, format)?
+            else {
+                if format != Format::Table {
+                    let empty = CleanResult::new(dry_run);
+                    output::print(format, &empty, &empty.items, CLEAN_COLUMNS, || {})?;
+                }
+                return Ok(ExitCode::SUCCESS);
+            };
+            if let Some(dir) = journal_dir {
+                plan = plan.with_journal_dir(dir);
+            }
+
+            if interactive {
+                let chosen = choose_items(&result.items)?;
+                plan.paths.retain(|path| chosen.contains(path));
+                if plan.paths.is_empty() {
+                    println!("Nothing selected.");
+                    return Ok(ExitCode::SUCCESS);
+                }
+            } else if !yes && !dry_run {
+                let active: Vec<_> = result.items.iter().filter(|i| i.is_active).collect();
+                if !active.is_empty() {
+                    println!();
+                    println!("⚠️  Recently built, you probably still need these:");
+                    for item in active {
+                        println!("   {} ({})", item.path.display(), item.kind_name());
+                    }
+                }
+                if !confirm_clean(&plan, &user_config.typed_confirm)? {
+                    println!("Aborted.");
+                    return Ok(ExitCode::SUCCESS);
+                }
+            }
+
+            run_clean(&plan, format)?;
+        }
+
+        Commands::Plan {
+            command: PlanCommands::Create { selection, output },
+        } => {
+            // Saved plans may be applied from another directory
+            let cwd = std::env::current_dir()?;
+            let mut user_config = load_config()?;
+            user_config.roots = user_config
+                .roots
+                .iter()
+                .map(|root| cwd.join(root))
+                .collect();
+            let selection = selection.read_stdin_targets()?;
+            let absolute = |paths: &[PathBuf]| paths.iter().map(|path| cwd.join(path)).collect();
+            let mut paths: Vec<PathBuf> = absolute(&selection.paths);
+            if paths.is_empty() && user_config.roots.is_empty() {
+                paths.push(cwd.clone());
+            }
+            let targets = absolute(&selection.targets);
+            let selection = PlanArgs {
+                paths,
+                targets,
+                ..selection
+            };
+            let Some((_, plan)) = plan_clean(selection, &user_config, false, Format::Table)? else {
+                return Ok(ExitCode::SUCCESS);
+            };
+
+            save_plan(&output, &plan)?;
+            println!();
+            println!(
+                "📝 Saved a plan to delete {} directories ({}) to {}",
+                plan.count(),
+                format_size(plan.total_size_bytes()),
+                output.display()
+            );
+            println!(
+                "   Review it, then run `devjunk apply {}`.",
+                output.display()
+            );
+        }
+
+        Commands::Plan {
+            command: PlanCommands::Diff { old, new, json },
+        } => {
+            let diff = diff_files(&old, &new)?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&diff)?);
+            } else {
+                print_diff(&diff);
+            }
+        }
+
+        Commands::Diff {
+            old,
+            new,
+            projects,
+            json,
+        } => {
+            let diff = diff_files(&old, &new)?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&diff)?);
+            } else {
+                print_diff(&diff);
+                print_project_growth(&diff, projects);
+            }
+        }
+
+        Commands::Apply {
+            plan: plan_path,
+            dry_run,
+            skip_changed,
+            yes,
+        } => {
+            let mut plan: CleanPlan = read_saved(&plan_path)
+                .with_context(|| format!("{} is not a clean plan", plan_path.display()))?;
+            plan.dry_run = dry_run;
+
+            let drifted = validate_plan(&plan);
+            if !drifted.is_empty() {
+                println!(
+                    "⚠️  {} directories changed since the plan was saved:",
+                    drifted.len()
+                );
+                for (path, drift) in &drifted {
+                    println!("   {} ({})", path.display(), drift);
+                }
+                if !skip_changed {
+                    bail!(
+                        "The plan is out of date; save a new one with `devjunk plan create`, \
+                         or pass --skip-changed to leave those directories out"
+                    );
+                }
+                plan.paths
+                    .retain(|path| !drifted.iter().any(|(drifted, _)| drifted == path));
+                println!();
+            }
+
+            if plan.paths.is_empty() {
+                println!("Nothing left to clean.");
+                return Ok(ExitCode::SUCCESS);
+            }
+            if let Some(dir) = default_journal_dir() {
+                plan = plan.with_journal_dir(dir);
+            }
+
+            if !yes && !dry_run && !confirm_clean(&plan, &load_config()?.typed_confirm)? {
+                println!("Aborted.");
+                return Ok(ExitCode::SUCCESS);
+            }
+
+            run_clean(&plan, Format::Table)?;
+        }
+
+        Commands::Watch {
+            paths,
+            home,
+            max_depth,
+            kind,
+            threshold,
+            interval,
+            on_exceed,
+        } => {
+            let base = base_config(paths, home, &load_config()?)?;
+            let config = build_scan_config(base, max_depth, false, &kind).build()?;
+            run_watch(&config, threshold, interval, on_exceed.as_deref())?;
+        }
+
+        Commands::Schedule {
+            command:
+                ScheduleCommands::Install {
+                    every,
+                    at: (hour, minute),
+                    name,
+                    clean_args,
+                },
+        } => {
+            let args = scheduled_clean_args(clean_args)?;
+            let program = std::env::current_exe().context("Failed to find the devjunk binary")?;
+            let schedule =
+                Schedule::new(name, every.frequency(), program, args).with_time(hour, minute);
+            install_schedule(&schedule)?;
+            println!(
+                "📅 Scheduled `devjunk {}` {} at {:02}:{:02}",
+                schedule.args.join(" "),
+                output::variant_name(&schedule.frequency),
+                hour,
+                minute
+            );
+        }
+
+        Commands::Schedule {
+            command: ScheduleCommands::Uninstall { name },
+        } => {
+            if uninstall_schedule(&name)? {
+                println!("🗑️  Removed the schedule '{}'", name);
+            } else {
+                bail!(
+                    "No schedule called '{}'; run `devjunk schedule status` to list them",
+                    name
+                );
+            }
+        }
+
+        Commands::Schedule {
+            command: ScheduleCommands::Status { json },
+        } => {
+            let schedules = list_schedules()?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&schedules)?);
+            } else {
+                print_schedules(&schedules);
+            }
+        }
+
+        Commands::Stats {
+            since,
+            projects,
+            json,
+        } => {
+            let records = match default_history_path() {
+                Some(path) => load_history(&path).context("Failed to read the history")?,
+                None => Vec::new(),
+            };
+            let mut stats = summarize_history(&records, since);
+            stats.by_project.truncate(projects);
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&stats)?);
+            } else {
+                print_stats(&stats, &records, since);
+            }
+        }
+
+        Commands::Restore {
+            path,
+            last,
+            archive_dirs,
+        } => {
+            let items = find_restorable(archive_dirs)?;
+            let item = match &path {
+                Some(path) => items.iter().find(|item| item.was_at(path)).with_context(|| {
+                    format!(
+                        "Nothing deleted from {} can be restored; run `devjunk restore` to list what can",
+                        path.display()
+                    )
+                })?,
+                None if last => items.first().context("Nothing to restore")?,
+                None => {
+                    print_restorable(&items);
+                    return Ok(ExitCode::SUCCESS);
+                }
+            };
+            let restored = restore(item)
+                .with_context(|| format!("Failed to restore {}", item.original_path.display()))?;
+            println!(
+                "♻️  Restored {} from the {}",
+                restored.display(),
+                output::variant_name(&item.source)
+            );
+        }
+
+        Commands::Ignore { command } => {
+            let config_path =
+                Config::default_path().context("Could not determine the config directory")?;
+            // Without the DEVJUNK_* variables, which must not end up in the file
+            let mut config =
+                Config::load(&config_path).context("Failed to read the config file")?;
+            let changed = match command {
+                IgnoreCommands::Add { paths } => {
+                    let mut changed = false;
+                    for path in paths {
+                        let path = std::path::absolute(&path)?;
+                        if config.add_ignore(path.clone()) {
+                            println!("Ignoring {}", path.display());
+                            changed = true;
+                        } else {
+                            println!("{} is ignored already", path.display());
+                        }
+                    }
+                    changed
+                }
+                IgnoreCommands::Remove { paths } => {
+                    let mut changed = false;
+                    for path in paths {
+                        let path = std::path::absolute(&path)?;
+                        if config.remove_ignore(&path) {
+                            println!("No longer ignoring {}", path.display());
+                            changed = true;
+                        } else {
+                            println!("{} isn't ignored", path.display());
+                        }
+                    }
+                    changed
+                }
+                IgnoreCommands::List { json } => {
+                    if json {
+                        println!("{}", serde_json::to_string_pretty(&config.ignore)?);
+                    } else if config.ignore.is_empty() {
+                        println!("Nothing is ignored; add directories with `devjunk ignore add`.");
+                    } else {
+                        for path in &config.ignore {
+                            println!("{}", path.display());
+                        }
+                    }
+                    false
+                }
+            };
+            if changed {
+                config
+                    .save(&config_path)
+                    .with_context(|| format!("Failed to write {}", config_path.display()))?;
+            }
+        }
+
+        Commands::Size { paths, format } => {
+            let mut paths = with_stdin_paths(paths)?;
+            if paths.is_empty() {
+                paths.push(PathBuf::from("."));
+            }
+            let sizes = paths
+                .iter()
+                .map(|path| measure_dir(path))
+                .collect::<Result<Vec<_>, _>>()?;
+            output::print(format, &sizes, &sizes, SIZE_COLUMNS, || print_sizes(&sizes))?;
+            if sizes.iter().any(|size| !size.errors.is_empty()) {
+                return Ok(ExitCode::from(EXIT_ERROR));
+            }
+        }
+
+        Commands::Doctor { format } => {
+            let caches = global_caches();
+            output::print(format, &caches, &caches, CACHE_COLUMNS, || {
+                print_caches(&caches)
+            })?;
+        }
+
+        Commands::Types { format, json } => {
+            let format = if json { Format::Json } else { format };
+            let kinds: Vec<KindInfo> = JunkKind::all().into_iter().map(KindInfo::new).collect();
+            output::print(format, &kinds, &kinds, TYPE_COLUMNS, print_junk_types)?;
+        }
+
+        Commands::Completions { shell } => {
+            clap_complete::generate(shell, &mut Cli::command(), "devjunk", &mut io::stdout());
+        }
+
+        Commands::Man { out_dir } => {
+            std::fs::create_dir_all(&out_dir)
+                .with_context(|| format!("Failed to create {}", out_dir.display()))?;
+            let mut cli = Cli::command().disable_help_subcommand(true);
+            cli.build();
+            write_man_pages(&cli, &out_dir)
+                .with_context(|| format!("Failed to write man pages to {}", out_dir.display()))?;
+            println!("Wrote man pages to {}", out_dir.display());
+        }
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Write the man page of `cmd` and of each of its visible subcommands into `dir`
+///
+/// Every page names devjunk and its version as its source, which the
+/// subcommands wouldn't otherwise carry.
+fn write_man_pages(cmd: &clap::Command, dir: &Path) -> io::Result<()> {
+    for subcommand in cmd.get_subcommands().filter(|sub| !sub.is_hide_set()) {
+        write_man_pages(subcommand, dir)?;
+    }
+    clap_mangen::Man::new(cmd.clone())
+        .source(concat!("devjunk ", env!("CARGO_PKG_VERSION")))
+        .generate_to(dir)?;
+    Ok(())
+}
+
+/// Scan with a spinner on stderr showing how far along the scan is
+///
+/// The spinner only appears when stdout is a terminal, so piped and
+/// redirected output stays as it was, and never alongside log lines or
+/// `--progress-json` events.
+fn scan_showing_progress(config: &ScanConfig) -> Result<ScanResult> {
+    let spinner = if io::stdout().is_terminal() && logging::shows_progress() && !progress::is_json()
+    {
+        ProgressBar::new_spinner()
+    } else {
+        ProgressBar::hidden()
+    };
+    spinner.set_style(ProgressStyle::with_template(
+        "{spinner} [{elapsed}] {wide_msg}",
+    )?);
+    spinner.enable_steady_tick(Duration::from_millis(100));
+
+    let result = scan_with_progress(config, |progress| {
+        if progress::is_json() {
+            progress::scan(progress);
+        } else {
+            spinner.set_message(format!(
+                "{} directories, {} junk ({}) {}",
+                progress.directories_scanned,
+                progress.items_found,
+                format_size(progress.bytes_found),
+                progress.current_path
+            ));
+        }
+    });
+    spinner.finish_and_clear();
+    Ok(result?)
+}
+
+/// Hand `result` over to the GUI and start it, showing the result ready to clean
+fn open_in_gui(result: &ScanResult, format: Format) -> Result<()> {
+    let path = default_handoff_path().context("Failed to find the local data directory")?;
+    save_handoff(&path, result).context("Failed to save the scan for the GUI")?;
+    let mut gui = gui_command().context("Failed to find the DevJunk GUI; install it first")?;
+    gui.arg(OPEN_SCAN_ARG)
+        .arg(&path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .context("Failed to start the DevJunk GUI")?;
+    if format == Format::Table {
+        println!();
+        println!("🖥  Opened the results in DevJunk");
+    }
+    Ok(())
+}
+
+/// The command starting the GUI: its binary next to this one or on the PATH,
+/// or else the installed app on macOS
+fn gui_command() -> Option<Command> {
+    let names = ["devjunk-gui", "DevJunk"].map(|name| format!("{}{}", name, EXE_SUFFIX));
+    let beside_cli = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(Path::to_path_buf));
+    let on_path = std::env::var_os("PATH")
+        .into_iter()
+        .flat_map(|path| std::env::split_paths(&path).collect::<Vec<_>>());
+    let program = beside_cli
+        .into_iter()
+        .chain(on_path)
+        .flat_map(|dir| names.iter().map(move |name| dir.join(name)))
+        .find(|program| program.is_file());
+    match program {
+        Some(program) => Some(Command::new(program)),
+        None if cfg!(target_os = "macos") => {
+            let mut open = Command::new("open");
+            open.args(["-a", "DevJunk", "--args"]);
+            Some(open)
+        }
+        None => None,
+    }
+}
+
+/// The exit status of a scan, for scripts and CI to branch on
+///
+/// Finding junk is the normal outcome, so it only changes the status when
+/// `junk_found_fails` is set. In GitHub Actions the junk goes to the
+/// annotations instead, and only fails the step when it's over the limit.
+fn scan_exit_code(
+    result: &ScanResult,
+    fail_if_over: Option<u64>,
+    junk_found_fails: bool,
+) -> ExitCode {
+    let total = result.total_size_bytes();
+    if let Some(limit) = fail_if_over.filter(|&limit| total > limit) {
+        eprintln!(
+            "❌ Found {} of junk, more than the {} allowed by --fail-if-over",
+            format_size(total),
+            format_size(limit)
+        );
+        return ExitCode::from(EXIT_OVER_LIMIT);
+    }
+    if result.has_errors() {
+        ExitCode::from(EXIT_ERROR)
+    } else if junk_found_fails && fail_if_over.is_none() && !result.items.is_empty() {
+        ExitCode::from(EXIT_JUNK_FOUND)
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+/// Scan for junk and plan cleaning it, printing what was found
+///
+/// Only the table format prints the scan; the others just report what was
+/// left out, on stderr. Returns None when no junk was found.
+fn plan_clean(
+    selection: PlanArgs,
+    user_config: &Config,
+    dry_run: bool,
+    format: Format,
+) -> Result<Option<(ScanResult, CleanPlan)>> {
+    let PlanArgs {
+        paths,
+        targets,
+        home,
+        from_scan,
+        revalidate,
+        max_depth,
+        kind,
+        all_except,
+        include_shared_target,
+        exclude,
+        thresholds,
+        skip_dirty,
+        keep_newest,
+        keep_per_project,
+        sweep_rust,
+        skip_in_use,
+        keep,
+        archive_to,
+        background_delete,
+        trash,
+    } = selection;
+
+    let base = base_config(paths, home, user_config)?;
+    let config = build_scan_config(base, max_depth, false, &kind).without_patterns(&all_except);
+    let config = thresholds
+        .apply(config)
+        .with_shared_target_dirs(include_shared_target)
+        .with_git_status(skip_dirty)
+        .with_exclude_globs(exclude)
+        .build()?;
+    let mut messages = output::messages(format);
+    let mut result = match from_scan {
+        Some(file) => {
+            let mut result: ScanResult = read_saved(&file)
+                .with_context(|| format!("{} is not a scan result", file.display()))?;
+            filter_scan_result(&mut result, &config)?;
+            if revalidate {
+                let missing = remeasure_scan_result(&mut result);
+                if !missing.is_empty() {
+                    writeln!(
+                        messages,
+                        "Skipping {} directories that no longer exist:",
+                        missing.len()
+                    )?;
+                    for path in missing {
+                        writeln!(messages, "   {}", path.display())?;
+                    }
+                }
+            }
+            result
+        }
+        None => scan_showing_progress(&config)?,
+    };
+
+    if !targets.is_empty() {
+        let not_junk = keep_targets(&mut result, &targets);
+        if !not_junk.is_empty() {
+            writeln!(
+                messages,
+                "Skipping {} paths that aren't junk directories matching the filters:",
+                not_junk.len()
+            )?;
+            for path in not_junk {
+                writeln!(messages, "   {}", path.display())?;
+            }
+        }
+    }
+
+    if skip_dirty {
+        let (dirty, clean): (Vec<_>, Vec<_>) = result
+            .items
+            .into_iter()
+            .partition(|i| i.git.as_ref().is_some_and(|g| g.is_dirty()));
+        result.items = clean;
+
+        if !dirty.is_empty() {
+            writeln!(
+                messages,
+                "Skipping {} directories in repositories with unsaved work:",
+                dirty.len()
+            )?;
+            for item in &dirty {
+                writeln!(messages, "   {}", item.path.display())?;
+            }
+        }
+    }
+
+    if !keep_newest.is_empty() {
+        let scope = if keep_per_project {
+            RetentionScope::Project
+        } else {
+            RetentionScope::Root
+        };
+        let policies: Vec<_> = keep_newest
+            .iter()
+            .map(|&(kind, keep)| RetentionPolicy::new(kind, keep).with_scope(scope))
+            .collect();
+
+        let all: Vec<PathBuf> = result.items.iter().map(|i| i.path.clone()).collect();
+        let selection = apply_retention(&result, &all, &policies);
+        let (clean, kept): (Vec<_>, Vec<_>) = result
+            .items
+            .into_iter()
+            .partition(|i| selection.contains(&i.path));
+        result.items = clean;
+
+        if !kept.is_empty() {
+            writeln!(
+                messages,
+                "Keeping the {} most recently modified directories:",
+                kept.len()
+            )?;
+            for item in &kept {
+                writeln!(
+                    messages,
+                    "   {} ({})",
+                    item.path.display(),
+                    item.kind_name()
+                )?;
+            }
+        }
+    }
+
+    let (deletable, undeletable): (Vec<_>, Vec<_>) =
+        result.items.into_iter().partition(|i| i.deletable);
+    result.items = deletable;
+    if !undeletable.is_empty() {
+        writeln!(
+            messages,
+            "Skipping {} directories you don't have permission to delete:",
+            undeletable.len()
+        )?;
+        for item in &undeletable {
+            let reason = item.undeletable_reason.as_deref().unwrap_or("unknown");
+            writeln!(messages, "   {} ({})", item.path.display(), reason)?;
+        }
+    }
+
+    if result.items.is_empty() {
+        writeln!(messages, "No junk directories found.")?;
+        return Ok(None);
+    }
+
+    if format == Format::Table {
+        print_table_result(&result);
+    }
+
+    // Build plan with all items selected
+    let all_paths: Vec<PathBuf> = result.items.iter().map(|i| i.path.clone()).collect();
+    let delete_mode = match archive_to {
+        Some(dir) => Some(DeleteMode::Archive { dir }),
+        None if trash => Some(DeleteMode::Trash),
+        None if background_delete => Some(DeleteMode::RenameDefer),
+        None => None,
+    };
+    let mut options = user_config
+        .clean_options()
+        .with_dry_run(dry_run)
+        .with_keep_globs(keep);
+    if let Some(mode) = delete_mode {
+        options = options.with_delete_mode(mode);
+    }
+    let mut plan = build_clean_plan(&result, &all_paths, &options)?.with_skip_in_use(skip_in_use);
+    if sweep_rust {
+        plan = plan.with_strategy(JunkKind::RustTarget, CleanStrategy::SweepRustTarget);
+    }
+
+    Ok(Some((result, plan)))
+}
+
+/// Ask before deleting the paths of `plan`
+///
+/// A plan over the `typed` thresholds is broken down per kind and has to be
+/// confirmed by typing a phrase like `delete 62GB`.
+fn confirm_clean(plan: &CleanPlan, typed: &TypedConfirm) -> Result<bool> {
+    println!();
+    println!(
+        "⚠️  This will delete {} directories ({}).",
+        plan.count(),
+        format_size(plan.total_size_bytes())
+    );
+    if plan.strategies.get(&JunkKind::RustTarget) == Some(&CleanStrategy::SweepRustTarget) {
+        println!("   Rust target directories keep their final binaries.");
+    }
+    match &plan.delete_mode {
+        DeleteMode::Archive { dir } => {
+            println!("   Each one is archived to {} first.", dir.display());
+        }
+        DeleteMode::Trash => println!("   They are moved to the trash."),
+        _ => {}
+    }
+    print_rebuild_hints(plan);
+    if !typed.applies(plan.total_size_bytes(), plan.count()) {
+        return confirm();
+    }
+
+    println!();
+    print_shares(&plan_kind_totals(plan));
+    let phrase = confirm_phrase(plan, typed);
+    print!("Type `{}` to continue: ", phrase);
+    std::io::Write::flush(&mut std::io::stdout())?;
+
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input)?;
+    let squash = |text: &str| text.split_whitespace().collect::<String>().to_lowercase();
+    Ok(squash(&input) == squash(&phrase))
+}
+
+/// What brings back each kind of directory in `plan`, so it's clear what the clean costs
+fn print_rebuild_hints(plan: &CleanPlan) {
+    let mut kinds: Vec<JunkKind> = Vec::new();
+    for kind in plan.paths.iter().filter_map(|path| plan.kind_of(path)) {
+        if !kinds.contains(&kind) {
+            kinds.push(kind);
+        }
+    }
+    if kinds.is_empty() {
+        return;
+    }
+    println!("   To get them back:");
+    for kind in kinds {
+        let caution = match kind.safety() {
+            KindSafety::Caution => " (may hold files that can't be recreated)",
+            KindSafety::Safe => "",
+        };
+        println!(
+            "   - {}: {}{}",
+            kind.display_name(),
+            kind.rebuild_hint(),
+            caution
+        );
+    }
+}
+
+/// What to type to confirm a big clean: its size if that is over the
+/// threshold, rounded to the unit shown, or else its number of directories
+fn confirm_phrase(plan: &CleanPlan, typed: &TypedConfirm) -> String {
+    let bytes = plan.total_size_bytes();
+    if bytes < typed.min_bytes {
+        return format!("delete {} directories", plan.count());
+    }
+    let size = format_size(bytes);
+    let (number, unit) = size.split_once(' ').unwrap_or((&size, ""));
+    let number: f64 = number.parse().unwrap_or(0.0);
+    format!("delete {}{}", number.round(), unit)
+}
+
+/// Execute a confirmed plan, stopping early on Ctrl+C, and print the result
+fn run_clean(plan: &CleanPlan, format: Format) -> Result<()> {
+    let cancel = cancel_on_ctrl_c()?;
+    let clean_result = execute_clean_with_cancel(plan, &cancel, progress::clean)?;
+    if !clean_result.was_dry_run {
+        add_to_history(HistoryRecord::from_clean(&clean_result));
+    }
+
+    let table = || {
+        print_clean_result(&clean_result);
+        if let DeleteMode::Archive { dir } = &plan.delete_mode {
+            if !plan.dry_run && clean_result.deleted_count() > 0 {
+                println!("📦 Archived to {}", dir.display());
+                println!();
+            }
+        }
+    };
+    output::print(
+        format,
+        &clean_result,
+        &clean_result.items,
+        CLEAN_COLUMNS,
+        table,
+    )
+}
+
+/// Finish the cleans that were interrupted, as recorded in their journals
+fn resume_cleans(interrupted: &[InterruptedClean], dry_run: bool, yes: bool) -> Result<()> {
+    if interrupted.is_empty() {
+        println!("No interrupted clean to resume.");
+        return Ok(());
+    }
+
+    let cancel = cancel_on_ctrl_c()?;
+    for clean in interrupted {
+        print_interrupted(clean);
+
+        let mut plan = clean.resume_plan();
+        plan.dry_run = dry_run;
+        if !yes && !dry_run && plan.count() > 0 {
+            println!();
+            println!(
+                "⚠️  This will delete the {} directories left.",
+                plan.count()
+            );
+            if !confirm()? {
+                println!("Aborted.");
+                return Ok(());
+            }
+        }
+
+        let result = execute_clean_with_cancel(&plan, &cancel, progress::clean)?;
+        if !result.was_dry_run {
+            add_to_history(HistoryRecord::from_clean(&result));
+        }
+        print_clean_result(&result);
+        if !dry_run && !result.cancelled {
+            clean.discard()?;
+        }
+        if cancel.is_cancelled() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Report what an interrupted clean got done
+fn print_interrupted(clean: &InterruptedClean) {
+    println!(
+        "⚠️  A clean of {} directories was interrupted after removing {} ({}); {} left.",
+        clean.plan.count(),
+        clean.removed().count(),
+        format_size(clean.bytes_freed),
+        clean.remaining().len() + clean.moved.len()
+    );
+    for path in clean.removed() {
+        println!("   ✓ {}", path.display());
+    }
+}
+
+/// Ask whether to go ahead
+fn confirm() -> Result<bool> {
+    print!("Continue? [y/N] ");
+    std::io::Write::flush(&mut std::io::stdout())?;
+
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input)?;
+    Ok(input.trim().eq_ignore_ascii_case("y"))
+}
+
+/// Ask about each of `items` in turn, returning the paths to delete
+///
+/// Answers are y (delete), n (keep), a (delete this and the rest) and
+/// q (keep this and the rest).
+fn choose_items(items: &[ScanItem]) -> Result<Vec<PathBuf>> {
+    let mut chosen = Vec::new();
+    for (i, item) in items.iter().enumerate() {
+        println!();
+        println!("[{}/{}] {}", i + 1, items.len(), item.path.display());
+        let age = item
+            .last_modified
+            .map(|time| format!(", modified {}", format_age(time)))
+            .unwrap_or_default();
+        let active = if item.is_active {
+            ", recently built"
+        } else {
+            ""
+        };
+        println!(
+            "   {}, {}{}{}",
+            item.kind_name(),
+            format_size(item.size_bytes),
+            age,
+            active
+        );
+
+        loop {
+            print!("Delete? [y/n/a/q] ");
+            io::stdout().flush()?;
+            let mut input = String::new();
+            // End of input keeps everything not yet chosen
+            if io::stdin().read_line(&mut input)? == 0 {
+                return Ok(chosen);
+            }
+            match input.trim().to_ascii_lowercase().as_str() {
+                "y" => chosen.push(item.path.clone()),
+                "n" => {}
+                "a" => {
+                    chosen.extend(items[i..].iter().map(|item| item.path.clone()));
+                    return Ok(chosen);
+                }
+                "q" => return Ok(chosen),
+                _ => {
+                    println!("   Answer y to delete, n to keep, a to delete the rest or q to keep the rest");
+                    continue;
+                }
+            }
+            break;
+        }
+    }
+    Ok(chosen)
+}
+
+/// How long ago `time` was, e.g. `3 days ago`
+fn format_age(time: SystemTime) -> String {
+    let secs = SystemTime::now()
+        .duration_since(time)
+        .unwrap_or_default()
+        .as_secs();
+    let (count, unit) = match secs {
+        s if s >= 86400 => (s / 86400, "day"),
+        s if s >= 3600 => (s / 3600, "hour"),
+        s if s >= 60 => (s / 60, "minute"),
+        _ => return "just now".to_string(),
+    };
+    let plural = if count == 1 { "" } else { "s" };
+    format!("{} {}{} ago", count, unit, plural)
+}
+
+/// A token cancelled by Ctrl-C
+///
+/// The first Ctrl-C stops after the directory being deleted; a second one quits right away.
+fn cancel_on_ctrl_c() -> Result<CancelToken> {
+    let cancel = CancelToken::new();
+    let handler_token = cancel.clone();
+    ctrlc::set_handler(move || {
+        if handler_token.is_cancelled() {
+            std::process::exit(130);
+        }
+        eprintln!();
+        eprintln!("Stopping after the current directory (press Ctrl-C again to quit now)...");
+        handler_token.cancel();
+    })
+    .context("Failed to install the Ctrl-C handler")?;
+    Ok(cancel)
+}
+
+/// Scan every `interval` and report each time the junk found crosses `threshold`
+///
+/// Runs until interrupted. Only the first scan failing is fatal; later
+/// failures are reported and the scan is retried at the next interval.
+fn run_watch(
+    config: &ScanConfig,
+    threshold: u64,
+    interval: Duration,
+    on_exceed: Option<&str>,
+) -> Result<()> {
+    println!(
+        "👀 Watching for more than {} of junk, scanning every {} (Ctrl-C to stop)",
+        format_size(threshold),
+        humantime::format_duration(interval)
+    );
+
+    let mut first = true;
+    let mut over = false;
+    loop {
+        let now = humantime::format_rfc3339_seconds(SystemTime::now());
+        match scan_with_progress(config, progress::scan) {
+            Ok(result) => {
+                let total = result.total_size_bytes();
+                println!(
+                    "[{}] {} in {} directories",
+                    now,
+                    format_size(total),
+                    result.item_count()
+                );
+
+                // Only report crossing the threshold, not every scan spent above it
+                let was_over = std::mem::replace(&mut over, total >= threshold);
+                if over && !was_over {
+                    report_exceeded(&result, threshold, on_exceed);
+                }
+            }
+            Err(e) if first => return Err(e.into()),
+            Err(e) => eprintln!("[{}] ⚠️  Scan failed: {}", now, e),
+        }
+        first = false;
+        std::thread::sleep(interval);
+    }
+}
+
+/// Warn that the junk in `result` is over `threshold` and run the `--on-exceed` command
+fn report_exceeded(result: &ScanResult, threshold: u64, on_exceed: Option<&str>) {
+    const LARGEST: usize = 5;
+
+    println!(
+        "⚠️  Junk is over {}, the largest directories are:",
+        format_size(threshold)
+    );
+    for item in result.items.iter().take(LARGEST) {
+        println!(
+            "   {:>12}  {}",
+            format_size(item.size_bytes),
+            item.path.display()
+        );
+    }
+
+    let Some(command) = on_exceed else {
+        return;
+    };
+    let mut shell = if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C");
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c");
+        shell
+    };
+    let status = shell
+        .arg(command)
+        .env("DEVJUNK_TOTAL_BYTES", result.total_size_bytes().to_string())
+        .status();
+    match status {
+        Ok(status) if !status.success() => {
+            eprintln!("⚠️  The --on-exceed command failed with {}", status)
+        }
+        Ok(_) => {}
+        Err(e) => eprintln!("⚠️  Could not run the --on-exceed command: {}", e),
+    }
+}
+
+/// Record a scan or clean for `devjunk stats`, warning instead of failing
+fn add_to_history(record: HistoryRecord) {
+    let Some(path) = default_history_path() else {
+        return;
+    };
+    if let Err(e) = record_history(&path, &record) {
+        eprintln!("⚠️  Could not record this run in the history: {}", e);
+    }
+}
+
+/// The arguments a scheduled job runs devjunk with, checked like a `clean` run now
+///
+/// Nobody is around to answer prompts, and the job doesn't run in the current
+/// directory, so interactive cleans, stdin and relative paths are refused.
+fn scheduled_clean_args(clean_args: Vec<String>) -> Result<Vec<String>> {
+    let mut args = vec!["clean".to_string()];
+    args.extend(clean_args);
+    let argv = std::iter::once("devjunk").chain(args.iter().map(String::as_str));
+    let cli = match Cli::try_parse_from(argv) {
+        Ok(cli) => cli,
+        Err(e) => bail!("`devjunk {}` is not a valid clean:\n{}", args.join(" "), e),
+    };
+    let Commands::Clean {
+        selection,
+        dry_run,
+        yes,
+        interactive,
+        ..
+    } = cli.command
+    else {
+        bail!("The arguments after `--` must be for `devjunk clean`");
+    };
+
+    if interactive {
+        bail!("A scheduled clean can't ask about each directory; leave out --interactive");
+    }
+    if reads_stdin(&selection.paths) {
+        bail!("A scheduled clean can't read paths from stdin");
+    }
+    if let Some(path) = selection.paths.iter().find(|path| path.is_relative()) {
+        bail!(
+            "Scheduled cleans don't run in this directory, so give {} as an absolute path",
+            path.display()
+        );
+    }
+    if selection.paths.is_empty() && !selection.home && load_config()?.roots.is_empty() {
+        bail!("Give the paths to clean after `--`, or set `roots` in the config file");
+    }
+    if !yes && !dry_run {
+        args.push("--yes".to_string());
+    }
+    Ok(args)
+}
+
+/// Parse a `--at` value like `03:00`
+fn parse_time_of_day(arg: &str) -> std::result::Result<(u8, u8), String> {
+    let invalid = || format!("'{}' is not a time of day like 03:00", arg);
+    let (hour, minute) = arg.split_once(':').ok_or_else(invalid)?;
+    let hour: u8 = hour.parse().map_err(|_| invalid())?;
+    let minute: u8 = minute.parse().map_err(|_| invalid())?;
+    if hour > 23 || minute > 59 {
+        return Err(invalid());
+    }
+    Ok((hour, minute))
+}
+
+/// Parse a `--since` value: a time ago like `30d`, or a UTC date like `2026-01-01`
+fn parse_since(arg: &str) -> std::result::Result<SystemTime, String> {
+    if let Ok(ago) = humantime::parse_duration(arg) {
+        return SystemTime::now()
+            .checked_sub(ago)
+            .ok_or_else(|| format!("'{}' is too long ago", arg));
+    }
+    let time = if arg.len() == "2026-01-01".len() {
+        format!("{} 00:00:00", arg)
+    } else {
+        arg.to_string()
+    };
+    humantime::parse_rfc3339_weak(&time).map_err(|_| {
+        format!(
+            "'{}' is neither a time ago like 30d nor a date like 2026-01-01",
+            arg
+        )
+    })
+}
+
+/// Read the config file, or the defaults if there is none, and the `DEVJUNK_*` variables over it
+fn load_config() -> Result<Config> {
+    let config = Config::load_default().context("Failed to read the config file")?;
+    Ok(config.with_env()?)
+}
+
+/// The path argument that stands for paths read from stdin
+const STDIN_PATH: &str = "-";
+
+/// Whether `paths` asks for paths from stdin
+fn reads_stdin(paths: &[PathBuf]) -> bool {
+    paths.iter().any(|path| path.as_os_str() == STDIN_PATH)
+}
+
+/// `paths` with `-` replaced by the paths read from stdin
+fn with_stdin_paths(paths: Vec<PathBuf>) -> Result<Vec<PathBuf>> {
+    if !reads_stdin(&paths) {
+        return Ok(paths);
+    }
+    let mut paths: Vec<PathBuf> = paths
+        .into_iter()
+        .filter(|path| path.as_os_str() != STDIN_PATH)
+        .collect();
+    paths.extend(read_stdin_paths()?);
+    Ok(paths)
+}
+
+/// Newline-separated paths from stdin, as printed by fd, find or fzf
+fn read_stdin_paths() -> Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    for line in io::stdin().lock().lines() {
+        let line = line.context("Failed to read paths from stdin")?;
+        let line = line.strip_suffix('\r').unwrap_or(&line);
+        if !line.is_empty() {
+            paths.push(PathBuf::from(line));
+        }
+    }
+    if paths.is_empty() {
+        bail!("No paths on stdin");
+    }
+    Ok(paths)
+}
+
+/// Keep only the items that are one of `targets`, returning the targets that aren't junk
+///
+/// A target nested in another one counts as found, since it goes with it.
+fn keep_targets(result: &mut ScanResult, targets: &[PathBuf]) -> Vec<PathBuf> {
+    result.items.retain(|item| targets.contains(&item.path));
+    let items = &result.items;
+    targets
+        .iter()
+        .filter(|target| !items.iter().any(|item| target.starts_with(&item.path)))
+        .cloned()
+        .collect()
+}
+
+/// Start from the home preset, the given paths or the configured roots, in that order
+///
+/// Falls back to the current directory, and applies the rest of the config file.
+fn base_config(paths: Vec<PathBuf>, home: bool, user_config: &Config) -> Result<ScanConfig> {
+    let base = if home {
+        ScanConfig::home().context("Could not determine your home directory")?
+    } else {
+        ScanConfig::new(paths)
+    };
+    let mut config = user_config.scan_config(base);
+    if config.roots.is_empty() {
+        config.roots.push(PathBuf::from("."));
+    }
+    Ok(config)
+}
+
+fn build_scan_config(
+    config: ScanConfig,
+    max_depth: Option<usize>,
+    include_hidden: bool,
+    kinds: &[JunkKind],
+) -> ScanConfigBuilder {
+    let mut config = ScanConfigBuilder::from(config).with_hidden(include_hidden);
+
+    if let Some(depth) = max_depth {
+        config = config.with_max_depth(depth);
+    }
+
+    // Filter by kind if specified
+    if !kinds.is_empty() {
+        config = config.with_patterns(kinds.to_vec());
+    }
+
+    config
+}
+
+/// Parses a `--kind` id or alias, and offers the kind ids to shell completion
+#[derive(Clone)]
+struct KindParser;
+
+impl TypedValueParser for KindParser {
+    type Value = JunkKind;
+
+    fn parse_ref(
+        &self,
+        cmd: &clap::Command,
+        arg: Option<&clap::Arg>,
+        value: &OsStr,
+    ) -> std::result::Result<JunkKind, clap::Error> {
+        StringValueParser::new()
+            .try_map(|name| parse_kind(&name))
+            .parse_ref(cmd, arg, value)
+    }
+
+    fn possible_values(&self) -> Option<Box<dyn Iterator<Item = PossibleValue> + '_>> {
+        Some(Box::new(JunkKind::all().into_iter().map(|kind| {
+            PossibleValue::new(kind.id())
+                .aliases(kind.aliases())
+                .help(kind.display_name())
+        })))
+    }
+}
+
+/// Parse a junk kind id or alias, pointing at `devjunk types` if it is neither
+fn parse_kind(name: &str) -> std::result::Result<JunkKind, String> {
+    name.parse()
+        .map_err(|e| format!("{}; run `devjunk types` to list the kinds", e))
+}
+
+/// Parse a `--keep-newest` value like `rust=2`
+fn parse_keep_newest(arg: &str) -> std::result::Result<(JunkKind, usize), String> {
+    let (kind, keep) = arg.split_once('=').ok_or("expected KIND=N, e.g. rust=2")?;
+    let kind = parse_kind(kind)?;
+    let keep = keep
+        .parse()
+        .map_err(|_| format!("'{}' is not a number of directories", keep))?;
+    Ok((kind, keep))
+}
+
+/// Parse a `--min-size` value like `500MB`, `1.5GB` or a plain number of bytes
+///
+/// Units are powers of 1024, as in the sizes devjunk prints.
+fn parse_size(arg: &str) -> std::result::Result<u64, String> {
+    let arg = arg.trim();
+    let split = arg
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(arg.len());
+    let (number, unit) = arg.split_at(split);
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format!("'{}' is not a size, e.g. 500MB", arg))?;
+    let scale: u64 = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kb" | "kib" => 1 << 10,
+        "m" | "mb" | "mib" => 1 << 20,
+        "g" | "gb" | "gib" => 1 << 30,
+        "t" | "tb" | "tib" => 1 << 40,
+        other => return Err(format!("unknown size unit '{}'", other)),
+    };
+    Ok((number * scale as f64) as u64)
+}
+
+fn print_table_result(result: &ScanResult) {
+    if result.items.is_empty() {
+        println!("No junk directories found.");
+        return;
+    }
+
+    // Header
+    println!();
+    let header = format!(
+        "{:<60} {:<15} {:>12} {:>10}",
+        "Path", "Type", "Size", "Files"
+    );
+    println!("{}", style::paint(header, Style::Bold));
+    println!("{}", "-".repeat(100));
+
+    // Items
+    for item in &result.items {
+        let path_str = item.path.display().to_string();
+        let truncated_path = if path_str.len() > 58 {
+            format!("...{}", &path_str[path_str.len() - 55..])
+        } else {
+            path_str
+        };
+
+        let kind = style::kind(format!("{:<15}", item.kind_name()), item.kind);
+        let size = format!("{:>12}", format_size(item.size_bytes));
+        println!(
+            "{:<60} {} {} {:>10}{}",
+            truncated_path,
+            kind,
+            style::size(size, item.size_bytes),
+            item.file_count,
+            item_markers(item)
+        );
+    }
+
+    // Summary
+    println!("{}", "-".repeat(100));
+    let summary = format!(
+        "Total: {} directories, {}, {} files",
+        result.item_count(),
+        format_size(result.total_size_bytes()),
+        result.total_file_count()
+    );
+    println!("{}", style::paint(summary, Style::Green));
+    println!();
+
+    let active = result.items.iter().filter(|i| i.is_active).count();
+    if active > 0 {
+        println!(
+            "* {} directories were built within the last day and may still be in use.",
+            active
+        );
+        println!();
+    }
+
+    let dirty = result
+        .items
+        .iter()
+        .filter(|i| i.git.as_ref().is_some_and(|g| g.is_dirty()))
+        .count();
+    if dirty > 0 {
+        println!(
+            "! {} directories belong to git repositories with uncommitted or unpushed work.",
+            dirty
+        );
+        println!();
+    }
+
+    print_kind_breakdown(result);
+    print_root_breakdown(result);
+    print_skipped_mounts(result);
+    print_scan_errors(&result.errors);
+}
+
+/// Trailing markers for a table row, explained below the table
+fn item_markers(item: &ScanItem) -> String {
+    let mut markers = String::new();
+    if item.is_active {
+        markers.push_str(" *");
+    }
+    if item.git.as_ref().is_some_and(|g| g.is_dirty()) {
+        markers.push_str(" !");
+    }
+    markers
+}
+
+/// Totals per junk kind and their share of all the junk, largest first
+fn print_kind_breakdown(result: &ScanResult) {
+    let groups = group_totals(result, GroupBy::Kind);
+    if groups.len() >= 2 {
+        print_shares(&groups);
+    }
+}
+
+/// Print each kind's totals and share of the size of all of `groups`
+fn print_shares(groups: &[GroupTotal]) {
+    let total: u64 = groups.iter().map(|group| group.size_bytes).sum();
+    println!("By type:");
+    for group in groups {
+        let share = match total {
+            0 => 0.0,
+            _ => group.size_bytes as f64 * 100.0 / total as f64,
+        };
+        println!(
+            "   {:<57} {:>5} dirs {:>12} {:>5.1}%",
+            group.group,
+            group.items,
+            format_size(group.size_bytes),
+            share
+        );
+    }
+    println!();
+}
+
+fn print_root_breakdown(result: &ScanResult) {
+    let groups = result.by_root();
+    if groups.len() < 2 {
+        return;
+    }
+
+    println!("By root:");
+    for (root, items) in groups {
+        let size: u64 = items.iter().map(|i| i.size_bytes).sum();
+        println!(
+            "   {:<57} {:>5} dirs {:>12}",
+            root.display(),
+            items.len(),
+            format_size(size)
+        );
+    }
+    println!();
+}
+
+fn print_skipped_mounts(result: &ScanResult) {
+    if result.skipped_mounts.is_empty() {
+        return;
+    }
+
+    println!(
+        "Skipped {} network/removable mounts (use --include-network-mounts to scan them):",
+        result.skipped_mounts.len()
+    );
+    for mount in &result.skipped_mounts {
+        println!("   {} ({})", mount.mount_point.display(), mount.fs_type);
+    }
+    println!();
+}
+
+fn print_scan_errors(errors: &[ScanError]) {
+    const MAX_SHOWN: usize = 5;
+
+    if errors.is_empty() {
+        return;
+    }
+
+    println!(
+        "⚠️  {} paths could not be read and were skipped:",
+        errors.len()
+    );
+    for error in errors.iter().take(MAX_SHOWN) {
+        println!("   {} - {}", error.path.display(), error.cause);
+    }
+    if errors.len() > MAX_SHOWN {
+        println!("   ... and {} more", errors.len() - MAX_SHOWN);
+    }
+    println!();
+}
+
+fn print_sizes(sizes: &[DirSize]) {
+    println!();
+    println!("{:>12} {:>10}   Path", "Size", "Files");
+    println!("{}", "-".repeat(100));
+    for size in sizes {
+        println!(
+            "{:>12} {:>10}   {}",
+            style::size(format_size(size.size_bytes), size.size_bytes),
+            size.file_count,
+            size.path.display()
+        );
+    }
+    if sizes.len() > 1 {
+        println!("{}", "-".repeat(100));
+        let bytes: u64 = sizes.iter().map(|size| size.size_bytes).sum();
+        let files: u64 = sizes.iter().map(|size| size.file_count).sum();
+        println!("{:>12} {:>10}   Total", format_size(bytes), files);
+    }
+    println!();
+
+    let errors: Vec<ScanError> = sizes.iter().flat_map(|size| size.errors.clone()).collect();
+    print_scan_errors(&errors);
+}
+
+fn print_clean_result(result: &CleanResult) {
+    println!();
+
+    if result.was_dry_run {
+        println!("🔍 DRY RUN - No files were deleted");
+        println!();
+    }
+
+    if result.deleted_count() > 0 {
+        let action = if result.was_dry_run {
+            "Would delete"
+        } else {
+            "Deleted"
+        };
+
+        let summary = format!(
+            "✅ {}: {} directories ({})",
+            action,
+            result.deleted_count(),
+            format_size(result.bytes_freed)
+        );
+        println!("{}", style::paint(summary, Style::Green));
+
+        let by_kind = result.bytes_freed_by_kind();
+        if by_kind.len() > 1 {
+            for (kind, bytes) in by_kind {
+                println!("   {:<20} {}", kind.display_name(), format_size(bytes));
+            }
+        }
+
+        // Hardlinks and filesystem compression make this differ from the sizes above
+        if let Some(space) = result.free_space {
+            println!(
+                "   Free space measured on disk: {}",
+                format_delta(space.reclaimed())
+            );
+        }
+    }
+
+    if result.cancelled {
+        println!("⏹️  Cancelled before every directory was cleaned");
+    }
+
+    if result.skipped_count() > 0 {
+        println!();
+        println!("⚠️  Skipped {} directories:", result.skipped_count());
+        for item in result.skipped() {
+            print_unclean_item(item);
+        }
+    }
+
+    if result.failed_count() > 0 {
+        let causes: Vec<_> = result
+            .failures_by_kind()
+            .into_iter()
+            .map(|(kind, count)| format!("{} {}", count, kind))
+            .collect();
+        let action = if result.was_dry_run {
+            "Would fail to delete"
+        } else {
+            "Failed to delete"
+        };
+        println!();
+        let summary = format!(
+            "❌ {} {} directories ({}):",
+            action,
+            result.failed_count(),
+            causes.join(", ")
+        );
+        println!("{}", style::paint(summary, Style::Red));
+        for item in result.failed() {
+            print_unclean_item(item);
+        }
+    }
+
+    println!();
+}
+
+fn print_unclean_item(item: &CleanItem) {
+    let reason = item.error.as_deref().unwrap_or("unknown");
+    println!("   {} - {}", item.path.display(), reason);
+}
+
+/// Compare two saved scans, or two saved plans
+fn diff_files(old: &Path, new: &Path) -> Result<ScanDiff> {
+    Ok(match (load_plan_file(old)?, load_plan_file(new)?) {
+        (PlanFile::Scan(a), PlanFile::Scan(b)) => diff_scans(&a, &b),
+        (PlanFile::Plan(a), PlanFile::Plan(b)) => diff_plans(&a, &b),
+        _ => bail!("Cannot compare a scan result with a clean plan"),
+    })
+}
+
+fn load_plan_file(path: &Path) -> Result<PlanFile> {
+    read_saved(path)
+        .with_context(|| format!("{} is not a scan result or clean plan", path.display()))
+}
+
+/// Whether a saved file is TOML rather than JSON, going by its extension
+fn is_toml(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "toml")
+}
+
+/// Read a saved plan or scan result
+fn read_saved<T: DeserializeOwned>(path: &Path) -> Result<T> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    if is_toml(path) {
+        Ok(toml::from_str(&contents)?)
+    } else {
+        Ok(serde_json::from_str(&contents)?)
+    }
+}
+
+fn save_plan(path: &Path, plan: &CleanPlan) -> Result<()> {
+    let contents = if is_toml(path) {
+        toml::to_string_pretty(plan)?
+    } else {
+        serde_json::to_string_pretty(plan)?
+    };
+    std::fs::write(path, contents).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+fn print_diff(diff: &ScanDiff) {
+    if diff.is_empty() {
+        println!("No differences.");
+        return;
+    }
+
+    println!();
+    for entry in &diff.added {
+        print_diff_entry('+', entry);
+    }
+    for entry in &diff.removed {
+        print_diff_entry('-', entry);
+    }
+    for entry in &diff.changed {
+        print_diff_entry('~', entry);
+    }
+
+    println!();
+    println!(
+        "{} added, {} removed, {} changed, net {}",
+        diff.added.len(),
+        diff.removed.len(),
+        diff.changed.len(),
+        format_delta(diff.total_delta_bytes())
+    );
+    println!();
+}
+
+fn print_diff_entry(marker: char, entry: &DiffEntry) {
+    let kind = entry.kind.map(|k| k.display_name()).unwrap_or("Unknown");
+    println!(
+        "{} {:<58} {:<15} {:>12}",
+        marker,
+        entry.path.display(),
+        kind,
+        format_delta(entry.size_delta_bytes)
+    );
+}
+
+/// List the projects whose junk changed the most, biggest growth first
+fn print_project_growth(diff: &ScanDiff, limit: usize) {
+    let projects = diff.by_project();
+    if projects.is_empty() || limit == 0 {
+        return;
+    }
+    println!("Change per project:");
+    for (project, delta) in projects.iter().take(limit) {
+        println!("   {:>12}  {}", format_delta(*delta), project.display());
+    }
+    println!();
+}
+
+/// Format a signed byte delta with an explicit sign
+fn format_delta(delta: i64) -> String {
+    let sign = if delta < 0 { "-" } else { "+" };
+    format!("{}{}", sign, format_size(delta.unsigned_abs()))
+}
+
+fn print_stats(stats: &HistoryStats, records: &[HistoryRecord], since: Option<SystemTime>) {
+    println!();
+    let period = match since {
+        Some(since) => format!(
+            "Since {}",
+            &humantime::format_rfc3339_seconds(since).to_string()[..10]
+        ),
+        None => "All time".to_string(),
+    };
+    println!(
+        "📊 {}: {} scans, {} cleans, {} freed",
+        period,
+        stats.scans,
+        stats.cleans,
+        format_size(stats.bytes_freed)
+    );
+    if stats.scans == 0 && stats.cleans == 0 {
+        println!("   Nothing recorded yet; scans and cleans are added as you run them.");
+        println!();
+        return;
+    }
+
+    // Months as YYYY-MM, which sort chronologically as strings
+    let mut months: BTreeMap<String, u64> = BTreeMap::new();
+    for record in records {
+        if record.event != HistoryEvent::Clean || since.is_some_and(|s| record.time() < s) {
+            continue;
+        }
+        let month = humantime::format_rfc3339_seconds(record.time()).to_string()[..7].to_string();
+        *months.entry(month).or_default() += record.total_bytes();
+    }
+    if !months.is_empty() {
+        println!();
+        println!("Freed per month:");
+        for (month, bytes) in &months {
+            println!("   {}  {:>12}", month, format_size(*bytes));
+        }
+    }
+
+    if !stats.by_kind.is_empty() {
+        println!();
+        println!("Freed per kind:");
+        for kind in &stats.by_kind {
+            println!(
+                "   {:<20} {:>12}",
+                kind.kind.display_name(),
+                format_size(kind.bytes_freed)
+            );
+        }
+    }
+
+    if !stats.by_project.is_empty() {
+        println!();
+        println!(
+            "{:<60} {:>12} {:>7} {:>12}",
+            "Project", "Freed", "Cleans", "Junk now"
+        );
+        println!("{}", "-".repeat(94));
+        for project in &stats.by_project {
+            let junk_now = project
+                .junk_bytes
+                .map(format_size)
+                .unwrap_or_else(|| "-".to_string());
+            println!(
+                "{:<60} {:>12} {:>7} {:>12}",
+                project.path.display(),
+                format_size(project.bytes_freed),
+                project.cleans,
+                junk_now
+            );
+        }
+    }
+    println!();
+}
+
+/// Columns of `scan` in the CSV and Markdown formats
+const SCAN_COLUMNS: &[Column<ScanItem>] = &[
+    Column {
+        name: "path",
+        value: |item| item.path.display().to_string(),
+    },
+    Column {
+        name: "kind",
+        value: |item| item.kind.id().to_string(),
+    },
+    Column {
+        name: "kind_name",
+        value: |item| item.kind_name().to_string(),
+    },
+    Column {
+        name: "size_bytes",
+        value: |item| item.size_bytes.to_string(),
+    },
+    Column {
+        name: "file_count",
+        value: |item| item.file_count.to_string(),
+    },
+    Column {
+        name: "last_modified",
+        value: |item| {
+            item.last_modified
+                .map(|time| humantime::format_rfc3339_seconds(time).to_string())
+                .unwrap_or_default()
+        },
+    },
+    Column {
+        name: "is_active",
+        value: |item| item.is_active.to_string(),
+    },
+    Column {
+        name: "deletable",
+        value: |item| item.deletable.to_string(),
+    },
+];
+
+/// Columns of `clean` in the CSV and Markdown formats
+const CLEAN_COLUMNS: &[Column<CleanItem>] = &[
+    Column {
+        name: "path",
+        value: |item| item.path.display().to_string(),
+    },
+    Column {
+        name: "kind",
+        value: |item| item.kind.map(|kind| kind.id()).unwrap_or("").to_string(),
+    },
+    Column {
+        name: "outcome",
+        value: |item| output::variant_name(&item.outcome),
+    },
+    Column {
+        name: "bytes_freed",
+        value: |item| item.bytes_freed.to_string(),
+    },
+    Column {
+        name: "files_removed",
+        value: |item| item.files_removed.to_string(),
+    },
+    Column {
+        name: "error_kind",
+        value: |item| {
+            item.error_kind
+                .map(|kind| output::variant_name(&kind))
+                .unwrap_or_default()
+        },
+    },
+    Column {
+        name: "error",
+        value: |item| item.error.clone().unwrap_or_default(),
+    },
+];
+
+/// Columns of `doctor` in the CSV and Markdown formats
+const CACHE_COLUMNS: &[Column<GlobalCache>] = &[
+    Column {
+        name: "name",
+        value: |cache| cache.name.to_string(),
+    },
+    Column {
+        name: "path",
+        value: |cache| cache.path.display().to_string(),
+    },
+    Column {
+        name: "size_bytes",
+        value: |cache| cache.size_bytes.to_string(),
+    },
+    Column {
+        name: "file_count",
+        value: |cache| cache.file_count.to_string(),
+    },
+    Column {
+        name: "cleanup",
+        value: |cache| cache.cleanup.to_string(),
+    },
+];
+
+/// Columns of `size` in the CSV and Markdown formats
+const SIZE_COLUMNS: &[Column<DirSize>] = &[
+    Column {
+        name: "path",
+        value: |size| size.path.display().to_string(),
+    },
+    Column {
+        name: "size_bytes",
+        value: |size| size.size_bytes.to_string(),
+    },
+    Column {
+        name: "file_count",
+        value: |size| size.file_count.to_string(),
+    },
+    Column {
+        name: "unreadable",
+        value: |size| size.errors.len().to_string(),
+    },
+];
+
+/// A row of `scan --group-by`
+#[derive(Serialize)]
+struct GroupTotal {
+    group: String,
+    items: usize,
+    size_bytes: u64,
+    file_count: u64,
+}
+
+/// Columns of `scan --group-by` in the CSV and Markdown formats
+const GROUP_COLUMNS: &[Column<GroupTotal>] = &[
+    Column {
+        name: "group",
+        value: |group| group.group.clone(),
+    },
+    Column {
+        name: "items",
+        value: |group| group.items.to_string(),
+    },
+    Column {
+        name: "size_bytes",
+        value: |group| group.size_bytes.to_string(),
+    },
+    Column {
+        name: "file_count",
+        value: |group| group.file_count.to_string(),
+    },
+];
+
+/// Columns of `biggest` in the CSV and Markdown formats
+const PROJECT_COLUMNS: &[Column<ProjectJunk>] = &[
+    Column {
+        name: "project",
+        value: |project| project.project.display().to_string(),
+    },
+    Column {
+        name: "items",
+        value: |project| project.items.to_string(),
+    },
+    Column {
+        name: "size_bytes",
+        value: |project| project.size_bytes.to_string(),
+    },
+    Column {
+        name: "top_kinds",
+        value: |project| top_kinds(project),
+    },
+];
+
+/// The largest kinds of junk in `project` with their sizes, e.g. `Rust Target 2.10 GB, ...`
+fn top_kinds(project: &ProjectJunk) -> String {
+    let mut kinds: Vec<String> = project
+        .kinds
+        .iter()
+        .take(3)
+        .map(|kind| format!("{} {}", kind.kind, format_size(kind.size_bytes)))
+        .collect();
+    if project.kinds.len() > 3 {
+        kinds.push(format!("+{} more", project.kinds.len() - 3));
+    }
+    kinds.join(", ")
+}
+
+fn print_biggest_projects(projects: &[ProjectJunk]) {
+    if projects.is_empty() {
+        println!("No junk directories found.");
+        return;
+    }
+
+    println!();
+    let header = format!(
+        "{:>3}  {:<50} {:>12}  {}",
+        "#", "Project", "Junk", "Top kinds"
+    );
+    println!("{}", style::paint(header, Style::Bold));
+    println!("{}", "-".repeat(100));
+    for (rank, project) in projects.iter().enumerate() {
+        let path = project.project.display().to_string();
+        let path = if path.len() > 48 {
+            format!("...{}", &path[path.len() - 45..])
+        } else {
+            path
+        };
+        let size = format!("{:>12}", format_size(project.size_bytes));
+        println!(
+            "{:>3}  {:<50} {}  {}",
+            rank + 1,
+            path,
+            style::size(size, project.size_bytes),
+            top_kinds(project)
+        );
+    }
+    println!();
+}
+
+/// Add up the paths of `plan` per kind, as far as the plan knows them, largest first
+fn plan_kind_totals(plan: &CleanPlan) -> Vec<GroupTotal> {
+    let mut kinds: BTreeMap<&str, GroupTotal> = BTreeMap::new();
+    for path in &plan.paths {
+        let name = plan
+            .kind_of(path)
+            .map_or("Unknown", |kind| kind.display_name());
+        let (size_bytes, file_count) = plan.known_size(path).unwrap_or((0, 0));
+        let total = kinds.entry(name).or_insert_with(|| GroupTotal {
+            group: name.to_string(),
+            items: 0,
+            size_bytes: 0,
+            file_count: 0,
+        });
+        total.items += 1;
+        total.size_bytes += size_bytes;
+        total.file_count += file_count;
+    }
+    let mut groups: Vec<GroupTotal> = kinds.into_values().collect();
+    groups.sort_by_key(|group| std::cmp::Reverse(group.size_bytes));
+    groups
+}
+
+/// Add up the items of `result` per group, largest first
+fn group_totals(result: &ScanResult, by: GroupBy) -> Vec<GroupTotal> {
+    let total = |group: String, items: Vec<&ScanItem>| GroupTotal {
+        group,
+        items: items.len(),
+        size_bytes: items.iter().map(|item| item.size_bytes).sum(),
+        file_count: items.iter().map(|item| item.file_count).sum(),
+    };
+    let mut groups: Vec<GroupTotal> = match by {
+        GroupBy::Project => result
+            .by_project()
+            .into_iter()
+            .map(|(project, items)| total(project.display().to_string(), items))
+            .collect(),
+        GroupBy::Kind => result
+            .by_kind()
+            .into_iter()
+            .map(|(kind, items)| total(kind.to_string(), items))
+            .collect(),
+    };
+    // Stable, so equal sizes stay in name order
+    groups.sort_by_key(|group| std::cmp::Reverse(group.size_bytes));
+    groups
+}
+
+/// A row of `types`
+#[derive(Serialize)]
+struct KindInfo {
+    id: &'static str,
+    name: &'static str,
+    aliases: &'static [&'static str],
+    patterns: &'static [&'static str],
+    markers: &'static [&'static str],
+    language: Option<&'static str>,
+    safety: KindSafety,
+    rebuild_hint: &'static str,
+}
+
+impl KindInfo {
+    fn new(kind: JunkKind) -> Self {
+        Self {
+            id: kind.id(),
+            name: kind.display_name(),
+            aliases: kind.aliases(),
+            patterns: kind.patterns(),
+            markers: kind.markers(),
+            language: kind.language(),
+            safety: kind.safety(),
+            rebuild_hint: kind.rebuild_hint(),
+        }
+    }
+}
+
+/// Columns of `types` in the CSV and Markdown formats
+const TYPE_COLUMNS: &[Column<KindInfo>] = &[
+    Column {
+        name: "id",
+        value: |kind| kind.id.to_string(),
+    },
+    Column {
+        name: "name",
+        value: |kind| kind.name.to_string(),
+    },
+    Column {
+        name: "aliases",
+        value: |kind| kind.aliases.join(" "),
+    },
+    Column {
+        name: "patterns",
+        value: |kind| kind.patterns.join(" "),
+    },
+    Column {
+        name: "markers",
+        value: |kind| kind.markers.join(" "),
+    },
+    Column {
+        name: "language",
+        value: |kind| kind.language.unwrap_or_default().to_string(),
+    },
+    Column {
+        name: "safety",
+        value: |kind| output::variant_name(&kind.safety),
+    },
+    Column {
+        name: "rebuild_hint",
+        value: |kind| kind.rebuild_hint.to_string(),
+    },
+];
+
+/// The name `format` is given on the command line
+fn format_name(format: Format) -> String {
+    format
+        .to_possible_value()
+        .map(|value| value.get_name().to_string())
+        .unwrap_or_default()
+}
+
+fn print_group_totals(groups: &[GroupTotal], by: GroupBy) {
+    if groups.is_empty() {
+        println!("No junk directories found.");
+        return;
+    }
+
+    let (heading, plural) = match by {
+        GroupBy::Project => ("Project", "projects"),
+        GroupBy::Kind => ("Type", "types"),
+    };
+    println!();
+    let header = format!(
+        "{:<60} {:>8} {:>12} {:>10}",
+        heading, "Dirs", "Size", "Files"
+    );
+    println!("{}", style::paint(header, Style::Bold));
+    println!("{}", "-".repeat(93));
+    for group in groups {
+        let name = if group.group.len() > 58 {
+            format!("...{}", &group.group[group.group.len() - 55..])
+        } else {
+            group.group.clone()
+        };
+        let size = format!("{:>12}", format_size(group.size_bytes));
+        println!(
+            "{:<60} {:>8} {} {:>10}",
+            name,
+            group.items,
+            style::size(size, group.size_bytes),
+            group.file_count
+        );
+    }
+    println!("{}", "-".repeat(93));
+    let size: u64 = groups.iter().map(|group| group.size_bytes).sum();
+    let items: usize = groups.iter().map(|group| group.items).sum();
+    let summary = format!(
+        "Total: {} {}, {} directories, {}",
+        groups.len(),
+        plural,
+        items,
+        format_size(size)
+    );
+    println!("{}", style::paint(summary, Style::Green));
+    println!();
+}
+
+/// What a clean archived to `archive_dirs` or the configured archive directory, or moved to the trash
+fn find_restorable(mut archive_dirs: Vec<PathBuf>) -> Result<Vec<Restorable>> {
+    if let DeleteMode::Archive { dir } = load_config()?.delete_mode {
+        archive_dirs.push(dir);
+    }
+    // A history that can't be read only hides trashed custom pattern matches
+    let records = default_history_path()
+        .and_then(|path| load_history(&path).ok())
+        .unwrap_or_default();
+    let cleaned: HashSet<PathBuf> = records
+        .into_iter()
+        .filter(|record| record.event == HistoryEvent::Clean)
+        .flat_map(|record| record.items)
+        .map(|item| item.path)
+        .collect();
+    list_restorable(&archive_dirs, &cleaned).context("Failed to list what can be restored")
+}
+
+fn print_restorable(items: &[Restorable]) {
+    if items.is_empty() {
+        println!(
+            "Nothing to restore. Directories cleaned with --archive-to or --trash show up here."
+        );
+        return;
+    }
+
+    println!();
+    println!("{:<20} {:<8} {:>12}   Path", "Deleted", "From", "Size");
+    println!("{}", "-".repeat(100));
+    for item in items {
+        let deleted = format_age(UNIX_EPOCH + Duration::from_secs(item.deleted_at));
+        let size = item.size_bytes.map(format_size).unwrap_or_default();
+        println!(
+            "{:<20} {:<8} {:>12}   {}",
+            deleted,
+            output::variant_name(&item.source),
+            size,
+            item.original_path.display()
+        );
+    }
+    println!();
+    println!(
+        "Run `devjunk restore <PATH>` to put one back, or `devjunk restore --last` for the newest."
+    );
+    println!();
+}
+
+fn print_schedules(schedules: &[ScheduleStatus]) {
+    if schedules.is_empty() {
+        println!("No schedules. Add one with `devjunk schedule install -- <PATHS>`.");
+        return;
+    }
+
+    println!();
+    println!("{:<16} {:<18} {:<28} Command", "Name", "When", "Job");
+    println!("{}", "-".repeat(100));
+    for status in schedules {
+        let schedule = &status.schedule;
+        let when = format!(
+            "{} {:02}:{:02}",
+            output::variant_name(&schedule.frequency),
+            schedule.hour,
+            schedule.minute
+        );
+        let job = if status.installed {
+            status.job.clone()
+        } else {
+            format!("{} (missing)", status.job)
+        };
+        println!(
+            "{:<16} {:<18} {:<28} {} {}",
+            schedule.name,
+            when,
+            job,
+            schedule.program.display(),
+            schedule.args.join(" ")
+        );
+    }
+    println!();
+    if schedules.iter().any(|status| !status.installed) {
+        println!("Jobs marked missing were removed from the scheduler; install them again or uninstall them.");
+        println!();
+    }
+}
+
+fn print_caches(caches: &[GlobalCache]) {
+    if caches.is_empty() {
+        println!("No package manager or build tool caches found.");
+        return;
+    }
+
+    println!();
+    println!("{:<20} {:>12}   Path", "Cache", "Size");
+    println!("{}", "-".repeat(100));
+    for cache in caches {
+        println!(
+            "{:<20} {:>12}   {}",
+            cache.name,
+            format_size(cache.size_bytes),
+            cache.path.display()
+        );
+        println!("{:<20} {:>12}   Clean up with: {}", "", "", cache.cleanup);
+    }
+    println!("{}", "-".repeat(100));
+    let total: u64 = caches.iter().map(|cache| cache.size_bytes).sum();
+    println!("Total: {} caches, {}", caches.len(), format_size(total));
+    println!();
+    println!("These are shared by all your projects, so `scan` and `clean` leave them alone.");
+    println!();
+}
+
+fn print_junk_types() {
+    println!();
+    println!("Supported junk directory types:");
+    println!();
+    println!(
+        "{:<20} {:<11} {:<8} {:<30} Rebuild",
+        "Type", "Language", "Safety", "Patterns"
+    );
+    println!("{}", "-".repeat(112));
+
+    for kind in JunkKind::all() {
+        let patterns = kind.patterns().join(", ");
+        let safety = output::variant_name(&kind.safety());
+        println!(
+            "{:<20} {:<11} {:<8} {:<30} {}",
+            kind.display_name(),
+            kind.language().unwrap_or("Any"),
+            safety,
+            patterns,
+            kind.rebuild_hint()
+        );
+    }
+
+    println!();
 }
@@ -0,0 +1,132 @@
+//! Output formats shared by the commands that print a list of items
+//!
+//! Each command keeps its own table for reading in a terminal. The other
+//! formats are generated from the command's result and a list of columns.
+//! Sizes are formatted here as well, in the units `--si` picks.
+
+use anyhow::Result;
+use clap::ValueEnum;
+use devjunk_core::SizeUnits;
+use serde::Serialize;
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static SI_UNITS: AtomicBool = AtomicBool::new(false);
+
+/// How a command prints its results
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// Aligned columns for reading in a terminal
+    #[default]
+    Table,
+    /// One pretty-printed JSON document
+    Json,
+    /// One JSON object per item and line, for streaming into other tools
+    Ndjson,
+    /// Comma-separated values with a header row
+    Csv,
+    /// One YAML document
+    Yaml,
+    /// A Markdown table
+    Markdown,
+}
+
+/// A column of the CSV and Markdown formats
+pub struct Column<T> {
+    /// Header row name
+    pub name: &'static str,
+    /// The cell of an item
+    pub value: fn(&T) -> String,
+}
+
+/// Print `items` in `format`, or call `table` for the `Table` format
+///
+/// JSON and YAML print the whole `document`, NDJSON prints each item on a line
+/// of its own, and CSV and Markdown print the `columns` of each item.
+pub fn print<D, T>(
+    format: Format,
+    document: &D,
+    items: &[T],
+    columns: &[Column<T>],
+    table: impl FnOnce(),
+) -> Result<()>
+where
+    D: Serialize + ?Sized,
+    T: Serialize,
+{
+    let mut out = io::stdout().lock();
+    match format {
+        Format::Table => table(),
+        Format::Json => {
+            serde_json::to_writer_pretty(&mut out, document)?;
+            writeln!(out)?;
+        }
+        Format::Ndjson => {
+            for item in items {
+                serde_json::to_writer(&mut out, item)?;
+                writeln!(out)?;
+            }
+        }
+        Format::Csv => {
+            let mut csv = csv::Writer::from_writer(out);
+            csv.write_record(columns.iter().map(|column| column.name))?;
+            for item in items {
+                csv.write_record(columns.iter().map(|column| (column.value)(item)))?;
+            }
+            csv.flush()?;
+        }
+        Format::Yaml => serde_yaml::to_writer(&mut out, document)?,
+        Format::Markdown => {
+            let row = |cells: Vec<String>| format!("| {} |", cells.join(" | "));
+            let names = columns.iter().map(|column| column.name.to_string());
+            writeln!(out, "{}", row(names.collect()))?;
+            writeln!(out, "{}", row(vec!["---".to_string(); columns.len()]))?;
+            for item in items {
+                let cells = columns
+                    .iter()
+                    .map(|column| markdown_escape(&(column.value)(item)));
+                writeln!(out, "{}", row(cells.collect()))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Show sizes in powers of 1000 for `--si`
+pub fn init_units(si: bool) {
+    SI_UNITS.store(si, Ordering::Relaxed);
+}
+
+/// `bytes` in the units chosen for this run, e.g. `1.50 GB`
+pub fn format_size(bytes: u64) -> String {
+    let units = if SI_UNITS.load(Ordering::Relaxed) {
+        SizeUnits::Si
+    } else {
+        SizeUnits::Binary
+    };
+    devjunk_core::format_size(bytes, units)
+}
+
+/// Where a command's progress messages go in `format`
+///
+/// Stdout for the table, stderr otherwise, so they never end up in the middle
+/// of output meant for another program.
+pub fn messages(format: Format) -> Box<dyn Write> {
+    match format {
+        Format::Table => Box::new(io::stdout()),
+        _ => Box::new(io::stderr()),
+    }
+}
+
+/// The name a unit enum variant serializes to, e.g. `in_use`
+pub fn variant_name<T: Serialize>(value: &T) -> String {
+    serde_json::to_value(value)
+        .ok()
+        .and_then(|value| value.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+/// Keep a cell from ending its table cell or row early
+fn markdown_escape(cell: &str) -> String {
+    cell.replace('|', "\\|").replace('\n', " ")
+}
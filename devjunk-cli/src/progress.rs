@@ -0,0 +1,67 @@
+//! Progress events as JSON lines on stderr, for `--progress-json`
+//!
+//! Each line is one object tagged with the `phase` it belongs to, `scan` or
+//! `clean`, plus the fields of the core's progress snapshot in camelCase, the
+//! same as the GUI's progress events. Scan events come at most every 100 ms,
+//! since a scan reports every directory it visits; clean events come for each
+//! stage of each path.
+
+use devjunk_core::{CleanProgress, ScanProgress};
+use serde::Serialize;
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+const SCAN_INTERVAL: Duration = Duration::from_millis(100);
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static LAST_SCAN_EVENT: Mutex<Option<Instant>> = Mutex::new(None);
+
+#[derive(Serialize)]
+#[serde(tag = "phase", rename_all = "snake_case")]
+enum Event<'a> {
+    Scan(&'a ScanProgress),
+    Clean(&'a CleanProgress),
+}
+
+/// Turn the events on for `--progress-json`
+pub fn init(json: bool) {
+    ENABLED.store(json, Ordering::Relaxed);
+}
+
+/// Whether progress goes out as JSON lines instead of a spinner
+pub fn is_json() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Report how far a scan got, unless the last event was too recent
+pub fn scan(progress: ScanProgress) {
+    if !is_json() {
+        return;
+    }
+    {
+        let mut last = LAST_SCAN_EVENT.lock().unwrap_or_else(|e| e.into_inner());
+        if last.is_some_and(|at| at.elapsed() < SCAN_INTERVAL) {
+            return;
+        }
+        *last = Some(Instant::now());
+    }
+    emit(&Event::Scan(&progress));
+}
+
+/// Report a path of a clean reaching the next stage
+pub fn clean(progress: CleanProgress) {
+    if is_json() {
+        emit(&Event::Clean(&progress));
+    }
+}
+
+fn emit(event: &Event) {
+    let Ok(mut line) = serde_json::to_vec(event) else {
+        return;
+    };
+    line.push(b'\n');
+    // A reader that went away must not fail the scan or clean itself
+    let _ = io::stderr().lock().write_all(&line);
+}
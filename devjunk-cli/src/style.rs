@@ -0,0 +1,89 @@
+//! Colors for the terminal output
+//!
+//! Colors are only used when stdout is a terminal, and never with `--no-color`
+//! or when `NO_COLOR` is set. Callers pad text to its column width first and
+//! paint it afterwards, since escape codes would throw off the padding.
+
+use devjunk_core::JunkKind;
+use std::env;
+use std::fmt::Display;
+use std::io::{self, IsTerminal};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Directories at least this large are highlighted
+pub const HUGE_BYTES: u64 = 1024 * 1024 * 1024;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Turn colors on if stdout is a terminal and nothing asks for plain output
+pub fn init(no_color: bool) {
+    let enabled = allowed(no_color) && io::stdout().is_terminal();
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether neither `--no-color` nor `NO_COLOR` asks for plain output
+pub fn allowed(no_color: bool) -> bool {
+    !no_color && env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+}
+
+/// A text color or emphasis
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    Bold,
+}
+
+impl Style {
+    fn code(self) -> &'static str {
+        match self {
+            Self::Red => "31",
+            Self::Green => "32",
+            Self::Yellow => "33",
+            Self::Blue => "34",
+            Self::Magenta => "35",
+            Self::Cyan => "36",
+            Self::Bold => "1",
+        }
+    }
+}
+
+/// `text` in `style`, or unchanged when colors are off
+pub fn paint(text: impl Display, style: Style) -> String {
+    if ENABLED.load(Ordering::Relaxed) {
+        format!("\x1b[{}m{}\x1b[0m", style.code(), text)
+    } else {
+        text.to_string()
+    }
+}
+
+/// A junk kind column, colored by ecosystem; generic build outputs stay plain
+pub fn kind(text: String, kind: JunkKind) -> String {
+    let style = match kind {
+        JunkKind::PythonVenv
+        | JunkKind::PythonTox
+        | JunkKind::PythonCache
+        | JunkKind::MypyCache
+        | JunkKind::PytestCache => Style::Blue,
+        JunkKind::NodeModules | JunkKind::NextDir | JunkKind::NuxtDir => Style::Yellow,
+        JunkKind::RustTarget => Style::Magenta,
+        JunkKind::GoVendor => Style::Cyan,
+        JunkKind::BuildDir | JunkKind::DistDir | JunkKind::OutDir | JunkKind::Custom => {
+            return text
+        }
+    };
+    paint(text, style)
+}
+
+/// A size column, in red for huge directories
+pub fn size(text: String, bytes: u64) -> String {
+    if bytes >= HUGE_BYTES {
+        paint(text, Style::Red)
+    } else {
+        text
+    }
+}
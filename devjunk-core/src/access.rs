@@ -0,0 +1,62 @@
+//! Whether the OS lets devjunk read everything it scans
+//!
+//! macOS keeps folders like Mail and Safari in the home directory from apps
+//! without Full Disk Access. Reading them fails with "permission denied", so a
+//! scan below them finds nothing rather than reporting an empty directory.
+//! Trying to list one of them tells whether the app has that access.
+
+use crate::filesystem::{FileSystem, StdFileSystem};
+use std::io;
+use std::path::Path;
+
+/// Folders of the home directory only readable with Full Disk Access
+const PROTECTED: &[&str] = &[
+    "Library/Mail",
+    "Library/Safari",
+    "Library/Messages",
+    "Library/Application Support/com.apple.TCC",
+];
+
+/// Whether this process has Full Disk Access
+///
+/// None outside macOS, which has no such setting, or when none of the
+/// protected folders exist to try.
+pub fn full_disk_access() -> Option<bool> {
+    if !cfg!(target_os = "macos") {
+        return None;
+    }
+    full_disk_access_with_fs(&dirs::home_dir()?, &StdFileSystem)
+}
+
+/// Try the protected folders below `home` on the given filesystem, like [`full_disk_access`]
+pub fn full_disk_access_with_fs(home: &Path, fs: &dyn FileSystem) -> Option<bool> {
+    let mut tried = false;
+    for dir in PROTECTED {
+        match fs.read_dir(&home.join(dir)) {
+            Ok(_) => return Some(true),
+            Err(e) if e.kind() == io::ErrorKind::PermissionDenied => tried = true,
+            Err(_) => {}
+        }
+    }
+    tried.then_some(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filesystem::MemoryFileSystem;
+
+    #[test]
+    fn test_denied_protected_folder_means_no_full_disk_access() {
+        let home = Path::new("/Users/me");
+        let fs = MemoryFileSystem::new();
+        assert_eq!(full_disk_access_with_fs(home, &fs), None);
+
+        fs.add_file("/Users/me/Library/Mail/V10/inbox.mbox", 1)
+            .deny_read("/Users/me/Library/Mail");
+        assert_eq!(full_disk_access_with_fs(home, &fs), Some(false));
+
+        fs.add_dir("/Users/me/Library/Safari");
+        assert_eq!(full_disk_access_with_fs(home, &fs), Some(true));
+    }
+}
@@ -0,0 +1,300 @@
+//! Archiving directories before deletion so a clean can be undone
+//!
+//! Each directory is packed into `<archive_dir>/<id>.tar.zst` and recorded in
+//! `<archive_dir>/manifest.json`. Archives are read from and restored to the
+//! real filesystem.
+
+use crate::error::{DevJunkError, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const MANIFEST_FILE: &str = "manifest.json";
+const ARCHIVE_EXTENSION: &str = "tar.zst";
+
+/// A directory that was archived before being deleted
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchiveEntry {
+    /// Unique identifier within the archive directory
+    pub id: String,
+    /// Where the directory was before it was deleted
+    pub original_path: PathBuf,
+    /// Archive file name, relative to the archive directory
+    pub archive_file: PathBuf,
+    /// Total size of the files that were archived
+    pub size_bytes: u64,
+    /// Size of the compressed archive
+    pub compressed_bytes: u64,
+    /// When the directory was archived (seconds since the Unix epoch)
+    pub archived_at: u64,
+    /// Whether a single file was archived rather than a directory
+    #[serde(default)]
+    pub is_file: bool,
+}
+
+/// The list of archives kept in an archive directory
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Manifest {
+    entries: Vec<ArchiveEntry>,
+}
+
+impl Manifest {
+    fn load(archive_dir: &Path) -> Result<Self> {
+        let path = archive_dir.join(MANIFEST_FILE);
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e.into()),
+        };
+        serde_json::from_str(&contents)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e).into())
+    }
+
+    /// Write the manifest through a temporary file so a crash never leaves it half written
+    fn save(&self, archive_dir: &Path) -> Result<()> {
+        let path = archive_dir.join(MANIFEST_FILE);
+        let tmp = archive_dir.join(format!("{}.tmp", MANIFEST_FILE));
+        let json = serde_json::to_vec_pretty(self).map_err(io::Error::other)?;
+        fs::write(&tmp, json)?;
+        fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+}
+
+/// List the archives in `archive_dir`, oldest first
+pub fn list_archives(archive_dir: &Path) -> Result<Vec<ArchiveEntry>> {
+    Ok(Manifest::load(archive_dir)?.entries)
+}
+
+/// Pack `path` into the archive directory and record it in the manifest
+///
+/// `path` is usually a directory, but may be a single file when a directory
+/// with keep globs is cleaned. `size_bytes` is its already-calculated size.
+pub(crate) fn archive_directory(
+    path: &Path,
+    archive_dir: &Path,
+    size_bytes: u64,
+) -> Result<ArchiveEntry> {
+    fs::create_dir_all(archive_dir)?;
+    let mut manifest = Manifest::load(archive_dir)?;
+
+    let archived_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let id = unique_id(path, archived_at, &manifest);
+    let archive_file = PathBuf::from(format!("{}.{}", id, ARCHIVE_EXTENSION));
+
+    // Write under a temporary name so an interrupted archive is never mistaken for a complete one
+    let partial = archive_dir.join(format!("{}.partial", archive_file.display()));
+    let is_file = !fs::symlink_metadata(path)?.is_dir();
+    if let Err(e) = write_archive(path, &partial, is_file) {
+        let _ = fs::remove_file(&partial);
+        return Err(e.into());
+    }
+    let final_path = archive_dir.join(&archive_file);
+    fs::rename(&partial, &final_path)?;
+
+    let entry = ArchiveEntry {
+        id,
+        // Restores may run from another directory than the clean did
+        original_path: std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf()),
+        archive_file,
+        size_bytes,
+        compressed_bytes: fs::metadata(&final_path)?.len(),
+        archived_at,
+        is_file,
+    };
+    manifest.entries.push(entry.clone());
+    manifest.save(archive_dir)?;
+
+    Ok(entry)
+}
+
+/// Restore an archived directory to its original location
+///
+/// Fails without touching anything if something already exists there. On
+/// success the archive is removed from the archive directory.
+///
+/// # Returns
+/// The path the directory was restored to
+pub fn restore_archive(archive_dir: &Path, id: &str) -> Result<PathBuf> {
+    let mut manifest = Manifest::load(archive_dir)?;
+    let index = manifest
+        .entries
+        .iter()
+        .position(|e| e.id == id)
+        .ok_or_else(|| DevJunkError::ArchiveNotFound(id.to_string()))?;
+    let entry = &manifest.entries[index];
+    let target = entry.original_path.clone();
+
+    if fs::symlink_metadata(&target).is_ok() {
+        return Err(DevJunkError::RestoreTargetExists(target));
+    }
+
+    let archive_path = archive_dir.join(&entry.archive_file);
+    let staging =
+        staging_path(&target).ok_or_else(|| DevJunkError::NotADirectory(target.clone()))?;
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    // Unpack next to the target and move it into place in one step
+    if let Err(e) = unpack_archive(&archive_path, &staging) {
+        let _ = fs::remove_dir_all(&staging);
+        return Err(e.into());
+    }
+    if entry.is_file {
+        // A file is archived under its own name, inside the staging directory
+        let name = target.file_name().unwrap_or_default();
+        fs::rename(staging.join(name), &target)?;
+        let _ = fs::remove_dir_all(&staging);
+    } else {
+        fs::rename(&staging, &target)?;
+    }
+
+    manifest.entries.remove(index);
+    manifest.save(archive_dir)?;
+    let _ = fs::remove_file(&archive_path);
+
+    Ok(target)
+}
+
+fn write_archive(source: &Path, dest: &Path, is_file: bool) -> io::Result<()> {
+    let file = BufWriter::new(File::create(dest)?);
+    let encoder = zstd::Encoder::new(file, 0)?;
+
+    let mut builder = tar::Builder::new(encoder);
+    // Keep links as links: following them could pull in (and later restore) data from outside the directory
+    builder.follow_symlinks(false);
+    match source.file_name() {
+        Some(name) if is_file => builder.append_path_with_name(source, name)?,
+        _ => builder.append_dir_all(".", source)?,
+    }
+
+    let mut file = builder.into_inner()?.finish()?;
+    file.flush()?;
+    file.get_ref().sync_all()
+}
+
+fn unpack_archive(archive: &Path, dest: &Path) -> io::Result<()> {
+    let decoder = zstd::Decoder::new(BufReader::new(File::open(archive)?))?;
+    let mut archive = tar::Archive::new(decoder);
+    archive.set_preserve_permissions(true);
+    archive.set_preserve_mtime(true);
+    fs::create_dir(dest)?;
+    archive.unpack(dest)
+}
+
+/// A sibling of `target` to unpack into before renaming
+fn staging_path(target: &Path) -> Option<PathBuf> {
+    let mut name = target.file_name()?.to_os_string();
+    name.push(".devjunk-restore");
+    Some(target.with_file_name(name))
+}
+
+/// Build an id from the directory name and time, unique within the manifest
+fn unique_id(path: &Path, archived_at: u64, manifest: &Manifest) -> String {
+    let name: String = path
+        .file_name()
+        .map(|n| n.to_string_lossy())
+        .unwrap_or_default()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    let base = format!("{}-{}", archived_at, name);
+
+    let taken = |id: &str| manifest.entries.iter().any(|e| e.id == id);
+    let mut id = base.clone();
+    let mut n = 1;
+    while taken(&id) {
+        n += 1;
+        id = format!("{}-{}", base, n);
+    }
+    id
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn make_dist(root: &Path) -> PathBuf {
+        let dist = root.join("project").join("dist");
+        fs::create_dir_all(dist.join("assets")).unwrap();
+        fs::write(dist.join("index.html"), b"<html></html>").unwrap();
+        fs::write(dist.join("assets").join("app.js"), b"console.log(1)").unwrap();
+        dist
+    }
+
+    #[test]
+    fn test_archive_and_restore_round_trip() {
+        let temp = TempDir::new().unwrap();
+        let dist = make_dist(temp.path());
+        let archives = temp.path().join("archives");
+
+        let entry = archive_directory(&dist, &archives, 27).unwrap();
+        assert_eq!(entry.original_path, dist);
+        assert!(archives.join(&entry.archive_file).is_file());
+        assert_eq!(list_archives(&archives).unwrap(), vec![entry.clone()]);
+
+        fs::remove_dir_all(&dist).unwrap();
+        assert_eq!(restore_archive(&archives, &entry.id).unwrap(), dist);
+
+        assert_eq!(
+            fs::read_to_string(dist.join("assets").join("app.js")).unwrap(),
+            "console.log(1)"
+        );
+        assert!(list_archives(&archives).unwrap().is_empty());
+        assert!(!archives.join(&entry.archive_file).exists());
+    }
+
+    #[test]
+    fn test_archive_and_restore_a_single_file() {
+        let temp = TempDir::new().unwrap();
+        let bundle = make_dist(temp.path()).join("index.html");
+        let archives = temp.path().join("archives");
+
+        let entry = archive_directory(&bundle, &archives, 13).unwrap();
+        assert!(entry.is_file);
+
+        fs::remove_file(&bundle).unwrap();
+        assert_eq!(restore_archive(&archives, &entry.id).unwrap(), bundle);
+        assert_eq!(fs::read_to_string(&bundle).unwrap(), "<html></html>");
+    }
+
+    #[test]
+    fn test_restore_refuses_to_overwrite() {
+        let temp = TempDir::new().unwrap();
+        let dist = make_dist(temp.path());
+        let archives = temp.path().join("archives");
+
+        let entry = archive_directory(&dist, &archives, 0).unwrap();
+
+        assert!(matches!(
+            restore_archive(&archives, &entry.id),
+            Err(DevJunkError::RestoreTargetExists(_))
+        ));
+        assert_eq!(list_archives(&archives).unwrap().len(), 1);
+        assert!(matches!(
+            restore_archive(&archives, "missing"),
+            Err(DevJunkError::ArchiveNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_archive_ids_are_unique() {
+        let temp = TempDir::new().unwrap();
+        let dist = make_dist(temp.path());
+        let archives = temp.path().join("archives");
+
+        let first = archive_directory(&dist, &archives, 0).unwrap();
+        let second = archive_directory(&dist, &archives, 0).unwrap();
+
+        assert_ne!(first.id, second.id);
+        assert_ne!(first.archive_file, second.archive_file);
+        assert_eq!(list_archives(&archives).unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,188 @@
+//! Well-known caches that package managers and build tools keep for the whole user
+//!
+//! These live outside any project, so a scan never finds them. They are only
+//! measured, never deleted: each is reported with the tool's own cleanup
+//! command, since the tool knows what is still in use.
+
+use crate::delete::dir_stats;
+use crate::filesystem::{FileKind, FileSystem, StdFileSystem};
+use rayon::prelude::*;
+use serde::Serialize;
+use std::env;
+use std::path::{Path, PathBuf};
+
+/// A user-level cache and how much space it takes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GlobalCache {
+    /// Name of the cache, e.g. `npm cache`
+    pub name: &'static str,
+    /// Directory holding the cache
+    pub path: PathBuf,
+    /// Total size in bytes
+    pub size_bytes: u64,
+    /// Number of files
+    pub file_count: u64,
+    /// Command that has the tool clean its own cache
+    pub cleanup: &'static str,
+}
+
+/// Where one of the caches lives
+struct CacheLocation {
+    name: &'static str,
+    path: PathBuf,
+    cleanup: &'static str,
+}
+
+/// Measure the well-known caches that exist for the current user, largest first
+///
+/// Directories the current user can't read, such as Docker's data on Linux
+/// without root, count as smaller than they are.
+pub fn global_caches() -> Vec<GlobalCache> {
+    let Some(home) = dirs::home_dir() else {
+        return Vec::new();
+    };
+    let cache_dir = dirs::cache_dir().unwrap_or_else(|| home.join(".cache"));
+    let env_path = |var: &str| {
+        env::var_os(var)
+            .filter(|value| !value.is_empty())
+            .map(PathBuf::from)
+    };
+    let locations = cache_locations(&home, &cache_dir, &env_path);
+    measure(&StdFileSystem, locations)
+}
+
+/// The cache directories of each tool, honouring the variables that move them
+///
+/// `cache_dir` is the platform's user cache directory (`~/.cache`,
+/// `~/Library/Caches` or `%LOCALAPPDATA%`).
+fn cache_locations(
+    home: &Path,
+    cache_dir: &Path,
+    env_path: &dyn Fn(&str) -> Option<PathBuf>,
+) -> Vec<CacheLocation> {
+    let cargo_home = env_path("CARGO_HOME").unwrap_or_else(|| home.join(".cargo"));
+    let npm = env_path("npm_config_cache").unwrap_or_else(|| {
+        if cfg!(windows) {
+            cache_dir.join("npm-cache")
+        } else {
+            home.join(".npm")
+        }
+    });
+    let pip = env_path("PIP_CACHE_DIR").unwrap_or_else(|| {
+        if cfg!(windows) {
+            cache_dir.join("pip").join("Cache")
+        } else {
+            cache_dir.join("pip")
+        }
+    });
+    let gradle = env_path("GRADLE_USER_HOME").unwrap_or_else(|| home.join(".gradle"));
+    let pub_cache = env_path("PUB_CACHE").unwrap_or_else(|| {
+        if cfg!(windows) {
+            cache_dir.join("Pub").join("Cache")
+        } else {
+            home.join(".pub-cache")
+        }
+    });
+    let docker = if cfg!(target_os = "macos") {
+        home.join("Library/Containers/com.docker.docker/Data")
+    } else if cfg!(windows) {
+        cache_dir.join("Docker").join("wsl")
+    } else {
+        PathBuf::from("/var/lib/docker")
+    };
+
+    vec![
+        CacheLocation {
+            name: "Cargo registry",
+            path: cargo_home.join("registry"),
+            cleanup: "cargo cache --autoclean",
+        },
+        CacheLocation {
+            name: "npm cache",
+            path: npm,
+            cleanup: "npm cache clean --force",
+        },
+        CacheLocation {
+            name: "pip cache",
+            path: pip,
+            cleanup: "pip cache purge",
+        },
+        CacheLocation {
+            name: "Docker",
+            path: docker,
+            cleanup: "docker system prune",
+        },
+        CacheLocation {
+            name: "Gradle caches",
+            path: gradle.join("caches"),
+            cleanup: "gradle --stop, then delete the directory",
+        },
+        CacheLocation {
+            name: "Dart pub cache",
+            path: pub_cache,
+            cleanup: "dart pub cache clean",
+        },
+    ]
+}
+
+/// Measure the locations that exist, largest first
+fn measure(fs: &dyn FileSystem, locations: Vec<CacheLocation>) -> Vec<GlobalCache> {
+    let mut caches: Vec<GlobalCache> = locations
+        .into_par_iter()
+        .filter(|location| {
+            fs.metadata(&location.path)
+                .is_ok_and(|m| m.kind == FileKind::Dir)
+        })
+        .map(|location| {
+            let (size_bytes, file_count) = dir_stats(fs, &location.path);
+            GlobalCache {
+                name: location.name,
+                path: location.path,
+                size_bytes,
+                file_count,
+                cleanup: location.cleanup,
+            }
+        })
+        .collect();
+    caches.sort_by_key(|cache| std::cmp::Reverse(cache.size_bytes));
+    caches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filesystem::MemoryFileSystem;
+
+    #[test]
+    fn test_cache_locations_honour_overrides() {
+        let env_path = |var: &str| (var == "CARGO_HOME").then(|| PathBuf::from("/opt/cargo"));
+        let locations = cache_locations(Path::new("/h"), Path::new("/h/.cache"), &env_path);
+        let path = |name: &str| {
+            locations
+                .iter()
+                .find(|location| location.name == name)
+                .map(|location| location.path.clone())
+                .unwrap()
+        };
+
+        assert_eq!(path("Cargo registry"), PathBuf::from("/opt/cargo/registry"));
+        assert_eq!(path("Gradle caches"), PathBuf::from("/h/.gradle/caches"));
+    }
+
+    #[test]
+    fn test_measure_skips_missing_caches() {
+        let fs = MemoryFileSystem::new();
+        fs.add_file("/h/.gradle/caches/modules-2/a.jar", 10)
+            .add_file("/h/.cargo/registry/cache/b.crate", 30)
+            .add_file("/h/.cargo/registry/index/c", 5);
+        let env_path = |_: &str| None;
+        let locations = cache_locations(Path::new("/h"), Path::new("/h/.cache"), &env_path);
+
+        let caches = measure(&fs, locations);
+        let found: Vec<_> = caches
+            .iter()
+            .map(|cache| (cache.name, cache.size_bytes, cache.file_count))
+            .collect();
+        assert_eq!(found, [("Cargo registry", 35, 2), ("Gradle caches", 10, 1)]);
+    }
+}
@@ -0,0 +1,106 @@
+//! Resolution of shared Cargo target directories
+//!
+//! When `CARGO_TARGET_DIR` or `build.target-dir` is set globally, projects
+//! have no `target` directory of their own and the build output lives in a
+//! single shared location instead.
+
+use serde::Deserialize;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The parts of `.cargo/config.toml` we care about
+#[derive(Debug, Default, Deserialize)]
+struct CargoConfig {
+    build: Option<BuildConfig>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct BuildConfig {
+    target_dir: Option<PathBuf>,
+}
+
+/// Find shared Cargo target directories configured for the current user
+///
+/// Checks, in order, `CARGO_TARGET_DIR`, `CARGO_BUILD_TARGET_DIR`, and
+/// `build.target-dir` in `$CARGO_HOME/config.toml`. Only existing
+/// directories are returned, without duplicates.
+pub fn shared_target_dirs() -> Vec<PathBuf> {
+    let cwd = env::current_dir().unwrap_or_default();
+    let mut dirs = Vec::new();
+
+    for var in ["CARGO_TARGET_DIR", "CARGO_BUILD_TARGET_DIR"] {
+        if let Some(value) = env::var_os(var).filter(|v| !v.is_empty()) {
+            dirs.push(cwd.join(value));
+        }
+    }
+
+    if let Some(cargo_home) = cargo_home() {
+        dirs.extend(config_target_dir(&cargo_home));
+    }
+
+    let mut result: Vec<PathBuf> = Vec::new();
+    for dir in dirs {
+        if dir.is_dir() && !result.contains(&dir) {
+            result.push(dir);
+        }
+    }
+    result
+}
+
+/// Locate `$CARGO_HOME`, defaulting to `~/.cargo`
+fn cargo_home() -> Option<PathBuf> {
+    env::var_os("CARGO_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| dirs::home_dir().map(|home| home.join(".cargo")))
+}
+
+/// Read `build.target-dir` from the config file in a `.cargo` directory
+///
+/// Relative paths are resolved against the parent of the `.cargo`
+/// directory, matching Cargo's own behaviour.
+fn config_target_dir(cargo_dir: &Path) -> Option<PathBuf> {
+    // Cargo still honours the extension-less legacy name
+    let contents = ["config.toml", "config"]
+        .iter()
+        .find_map(|name| fs::read_to_string(cargo_dir.join(name)).ok())?;
+
+    let config: CargoConfig = toml::from_str(&contents).ok()?;
+    let target_dir = config.build?.target_dir?;
+
+    let base = cargo_dir.parent().unwrap_or(cargo_dir);
+    Some(base.join(target_dir))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_config_target_dir_relative_to_cargo_parent() {
+        let temp = TempDir::new().unwrap();
+        let cargo_dir = temp.path().join(".cargo");
+        fs::create_dir_all(&cargo_dir).unwrap();
+        fs::write(
+            cargo_dir.join("config.toml"),
+            "[build]\ntarget-dir = \"shared-target\"\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            config_target_dir(&cargo_dir),
+            Some(temp.path().join("shared-target"))
+        );
+    }
+
+    #[test]
+    fn test_config_without_target_dir() {
+        let temp = TempDir::new().unwrap();
+        fs::write(temp.path().join("config.toml"), "[net]\nretry = 3\n").unwrap();
+
+        assert_eq!(config_target_dir(temp.path()), None);
+    }
+}
@@ -1,33 +1,207 @@
 //! Directory cleaning/deletion logic
 
+use crate::control::CancelToken;
+use crate::delete::{dir_stats, DeleteError, DeleteStrategy, Deletion};
 use crate::error::Result;
-use crate::types::{CleanPlan, CleanResult, ScanResult};
-use std::fs;
-use std::path::PathBuf;
+use crate::filesystem::{FileKind, FileSystem, StdFileSystem, Walk};
+use crate::globs::compile_globs;
+use crate::journal::Journal;
+use crate::processes::{in_use_message, processes_using};
+use crate::safety::SafetyGuards;
+use crate::space::SpaceProbe;
+use crate::types::{
+    CleanErrorKind, CleanItem, CleanOptions, CleanOutcome, CleanPlan, CleanResult, CleanStrategy,
+    FreeSpace, JunkKind, PlanDrift, RetentionPolicy, RetentionScope, ScanItem, ScanResult,
+};
+use globset::GlobSet;
+use rayon::prelude::*;
+use rayon::ThreadPoolBuilder;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Instant;
+use tracing::{debug, info};
+
+/// What just happened to the path a CleanProgress update is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CleanStage {
+    /// The path is about to be deleted
+    Started,
+    /// The path was renamed aside and is being deleted in the background
+    Moved,
+    /// The path was deleted (or would be, in a dry run)
+    Finished,
+    /// Deleting the path failed
+    Failed,
+    /// The path was left alone because it no longer looked like junk
+    Skipped,
+}
+
+impl CleanStage {
+    /// Whether the path is done with after reaching this stage
+    fn is_final(self) -> bool {
+        matches!(self, Self::Finished | Self::Failed | Self::Skipped)
+    }
+}
+
+/// Progress information during a clean operation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CleanProgress {
+    /// Path being deleted
+    pub current_path: String,
+    /// Stage the current path has reached
+    pub stage: CleanStage,
+    /// Number of plan paths handled so far
+    pub paths_done: usize,
+    /// Number of paths in the plan
+    pub paths_total: usize,
+    /// Total bytes freed so far
+    pub bytes_freed: u64,
+    /// Number of files removed so far
+    pub files_removed: u64,
+}
 
 /// Build a clean plan from scan results and selected paths
 ///
 /// # Arguments
 /// * `result` - The scan result containing all discovered items
 /// * `selection` - Paths that should be included in the clean plan
-/// * `dry_run` - Whether this is a dry run (no actual deletion)
+/// * `options` - How to clean them; with a `min_age`, newer items are left out
 ///
 /// # Returns
-/// A CleanPlan containing the paths to delete
-pub fn build_clean_plan(result: &ScanResult, selection: &[PathBuf], dry_run: bool) -> CleanPlan {
+/// A CleanPlan containing the paths to delete, or `DevJunkError::UnsafePath`
+/// if a selected path must never be deleted
+pub fn build_clean_plan(
+    result: &ScanResult,
+    selection: &[PathBuf],
+    options: &CleanOptions,
+) -> Result<CleanPlan> {
     // Filter result items to only include selected paths
-    let paths: Vec<PathBuf> = result
+    let items: Vec<_> = result
         .items
         .iter()
         .filter(|item| selection.contains(&item.path))
-        .map(|item| item.path.clone())
+        .filter(|item| match options.min_age {
+            Some(age) => item.is_older_than(age),
+            None => true,
+        })
+        .collect();
+
+    let mut plan = CleanPlan::new(
+        items.iter().map(|item| item.path.clone()).collect(),
+        options.dry_run,
+    )
+    .with_delete_mode(options.delete_mode.clone());
+    plan.concurrency = options.concurrency;
+    plan.verify_before_delete = options.verify_before_delete;
+    if !options.keep_globs.is_empty() {
+        plan.keep_globs = plan
+            .paths
+            .iter()
+            .map(|path| (path.clone(), options.keep_globs.clone()))
+            .collect();
+    }
+    plan.kinds = items
+        .iter()
+        .map(|item| (item.path.clone(), item.kind))
         .collect();
+    plan.custom_globs = items
+        .iter()
+        .filter_map(|item| Some((item.path.clone(), item.custom_glob.clone()?)))
+        .collect();
+    plan.sizes = items
+        .iter()
+        .map(|item| (item.path.clone(), item.size_bytes))
+        .collect();
+    plan.file_counts = items
+        .iter()
+        .map(|item| (item.path.clone(), item.file_count))
+        .collect();
+
+    let mut roots: Vec<PathBuf> = items
+        .iter()
+        .filter(|item| !item.root.as_os_str().is_empty())
+        .map(|item| item.root.clone())
+        .collect();
+    roots.sort();
+    roots.dedup();
+    plan.roots = roots;
+
+    SafetyGuards::current().check_plan(&plan)?;
+    Ok(plan)
+}
 
-    CleanPlan::new(paths, dry_run)
+/// Take the directories each retention policy keeps out of a selection
+///
+/// Directories are ranked against every directory of the same kind and group
+/// in the scan, not just the selected ones. Directories whose age is unknown
+/// rank as the newest, so they are kept before any dated one is.
+///
+/// # Example
+/// ```no_run
+/// use devjunk_core::{apply_retention, scan, JunkKind, RetentionPolicy, ScanConfig};
+/// use std::path::PathBuf;
+///
+/// let result = scan(&ScanConfig::new(vec![PathBuf::from(".")])).unwrap();
+/// let all: Vec<_> = result.items.iter().map(|i| i.path.clone()).collect();
+///
+/// // Keep the two most recently built Rust target directories
+/// let policies = [RetentionPolicy::new(JunkKind::RustTarget, 2)];
+/// let selection = apply_retention(&result, &all, &policies);
+/// ```
+pub fn apply_retention(
+    result: &ScanResult,
+    selection: &[PathBuf],
+    policies: &[RetentionPolicy],
+) -> Vec<PathBuf> {
+    let mut kept: Vec<&Path> = Vec::new();
+    for policy in policies {
+        let mut groups: HashMap<&Path, Vec<&ScanItem>> = HashMap::new();
+        for item in result.items.iter().filter(|item| item.kind == policy.kind) {
+            let group = match policy.scope {
+                RetentionScope::Root => item.root.as_path(),
+                RetentionScope::Project => item.path.parent().unwrap_or(Path::new("")),
+            };
+            groups.entry(group).or_default().push(item);
+        }
+
+        for mut items in groups.into_values() {
+            // Newest first, with unknown ages ahead of every known one
+            items.sort_by(|a, b| match (a.last_modified, b.last_modified) {
+                (Some(a), Some(b)) => b.cmp(&a),
+                (a, b) => a.is_some().cmp(&b.is_some()),
+            });
+            let newest = items.iter().take(policy.keep);
+            kept.extend(newest.map(|item| item.path.as_path()));
+        }
+    }
+
+    selection
+        .iter()
+        .filter(|path| !kept.contains(&path.as_path()))
+        .cloned()
+        .collect()
 }
 
 /// Execute a clean plan, deleting the specified directories
 ///
+/// Nothing is deleted if any path in the plan is unsafe to delete (a
+/// filesystem root, the home directory, a mount point, or outside the
+/// plan's scan roots); `DevJunkError::UnsafePath` is returned instead.
+///
+/// When the plan has a `journal_dir`, the plan is journaled there before
+/// anything is deleted, so a clean that gets killed can be found with
+/// `interrupted_cleans` and resumed.
+///
+/// A dry run deletes nothing, but checks that each path could be deleted and
+/// reports the ones that couldn't as failed, much as the real clean would.
+///
+/// Paths whose size the plan recorded (see `CleanPlan::with_known_size`) are
+/// reported with that size rather than walked again before deleting them.
+///
 /// # Arguments
 /// * `plan` - The clean plan specifying what to delete
 ///
@@ -36,96 +210,685 @@ pub fn build_clean_plan(result: &ScanResult, selection: &[PathBuf], dry_run: boo
 ///
 /// # Example
 /// ```no_run
-/// use devjunk_core::{scan, build_clean_plan, execute_clean, ScanConfig};
+/// use devjunk_core::{scan, build_clean_plan, execute_clean, CleanOptions, ScanConfig};
 /// use std::path::PathBuf;
 ///
-/// let config = ScanConfig::new(vec![PathBuf::from(".")]);
+/// let config = ScanConfig::builder(vec![PathBuf::from(".")]).build().unwrap();
 /// let result = scan(&config).unwrap();
 ///
 /// // Select all paths for deletion
 /// let all_paths: Vec<_> = result.items.iter().map(|i| i.path.clone()).collect();
-/// let plan = build_clean_plan(&result, &all_paths, true); // dry run
+/// let options = CleanOptions::new().with_dry_run(true);
+/// let plan = build_clean_plan(&result, &all_paths, &options).unwrap();
 ///
 /// let clean_result = execute_clean(&plan).unwrap();
 /// println!("Would delete {} items", clean_result.deleted_count());
 /// ```
 pub fn execute_clean(plan: &CleanPlan) -> Result<CleanResult> {
+    execute_clean_with_cancel(plan, &CancelToken::new(), |_| {})
+}
+
+/// Execute a clean plan against the given filesystem
+///
+/// Trash and archive deletions always act on the real filesystem. Free space
+/// isn't measured, so `CleanResult::free_space` is always None.
+pub fn execute_clean_with_fs(plan: &CleanPlan, fs: &dyn FileSystem) -> Result<CleanResult> {
+    let backend = plan.delete_mode.strategy();
+    clean_in(plan, fs, backend.as_ref(), &CancelToken::new(), &mut |_| {})
+}
+
+/// Execute a clean plan with progress callback
+///
+/// # Arguments
+/// * `plan` - The clean plan specifying what to delete
+/// * `on_progress` - Callback function called when each path starts and finishes
+///
+/// # Returns
+/// * `Result<CleanResult>` - The result of the clean operation
+pub fn execute_clean_with_progress<F>(plan: &CleanPlan, on_progress: F) -> Result<CleanResult>
+where
+    F: FnMut(CleanProgress),
+{
+    execute_clean_with_cancel(plan, &CancelToken::new(), on_progress)
+}
+
+/// Execute a clean plan with progress callback and cancellation
+///
+/// The token is checked before each path. Once it is cancelled, the paths not
+/// yet reached are left alone and recorded in `CleanResult::skipped`, and
+/// `CleanResult::cancelled` is set.
+///
+/// # Arguments
+/// * `plan` - The clean plan specifying what to delete
+/// * `cancel` - Token that stops the clean when cancelled
+/// * `on_progress` - Callback function called when each path starts and finishes
+pub fn execute_clean_with_cancel<F>(
+    plan: &CleanPlan,
+    cancel: &CancelToken,
+    on_progress: F,
+) -> Result<CleanResult>
+where
+    F: FnMut(CleanProgress),
+{
+    let backend = plan.delete_mode.strategy();
+    execute_clean_with_strategy(plan, backend.as_ref(), cancel, on_progress)
+}
+
+/// Execute a clean plan, removing directories with a custom delete strategy
+///
+/// The plan's `delete_mode` is ignored in favour of `backend`. Free space on
+/// the cleaned filesystems is measured before and after, unless this is a dry run.
+///
+/// # Arguments
+/// * `plan` - The clean plan specifying what to delete
+/// * `backend` - How each directory is removed
+/// * `cancel` - Token that stops the clean when cancelled
+/// * `on_progress` - Callback function called when each path starts and finishes
+pub fn execute_clean_with_strategy<F>(
+    plan: &CleanPlan,
+    backend: &dyn DeleteStrategy,
+    cancel: &CancelToken,
+    mut on_progress: F,
+) -> Result<CleanResult>
+where
+    F: FnMut(CleanProgress),
+{
+    let probe = (!plan.dry_run).then(|| SpaceProbe::new(&plan.paths));
+    let before = probe.as_ref().and_then(SpaceProbe::free_bytes);
+
+    let mut result = clean_in(plan, &StdFileSystem, backend, cancel, &mut on_progress)?;
+    if let (Some(probe), Some(before)) = (&probe, before) {
+        result.free_space = probe.free_bytes().map(|after| FreeSpace { before, after });
+    }
+    Ok(result)
+}
+
+/// Reason recorded for paths a cancelled clean never reached
+const CANCELLED: &str = "cancelled";
+
+/// A plan path whose directories the delete strategy deferred
+struct Deferred {
+    path: PathBuf,
+    kind: Option<JunkKind>,
+    /// Each deferred directory, with where it was moved to
+    moved: Vec<(PathBuf, PathBuf)>,
+    /// What was already removed outright
+    freed: (u64, u64),
+    /// Size and file count of the path from the scan, if it was moved aside whole
+    known: Option<(u64, u64)>,
+    started: Instant,
+}
+
+fn clean_in(
+    plan: &CleanPlan,
+    fs: &dyn FileSystem,
+    backend: &dyn DeleteStrategy,
+    cancel: &CancelToken,
+    on_progress: &mut dyn FnMut(CleanProgress),
+) -> Result<CleanResult> {
+    let started = Instant::now();
+    SafetyGuards::current().check_plan(plan)?;
+    // A typo in a glob must not turn into deleting what it was meant to keep
+    let keep_globs = compile_keep_globs(plan)?;
+
+    let mut journal = Journal::start(plan)?;
     let mut result = CleanResult::new(plan.dry_run);
 
     // Track deleted paths to skip nested directories that were already deleted
     // as part of a parent directory deletion
     let mut deleted_paths: Vec<PathBuf> = Vec::new();
+    let mut deferred: Vec<Deferred> = Vec::new();
+
+    let mut paths_done = 0;
+    let mut report = |path: &Path, stage: CleanStage, result: &CleanResult| {
+        paths_done += usize::from(stage.is_final());
+        on_progress(CleanProgress {
+            current_path: path.to_string_lossy().to_string(),
+            stage,
+            paths_done,
+            paths_total: plan.paths.len(),
+            bytes_freed: result.bytes_freed,
+            files_removed: result.files_removed(),
+        })
+    };
 
     for path in &plan.paths {
+        let kind = plan.kind_of(path);
+
+        if cancel.is_cancelled() {
+            result.cancelled = true;
+            let item = cancelled_item(path.clone(), kind);
+            record(&mut result, &mut journal, item);
+            report(path, CleanStage::Skipped, &result);
+            continue;
+        }
+
         // Skip if this path is a subdirectory of an already deleted path
-        if deleted_paths.iter().any(|deleted| path.starts_with(deleted)) {
+        if deleted_paths
+            .iter()
+            .any(|deleted| path.starts_with(deleted))
+        {
             // Already deleted as part of parent - count as success without re-deleting
+            debug!(path = %path.display(), "already deleted with its parent");
             continue;
         }
 
-        if plan.dry_run {
-            // In dry run mode, just record what would be deleted
-            if path.exists() {
-                // Calculate size for reporting
-                let size = calculate_dir_size(path);
-                result.bytes_freed += size;
-                result.deleted.push(path.clone());
-                deleted_paths.push(path.clone());
+        // Skip if path no longer exists (already deleted by another operation)
+        if fs.metadata(path).is_err() {
+            debug!(path = %path.display(), "no longer exists");
+            continue;
+        }
+
+        let started = Instant::now();
+        report(path, CleanStage::Started, &result);
+
+        // The directory may have been replaced with real data since the scan
+        if let Some(&scanned) = plan.kinds.get(path).filter(|_| plan.verify_before_delete) {
+            let custom_glob = plan.custom_globs.get(path).map(String::as_str);
+            if let Err(reason) = verify_identity(fs, path, scanned, custom_glob) {
+                let item = CleanItem::unclean(
+                    path.clone(),
+                    kind,
+                    CleanOutcome::Skipped,
+                    CleanErrorKind::ChangedIdentity,
+                    reason,
+                );
+                let item = item.with_elapsed(started.elapsed());
+                record(&mut result, &mut journal, item);
+                report(path, CleanStage::Skipped, &result);
+                continue;
             }
-        } else {
-            // Skip if path no longer exists (already deleted by another operation)
-            if !path.exists() {
+        }
+
+        // Deleting files a dev server still has open would fail part way through
+        if plan.skip_in_use {
+            let holders = processes_using(path);
+            if !holders.is_empty() {
+                let item = CleanItem::unclean(
+                    path.clone(),
+                    kind,
+                    CleanOutcome::Skipped,
+                    CleanErrorKind::InUse,
+                    in_use_message(&holders),
+                );
+                let item = item.with_elapsed(started.elapsed());
+                record(&mut result, &mut journal, item);
+                report(path, CleanStage::Skipped, &result);
+                continue;
+            }
+        }
+
+        let strategy = plan.strategy_for(path);
+        let keep = keep_globs.get(path.as_path());
+        let mut targets = removal_targets(fs, path, strategy);
+        if let Some(keep) = keep {
+            targets = targets
+                .iter()
+                .flat_map(|target| unkept_entries(fs, path, target, keep))
+                .collect();
+        }
+
+        // The scan already measured the path, so only parts of it need walking
+        let known = plan.known_size(path);
+        let known_for = |target: &Path| known.filter(|_| target == path);
+
+        let mut freed = (0, 0);
+        let mut moved = Vec::new();
+        let mut error = None;
+        for target in targets {
+            if plan.dry_run {
+                // In dry run mode, predict the outcome and record what would be deleted
+                if let Err(e) = predict_deletion(fs, &target) {
+                    error = Some(e);
+                    break;
+                }
+                let (size, files) = known_for(&target).unwrap_or_else(|| dir_stats(fs, &target));
+                freed = (freed.0 + size, freed.1 + files);
                 continue;
             }
 
             // Actually delete the directory
-            match delete_directory(path) {
-                Ok(size) => {
-                    result.bytes_freed += size;
-                    result.deleted.push(path.clone());
-                    deleted_paths.push(path.clone());
+            let deletion = match known_for(&target) {
+                Some((bytes, files)) => backend.delete_measured(fs, &target, bytes, files),
+                None => backend.delete(fs, &target),
+            };
+            match deletion {
+                Ok(Deletion::Removed { bytes, files }) => {
+                    freed = (freed.0 + bytes, freed.1 + files)
                 }
+                Ok(Deletion::Deferred(to)) => moved.push((target, to)),
                 Err(e) => {
-                    result.failed.push((path.clone(), e.to_string()));
+                    error = Some(e);
+                    break;
                 }
             }
         }
+
+        if let Some(e) = error {
+            // Don't leave parts moved aside behind once another part failed
+            for (_, to) in moved {
+                let (size, files) = dir_stats(fs, &to);
+                if backend.finish(fs, &to).is_ok() {
+                    freed = (freed.0 + size, freed.1 + files);
+                }
+            }
+
+            // Whatever was removed before the failure still counts as freed
+            let mut item =
+                CleanItem::unclean(path.clone(), kind, CleanOutcome::Failed, e.kind, e.message);
+            item.bytes_freed = freed.0;
+            item.files_removed = freed.1;
+            let item = item.with_elapsed(started.elapsed());
+            record(&mut result, &mut journal, item);
+            report(path, CleanStage::Failed, &result);
+            continue;
+        }
+
+        // Paths with kept contents still exist, and so may the paths nested in them
+        if strategy == CleanStrategy::Remove && keep.is_none() {
+            deleted_paths.push(path.clone());
+        }
+        if moved.is_empty() {
+            let item = CleanItem::deleted(path.clone(), kind, freed.0, freed.1)
+                .with_elapsed(started.elapsed());
+            record(&mut result, &mut journal, item);
+            report(path, CleanStage::Finished, &result);
+        } else {
+            for (_, to) in &moved {
+                journal.moved(path, to);
+            }
+            deferred.push(Deferred {
+                path: path.clone(),
+                kind,
+                moved,
+                freed,
+                known,
+                started,
+            });
+            report(path, CleanStage::Moved, &result);
+        }
     }
 
+    if deferred.is_empty() {
+        journal.finish();
+        log_finished(&result, started);
+        return Ok(result);
+    }
+
+    // Finish the deferred directories in parallel, reporting each path as it completes
+    std::thread::scope(|scope| {
+        let (tx, rx) = mpsc::channel();
+        scope.spawn(move || {
+            let finish_all = || {
+                deferred.par_iter().for_each_with(tx, |tx, entry| {
+                    let item = finish_deferred(fs, backend, cancel, entry);
+                    let _ = tx.send(item.with_elapsed(entry.started.elapsed()));
+                })
+            };
+            // Without a pool of its own the clean shares the global one
+            let pool = plan
+                .concurrency
+                .and_then(|threads| ThreadPoolBuilder::new().num_threads(threads).build().ok());
+            match pool {
+                Some(pool) => pool.install(finish_all),
+                None => finish_all(),
+            }
+        });
+
+        for item in rx {
+            let stage = match item.outcome {
+                CleanOutcome::Deleted => CleanStage::Finished,
+                CleanOutcome::Failed => CleanStage::Failed,
+                CleanOutcome::Skipped => {
+                    result.cancelled = true;
+                    CleanStage::Skipped
+                }
+            };
+            let path = item.path.clone();
+            record(&mut result, &mut journal, item);
+            report(&path, stage, &result);
+        }
+    });
+
+    journal.finish();
+    log_finished(&result, started);
     Ok(result)
 }
 
-/// Delete a directory and all its contents
-fn delete_directory(path: &PathBuf) -> std::result::Result<u64, std::io::Error> {
-    // Calculate size before deletion
-    let size = calculate_dir_size(path);
+/// Add a path that is done with to the result and the journal
+fn record(result: &mut CleanResult, journal: &mut Journal, item: CleanItem) {
+    match &item.error {
+        Some(error) => info!(
+            path = %item.path.display(),
+            outcome = ?item.outcome,
+            error = %error,
+            "not cleaned"
+        ),
+        None => debug!(
+            path = %item.path.display(),
+            bytes_freed = item.bytes_freed,
+            elapsed_ms = item.elapsed_ms,
+            "cleaned"
+        ),
+    }
+    journal.done(&item);
+    result.push(item);
+}
+
+/// Log the totals of a clean that is about to return
+fn log_finished(result: &CleanResult, started: Instant) {
+    info!(
+        paths = result.items.len(),
+        bytes_freed = result.bytes_freed,
+        elapsed = ?started.elapsed(),
+        "clean finished"
+    );
+}
+
+/// Remove the directories of a deferred path, or put them back if the clean was cancelled
+fn finish_deferred(
+    fs: &dyn FileSystem,
+    backend: &dyn DeleteStrategy,
+    cancel: &CancelToken,
+    entry: &Deferred,
+) -> CleanItem {
+    let path = entry.path.clone();
+    let mut freed = entry.freed;
+    let cancelled = cancel.is_cancelled();
+
+    for (original, moved) in &entry.moved {
+        let outcome = if cancelled {
+            backend.restore(fs, moved, original)
+        } else {
+            let (size, files) = match entry.known {
+                Some(known) if *original == entry.path => known,
+                _ => dir_stats(fs, moved),
+            };
+            backend
+                .finish(fs, moved)
+                .map(|()| freed = (freed.0 + size, freed.1 + files))
+        };
+
+        if let Err(e) = outcome {
+            let e = DeleteError::from(e);
+            let message = format!("{} (left at {})", e.message, moved.display());
+            let mut item =
+                CleanItem::unclean(path, entry.kind, CleanOutcome::Failed, e.kind, message);
+            item.bytes_freed = freed.0;
+            item.files_removed = freed.1;
+            return item;
+        }
+    }
+
+    if cancelled {
+        cancelled_item(path, entry.kind)
+    } else {
+        CleanItem::deleted(path, entry.kind, freed.0, freed.1)
+    }
+}
+
+/// Check, without deleting anything, whether deleting `target` would fail
+///
+/// Every directory inside is checked as well, since one that can't be
+/// emptied stops the deletion part way through. Anything else that goes
+/// wrong at the time, like a file created by a build meanwhile, isn't foreseen.
+fn predict_deletion(fs: &dyn FileSystem, target: &Path) -> std::result::Result<(), DeleteError> {
+    let denied = |path: &Path, reason: String| {
+        let message = if path == target {
+            reason
+        } else {
+            format!("{}: {}", path.display(), reason)
+        };
+        DeleteError::new(CleanErrorKind::PermissionDenied, message)
+    };
 
-    // Remove the directory recursively
-    fs::remove_dir_all(path)?;
+    fs.check_deletable(target)
+        .map_err(|reason| denied(target, reason))?;
+    // Only the link itself would be deleted, not what it points to
+    if fs
+        .metadata(target)
+        .is_ok_and(|meta| meta.kind == FileKind::Link)
+    {
+        return Ok(());
+    }
+    for entry in Walk::new(fs, target) {
+        match entry {
+            Ok(entry) if entry.depth > 0 && entry.kind == FileKind::Dir => fs
+                .check_deletable(&entry.path)
+                .map_err(|reason| denied(&entry.path, reason))?,
+            Ok(_) => {}
+            Err(e) => return Err(denied(&e.path, e.error.to_string())),
+        }
+    }
+
+    // Open files only stand in the way of deleting them on Windows
+    if cfg!(windows) {
+        let holders = processes_using(target);
+        if !holders.is_empty() {
+            return Err(DeleteError::new(
+                CleanErrorKind::InUse,
+                in_use_message(&holders),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Record of a path a cancelled clean never got to
+fn cancelled_item(path: PathBuf, kind: Option<JunkKind>) -> CleanItem {
+    CleanItem::unclean(
+        path,
+        kind,
+        CleanOutcome::Skipped,
+        CleanErrorKind::Cancelled,
+        CANCELLED,
+    )
+}
+
+/// Check that the paths of a saved plan still match what was planned
+///
+/// Each path is compared with the kind and size the plan recorded from its
+/// scan, so a plan reviewed earlier isn't applied to directories that have
+/// since been rebuilt or replaced. Returns the paths that drifted, in plan order.
+pub fn validate_plan(plan: &CleanPlan) -> Vec<(PathBuf, PlanDrift)> {
+    validate_plan_with_fs(plan, &StdFileSystem)
+}
+
+/// Check a saved plan against the given filesystem (see `validate_plan`)
+pub fn validate_plan_with_fs(plan: &CleanPlan, fs: &dyn FileSystem) -> Vec<(PathBuf, PlanDrift)> {
+    plan.paths
+        .iter()
+        .filter_map(|path| {
+            if fs.metadata(path).is_err() {
+                return Some((path.clone(), PlanDrift::Missing));
+            }
+            if let Some(&kind) = plan.kinds.get(path) {
+                let custom_glob = plan.custom_globs.get(path).map(String::as_str);
+                if let Err(reason) = verify_identity(fs, path, kind, custom_glob) {
+                    return Some((path.clone(), PlanDrift::ChangedIdentity { reason }));
+                }
+            }
+            let &planned = plan.sizes.get(path)?;
+            let (actual, _) = dir_stats(fs, path);
+            (actual != planned).then(|| (path.clone(), PlanDrift::SizeChanged { planned, actual }))
+        })
+        .collect()
+}
+
+/// Entries whose presence means a directory holds a checkout, not build output
+const VCS_DIRS: &[&str] = &[".git", ".hg", ".svn"];
+
+/// Confirm that `path` still is the kind of junk directory it was scanned as
+///
+/// Fails with the reason if it is no longer a plain directory, no longer
+/// carries a matching name, holds a version-controlled checkout, or lacks
+/// all of the kind's marker files. `Custom` directories have no names of
+/// their own, so their name is checked against `custom_glob`, the glob of
+/// the pattern that found them.
+fn verify_identity(
+    fs: &dyn FileSystem,
+    path: &Path,
+    kind: JunkKind,
+    custom_glob: Option<&str>,
+) -> std::result::Result<(), String> {
+    match fs.metadata(path) {
+        Ok(m) if m.kind == FileKind::Dir => {}
+        _ => return Err("no longer a directory".to_string()),
+    }
+
+    let named_like_kind = match (kind, custom_glob) {
+        (JunkKind::Custom, Some(glob)) => {
+            let globs = compile_globs(&[glob.to_string()]).map_err(|e| e.to_string())?;
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| globs.is_match(name))
+        }
+        _ => path
+            .file_name()
+            .is_some_and(|name| kind.matches_os_name(name)),
+    };
+    if !named_like_kind {
+        return Err(format!("no longer named like a {} directory", kind));
+    }
+
+    let exists = |name: &str| fs.metadata(&path.join(name)).is_ok();
+    if let Some(vcs) = VCS_DIRS.iter().find(|name| exists(name)) {
+        return Err(format!("contains {}, so it looks like a checkout", vcs));
+    }
+
+    let markers = kind.markers();
+    if !markers.is_empty() && !markers.iter().any(|name| exists(name)) {
+        return Err(format!(
+            "has none of the files a {} directory contains ({})",
+            kind,
+            markers.join(", ")
+        ));
+    }
+
+    Ok(())
+}
+
+/// Directories removed when cleaning `path` with `strategy`
+fn removal_targets(fs: &dyn FileSystem, path: &Path, strategy: CleanStrategy) -> Vec<PathBuf> {
+    match strategy {
+        CleanStrategy::Remove => vec![path.to_path_buf()],
+        CleanStrategy::SweepRustTarget => rust_profile_dirs(fs, path)
+            .into_iter()
+            .flat_map(|profile| RUST_SWEEP_DIRS.iter().map(move |name| profile.join(name)))
+            .filter(|dir| matches!(fs.metadata(dir), Ok(m) if m.kind == FileKind::Dir))
+            .collect(),
+    }
+}
+
+/// Compile the keep globs of each plan path
+fn compile_keep_globs(plan: &CleanPlan) -> Result<HashMap<&Path, GlobSet>> {
+    let mut compiled = HashMap::new();
+    for (path, globs) in &plan.keep_globs {
+        if globs.is_empty() {
+            continue;
+        }
+        compiled.insert(path.as_path(), compile_globs(globs)?);
+    }
+    Ok(compiled)
+}
+
+/// What to remove of `target` so that the entries of `root` matching `keep` survive
+///
+/// `target` itself when nothing in it is kept, otherwise the largest files and
+/// directories inside it that hold nothing kept. Directories that can't be
+/// read are left alone, since they might hold something kept.
+fn unkept_entries(fs: &dyn FileSystem, root: &Path, target: &Path, keep: &GlobSet) -> Vec<PathBuf> {
+    if target != root && keep.is_match(target.strip_prefix(root).unwrap_or(target)) {
+        return Vec::new();
+    }
+    let mut unkept = Vec::new();
+    if collect_unkept(fs, root, target, keep, &mut unkept) {
+        unkept
+    } else {
+        vec![target.to_path_buf()]
+    }
+}
+
+/// Add the entries of `dir` that hold nothing kept to `unkept`, returning
+/// whether anything in `dir` is kept
+///
+/// Nothing is added when nothing is kept, as `dir` can then go as a whole.
+fn collect_unkept(
+    fs: &dyn FileSystem,
+    root: &Path,
+    dir: &Path,
+    keep: &GlobSet,
+    unkept: &mut Vec<PathBuf>,
+) -> bool {
+    let Ok(entries) = fs.read_dir(dir) else {
+        return true;
+    };
 
-    Ok(size)
+    let mut kept_any = false;
+    let mut removable = Vec::new();
+    for entry in entries {
+        let relative = entry.path.strip_prefix(root).unwrap_or(&entry.path);
+        let holds_kept = keep.is_match(relative)
+            || (entry.kind == FileKind::Dir
+                && collect_unkept(fs, root, &entry.path, keep, &mut removable));
+        if holds_kept {
+            kept_any = true;
+        } else {
+            removable.push(entry.path);
+        }
+    }
+    if kept_any {
+        unkept.append(&mut removable);
+    }
+    kept_any
 }
 
-/// Calculate the total size of a directory
-fn calculate_dir_size(path: &PathBuf) -> u64 {
-    walkdir::WalkDir::new(path)
-        .follow_links(false)
-        .into_iter()
-        .filter_map(|e| e.ok())
-        .filter(|e| e.file_type().is_file())
-        .filter_map(|e| fs::metadata(e.path()).ok())
-        .map(|m| m.len())
-        .sum()
+/// Intermediate outputs inside a Cargo profile directory, rebuilt on the next build
+const RUST_SWEEP_DIRS: &[&str] = &["incremental", "deps", "build", ".fingerprint"];
+
+/// Profile directories of a Rust target directory
+///
+/// Covers `target/<profile>` as well as `target/<triple>/<profile>` for
+/// cross-compiled builds. A profile is recognised by its `.fingerprint` dir.
+fn rust_profile_dirs(fs: &dyn FileSystem, target: &Path) -> Vec<PathBuf> {
+    let is_profile = |dir: &Path| fs.metadata(&dir.join(".fingerprint")).is_ok();
+    let subdirs = |dir: &Path| {
+        fs.read_dir(dir)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|entry| entry.kind == FileKind::Dir)
+            .map(|entry| entry.path)
+    };
+
+    let mut profiles = Vec::new();
+    for dir in subdirs(target) {
+        if is_profile(&dir) {
+            profiles.push(dir);
+        } else {
+            profiles.extend(subdirs(&dir).filter(|nested| is_profile(nested)));
+        }
+    }
+    profiles
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::{JunkKind, ScanItem};
-    use std::fs::File;
+    use crate::delete::DeleteMode;
+    use crate::error::DevJunkError;
+    use crate::filesystem::MemoryFileSystem;
+    use crate::journal::interrupted_cleans;
+    use crate::scanner::scan_with_fs;
+    use crate::types::{CustomPattern, ScanConfig, ScanItem};
+    use std::fs::{self, File};
     use std::io::Write;
+    use std::time::{Duration, SystemTime};
     use tempfile::TempDir;
 
+    fn paths_of<'a>(items: impl Iterator<Item = &'a CleanItem>) -> Vec<PathBuf> {
+        items.map(|item| item.path.clone()).collect()
+    }
+
     #[test]
     fn test_build_clean_plan_filters_selection() {
         let result = ScanResult {
@@ -144,6 +907,7 @@ mod tests {
                     5,
                 ),
             ],
+            ..Default::default()
         };
 
         let selection = vec![
@@ -151,7 +915,8 @@ mod tests {
             PathBuf::from("/c/__pycache__"),
         ];
 
-        let plan = build_clean_plan(&result, &selection, true);
+        let options = CleanOptions::new().with_dry_run(true);
+        let plan = build_clean_plan(&result, &selection, &options).unwrap();
 
         assert_eq!(plan.count(), 2);
         assert!(plan.paths.contains(&PathBuf::from("/a/node_modules")));
@@ -159,6 +924,125 @@ mod tests {
         assert!(!plan.paths.contains(&PathBuf::from("/b/target")));
     }
 
+    #[test]
+    fn test_build_clean_plan_applies_options() {
+        let fs = MemoryFileSystem::new();
+        // No CACHEDIR.TAG or .rustc_info.json, so it no longer looks like a target dir
+        fs.add_file("/p/target/notes.txt", 10);
+        let result = ScanResult {
+            items: vec![ScanItem::new(
+                PathBuf::from("/p/target"),
+                JunkKind::RustTarget,
+                10,
+                1,
+            )],
+            ..Default::default()
+        };
+        let selection = [PathBuf::from("/p/target")];
+
+        let options = CleanOptions::new()
+            .with_delete_mode(DeleteMode::RenameDefer)
+            .with_concurrency(2)
+            .with_keep_globs(vec!["*.txt".into()]);
+        let plan = build_clean_plan(&result, &selection, &options).unwrap();
+        assert_eq!(plan.delete_mode, DeleteMode::RenameDefer);
+        assert_eq!(plan.concurrency, Some(2));
+        assert_eq!(plan.keep_globs[&selection[0]], ["*.txt"]);
+
+        let verified = build_clean_plan(&result, &selection, &CleanOptions::new()).unwrap();
+        let skipped = execute_clean_with_fs(&verified, &fs).unwrap();
+        assert_eq!(skipped.skipped_count(), 1);
+
+        let unverified = CleanOptions::new().with_verify_before_delete(false);
+        let plan = build_clean_plan(&result, &selection, &unverified).unwrap();
+        let deleted = execute_clean_with_fs(&plan, &fs).unwrap();
+        assert_eq!(deleted.deleted_count(), 1);
+        assert!(!fs.exists("/p/target"));
+    }
+
+    #[test]
+    fn test_build_clean_plan_skips_recent_items() {
+        let day = Duration::from_secs(24 * 60 * 60);
+        let item = |path: &str, age: Option<Duration>| {
+            let mut item = ScanItem::new(PathBuf::from(path), JunkKind::DistDir, 1, 1);
+            item.last_modified = age.map(|age| SystemTime::now() - age);
+            item
+        };
+        let result = ScanResult {
+            items: vec![
+                item("/old/dist", Some(day * 45)),
+                item("/fresh/dist", Some(day / 24)),
+                item("/unknown/dist", None),
+            ],
+            ..Default::default()
+        };
+        let all: Vec<_> = result.items.iter().map(|i| i.path.clone()).collect();
+
+        let options = CleanOptions::new().with_dry_run(true);
+        let old_only = options.clone().with_min_age(day * 30);
+        let plan = build_clean_plan(&result, &all, &old_only).unwrap();
+        assert_eq!(plan.paths, vec![PathBuf::from("/old/dist")]);
+
+        let plan = build_clean_plan(&result, &all, &options).unwrap();
+        assert_eq!(plan.count(), 3);
+    }
+
+    #[test]
+    fn test_retention_keeps_the_newest_directories() {
+        let day = Duration::from_secs(24 * 60 * 60);
+        let item = |path: &str, kind, age: Option<u32>| {
+            let mut item = ScanItem::new(PathBuf::from(path), kind, 1, 1);
+            item.root = PathBuf::from("/ws");
+            item.last_modified = age.map(|age| SystemTime::now() - day * age);
+            item
+        };
+        let result = ScanResult {
+            items: vec![
+                item("/ws/a/target", JunkKind::RustTarget, Some(30)),
+                item("/ws/b/target", JunkKind::RustTarget, Some(1)),
+                item("/ws/c/target", JunkKind::RustTarget, Some(7)),
+                item("/ws/d/target", JunkKind::RustTarget, None),
+                item("/ws/a/node_modules", JunkKind::NodeModules, Some(90)),
+            ],
+            ..Default::default()
+        };
+        let all: Vec<_> = result.items.iter().map(|i| i.path.clone()).collect();
+
+        let policies = [RetentionPolicy::new(JunkKind::RustTarget, 2)];
+        assert_eq!(
+            apply_retention(&result, &all, &policies),
+            vec![
+                PathBuf::from("/ws/a/target"),
+                PathBuf::from("/ws/c/target"),
+                PathBuf::from("/ws/a/node_modules"),
+            ]
+        );
+
+        // Every project has just the one target directory, which it keeps
+        let policies =
+            [RetentionPolicy::new(JunkKind::RustTarget, 1).with_scope(RetentionScope::Project)];
+        assert_eq!(
+            apply_retention(&result, &all, &policies),
+            vec![PathBuf::from("/ws/a/node_modules")]
+        );
+
+        let result = ScanResult {
+            items: vec![
+                item("/ws/e/build", JunkKind::BuildDir, Some(20)),
+                item("/ws/e/build-release", JunkKind::BuildDir, Some(2)),
+                item("/ws/f/build", JunkKind::BuildDir, Some(40)),
+            ],
+            ..Default::default()
+        };
+        let all: Vec<_> = result.items.iter().map(|i| i.path.clone()).collect();
+        let policies =
+            [RetentionPolicy::new(JunkKind::BuildDir, 1).with_scope(RetentionScope::Project)];
+        assert_eq!(
+            apply_retention(&result, &all, &policies),
+            vec![PathBuf::from("/ws/e/build")]
+        );
+    }
+
     #[test]
     fn test_execute_clean_dry_run() {
         let temp = TempDir::new().unwrap();
@@ -177,6 +1061,43 @@ mod tests {
         assert!(test_dir.exists()); // Should still exist after dry run
     }
 
+    #[test]
+    fn test_dry_run_predicts_failures() {
+        let fs = MemoryFileSystem::new();
+        fs.add_file("/r/a/node_modules/x.js", 10)
+            .add_file("/r/b/node_modules/pkg/y.js", 20)
+            .add_file("/r/c/node_modules/z.js", 30);
+        fs.lock("/r/b/node_modules/pkg")
+            .deny_read("/r/c/node_modules");
+
+        let paths = [
+            "/r/a/node_modules",
+            "/r/b/node_modules",
+            "/r/c/node_modules",
+        ];
+        let plan = CleanPlan::new(paths.iter().map(PathBuf::from).collect(), true);
+        let result = execute_clean_with_fs(&plan, &fs).unwrap();
+
+        assert_eq!(
+            paths_of(result.deleted()),
+            vec![PathBuf::from("/r/a/node_modules")]
+        );
+        assert_eq!(result.bytes_freed, 10);
+        let failed: Vec<_> = result.failed().collect();
+        assert_eq!(
+            paths_of(failed.iter().copied()),
+            [PathBuf::from(paths[1]), PathBuf::from(paths[2])]
+        );
+        assert!(failed
+            .iter()
+            .all(|item| item.error_kind == Some(CleanErrorKind::PermissionDenied)));
+        assert_eq!(
+            failed[0].error.as_deref(),
+            Some("/r/b/node_modules/pkg: directory is locked")
+        );
+        assert!(fs.exists("/r/b/node_modules/pkg/y.js"));
+    }
+
     #[test]
     fn test_execute_clean_actual() {
         let temp = TempDir::new().unwrap();
@@ -193,5 +1114,597 @@ mod tests {
         assert!(!result.was_dry_run);
         assert_eq!(result.deleted_count(), 1);
         assert!(!test_dir.exists()); // Should be deleted
+        assert!(result.free_space.is_some());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_execute_clean_skips_directories_in_use() {
+        let temp = TempDir::new().unwrap();
+        let busy = temp.path().join("busy/node_modules");
+        let idle = temp.path().join("idle/node_modules");
+        fs::create_dir_all(&busy).unwrap();
+        fs::create_dir_all(&idle).unwrap();
+        let mut server = std::process::Command::new("sleep")
+            .arg("30")
+            .current_dir(&busy)
+            .spawn()
+            .unwrap();
+
+        let plan = CleanPlan::new(vec![busy.clone(), idle.clone()], false).with_skip_in_use(true);
+        let result = execute_clean(&plan).unwrap();
+        server.kill().unwrap();
+        server.wait().unwrap();
+
+        assert_eq!(paths_of(result.deleted()), vec![idle]);
+        let skipped: Vec<_> = result.skipped().collect();
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(skipped[0].error_kind, Some(CleanErrorKind::InUse));
+        assert_eq!(
+            skipped[0].error.as_deref(),
+            Some(format!("in use by sleep (pid {})", server.id()).as_str())
+        );
+        assert!(busy.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_execute_clean_non_utf8_path() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let temp = TempDir::new().unwrap();
+        let project = temp.path().join(OsStr::from_bytes(b"proj\xe9ct"));
+        let junk = project.join("node_modules");
+        fs::create_dir_all(&junk).unwrap();
+        File::create(junk.join(OsStr::from_bytes(b"\xff.js")))
+            .unwrap()
+            .write_all(b"x")
+            .unwrap();
+
+        let plan = CleanPlan::new(vec![junk.clone()], false);
+        let result = execute_clean(&plan).unwrap();
+
+        assert!(result.is_success());
+        assert_eq!(result.bytes_freed, 1);
+        assert!(!junk.exists());
+    }
+
+    #[test]
+    fn test_execute_clean_with_progress_reports_each_path() {
+        let temp = TempDir::new().unwrap();
+        let first = temp.path().join("a").join("node_modules");
+        let second = temp.path().join("b").join("dist");
+        fs::create_dir_all(first.join("pkg")).unwrap();
+        fs::create_dir_all(&second).unwrap();
+        fs::write(first.join("pkg").join("index.js"), b"12345").unwrap();
+        fs::write(first.join("pkg").join("package.json"), b"{}").unwrap();
+        fs::write(second.join("bundle.js"), b"123").unwrap();
+
+        let plan = CleanPlan::new(vec![first, second.clone()], false);
+        let mut updates = Vec::new();
+        let result = execute_clean_with_progress(&plan, |p| updates.push(p)).unwrap();
+
+        assert!(result.is_success());
+        let stages: Vec<_> = updates.iter().map(|p| (p.stage, p.paths_done)).collect();
+        assert_eq!(
+            stages,
+            vec![
+                (CleanStage::Started, 0),
+                (CleanStage::Finished, 1),
+                (CleanStage::Started, 1),
+                (CleanStage::Finished, 2),
+            ]
+        );
+
+        let last = updates.last().unwrap();
+        assert_eq!(last.current_path, second.to_string_lossy());
+        assert_eq!(last.paths_total, 2);
+        assert_eq!(last.bytes_freed, 10);
+        assert_eq!(last.files_removed, 3);
+    }
+
+    #[test]
+    fn test_execute_clean_skips_directories_that_changed_identity() {
+        let fs = MemoryFileSystem::new();
+        fs.add_file("/r/web/node_modules/react/index.js", 10)
+            .add_file("/r/app/build/main.c", 20)
+            .add_dir("/r/app/build/.git")
+            .add_file("/r/py/.venv/lib/site.py", 30)
+            .add_file("/r/rs/target/CACHEDIR.TAG", 1);
+
+        let kinds = [
+            ("/r/web/node_modules", JunkKind::NodeModules),
+            ("/r/app/build", JunkKind::BuildDir),
+            ("/r/py/.venv", JunkKind::PythonVenv),
+            ("/r/rs/target", JunkKind::RustTarget),
+        ];
+        let mut plan = CleanPlan::new(kinds.iter().map(|(p, _)| PathBuf::from(p)).collect(), false);
+        plan.kinds = kinds.iter().map(|(p, k)| (PathBuf::from(p), *k)).collect();
+
+        let result = execute_clean_with_fs(&plan, &fs).unwrap();
+
+        assert_eq!(
+            paths_of(result.deleted()),
+            vec![
+                PathBuf::from("/r/web/node_modules"),
+                PathBuf::from("/r/rs/target")
+            ]
+        );
+        assert_eq!(
+            paths_of(result.skipped()),
+            vec![PathBuf::from("/r/app/build"), PathBuf::from("/r/py/.venv")]
+        );
+        let venv = result.skipped().nth(1).unwrap();
+        assert_eq!(venv.error_kind, Some(CleanErrorKind::ChangedIdentity));
+        assert!(venv.error.as_deref().unwrap().contains("pyvenv.cfg"));
+        assert!(result.is_success());
+        assert!(fs.exists("/r/app/build/main.c"));
+        assert!(fs.exists("/r/py/.venv/lib/site.py"));
+    }
+
+    #[test]
+    fn test_execute_clean_removes_custom_pattern_directories() {
+        let fs = MemoryFileSystem::new();
+        fs.add_file("/r/app/.bazel-cache-x1/out.o", 10)
+            .add_file("/r/app/src/main.c", 20);
+        let config = ScanConfig::builder(vec![PathBuf::from("/r")])
+            .with_skip_network_mounts(false)
+            .with_custom_patterns(vec![CustomPattern::new("Bazel cache", ".bazel-cache-*")])
+            .build_with_fs(&fs)
+            .unwrap();
+        let scanned = scan_with_fs(&config, &fs).unwrap();
+        let selection: Vec<_> = scanned.items.iter().map(|item| item.path.clone()).collect();
+        let cache = PathBuf::from("/r/app/.bazel-cache-x1");
+        assert_eq!(selection, vec![cache.clone()]);
+
+        let plan = build_clean_plan(&scanned, &selection, &CleanOptions::new()).unwrap();
+        assert!(validate_plan_with_fs(&plan, &fs).is_empty());
+        let result = execute_clean_with_fs(&plan, &fs).unwrap();
+
+        assert_eq!(paths_of(result.deleted()), vec![cache]);
+        assert!(!fs.exists("/r/app/.bazel-cache-x1"));
+        assert!(fs.exists("/r/app/src/main.c"));
+    }
+
+    #[test]
+    fn test_sweep_rust_target_keeps_final_artifacts() {
+        let fs = MemoryFileSystem::new();
+        fs.add_file("/p/target/debug/app", 100)
+            .add_file("/p/target/debug/deps/app-1a2b3c", 100)
+            .add_file("/p/target/debug/incremental/app-x/s-1/query-cache.bin", 40)
+            .add_file("/p/target/debug/build/foo-9f/out/gen.rs", 5)
+            .add_file("/p/target/debug/.fingerprint/app-1a2b3c/dep-bin-app", 1)
+            .add_file("/p/target/x86_64-pc-windows-gnu/release/app.exe", 80)
+            .add_file(
+                "/p/target/x86_64-pc-windows-gnu/release/deps/app-4d5e.exe",
+                80,
+            )
+            .add_dir("/p/target/x86_64-pc-windows-gnu/release/.fingerprint")
+            .add_file("/p/target/doc/app/index.html", 7);
+
+        let plan = CleanPlan::new(vec![PathBuf::from("/p/target")], false)
+            .with_strategy(JunkKind::RustTarget, CleanStrategy::SweepRustTarget);
+        let result = execute_clean_with_fs(&plan, &fs).unwrap();
+
+        assert!(result.is_success());
+        assert_eq!(paths_of(result.deleted()), vec![PathBuf::from("/p/target")]);
+        assert_eq!(result.bytes_freed, 226);
+        assert!(fs.exists("/p/target/debug/app"));
+        assert!(fs.exists("/p/target/x86_64-pc-windows-gnu/release/app.exe"));
+        assert!(fs.exists("/p/target/doc/app/index.html"));
+        assert!(!fs.exists("/p/target/debug/deps"));
+        assert!(!fs.exists("/p/target/debug/incremental"));
+        assert!(!fs.exists("/p/target/debug/build"));
+        assert!(!fs.exists("/p/target/debug/.fingerprint"));
+        assert!(!fs.exists("/p/target/x86_64-pc-windows-gnu/release/deps"));
+    }
+
+    #[test]
+    fn test_keep_globs_spare_matching_contents() {
+        let fs = MemoryFileSystem::new();
+        fs.add_file("/p/target/release/myapp", 100)
+            .add_file("/p/target/release/deps/myapp-1a2b", 100)
+            .add_file("/p/target/debug/myapp", 50)
+            .add_file("/p/target/.rustc_info.json", 1)
+            .add_file("/w/node_modules/.bin/tsc", 1)
+            .add_file("/w/node_modules/lodash/index.js", 30)
+            .add_file("/w/node_modules/lodash/package.json", 2);
+
+        let plan = CleanPlan::new(
+            vec![
+                PathBuf::from("/p/target"),
+                PathBuf::from("/w/node_modules"),
+                PathBuf::from("/w/node_modules/lodash"),
+            ],
+            false,
+        )
+        .with_keep_globs(PathBuf::from("/p/target"), vec!["release/myapp".into()])
+        .with_keep_globs(PathBuf::from("/w/node_modules"), vec![".bin".into()]);
+        let result = execute_clean_with_fs(&plan, &fs).unwrap();
+
+        assert!(result.is_success());
+        assert_eq!(result.bytes_freed, 183);
+        assert!(fs.exists("/p/target/release/myapp"));
+        assert!(!fs.exists("/p/target/release/deps"));
+        assert!(!fs.exists("/p/target/debug"));
+        assert!(!fs.exists("/p/target/.rustc_info.json"));
+        assert!(fs.exists("/w/node_modules/.bin/tsc"));
+        assert!(!fs.exists("/w/node_modules/lodash"));
+
+        let typo = CleanPlan::new(vec![PathBuf::from("/p/target")], false)
+            .with_keep_globs(PathBuf::from("/p/target"), vec!["release/{myapp".into()]);
+        assert!(matches!(
+            execute_clean_with_fs(&typo, &fs),
+            Err(DevJunkError::InvalidGlob { .. })
+        ));
+        assert!(fs.exists("/p/target/release/myapp"));
+    }
+
+    #[test]
+    fn test_keep_globs_remove_unkept_links_but_not_their_targets() {
+        let fs = MemoryFileSystem::new();
+        fs.add_file("/w/node_modules/.bin/tsc", 1)
+            .add_link("/w/node_modules/lodash", "/store/lodash")
+            .add_file("/store/lodash/index.js", 30);
+
+        let plan = CleanPlan::new(vec![PathBuf::from("/w/node_modules")], true)
+            .with_keep_globs(PathBuf::from("/w/node_modules"), vec![".bin".into()]);
+        let dry_run = execute_clean_with_fs(&plan, &fs).unwrap();
+        assert!(dry_run.is_success());
+        assert_eq!(dry_run.bytes_freed, 0);
+
+        let plan = CleanPlan::new(vec![PathBuf::from("/w/node_modules")], false)
+            .with_keep_globs(PathBuf::from("/w/node_modules"), vec![".bin".into()]);
+        let result = execute_clean_with_fs(&plan, &fs).unwrap();
+
+        assert!(result.is_success());
+        assert!(!fs.exists("/w/node_modules/lodash"));
+        assert!(fs.exists("/w/node_modules/.bin/tsc"));
+        assert!(fs.exists("/store/lodash/index.js"));
+    }
+
+    #[test]
+    fn test_known_sizes_are_not_measured_again() {
+        let fs = MemoryFileSystem::new();
+        fs.add_file("/r/a/node_modules/x.js", 10)
+            .add_file("/r/b/node_modules/y.js", 10)
+            .add_file("/r/c/node_modules/z.js", 10);
+
+        // Sizes as the scan saw them, which the contents no longer match
+        let plan = |path: &str, dry_run| {
+            CleanPlan::new(vec![PathBuf::from(path)], dry_run).with_known_size(
+                PathBuf::from(path),
+                1000,
+                3,
+            )
+        };
+        let dry_run = execute_clean_with_fs(&plan("/r/a/node_modules", true), &fs).unwrap();
+        assert_eq!((dry_run.bytes_freed, dry_run.files_removed()), (1000, 3));
+
+        let removed = execute_clean_with_fs(&plan("/r/a/node_modules", false), &fs).unwrap();
+        assert_eq!((removed.bytes_freed, removed.files_removed()), (1000, 3));
+        assert!(!fs.exists("/r/a/node_modules"));
+
+        let deferred = plan("/r/b/node_modules", false).with_delete_mode(DeleteMode::RenameDefer);
+        let deferred = execute_clean_with_fs(&deferred, &fs).unwrap();
+        assert_eq!(deferred.bytes_freed, 1000);
+
+        // Without both a size and a file count the path is measured
+        let mut unknown = plan("/r/c/node_modules", false);
+        unknown.file_counts.clear();
+        let measured = execute_clean_with_fs(&unknown, &fs).unwrap();
+        assert_eq!(measured.bytes_freed, 10);
+    }
+
+    #[test]
+    fn test_validate_plan_reports_drifted_paths() {
+        let fs = MemoryFileSystem::new();
+        fs.add_file("/r/a/node_modules/x.js", 10)
+            .add_file("/r/b/node_modules/y.js", 10)
+            .add_file("/r/c/target/.rustc_info.json", 10)
+            .add_file("/r/d/node_modules/z.js", 10);
+        let mut plan = CleanPlan::new(
+            [
+                "a/node_modules",
+                "b/node_modules",
+                "c/target",
+                "d/node_modules",
+            ]
+            .iter()
+            .map(|path| Path::new("/r").join(path))
+            .collect(),
+            false,
+        );
+        for path in &plan.paths {
+            plan.sizes.insert(path.clone(), 10);
+            plan.kinds.insert(path.clone(), plan.kind_of(path).unwrap());
+        }
+        assert!(validate_plan_with_fs(&plan, &fs).is_empty());
+
+        fs.add_file("/r/b/node_modules/new.js", 5);
+        fs.remove_file(Path::new("/r/c/target/.rustc_info.json"))
+            .unwrap();
+        fs.remove_dir_all(Path::new("/r/d/node_modules")).unwrap();
+
+        let drift = validate_plan_with_fs(&plan, &fs);
+        assert_eq!(drift.len(), 3);
+        assert_eq!(
+            drift[0],
+            (
+                PathBuf::from("/r/b/node_modules"),
+                PlanDrift::SizeChanged {
+                    planned: 10,
+                    actual: 15
+                }
+            )
+        );
+        assert!(matches!(drift[1].1, PlanDrift::ChangedIdentity { .. }));
+        assert_eq!(
+            drift[2],
+            (PathBuf::from("/r/d/node_modules"), PlanDrift::Missing)
+        );
+    }
+
+    #[test]
+    fn test_strategy_uses_scanned_kind() {
+        let result = ScanResult {
+            items: vec![ScanItem::new(
+                PathBuf::from("/shared/cargo-out"),
+                JunkKind::RustTarget,
+                0,
+                0,
+            )],
+            ..Default::default()
+        };
+
+        let options = CleanOptions::new().with_dry_run(true);
+        let plan = build_clean_plan(&result, &[PathBuf::from("/shared/cargo-out")], &options)
+            .unwrap()
+            .with_strategy(JunkKind::RustTarget, CleanStrategy::SweepRustTarget);
+
+        assert_eq!(
+            plan.strategy_for(Path::new("/shared/cargo-out")),
+            CleanStrategy::SweepRustTarget
+        );
+        assert_eq!(
+            plan.strategy_for(Path::new("/p/node_modules")),
+            CleanStrategy::Remove
+        );
+    }
+
+    #[test]
+    fn test_execute_clean_archives_before_deleting() {
+        let temp = TempDir::new().unwrap();
+        let dist = temp.path().join("project").join("dist");
+        fs::create_dir_all(&dist).unwrap();
+        fs::write(dist.join("bundle.js"), b"one-off build").unwrap();
+        let archives = temp.path().join("archives");
+
+        let mode = DeleteMode::Archive {
+            dir: archives.clone(),
+        };
+        let plan = CleanPlan::new(vec![dist.clone()], false).with_delete_mode(mode);
+        let result = execute_clean(&plan).unwrap();
+
+        assert!(result.is_success());
+        assert!(!dist.exists());
+
+        let entries = crate::archive::list_archives(&archives).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].size_bytes, 13);
+
+        crate::archive::restore_archive(&archives, &entries[0].id).unwrap();
+        assert_eq!(
+            fs::read_to_string(dist.join("bundle.js")).unwrap(),
+            "one-off build"
+        );
+    }
+
+    #[test]
+    fn test_background_delete_frees_paths_before_removing_them() {
+        let fs = MemoryFileSystem::new();
+        fs.add_file("/r/a/node_modules/x.js", 10)
+            .add_file("/r/b/node_modules/y.js", 20);
+        fs.lock("/r/b/node_modules/y.js");
+
+        let plan = CleanPlan::new(
+            vec![
+                PathBuf::from("/r/a/node_modules"),
+                PathBuf::from("/r/b/node_modules"),
+            ],
+            false,
+        )
+        .with_delete_mode(DeleteMode::RenameDefer);
+        let backend = plan.delete_mode.strategy();
+        let mut updates = Vec::new();
+        let result = clean_in(
+            &plan,
+            &fs,
+            backend.as_ref(),
+            &CancelToken::new(),
+            &mut |p| updates.push(p),
+        )
+        .unwrap();
+
+        let stages: Vec<_> = updates.iter().map(|p| (p.stage, p.paths_done)).collect();
+        assert_eq!(
+            stages,
+            vec![
+                (CleanStage::Started, 0),
+                (CleanStage::Moved, 0),
+                (CleanStage::Started, 0),
+                // Locked directories can't be renamed, so deleting in place fails
+                (CleanStage::Failed, 1),
+                (CleanStage::Finished, 2),
+            ]
+        );
+        assert_eq!(
+            paths_of(result.deleted()),
+            vec![PathBuf::from("/r/a/node_modules")]
+        );
+        assert_eq!(result.bytes_freed, 10);
+        assert!(!fs.exists("/r/a/node_modules"));
+        assert!(fs.read_dir(Path::new("/r/a")).unwrap().is_empty());
+        assert!(fs.exists("/r/b/node_modules/y.js"));
+    }
+
+    #[test]
+    fn test_cancel_skips_remaining_paths() {
+        let fs = MemoryFileSystem::new();
+        fs.add_file("/r/a/node_modules/x.js", 10)
+            .add_file("/r/b/node_modules/y.js", 20)
+            .add_file("/r/c/node_modules/z.js", 30);
+        let paths = [
+            "/r/a/node_modules",
+            "/r/b/node_modules",
+            "/r/c/node_modules",
+        ];
+        let plan = CleanPlan::new(paths.iter().map(PathBuf::from).collect(), false);
+
+        // Cancel as soon as the first path is done
+        let backend = plan.delete_mode.strategy();
+        let cancel = CancelToken::new();
+        let result = clean_in(&plan, &fs, backend.as_ref(), &cancel, &mut |p| {
+            if p.stage == CleanStage::Finished {
+                cancel.cancel();
+            }
+        })
+        .unwrap();
+
+        assert!(result.cancelled);
+        assert!(result.is_success());
+        assert_eq!(
+            paths_of(result.deleted()),
+            vec![PathBuf::from("/r/a/node_modules")]
+        );
+        assert_eq!(
+            paths_of(result.skipped()),
+            vec![
+                PathBuf::from("/r/b/node_modules"),
+                PathBuf::from("/r/c/node_modules")
+            ]
+        );
+        assert!(fs.exists("/r/b/node_modules/y.js"));
+        assert!(fs.exists("/r/c/node_modules/z.js"));
+    }
+
+    #[test]
+    fn test_cancel_puts_back_directories_moved_aside() {
+        let fs = MemoryFileSystem::new();
+        fs.add_file("/r/a/node_modules/x.js", 10);
+        let plan = CleanPlan::new(vec![PathBuf::from("/r/a/node_modules")], false)
+            .with_delete_mode(DeleteMode::RenameDefer);
+
+        let backend = plan.delete_mode.strategy();
+        let cancel = CancelToken::new();
+        let result = clean_in(&plan, &fs, backend.as_ref(), &cancel, &mut |p| {
+            if p.stage == CleanStage::Moved {
+                cancel.cancel();
+            }
+        })
+        .unwrap();
+
+        assert!(result.cancelled);
+        assert_eq!(result.deleted_count(), 0);
+        assert_eq!(result.skipped_count(), 1);
+        assert!(fs.exists("/r/a/node_modules/x.js"));
+        assert_eq!(fs.read_dir(Path::new("/r/a")).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_journal_tracks_the_clean_until_it_returns() {
+        let fs = MemoryFileSystem::new();
+        fs.add_file("/r/a/node_modules/x.js", 10)
+            .add_file("/r/b/node_modules/y.js", 20);
+        let journal_dir = TempDir::new().unwrap();
+        let plan = CleanPlan::new(
+            vec![
+                PathBuf::from("/r/a/node_modules"),
+                PathBuf::from("/r/b/node_modules"),
+            ],
+            false,
+        )
+        .with_journal_dir(journal_dir.path().to_path_buf());
+
+        let backend = plan.delete_mode.strategy();
+        let cancel = CancelToken::new();
+        let mut seen = Vec::new();
+        clean_in(&plan, &fs, backend.as_ref(), &cancel, &mut |p| {
+            if p.stage == CleanStage::Finished {
+                let cleans = interrupted_cleans(journal_dir.path()).unwrap();
+                seen.push(cleans[0].remaining());
+            }
+        })
+        .unwrap();
+
+        assert_eq!(seen, vec![vec![PathBuf::from("/r/b/node_modules")], vec![]]);
+        assert!(interrupted_cleans(journal_dir.path()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_clean_result_totals_by_kind() {
+        let fs = MemoryFileSystem::new();
+        fs.add_file("/r/a/node_modules/x.js", 10)
+            .add_file("/r/b/node_modules/y.js", 20)
+            .add_file("/r/c/dist/app.js", 25)
+            .add_file("/r/d/dist/app.js", 5);
+        fs.lock("/r/d/dist/app.js");
+
+        let paths = [
+            "/r/a/node_modules",
+            "/r/b/node_modules",
+            "/r/c/dist",
+            "/r/d/dist",
+        ];
+        let plan = CleanPlan::new(paths.iter().map(PathBuf::from).collect(), false);
+        let result = execute_clean_with_fs(&plan, &fs).unwrap();
+
+        assert_eq!(
+            result.bytes_freed_by_kind(),
+            vec![(JunkKind::NodeModules, 30), (JunkKind::DistDir, 25)]
+        );
+        assert_eq!(
+            result.failures_by_kind(),
+            vec![(CleanErrorKind::PermissionDenied, 1)]
+        );
+        assert_eq!(result.files_removed(), 3);
+        assert!(result
+            .items
+            .iter()
+            .all(|item| item.kind == plan.kind_of(&item.path)));
+    }
+
+    #[test]
+    fn test_execute_clean_reports_locked_and_vanished_paths() {
+        let fs = MemoryFileSystem::new();
+        fs.add_file("/r/a/node_modules/x.js", 10)
+            .add_file("/r/b/node_modules/y.js", 20)
+            .add_dir("/r/c/node_modules");
+        fs.lock("/r/b/node_modules/y.js");
+
+        let paths = [
+            "/r/a/node_modules",
+            "/r/b/node_modules",
+            "/r/c/node_modules",
+        ];
+        let plan = CleanPlan::new(paths.iter().map(PathBuf::from).collect(), false);
+
+        // Deleted by something else between scan and clean
+        fs.remove_dir_all(Path::new("/r/c/node_modules")).unwrap();
+
+        let result = execute_clean_with_fs(&plan, &fs).unwrap();
+
+        assert_eq!(
+            paths_of(result.deleted()),
+            vec![PathBuf::from("/r/a/node_modules")]
+        );
+        assert_eq!(result.bytes_freed, 10);
+        let failed: Vec<_> = result.failed().collect();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].path, PathBuf::from("/r/b/node_modules"));
+        assert_eq!(failed[0].kind, Some(JunkKind::NodeModules));
+        assert_eq!(failed[0].error_kind, Some(CleanErrorKind::PermissionDenied));
+        assert!(fs.exists("/r/b/node_modules/y.js"));
     }
 }
@@ -0,0 +1,519 @@
+//! The user's config file, shared by the CLI and the GUI
+//!
+//! Lives in `devjunk/config.toml` under the platform's config directory
+//! (`~/.config/devjunk/config.toml` on Linux). Every setting is optional, so a
+//! missing or empty file means the built-in defaults, and flags given on the
+//! command line override what the file says.
+//!
+//! The CLI also reads `DEVJUNK_ROOTS`, `DEVJUNK_EXCLUDE` and `DEVJUNK_KINDS`,
+//! which take the place of the file's settings, so containers and CI jobs can
+//! configure devjunk without a file or long argument lists.
+
+use crate::delete::DeleteMode;
+use crate::error::{DevJunkError, Result};
+use crate::globs::compile_globs;
+use crate::types::{CleanOptions, CustomPattern, JunkKind, ScanConfig};
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Settings read from `config.toml`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Directories to scan when none are given
+    pub roots: Vec<PathBuf>,
+    /// Globs of paths never to scan or clean, e.g. `**/work/critical-project/**`
+    pub exclude: Vec<String>,
+    /// Directories never to scan or clean, with everything below them
+    pub ignore: Vec<PathBuf>,
+    /// Built-in junk kinds to look for (None = all of them)
+    pub kinds: Option<Vec<JunkKind>>,
+    /// Extra directory names to treat as junk
+    pub custom_patterns: Vec<CustomPattern>,
+    /// How directories are deleted
+    pub delete_mode: DeleteMode,
+    /// Maximum number of directories deleted at once (None = one per CPU)
+    pub concurrency: Option<usize>,
+    /// Worker threads for scanning and deleting (None = one per CPU)
+    pub threads: Option<usize>,
+    /// How big a clean must be for the CLI to have its size typed out instead of `y`
+    pub typed_confirm: TypedConfirm,
+    /// Preferences only the GUI uses
+    pub gui: GuiSettings,
+}
+
+/// The `[gui]` section, which the CLI ignores
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GuiSettings {
+    /// Color theme of the window
+    pub theme: Theme,
+    /// Minutes between scans of the roots while the GUI runs in the tray (None = never)
+    pub background_scan_minutes: Option<u64>,
+    /// Look up the git status of each item's repository while scanning, which runs git per project
+    pub git_status: bool,
+    /// Named sets of directories to scan together
+    pub workspaces: Vec<Workspace>,
+}
+
+impl GuiSettings {
+    /// Add `workspace`, replacing the one with the same name
+    ///
+    /// Returns false if it replaced one.
+    pub fn set_workspace(&mut self, workspace: Workspace) -> bool {
+        match self
+            .workspaces
+            .iter_mut()
+            .find(|w| w.name == workspace.name)
+        {
+            Some(existing) => {
+                *existing = workspace;
+                false
+            }
+            None => {
+                self.workspaces.push(workspace);
+                true
+            }
+        }
+    }
+
+    /// Remove the workspace called `name`
+    ///
+    /// Returns false if there was none.
+    pub fn remove_workspace(&mut self, name: &str) -> bool {
+        let before = self.workspaces.len();
+        self.workspaces.retain(|workspace| workspace.name != name);
+        self.workspaces.len() != before
+    }
+}
+
+/// A named set of directories, like "Work repos", scanned together in the GUI
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Workspace {
+    pub name: String,
+    pub paths: Vec<PathBuf>,
+}
+
+/// Color theme of the GUI
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Theme {
+    /// Follow the light or dark setting of the OS
+    #[default]
+    System,
+    /// Always light
+    Light,
+    /// Always dark
+    Dark,
+}
+
+/// Thresholds over which a clean asks to type a phrase like `delete 62GB`
+///
+/// Reaching either of them is enough. Cleans run with `--yes` never ask.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TypedConfirm {
+    /// Total size in bytes, 50 GiB by default
+    pub min_bytes: u64,
+    /// Number of directories, 100 by default
+    pub min_dirs: usize,
+}
+
+impl Default for TypedConfirm {
+    fn default() -> Self {
+        Self {
+            min_bytes: 50 * 1024 * 1024 * 1024,
+            min_dirs: 100,
+        }
+    }
+}
+
+impl TypedConfirm {
+    /// Whether deleting `dirs` directories holding `bytes` needs the phrase
+    pub fn applies(&self, bytes: u64, dirs: usize) -> bool {
+        bytes >= self.min_bytes || dirs >= self.min_dirs
+    }
+}
+
+impl Config {
+    /// Where the config file lives, or None if there is no config directory
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("devjunk").join("config.toml"))
+    }
+
+    /// Read the config file at the default path
+    ///
+    /// Returns the defaults if there is no config file.
+    pub fn load_default() -> Result<Self> {
+        match Self::default_path() {
+            Some(path) => Self::load(&path),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Read the config file at `path`, or the defaults if it doesn't exist
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e.into()),
+        };
+        toml::from_str(&contents).map_err(|e| DevJunkError::InvalidConfig {
+            path: path.to_path_buf(),
+            reason: e.message().to_string(),
+        })
+    }
+
+    /// Write the config to `path`, creating its directory if needed
+    ///
+    /// Goes through a temporary file so the CLI never reads a half-written config.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let contents = toml::to_string_pretty(self).map_err(io::Error::other)?;
+        let tmp = path.with_extension("toml.tmp");
+        fs::write(&tmp, contents)?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    /// Replace settings with those of the `DEVJUNK_*` environment variables that are set
+    ///
+    /// `DEVJUNK_ROOTS` is a list of paths like `PATH`, `DEVJUNK_EXCLUDE` a
+    /// comma-separated list of globs and `DEVJUNK_KINDS` of kind ids or aliases.
+    /// Empty variables count as unset. Meant for a run of the CLI only: saving
+    /// the result would write the variables into the file.
+    pub fn with_env(self) -> Result<Self> {
+        self.with_vars(|name| env::var_os(name))
+    }
+
+    fn with_vars(mut self, var: impl Fn(&str) -> Option<OsString>) -> Result<Self> {
+        let var = |name: &str| var(name).filter(|value| !value.is_empty());
+        if let Some(roots) = var("DEVJUNK_ROOTS") {
+            self.roots = env::split_paths(&roots).collect();
+        }
+        if let Some(exclude) = var("DEVJUNK_EXCLUDE") {
+            self.exclude = split_list(&text_var("DEVJUNK_EXCLUDE", exclude)?);
+        }
+        if let Some(kinds) = var("DEVJUNK_KINDS") {
+            let invalid = |e: DevJunkError| DevJunkError::InvalidEnvVar {
+                name: "DEVJUNK_KINDS".into(),
+                reason: e.to_string(),
+            };
+            let kinds = split_list(&text_var("DEVJUNK_KINDS", kinds)?)
+                .iter()
+                .map(|name| name.parse().map_err(invalid))
+                .collect::<Result<_>>()?;
+            self.kinds = Some(kinds);
+        }
+        Ok(self)
+    }
+
+    /// Add `path` to the ignored directories
+    ///
+    /// Returns false if it was ignored already.
+    pub fn add_ignore(&mut self, path: PathBuf) -> bool {
+        if self.ignore.contains(&path) {
+            return false;
+        }
+        self.ignore.push(path);
+        true
+    }
+
+    /// Stop ignoring `path`
+    ///
+    /// Returns false if it wasn't ignored.
+    pub fn remove_ignore(&mut self, path: &Path) -> bool {
+        let before = self.ignore.len();
+        self.ignore.retain(|ignored| ignored != path);
+        self.ignore.len() != before
+    }
+
+    /// Add `pattern`, replacing the custom pattern with the same name
+    ///
+    /// Returns false if it replaced one. An invalid glob is refused, since
+    /// it would fail every scan.
+    pub fn set_custom_pattern(&mut self, pattern: CustomPattern) -> Result<bool> {
+        compile_globs(std::slice::from_ref(&pattern.glob))?;
+        match self
+            .custom_patterns
+            .iter_mut()
+            .find(|p| p.name == pattern.name)
+        {
+            Some(existing) => {
+                *existing = pattern;
+                Ok(false)
+            }
+            None => {
+                self.custom_patterns.push(pattern);
+                Ok(true)
+            }
+        }
+    }
+
+    /// Remove the custom pattern called `name`
+    ///
+    /// Returns false if there was none.
+    pub fn remove_custom_pattern(&mut self, name: &str) -> bool {
+        let before = self.custom_patterns.len();
+        self.custom_patterns.retain(|pattern| pattern.name != name);
+        self.custom_patterns.len() != before
+    }
+
+    /// Apply the scan settings to `config`
+    ///
+    /// The configured roots are only used when `config` has none.
+    pub fn scan_config(&self, mut config: ScanConfig) -> ScanConfig {
+        if config.roots.is_empty() {
+            config.roots.clone_from(&self.roots);
+        }
+        if let Some(kinds) = &self.kinds {
+            config.include_patterns.clone_from(kinds);
+        }
+        config.exclude_paths.extend(self.ignore.iter().cloned());
+        config.exclude_globs.extend(self.exclude.iter().cloned());
+        config
+            .custom_patterns
+            .extend(self.custom_patterns.iter().cloned());
+        config
+    }
+
+    /// Clean options with the configured delete mode and concurrency
+    pub fn clean_options(&self) -> CleanOptions {
+        let options = CleanOptions::new().with_delete_mode(self.delete_mode.clone());
+        match self.concurrency {
+            Some(concurrency) => options.with_concurrency(concurrency),
+            None => options,
+        }
+    }
+}
+
+/// The value of a variable that must be text, unlike a list of paths
+fn text_var(name: &str, value: OsString) -> Result<String> {
+    value
+        .into_string()
+        .map_err(|_| DevJunkError::InvalidEnvVar {
+            name: name.into(),
+            reason: "not valid UTF-8".into(),
+        })
+}
+
+/// Split a comma-separated list, keeping commas inside glob braces like `{a,b}`
+fn split_list(list: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut item = String::new();
+    let mut depth = 0usize;
+    for c in list.chars() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                items.push(std::mem::take(&mut item));
+                continue;
+            }
+            _ => {}
+        }
+        item.push(c);
+    }
+    items.push(item);
+    items
+        .into_iter()
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_missing_config_is_the_default() {
+        let temp = TempDir::new().unwrap();
+        let config = Config::load(&temp.path().join("config.toml")).unwrap();
+        assert_eq!(config, Config::default());
+    }
+
+    #[test]
+    fn test_config_round_trips_through_toml() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("devjunk").join("config.toml");
+        let config = Config {
+            roots: vec![PathBuf::from("/home/me/projects")],
+            exclude: vec!["**/critical/**".to_string()],
+            ignore: vec![PathBuf::from("/home/me/work/legacy/vendor")],
+            kinds: Some(vec![JunkKind::NodeModules, JunkKind::RustTarget]),
+            custom_patterns: vec![CustomPattern::new("Bazel cache", ".bazel-cache-*")],
+            delete_mode: DeleteMode::Trash,
+            concurrency: Some(2),
+            threads: Some(4),
+            typed_confirm: TypedConfirm {
+                min_bytes: 1 << 30,
+                min_dirs: 20,
+            },
+            gui: GuiSettings {
+                theme: Theme::Dark,
+                background_scan_minutes: Some(240),
+                git_status: true,
+                workspaces: vec![Workspace {
+                    name: "Work repos".into(),
+                    paths: vec![PathBuf::from("/work/api"), PathBuf::from("/work/web")],
+                }],
+            },
+        };
+
+        config.save(&path).unwrap();
+        assert_eq!(Config::load(&path).unwrap(), config);
+    }
+
+    #[test]
+    fn test_config_reads_a_handwritten_file() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("config.toml");
+        fs::write(
+            &path,
+            "roots = [\"/src\"]\n\
+             kinds = [\"node_modules\"]\n\
+             \n\
+             [delete_mode]\n\
+             mode = \"rename_defer\"\n\
+             \n\
+             [typed_confirm]\n\
+             min_dirs = 10\n\
+             \n\
+             [gui]\n\
+             theme = \"light\"\n\
+             \n\
+             [[custom_patterns]]\n\
+             name = \"Bazel cache\"\n\
+             glob = \".bazel-cache-*\"\n",
+        )
+        .unwrap();
+
+        let config = Config::load(&path).unwrap();
+        let scan = config.scan_config(ScanConfig::default());
+        assert_eq!(scan.roots, [PathBuf::from("/src")]);
+        assert_eq!(scan.include_patterns, [JunkKind::NodeModules]);
+        assert_eq!(scan.custom_patterns.len(), 1);
+        assert_eq!(config.clean_options().delete_mode, DeleteMode::RenameDefer);
+        assert_eq!(config.typed_confirm.min_dirs, 10);
+        assert_eq!(
+            config.typed_confirm.min_bytes,
+            TypedConfirm::default().min_bytes
+        );
+        assert_eq!(config.gui.theme, Theme::Light);
+
+        // Roots given explicitly win over the configured ones
+        let scan = config.scan_config(ScanConfig::new(vec![PathBuf::from("/other")]));
+        assert_eq!(scan.roots, [PathBuf::from("/other")]);
+    }
+
+    #[test]
+    fn test_workspaces_are_replaced_by_name() {
+        let mut gui = GuiSettings::default();
+        let workspace = |name: &str, path: &str| Workspace {
+            name: name.into(),
+            paths: vec![PathBuf::from(path)],
+        };
+        assert!(gui.set_workspace(workspace("Work", "/work")));
+        assert!(gui.set_workspace(workspace("Personal", "/home/me/src")));
+        assert!(!gui.set_workspace(workspace("Work", "/work2")));
+        assert_eq!(gui.workspaces.len(), 2);
+        assert_eq!(gui.workspaces[0].paths, [PathBuf::from("/work2")]);
+
+        assert!(gui.remove_workspace("Work"));
+        assert!(!gui.remove_workspace("Work"));
+        assert_eq!(gui.workspaces[0].name, "Personal");
+    }
+
+    #[test]
+    fn test_custom_patterns_are_replaced_by_name() {
+        let mut config = Config::default();
+        assert!(config
+            .set_custom_pattern(CustomPattern::new("Bazel cache", ".bazel-cache-*"))
+            .unwrap());
+        assert!(!config
+            .set_custom_pattern(CustomPattern::new("Bazel cache", ".bazel-*"))
+            .unwrap());
+        assert!(config
+            .set_custom_pattern(CustomPattern::new("Broken", "[a-"))
+            .is_err());
+        assert_eq!(
+            config.custom_patterns,
+            [CustomPattern::new("Bazel cache", ".bazel-*")]
+        );
+
+        assert!(config.remove_custom_pattern("Bazel cache"));
+        assert!(!config.remove_custom_pattern("Bazel cache"));
+    }
+
+    #[test]
+    fn test_ignored_directories_are_left_out_of_scans() {
+        let mut config = Config::default();
+        let vendor = PathBuf::from("/home/me/work/legacy/vendor");
+        assert!(config.add_ignore(vendor.clone()));
+        assert!(!config.add_ignore(vendor.clone()));
+        assert!(config.add_ignore(PathBuf::from("/tmp/other")));
+
+        let scan = config.scan_config(ScanConfig::for_home(PathBuf::from("/home/me")));
+        assert!(scan.exclude_paths.contains(&vendor));
+
+        assert!(config.remove_ignore(&vendor));
+        assert!(!config.remove_ignore(&vendor));
+        assert_eq!(config.ignore, [PathBuf::from("/tmp/other")]);
+    }
+
+    #[test]
+    fn test_env_vars_replace_the_file_settings() {
+        let file = Config {
+            roots: vec![PathBuf::from("/from/file")],
+            exclude: vec!["**/keep/**".to_string()],
+            ..Config::default()
+        };
+        let roots = env::join_paths(["/ci/a", "/ci/b"]).unwrap();
+        let vars = |name: &str| match name {
+            "DEVJUNK_ROOTS" => Some(roots.clone()),
+            "DEVJUNK_EXCLUDE" => Some(OsString::from("**/{vendor,third_party}/**, **/keep/**")),
+            "DEVJUNK_KINDS" => Some(OsString::from("nm,rust")),
+            _ => None,
+        };
+
+        let config = file.clone().with_vars(vars).unwrap();
+        assert_eq!(
+            config.roots,
+            [PathBuf::from("/ci/a"), PathBuf::from("/ci/b")]
+        );
+        assert_eq!(config.exclude, ["**/{vendor,third_party}/**", "**/keep/**"]);
+        assert_eq!(
+            config.kinds,
+            Some(vec![JunkKind::NodeModules, JunkKind::RustTarget])
+        );
+
+        // Unset and empty variables leave the file alone
+        let empty = |_: &str| Some(OsString::new());
+        assert_eq!(file.clone().with_vars(empty).unwrap(), file);
+        let unknown = |name: &str| (name == "DEVJUNK_KINDS").then(|| OsString::from("nm,bogus"));
+        assert!(matches!(
+            file.with_vars(unknown),
+            Err(DevJunkError::InvalidEnvVar { .. })
+        ));
+    }
+
+    #[test]
+    fn test_invalid_config_names_the_file() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("config.toml");
+        fs::write(&path, "concurrency = \"lots\"\n").unwrap();
+
+        match Config::load(&path) {
+            Err(DevJunkError::InvalidConfig { path: bad, .. }) => assert_eq!(bad, path),
+            other => panic!("expected an invalid config error, got {:?}", other),
+        }
+    }
+}
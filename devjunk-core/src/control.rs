@@ -0,0 +1,144 @@
+//! Pause, resume and cancellation of running scans, and cancellation of running cleans
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+
+/// Shared handle for pausing, resuming and cancelling a running scan
+///
+/// Clones share the same state, so one clone can be handed to the scan
+/// while another stays with the UI. The walker checks the handle between
+/// directory entries and blocks while paused, keeping everything found so
+/// far. Once cancelled it stops walking, paused or not.
+#[derive(Debug, Clone, Default)]
+pub struct ScanControl {
+    inner: Arc<ControlState>,
+}
+
+#[derive(Debug, Default)]
+struct ControlState {
+    /// Fast-path flag polled by the walker without taking the lock
+    paused: AtomicBool,
+    cancelled: AtomicBool,
+    lock: Mutex<()>,
+    resumed: Condvar,
+}
+
+impl ScanControl {
+    /// Create a new, unpaused control handle
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Pause the scan at the next directory entry
+    pub fn pause(&self) {
+        let _guard = self.inner.lock.lock().unwrap_or_else(|e| e.into_inner());
+        self.inner.paused.store(true, Ordering::SeqCst);
+    }
+
+    /// Resume a paused scan
+    pub fn resume(&self) {
+        let _guard = self.inner.lock.lock().unwrap_or_else(|e| e.into_inner());
+        self.inner.paused.store(false, Ordering::SeqCst);
+        self.inner.resumed.notify_all();
+    }
+
+    /// Whether the scan is currently paused
+    pub fn is_paused(&self) -> bool {
+        self.inner.paused.load(Ordering::SeqCst)
+    }
+
+    /// Stop the scan at the next directory entry, even while paused
+    pub fn cancel(&self) {
+        let _guard = self.inner.lock.lock().unwrap_or_else(|e| e.into_inner());
+        self.inner.cancelled.store(true, Ordering::SeqCst);
+        self.inner.resumed.notify_all();
+    }
+
+    /// Whether the scan has been asked to stop
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Block the calling thread until the scan is resumed or cancelled
+    pub(crate) fn wait_while_paused(&self) {
+        if !self.is_paused() {
+            return;
+        }
+
+        let mut guard = self.inner.lock.lock().unwrap_or_else(|e| e.into_inner());
+        while self.is_paused() && !self.is_cancelled() {
+            guard = self
+                .inner
+                .resumed
+                .wait(guard)
+                .unwrap_or_else(|e| e.into_inner());
+        }
+    }
+}
+
+/// Shared handle for stopping a running clean
+///
+/// Clones share the same flag. The cleaner checks it before each path, so
+/// the directory being deleted when it is cancelled is still finished.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancelToken {
+    /// Create a new, uncancelled token
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ask the clean to stop before its next path
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    /// Whether the clean has been asked to stop
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn test_wait_blocks_until_resumed() {
+        let control = ScanControl::new();
+        control.pause();
+        assert!(control.is_paused());
+
+        let waiter = {
+            let control = control.clone();
+            thread::spawn(move || control.wait_while_paused())
+        };
+
+        thread::sleep(Duration::from_millis(50));
+        assert!(!waiter.is_finished());
+
+        control.resume();
+        waiter.join().unwrap();
+        assert!(!control.is_paused());
+    }
+
+    #[test]
+    fn test_cancel_releases_a_paused_scan() {
+        let control = ScanControl::new();
+        control.pause();
+
+        let waiter = {
+            let control = control.clone();
+            thread::spawn(move || control.wait_while_paused())
+        };
+
+        control.cancel();
+        waiter.join().unwrap();
+        assert!(control.is_cancelled());
+    }
+}
@@ -0,0 +1,351 @@
+//! Backends that remove a directory once the cleaner has decided to delete it
+//!
+//! The cleaner decides *what* to delete; a [`DeleteStrategy`] decides *how*.
+//! [`DeleteMode`] selects one of the built-in strategies from a `CleanPlan`,
+//! and `execute_clean_with_strategy` accepts any other implementation.
+
+use crate::archive::archive_directory;
+use crate::filesystem::{FileKind, FileSystem, Walk};
+use crate::types::CleanErrorKind;
+use serde::{Deserialize, Serialize};
+use std::ffi::OsString;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// What a DeleteStrategy did with a directory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Deletion {
+    /// The directory is gone, having held this many bytes and files
+    Removed { bytes: u64, files: u64 },
+    /// The directory was moved here; the cleaner finishes it after the other paths
+    Deferred(PathBuf),
+}
+
+/// Why a DeleteStrategy could not delete a directory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeleteError {
+    /// Category of the failure
+    pub kind: CleanErrorKind,
+    /// Human-readable cause
+    pub message: String,
+}
+
+impl DeleteError {
+    /// Create a new DeleteError
+    pub fn new(kind: CleanErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<io::Error> for DeleteError {
+    fn from(e: io::Error) -> Self {
+        Self::new(error_kind(&e), e.to_string())
+    }
+}
+
+impl std::fmt::Display for DeleteError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+/// A way of removing directories
+///
+/// Implementations must be safe to call from several threads at once.
+pub trait DeleteStrategy: Send + Sync {
+    /// Remove the directory at `path`
+    fn delete(&self, fs: &dyn FileSystem, path: &Path) -> Result<Deletion, DeleteError>;
+
+    /// Remove the directory at `path`, whose size and file count are already known
+    ///
+    /// Lets the cleaner pass on what the scan measured instead of walking the
+    /// directory again. Defaults to `delete`, which measures it itself.
+    fn delete_measured(
+        &self,
+        fs: &dyn FileSystem,
+        path: &Path,
+        _bytes: u64,
+        _files: u64,
+    ) -> Result<Deletion, DeleteError> {
+        self.delete(fs, path)
+    }
+
+    /// Remove a directory this strategy deferred to `moved`
+    ///
+    /// Deferred directories are finished in parallel once every path has been handled.
+    fn finish(&self, fs: &dyn FileSystem, moved: &Path) -> io::Result<()> {
+        remove_entry(fs, moved)
+    }
+
+    /// Put a deferred directory back where it was, when the clean is cancelled
+    fn restore(&self, fs: &dyn FileSystem, moved: &Path, original: &Path) -> io::Result<()> {
+        fs.rename(moved, original)
+    }
+}
+
+/// Delete directories outright
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PermanentDelete;
+
+impl DeleteStrategy for PermanentDelete {
+    fn delete(&self, fs: &dyn FileSystem, path: &Path) -> Result<Deletion, DeleteError> {
+        let (bytes, files) = dir_stats(fs, path);
+        self.delete_measured(fs, path, bytes, files)
+    }
+
+    fn delete_measured(
+        &self,
+        fs: &dyn FileSystem,
+        path: &Path,
+        bytes: u64,
+        files: u64,
+    ) -> Result<Deletion, DeleteError> {
+        remove_entry(fs, path)?;
+        Ok(Deletion::Removed { bytes, files })
+    }
+}
+
+/// Move directories to the system trash or recycle bin
+///
+/// Always uses the real filesystem, whatever `fs` is given.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TrashDelete;
+
+impl DeleteStrategy for TrashDelete {
+    fn delete(&self, fs: &dyn FileSystem, path: &Path) -> Result<Deletion, DeleteError> {
+        let (bytes, files) = dir_stats(fs, path);
+        self.delete_measured(fs, path, bytes, files)
+    }
+
+    fn delete_measured(
+        &self,
+        _fs: &dyn FileSystem,
+        path: &Path,
+        bytes: u64,
+        files: u64,
+    ) -> Result<Deletion, DeleteError> {
+        trash::delete(path).map_err(|e| {
+            DeleteError::new(
+                CleanErrorKind::Other,
+                format!("Failed to move to trash: {}", e),
+            )
+        })?;
+        Ok(Deletion::Removed { bytes, files })
+    }
+}
+
+/// Archive each directory as tar.zst before deleting it, so it can be restored
+///
+/// Archives are always written from the real filesystem.
+#[derive(Debug, Clone)]
+pub struct ArchiveDelete {
+    dir: PathBuf,
+}
+
+impl ArchiveDelete {
+    /// Archive into `dir`, which is created if needed
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+}
+
+impl DeleteStrategy for ArchiveDelete {
+    fn delete(&self, fs: &dyn FileSystem, path: &Path) -> Result<Deletion, DeleteError> {
+        let (bytes, files) = dir_stats(fs, path);
+        self.delete_measured(fs, path, bytes, files)
+    }
+
+    fn delete_measured(
+        &self,
+        fs: &dyn FileSystem,
+        path: &Path,
+        bytes: u64,
+        files: u64,
+    ) -> Result<Deletion, DeleteError> {
+        // Never delete a directory that could not be archived
+        archive_directory(path, &self.dir, bytes).map_err(|e| {
+            DeleteError::new(
+                CleanErrorKind::ArchiveFailed,
+                format!("Failed to archive: {}", e),
+            )
+        })?;
+
+        remove_entry(fs, path)?;
+        Ok(Deletion::Removed { bytes, files })
+    }
+}
+
+/// Rename each directory to a hidden sibling right away and delete it afterwards
+///
+/// The original path is free as soon as the rename is done, so builds can
+/// recreate it while the old contents are still being removed. Directories
+/// that can't be renamed are deleted in place.
+#[derive(Debug, Default)]
+pub struct RenameDefer {
+    /// Makes the hidden names unique within this process
+    renamed: AtomicUsize,
+}
+
+impl RenameDefer {
+    /// Create a new RenameDefer strategy
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A hidden sibling of `path` to move it to
+    fn hidden_sibling(&self, path: &Path) -> Option<PathBuf> {
+        let n = self.renamed.fetch_add(1, Ordering::Relaxed);
+        let mut hidden = OsString::from(".");
+        hidden.push(path.file_name()?);
+        hidden.push(format!(".devjunk-delete-{}-{}", std::process::id(), n));
+        Some(path.with_file_name(hidden))
+    }
+
+    /// Move `path` to a hidden sibling, returning where it went
+    fn move_aside(&self, fs: &dyn FileSystem, path: &Path) -> Option<PathBuf> {
+        let moved = self.hidden_sibling(path)?;
+        fs.rename(path, &moved).ok()?;
+        Some(moved)
+    }
+}
+
+impl DeleteStrategy for RenameDefer {
+    fn delete(&self, fs: &dyn FileSystem, path: &Path) -> Result<Deletion, DeleteError> {
+        match self.move_aside(fs, path) {
+            Some(moved) => Ok(Deletion::Deferred(moved)),
+            None => PermanentDelete.delete(fs, path),
+        }
+    }
+
+    fn delete_measured(
+        &self,
+        fs: &dyn FileSystem,
+        path: &Path,
+        bytes: u64,
+        files: u64,
+    ) -> Result<Deletion, DeleteError> {
+        match self.move_aside(fs, path) {
+            Some(moved) => Ok(Deletion::Deferred(moved)),
+            None => PermanentDelete.delete_measured(fs, path, bytes, files),
+        }
+    }
+}
+
+/// Built-in delete strategies, as recorded in a CleanPlan
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "mode")]
+pub enum DeleteMode {
+    /// Delete directories outright
+    #[default]
+    Permanent,
+    /// Move directories to the system trash
+    Trash,
+    /// Archive directories into `dir` before deleting them
+    Archive { dir: PathBuf },
+    /// Free each path instantly by renaming it, then delete it afterwards
+    RenameDefer,
+}
+
+impl DeleteMode {
+    /// The strategy implementing this mode
+    pub fn strategy(&self) -> Box<dyn DeleteStrategy> {
+        match self {
+            Self::Permanent => Box::new(PermanentDelete),
+            Self::Trash => Box::new(TrashDelete),
+            Self::Archive { dir } => Box::new(ArchiveDelete::new(dir.clone())),
+            Self::RenameDefer => Box::new(RenameDefer::new()),
+        }
+    }
+}
+
+/// Delete a directory, or a single file or link left over from cleaning with keep globs
+///
+/// A link is removed on its own, never the directory it points to.
+fn remove_entry(fs: &dyn FileSystem, path: &Path) -> io::Result<()> {
+    match fs.metadata(path)?.kind {
+        FileKind::File | FileKind::Link => fs.remove_file(path),
+        FileKind::Dir => fs.remove_dir_all(path),
+    }
+}
+
+/// Total size and file count of a directory (or the size of a single file or link)
+pub fn dir_stats(fs: &dyn FileSystem, path: &Path) -> (u64, u64) {
+    // Deleting a link frees the link, not what it points to
+    if let Ok(meta) = fs.metadata(path) {
+        if meta.kind == FileKind::Link {
+            return (meta.len, 1);
+        }
+    }
+    Walk::new(fs, path)
+        .filter_map(|e| e.ok())
+        .filter(|e| e.kind == FileKind::File)
+        .filter_map(|e| fs.metadata(&e.path).ok())
+        .fold((0, 0), |(size, files), m| (size + m.len, files + 1))
+}
+
+/// Categorise an IO error from deleting or renaming a directory
+fn error_kind(e: &io::Error) -> CleanErrorKind {
+    #[cfg(unix)]
+    const IN_USE: &[i32] = &[libc::EBUSY, libc::ETXTBSY];
+    // ERROR_SHARING_VIOLATION and ERROR_LOCK_VIOLATION
+    #[cfg(windows)]
+    const IN_USE: &[i32] = &[32, 33];
+    #[cfg(not(any(unix, windows)))]
+    const IN_USE: &[i32] = &[];
+
+    if e.raw_os_error().is_some_and(|code| IN_USE.contains(&code)) {
+        return CleanErrorKind::InUse;
+    }
+    match e.kind() {
+        io::ErrorKind::PermissionDenied => CleanErrorKind::PermissionDenied,
+        io::ErrorKind::NotFound => CleanErrorKind::NotFound,
+        _ => CleanErrorKind::Other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filesystem::MemoryFileSystem;
+
+    #[test]
+    fn test_rename_defer_moves_directories_aside() {
+        let fs = MemoryFileSystem::new();
+        fs.add_file("/r/a/node_modules/x.js", 10)
+            .add_file("/r/b/node_modules/y.js", 20);
+        let strategy = RenameDefer::new();
+
+        let moved = match strategy.delete(&fs, Path::new("/r/a/node_modules")) {
+            Ok(Deletion::Deferred(moved)) => moved,
+            other => panic!("expected a deferred deletion, got {:?}", other),
+        };
+        assert!(!fs.exists("/r/a/node_modules"));
+        assert!(fs.exists(moved.join("x.js")));
+        strategy.finish(&fs, &moved).unwrap();
+        assert!(!fs.exists(&moved));
+
+        let other = strategy
+            .delete(&fs, Path::new("/r/b/node_modules"))
+            .unwrap();
+        let other = match other {
+            Deletion::Deferred(moved) => moved,
+            removed => panic!("expected a deferred deletion, got {:?}", removed),
+        };
+        assert_ne!(other, moved);
+    }
+
+    #[test]
+    fn test_mode_round_trips_through_json() {
+        let mode = DeleteMode::Archive {
+            dir: PathBuf::from("/archives"),
+        };
+        let json = serde_json::to_string(&mode).unwrap();
+
+        assert_eq!(json, r#"{"mode":"archive","dir":"/archives"}"#);
+        assert_eq!(serde_json::from_str::<DeleteMode>(&json).unwrap(), mode);
+    }
+}
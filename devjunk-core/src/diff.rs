@@ -0,0 +1,253 @@
+//! Comparison of scan results and clean plans
+
+use crate::types::{CleanPlan, JunkKind, ScanResult};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// A single path that differs between two scans or plans
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiffEntry {
+    /// Full path to the junk directory
+    pub path: PathBuf,
+    /// Type of junk, if known
+    pub kind: Option<JunkKind>,
+    /// Size in the old scan/plan (None if the path was added)
+    pub old_size_bytes: Option<u64>,
+    /// Size in the new scan/plan (None if the path was removed)
+    pub new_size_bytes: Option<u64>,
+    /// Signed size change from old to new
+    pub size_delta_bytes: i64,
+}
+
+impl DiffEntry {
+    fn new(
+        path: PathBuf,
+        kind: Option<JunkKind>,
+        old_size_bytes: Option<u64>,
+        new_size_bytes: Option<u64>,
+    ) -> Self {
+        let size_delta_bytes =
+            new_size_bytes.unwrap_or(0) as i64 - old_size_bytes.unwrap_or(0) as i64;
+        Self {
+            path,
+            kind,
+            old_size_bytes,
+            new_size_bytes,
+            size_delta_bytes,
+        }
+    }
+}
+
+/// Differences between two scans or plans, ordered by path
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScanDiff {
+    /// Paths present only in the new scan/plan
+    pub added: Vec<DiffEntry>,
+    /// Paths present only in the old scan/plan
+    pub removed: Vec<DiffEntry>,
+    /// Paths present in both with a different size or kind
+    pub changed: Vec<DiffEntry>,
+}
+
+impl ScanDiff {
+    /// Whether the two inputs were identical
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /// Net size change across all entries
+    pub fn total_delta_bytes(&self) -> i64 {
+        self.added
+            .iter()
+            .chain(&self.removed)
+            .chain(&self.changed)
+            .map(|e| e.size_delta_bytes)
+            .sum()
+    }
+
+    /// Net size change per project, the directory holding each path, largest growth first
+    ///
+    /// Projects whose paths add up to no change are left out.
+    pub fn by_project(&self) -> Vec<(&Path, i64)> {
+        let mut projects: BTreeMap<&Path, i64> = BTreeMap::new();
+        for entry in self.added.iter().chain(&self.removed).chain(&self.changed) {
+            let project = entry.path.parent().unwrap_or(&entry.path);
+            *projects.entry(project).or_default() += entry.size_delta_bytes;
+        }
+        let mut projects: Vec<_> = projects.into_iter().filter(|&(_, d)| d != 0).collect();
+        projects.sort_by_key(|&(_, delta)| std::cmp::Reverse(delta));
+        projects
+    }
+}
+
+/// Compare two scan results item by item
+pub fn diff_scans(old: &ScanResult, new: &ScanResult) -> ScanDiff {
+    let collect = |result: &ScanResult| {
+        result
+            .items
+            .iter()
+            .map(|item| (item.path.clone(), (Some(item.kind), item.size_bytes)))
+            .collect::<BTreeMap<_, _>>()
+    };
+
+    diff_maps(collect(old), collect(new))
+}
+
+/// Compare two clean plans path by path
+///
+/// Kinds that weren't recorded are inferred from directory names, and sizes
+/// that weren't recorded are reported as zero.
+pub fn diff_plans(old: &CleanPlan, new: &CleanPlan) -> ScanDiff {
+    let collect = |plan: &CleanPlan| {
+        plan.paths
+            .iter()
+            .map(|path| {
+                let size = plan.sizes.get(path).copied().unwrap_or(0);
+                (path.clone(), (plan.kind_of(path), size))
+            })
+            .collect::<BTreeMap<_, _>>()
+    };
+
+    diff_maps(collect(old), collect(new))
+}
+
+type EntryMap = BTreeMap<PathBuf, (Option<JunkKind>, u64)>;
+
+fn diff_maps(old: EntryMap, mut new: EntryMap) -> ScanDiff {
+    let mut diff = ScanDiff::default();
+
+    for (path, (old_kind, old_size)) in old {
+        match new.remove(&path) {
+            Some((new_kind, new_size)) => {
+                if old_kind != new_kind || old_size != new_size {
+                    diff.changed.push(DiffEntry::new(
+                        path,
+                        new_kind.or(old_kind),
+                        Some(old_size),
+                        Some(new_size),
+                    ));
+                }
+            }
+            None => {
+                diff.removed
+                    .push(DiffEntry::new(path, old_kind, Some(old_size), None));
+            }
+        }
+    }
+
+    for (path, (kind, size)) in new {
+        diff.added
+            .push(DiffEntry::new(path, kind, None, Some(size)));
+    }
+
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::ScanItem;
+
+    fn result(items: Vec<ScanItem>) -> ScanResult {
+        ScanResult {
+            items,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_diff_scans_reports_added_removed_changed() {
+        let old = result(vec![
+            ScanItem::new(
+                PathBuf::from("/a/node_modules"),
+                JunkKind::NodeModules,
+                100,
+                1,
+            ),
+            ScanItem::new(PathBuf::from("/b/target"), JunkKind::RustTarget, 500, 5),
+            ScanItem::new(PathBuf::from("/c/dist"), JunkKind::DistDir, 10, 1),
+        ]);
+        let new = result(vec![
+            ScanItem::new(PathBuf::from("/b/target"), JunkKind::RustTarget, 800, 8),
+            ScanItem::new(PathBuf::from("/c/dist"), JunkKind::DistDir, 10, 1),
+            ScanItem::new(PathBuf::from("/d/.venv"), JunkKind::PythonVenv, 50, 2),
+        ]);
+
+        let diff = diff_scans(&old, &new);
+
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.added[0].path, PathBuf::from("/d/.venv"));
+        assert_eq!(diff.removed.len(), 1);
+        assert_eq!(diff.removed[0].size_delta_bytes, -100);
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].size_delta_bytes, 300);
+        assert_eq!(diff.total_delta_bytes(), 250);
+    }
+
+    #[test]
+    fn test_diff_by_project_orders_by_growth() {
+        let old = result(vec![
+            ScanItem::new(
+                PathBuf::from("/a/node_modules"),
+                JunkKind::NodeModules,
+                100,
+                1,
+            ),
+            ScanItem::new(PathBuf::from("/a/dist"), JunkKind::DistDir, 10, 1),
+            ScanItem::new(PathBuf::from("/b/target"), JunkKind::RustTarget, 500, 5),
+            ScanItem::new(PathBuf::from("/c/.venv"), JunkKind::PythonVenv, 50, 1),
+        ]);
+        let new = result(vec![
+            ScanItem::new(
+                PathBuf::from("/a/node_modules"),
+                JunkKind::NodeModules,
+                90,
+                1,
+            ),
+            ScanItem::new(PathBuf::from("/a/dist"), JunkKind::DistDir, 20, 1),
+            ScanItem::new(PathBuf::from("/b/target"), JunkKind::RustTarget, 4000, 40),
+            ScanItem::new(PathBuf::from("/d/.venv"), JunkKind::PythonVenv, 70, 1),
+        ]);
+
+        let diff = diff_scans(&old, &new);
+
+        assert_eq!(
+            diff.by_project(),
+            [
+                (Path::new("/b"), 3500),
+                (Path::new("/d"), 70),
+                (Path::new("/c"), -50)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diff_is_ordered_by_path() {
+        let old = result(Vec::new());
+        let new = result(vec![
+            ScanItem::new(PathBuf::from("/z/target"), JunkKind::RustTarget, 1, 1),
+            ScanItem::new(PathBuf::from("/a/target"), JunkKind::RustTarget, 2, 1),
+        ]);
+
+        let diff = diff_scans(&old, &new);
+        let paths: Vec<_> = diff.added.iter().map(|e| e.path.clone()).collect();
+
+        assert_eq!(
+            paths,
+            vec![PathBuf::from("/a/target"), PathBuf::from("/z/target")]
+        );
+    }
+
+    #[test]
+    fn test_diff_plans_infers_kind() {
+        let old = CleanPlan::new(vec![PathBuf::from("/a/node_modules")], false);
+        let new = CleanPlan::new(vec![PathBuf::from("/b/__pycache__")], false);
+
+        let diff = diff_plans(&old, &new);
+
+        assert_eq!(diff.added[0].kind, Some(JunkKind::PythonCache));
+        assert_eq!(diff.removed[0].kind, Some(JunkKind::NodeModules));
+        assert!(diff_plans(&old, &old).is_empty());
+    }
+}
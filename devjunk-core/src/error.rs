@@ -49,6 +49,50 @@ pub enum DevJunkError {
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
+    /// A path the cleaner must never delete
+    #[error("Refusing to delete {path}: {reason}")]
+    UnsafePath { path: PathBuf, reason: String },
+
+    /// No archive with the given id exists in the archive directory
+    #[error("Archive not found: {0}")]
+    ArchiveNotFound(String),
+
+    /// A keep, exclude or custom pattern glob could not be parsed
+    #[error("Invalid glob '{pattern}': {reason}")]
+    InvalidGlob { pattern: String, reason: String },
+
+    /// The config file could not be parsed
+    #[error("Invalid config file {path}: {reason}")]
+    InvalidConfig { path: PathBuf, reason: String },
+
+    /// A `DEVJUNK_*` environment variable could not be parsed
+    #[error("Invalid {name}: {reason}")]
+    InvalidEnvVar { name: String, reason: String },
+
+    /// A junk kind name that is neither a kind id nor one of its aliases
+    #[error("Unknown junk kind '{0}'")]
+    UnknownKind(String),
+
+    /// Something already exists where an archive would be restored
+    #[error("Restore target already exists: {0}")]
+    RestoreTargetExists(PathBuf),
+
+    /// A schedule with a name or time the schedulers can't take
+    #[error("Invalid schedule: {0}")]
+    InvalidSchedule(String),
+
+    /// The OS scheduler could not be reached or refused a job
+    #[error("Scheduler error: {0}")]
+    Scheduler(String),
+
+    /// A scan config without any directory to scan
+    #[error("No directories to scan")]
+    NoRoots,
+
+    /// Scan options under which a scan can't find anything
+    #[error("Invalid scan config: {0}")]
+    InvalidScanConfig(String),
+
     /// Multiple errors occurred during operation
     #[error("Multiple errors occurred: {0} errors")]
     MultipleErrors(usize),
@@ -65,6 +109,14 @@ impl DevJunkError {
         Self::DeletionError { path, source }
     }
 
+    /// Create an unsafe path error
+    pub fn unsafe_path(path: PathBuf, reason: impl Into<String>) -> Self {
+        Self::UnsafePath {
+            path,
+            reason: reason.into(),
+        }
+    }
+
     /// Create a metadata error
     pub fn metadata(path: PathBuf, source: std::io::Error) -> Self {
         Self::MetadataError { path, source }
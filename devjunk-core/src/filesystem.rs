@@ -0,0 +1,995 @@
+//! Filesystem access used by the scanner and cleaner
+//!
+//! Everything that touches the disk goes through the [`FileSystem`] trait,
+//! so edge cases like unreadable directories, links and paths vanishing
+//! between scan and clean can be tested against [`MemoryFileSystem`]
+//! instead of a real directory tree.
+
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use std::time::SystemTime;
+
+/// What kind of filesystem object an entry is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileKind {
+    File,
+    Dir,
+    /// Symlink, junction or other reparse point; never followed
+    Link,
+}
+
+/// The metadata the scanner and cleaner need about a path
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FsMetadata {
+    pub kind: FileKind,
+    /// Size in bytes (for links, the size of the link itself)
+    pub len: u64,
+    pub modified: Option<SystemTime>,
+}
+
+/// A single entry returned by [`FileSystem::read_dir`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FsEntry {
+    /// Full path of the entry
+    pub path: PathBuf,
+    pub kind: FileKind,
+}
+
+impl FsEntry {
+    /// The final component of the entry's path
+    pub fn file_name(&self) -> &OsStr {
+        self.path.file_name().unwrap_or(self.path.as_os_str())
+    }
+}
+
+/// Filesystem operations used by scanning and cleaning
+///
+/// None of the methods follow a link at the end of the path.
+pub trait FileSystem: Send + Sync {
+    /// List the entries of a directory
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<FsEntry>>;
+
+    /// Get metadata for a path
+    fn metadata(&self, path: &Path) -> io::Result<FsMetadata>;
+
+    /// Delete a directory and everything in it
+    fn remove_dir_all(&self, path: &Path) -> io::Result<()>;
+
+    /// Delete a file or link
+    ///
+    /// The default reports `Unsupported`. Only needed to clean directories
+    /// with keep globs, which are emptied file by file.
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        let _ = path;
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    /// Rename `from` to `to` on the same filesystem
+    ///
+    /// The default reports `Unsupported`, which makes the cleaner delete in
+    /// place instead.
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        let _ = (from, to);
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    /// Cheaply check whether `remove_dir_all` is likely to succeed
+    ///
+    /// Returns a human-readable reason when deletion is bound to fail.
+    fn check_deletable(&self, path: &Path) -> Result<(), String> {
+        self.metadata(path).map(|_| ()).map_err(|e| e.to_string())
+    }
+}
+
+/// The real filesystem, via `std::fs`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StdFileSystem;
+
+impl FileSystem for StdFileSystem {
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<FsEntry>> {
+        let mut entries = Vec::new();
+        for entry in std::fs::read_dir(os_path(path))? {
+            let entry = entry?;
+            // Keep the caller's form of the path rather than the verbatim one
+            let path = path.join(entry.file_name());
+
+            #[cfg(windows)]
+            let kind = file_kind(&entry.metadata()?);
+            #[cfg(not(windows))]
+            let kind = {
+                let file_type = entry.file_type()?;
+                if file_type.is_symlink() {
+                    FileKind::Link
+                } else if file_type.is_dir() {
+                    FileKind::Dir
+                } else {
+                    FileKind::File
+                }
+            };
+
+            entries.push(FsEntry { path, kind });
+        }
+        Ok(entries)
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<FsMetadata> {
+        let meta = std::fs::symlink_metadata(os_path(path))?;
+        Ok(FsMetadata {
+            kind: file_kind(&meta),
+            len: meta.len(),
+            modified: meta.modified().ok(),
+        })
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> io::Result<()> {
+        remove_dir_all(os_path(path))
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        remove_file(os_path(path))
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        std::fs::rename(os_path(from), os_path(to))
+    }
+
+    fn check_deletable(&self, path: &Path) -> Result<(), String> {
+        check_deletable(path)
+    }
+}
+
+#[cfg(not(windows))]
+fn remove_dir_all(path: impl AsRef<Path>) -> io::Result<()> {
+    std::fs::remove_dir_all(path)
+}
+
+#[cfg(not(windows))]
+fn remove_file(path: impl AsRef<Path>) -> io::Result<()> {
+    std::fs::remove_file(path)
+}
+
+/// Delete a file or link, clearing its read-only attribute and retrying sharing violations
+#[cfg(windows)]
+fn remove_file(path: impl AsRef<Path>) -> io::Result<()> {
+    let path = path.as_ref();
+    remove_non_dir(path, &std::fs::symlink_metadata(path)?)
+}
+
+#[cfg(windows)]
+fn clear_readonly(path: &Path, meta: &std::fs::Metadata) -> io::Result<()> {
+    if meta.permissions().readonly() {
+        let mut permissions = meta.permissions();
+        // On Windows this only clears FILE_ATTRIBUTE_READONLY
+        #[allow(clippy::permissions_set_readonly_false)]
+        permissions.set_readonly(false);
+        std::fs::set_permissions(path, permissions)?;
+    }
+    Ok(())
+}
+
+/// Recursively delete a directory the way Windows needs it
+///
+/// `std::fs::remove_dir_all` gives up on read-only files and on files that
+/// Defender or the search indexer briefly hold open. This clears read-only
+/// attributes and retries sharing violations with backoff. `path` is
+/// expected in verbatim form, so paths longer than MAX_PATH work too.
+#[cfg(windows)]
+fn remove_dir_all(path: impl AsRef<Path>) -> io::Result<()> {
+    let path = path.as_ref();
+    // A linked path is unlinked rather than emptied, like std has done since CVE-2022-21658
+    let meta = std::fs::symlink_metadata(path)?;
+    if file_kind(&meta) == FileKind::Link {
+        return remove_non_dir(path, &meta);
+    }
+
+    for entry in std::fs::read_dir(path)? {
+        let entry = entry?;
+        let child = entry.path();
+        // Doesn't follow links on Windows
+        let meta = entry.metadata()?;
+
+        if file_kind(&meta) == FileKind::Dir {
+            remove_dir_all(&child)?;
+        } else {
+            remove_non_dir(&child, &meta)?;
+        }
+    }
+
+    with_retries(|| std::fs::remove_dir(path))
+}
+
+/// Delete a file or link, clearing its read-only attribute first
+///
+/// Directory symlinks and junctions are removed like empty directories,
+/// which deletes the link and leaves its target alone.
+#[cfg(windows)]
+fn remove_non_dir(path: &Path, meta: &std::fs::Metadata) -> io::Result<()> {
+    use std::os::windows::fs::MetadataExt;
+    use windows_sys::Win32::Storage::FileSystem::FILE_ATTRIBUTE_DIRECTORY;
+
+    clear_readonly(path, meta)?;
+    if meta.file_attributes() & FILE_ATTRIBUTE_DIRECTORY != 0 {
+        with_retries(|| std::fs::remove_dir(path))
+    } else {
+        with_retries(|| std::fs::remove_file(path))
+    }
+}
+
+/// Run a removal, retrying while another process briefly holds the file
+#[cfg(windows)]
+fn with_retries(mut remove: impl FnMut() -> io::Result<()>) -> io::Result<()> {
+    use windows_sys::Win32::Foundation::{
+        ERROR_ACCESS_DENIED, ERROR_DIR_NOT_EMPTY, ERROR_LOCK_VIOLATION, ERROR_SHARING_VIOLATION,
+    };
+
+    // Access denied is also what a file with a pending delete reports, and a
+    // directory stays non-empty until its children's pending deletes finish
+    let is_transient = |e: &io::Error| {
+        matches!(
+            e.raw_os_error().map(|code| code as u32),
+            Some(
+                ERROR_SHARING_VIOLATION
+                    | ERROR_LOCK_VIOLATION
+                    | ERROR_ACCESS_DENIED
+                    | ERROR_DIR_NOT_EMPTY
+            )
+        )
+    };
+
+    const BACKOFF_MS: &[u64] = &[10, 50, 100, 250, 500];
+    for delay in BACKOFF_MS {
+        match remove() {
+            Err(e) if is_transient(&e) => {
+                std::thread::sleep(std::time::Duration::from_millis(*delay))
+            }
+            result => return result,
+        }
+    }
+    remove()
+}
+
+/// Classify std metadata, treating Windows reparse points as links
+///
+/// On Windows, pnpm and friends populate `node_modules` with directory
+/// junctions, which std does not always report as symlinks.
+fn file_kind(meta: &std::fs::Metadata) -> FileKind {
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::MetadataExt;
+        use windows_sys::Win32::Storage::FileSystem::FILE_ATTRIBUTE_REPARSE_POINT;
+
+        if meta.file_attributes() & FILE_ATTRIBUTE_REPARSE_POINT != 0 {
+            return FileKind::Link;
+        }
+    }
+
+    if meta.file_type().is_symlink() {
+        FileKind::Link
+    } else if meta.is_dir() {
+        FileKind::Dir
+    } else {
+        FileKind::File
+    }
+}
+
+/// The form of `path` to hand to the OS
+#[cfg(windows)]
+fn os_path(path: &Path) -> PathBuf {
+    verbatim_path(path)
+}
+
+#[cfg(not(windows))]
+fn os_path(path: &Path) -> &Path {
+    path
+}
+
+/// Convert a path to its `\\?\` verbatim form
+///
+/// Win32 path normalization strips trailing dots and spaces from names and
+/// caps paths at MAX_PATH, so such directories can be listed but not deleted
+/// through their ordinary path. Verbatim paths bypass normalization, which
+/// means `/`, `.` and `..` have to be resolved here instead.
+#[cfg(windows)]
+fn verbatim_path(path: &Path) -> PathBuf {
+    use std::ffi::OsString;
+    use std::path::{Component, Prefix};
+
+    let absolute;
+    let path = if path.is_absolute() {
+        path
+    } else {
+        match std::env::current_dir() {
+            Ok(cwd) => {
+                absolute = cwd.join(path);
+                &absolute
+            }
+            Err(_) => return path.to_path_buf(),
+        }
+    };
+
+    let mut components = path.components();
+    let mut verbatim = match components.next() {
+        Some(Component::Prefix(prefix)) => match prefix.kind() {
+            Prefix::Disk(letter) => OsString::from(format!(r"\\?\{}:", letter as char)),
+            Prefix::UNC(server, share) => {
+                let mut s = OsString::from(r"\\?\UNC\");
+                s.push(server);
+                s.push(r"\");
+                s.push(share);
+                s
+            }
+            // Already verbatim, or a device path we shouldn't touch
+            _ => return path.to_path_buf(),
+        },
+        _ => return path.to_path_buf(),
+    };
+
+    let mut names: Vec<&OsStr> = Vec::new();
+    for component in components {
+        match component {
+            Component::Normal(name) => names.push(name),
+            Component::ParentDir => {
+                names.pop();
+            }
+            Component::RootDir | Component::CurDir | Component::Prefix(_) => {}
+        }
+    }
+    for name in names {
+        verbatim.push(r"\");
+        verbatim.push(name);
+    }
+    PathBuf::from(verbatim)
+}
+
+/// Cheaply check whether the current user could delete `path`
+///
+/// Only the directory itself and its parent are inspected, so a read-only
+/// file or a busy handle deeper inside can still make the deletion fail.
+fn check_deletable(path: &Path) -> Result<(), String> {
+    let meta = std::fs::symlink_metadata(os_path(path)).map_err(|e| e.to_string())?;
+    let parent = path
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .ok_or("cannot delete a filesystem root")?;
+    let parent_meta = std::fs::metadata(os_path(parent)).map_err(|e| e.to_string())?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+
+        if meta.dev() != parent_meta.dev() {
+            return Err("directory is a mount point".to_string());
+        }
+        if is_read_only_mount(parent) {
+            return Err("filesystem is mounted read-only".to_string());
+        }
+        if !has_access(parent, libc::W_OK | libc::X_OK) {
+            return Err("parent directory is not writable".to_string());
+        }
+        // Files only need their parent to be writable, directories need to be listed and emptied too
+        if meta.is_dir() && !has_access(path, libc::R_OK | libc::W_OK | libc::X_OK) {
+            return Err("directory is not writable".to_string());
+        }
+
+        // In a sticky directory like /tmp only the owner may remove entries
+        let euid = unsafe { libc::geteuid() };
+        let sticky = parent_meta.mode() & 0o1000 != 0;
+        if sticky && euid != 0 && meta.uid() != euid && parent_meta.uid() != euid {
+            return Err("directory is owned by another user".to_string());
+        }
+    }
+
+    #[cfg(not(unix))]
+    let _ = (meta, parent_meta);
+
+    Ok(())
+}
+
+/// Whether the filesystem holding `path` is mounted read-only
+#[cfg(unix)]
+fn is_read_only_mount(path: &Path) -> bool {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let Ok(c_path) = CString::new(path.as_os_str().as_bytes()) else {
+        return false;
+    };
+    // SAFETY: statvfs is plain data, for which all zeroes is valid
+    let mut stats: libc::statvfs = unsafe { std::mem::zeroed() };
+    // SAFETY: c_path is a valid NUL-terminated string and stats is valid for writes
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stats) } != 0 {
+        return false;
+    }
+    stats.f_flag & libc::ST_RDONLY != 0
+}
+
+/// Whether the effective user has the given access to `path`
+#[cfg(unix)]
+fn has_access(path: &Path, mode: libc::c_int) -> bool {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let Ok(c_path) = CString::new(path.as_os_str().as_bytes()) else {
+        return false;
+    };
+    // SAFETY: c_path is a valid NUL-terminated string for the whole call
+    unsafe { libc::faccessat(libc::AT_FDCWD, c_path.as_ptr(), mode, libc::AT_EACCESS) == 0 }
+}
+
+/// An in-memory filesystem for tests
+///
+/// Parent directories are created implicitly. Paths can be made unreadable
+/// with [`deny_read`](Self::deny_read) or undeletable with
+/// [`lock`](Self::lock) to simulate permission problems, and can be added or
+/// removed at any time to simulate concurrent changes.
+#[derive(Debug, Default)]
+pub struct MemoryFileSystem {
+    nodes: RwLock<BTreeMap<PathBuf, MemoryNode>>,
+}
+
+#[derive(Debug, Clone)]
+struct MemoryNode {
+    kind: FileKind,
+    len: u64,
+    modified: SystemTime,
+    unreadable: bool,
+    locked: bool,
+    /// What a link points to
+    target: Option<PathBuf>,
+}
+
+impl MemoryNode {
+    fn new(kind: FileKind, len: u64) -> Self {
+        Self {
+            kind,
+            len,
+            modified: SystemTime::UNIX_EPOCH,
+            unreadable: false,
+            locked: false,
+            target: None,
+        }
+    }
+}
+
+impl MemoryFileSystem {
+    /// Create an empty filesystem
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a directory (and any missing parents)
+    pub fn add_dir(&self, path: impl AsRef<Path>) -> &Self {
+        self.insert(path.as_ref(), MemoryNode::new(FileKind::Dir, 0))
+    }
+
+    /// Add a file of the given size (and any missing parents)
+    pub fn add_file(&self, path: impl AsRef<Path>, len: u64) -> &Self {
+        self.insert(path.as_ref(), MemoryNode::new(FileKind::File, len))
+    }
+
+    /// Add a link to `target` (and any missing parents)
+    ///
+    /// Like a symlink or junction on disk, listing the link lists `target`,
+    /// and paths below the link lead into `target`. Deleting the link leaves
+    /// `target` alone.
+    pub fn add_link(&self, path: impl AsRef<Path>, target: impl AsRef<Path>) -> &Self {
+        let node = MemoryNode {
+            target: Some(target.as_ref().to_path_buf()),
+            ..MemoryNode::new(FileKind::Link, 0)
+        };
+        self.insert(path.as_ref(), node)
+    }
+
+    /// Set the modification time of an existing path
+    pub fn set_modified(&self, path: impl AsRef<Path>, modified: SystemTime) -> &Self {
+        self.update(path.as_ref(), |node| node.modified = modified)
+    }
+
+    /// Make listing a directory fail with `PermissionDenied`
+    pub fn deny_read(&self, path: impl AsRef<Path>) -> &Self {
+        self.update(path.as_ref(), |node| node.unreadable = true)
+    }
+
+    /// Make deleting a path, or any directory containing it, fail
+    pub fn lock(&self, path: impl AsRef<Path>) -> &Self {
+        self.update(path.as_ref(), |node| node.locked = true)
+    }
+
+    /// Whether a path exists
+    pub fn exists(&self, path: impl AsRef<Path>) -> bool {
+        self.read().contains_key(path.as_ref())
+    }
+
+    fn insert(&self, path: &Path, node: MemoryNode) -> &Self {
+        let mut nodes = self.write();
+        for ancestor in path.ancestors().skip(1) {
+            if ancestor.as_os_str().is_empty() {
+                break;
+            }
+            nodes
+                .entry(ancestor.to_path_buf())
+                .or_insert_with(|| MemoryNode::new(FileKind::Dir, 0));
+        }
+        nodes.insert(path.to_path_buf(), node);
+        drop(nodes);
+        self
+    }
+
+    fn update(&self, path: &Path, f: impl FnOnce(&mut MemoryNode)) -> &Self {
+        if let Some(node) = self.write().get_mut(path) {
+            f(node);
+        }
+        self
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, BTreeMap<PathBuf, MemoryNode>> {
+        self.nodes.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, BTreeMap<PathBuf, MemoryNode>> {
+        self.nodes.write().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// `path` with the links among its ancestors replaced by what they point to
+fn resolve(nodes: &BTreeMap<PathBuf, MemoryNode>, path: &Path) -> PathBuf {
+    for ancestor in path.ancestors().skip(1) {
+        if let Some(target) = nodes.get(ancestor).and_then(|node| node.target.as_ref()) {
+            let below = path.strip_prefix(ancestor).unwrap_or(path);
+            return resolve(nodes, &target.join(below));
+        }
+    }
+    path.to_path_buf()
+}
+
+fn not_found(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("{}: not found", path.display()),
+    )
+}
+
+fn permission_denied(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::PermissionDenied,
+        format!("{}: permission denied", path.display()),
+    )
+}
+
+impl FileSystem for MemoryFileSystem {
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<FsEntry>> {
+        let nodes = self.read();
+        // Listing a link lists what it points to, under the link's own path
+        let mut dir = resolve(&nodes, path);
+        let mut node = nodes.get(&dir).ok_or_else(|| not_found(path))?;
+        if let Some(target) = &node.target {
+            dir = resolve(&nodes, target);
+            node = nodes.get(&dir).ok_or_else(|| not_found(path))?;
+        }
+        if node.kind != FileKind::Dir {
+            return Err(io::Error::other(format!(
+                "{}: not a directory",
+                path.display()
+            )));
+        }
+        if node.unreadable {
+            return Err(permission_denied(path));
+        }
+
+        Ok(nodes
+            .range(dir.clone()..)
+            .skip(1)
+            .take_while(|(p, _)| p.starts_with(&dir))
+            .filter(|(p, _)| p.parent() == Some(&dir))
+            .map(|(p, node)| FsEntry {
+                path: path.join(p.strip_prefix(&dir).unwrap_or(p)),
+                kind: node.kind,
+            })
+            .collect())
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<FsMetadata> {
+        let nodes = self.read();
+        let node = nodes
+            .get(&resolve(&nodes, path))
+            .ok_or_else(|| not_found(path))?;
+        Ok(FsMetadata {
+            kind: node.kind,
+            len: node.len,
+            modified: Some(node.modified),
+        })
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> io::Result<()> {
+        let mut nodes = self.write();
+        if !nodes.contains_key(path) {
+            return Err(not_found(path));
+        }
+        if nodes
+            .iter()
+            .any(|(p, node)| p.starts_with(path) && node.locked)
+        {
+            return Err(permission_denied(path));
+        }
+        nodes.retain(|p, _| !p.starts_with(path));
+        Ok(())
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        let mut nodes = self.write();
+        let node = nodes.get(path).ok_or_else(|| not_found(path))?;
+        if node.kind == FileKind::Dir {
+            return Err(io::Error::other(format!(
+                "{}: is a directory",
+                path.display()
+            )));
+        }
+        if node.locked {
+            return Err(permission_denied(path));
+        }
+        nodes.remove(path);
+        Ok(())
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        let mut nodes = self.write();
+        if !nodes.contains_key(from) {
+            return Err(not_found(from));
+        }
+        if nodes.contains_key(to) {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{}: already exists", to.display()),
+            ));
+        }
+        // Like Windows, refuse to move a directory while something inside is held
+        if nodes
+            .iter()
+            .any(|(p, node)| p.starts_with(from) && node.locked)
+        {
+            return Err(permission_denied(from));
+        }
+
+        let moved: Vec<PathBuf> = nodes
+            .keys()
+            .filter(|p| p.starts_with(from))
+            .cloned()
+            .collect();
+        for old in moved {
+            if let Some(node) = nodes.remove(&old) {
+                let rest = old.strip_prefix(from).unwrap_or(Path::new(""));
+                let new = if rest.as_os_str().is_empty() {
+                    to.to_path_buf()
+                } else {
+                    to.join(rest)
+                };
+                nodes.insert(new, node);
+            }
+        }
+        Ok(())
+    }
+
+    fn check_deletable(&self, path: &Path) -> Result<(), String> {
+        let nodes = self.read();
+        match nodes.get(path) {
+            None => Err(not_found(path).to_string()),
+            Some(node) if node.locked => Err("directory is locked".to_string()),
+            Some(_) => Ok(()),
+        }
+    }
+}
+
+/// A directory entry yielded by [`Walk`]
+#[derive(Debug, Clone)]
+pub(crate) struct WalkEntry {
+    pub(crate) path: PathBuf,
+    pub(crate) kind: FileKind,
+    /// Depth below the walk's root (the root itself is 0)
+    pub(crate) depth: usize,
+}
+
+impl WalkEntry {
+    pub(crate) fn file_name(&self) -> &OsStr {
+        self.path.file_name().unwrap_or(self.path.as_os_str())
+    }
+}
+
+/// A path that couldn't be read during a walk
+#[derive(Debug)]
+pub(crate) struct WalkError {
+    pub(crate) path: PathBuf,
+    pub(crate) error: io::Error,
+}
+
+/// Depth-first, pre-order walk of a directory tree over a [`FileSystem`]
+///
+/// Links are yielded but never followed, except for the root itself.
+/// Each directory's children are listed lazily, on the call to `next` after
+/// the directory was yielded, so [`skip_current_dir`](Self::skip_current_dir)
+/// avoids reading a directory at all. Siblings are yielded in name order.
+pub(crate) struct Walk<'a> {
+    fs: &'a dyn FileSystem,
+    max_depth: Option<usize>,
+    stack: Vec<Result<WalkEntry, WalkError>>,
+    /// Directory yielded last, whose children have not been listed yet
+    pending: Option<(PathBuf, usize)>,
+}
+
+impl<'a> Walk<'a> {
+    pub(crate) fn new(fs: &'a dyn FileSystem, root: &Path) -> Self {
+        let root_entry = fs
+            .metadata(root)
+            .map(|meta| WalkEntry {
+                path: root.to_path_buf(),
+                // A linked root was asked for explicitly, so walk into it
+                kind: match meta.kind {
+                    FileKind::Link => FileKind::Dir,
+                    kind => kind,
+                },
+                depth: 0,
+            })
+            .map_err(|error| WalkError {
+                path: root.to_path_buf(),
+                error,
+            });
+
+        Self {
+            fs,
+            max_depth: None,
+            stack: vec![root_entry],
+            pending: None,
+        }
+    }
+
+    /// Don't yield entries deeper than `depth` below the root
+    pub(crate) fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    /// Don't descend into the directory yielded last
+    pub(crate) fn skip_current_dir(&mut self) {
+        self.pending = None;
+    }
+}
+
+impl Iterator for Walk<'_> {
+    type Item = Result<WalkEntry, WalkError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some((dir, depth)) = self.pending.take() {
+            match self.fs.read_dir(&dir) {
+                Ok(mut children) => {
+                    // Reverse name order, so popping yields them in name order
+                    children.sort_by(|a, b| b.path.cmp(&a.path));
+                    self.stack.extend(children.into_iter().map(|child| {
+                        Ok(WalkEntry {
+                            path: child.path,
+                            kind: child.kind,
+                            depth: depth + 1,
+                        })
+                    }));
+                }
+                Err(error) => self.stack.push(Err(WalkError { path: dir, error })),
+            }
+        }
+
+        let item = self.stack.pop()?;
+        if let Ok(entry) = &item {
+            let can_descend = match self.max_depth {
+                Some(max) => entry.depth < max,
+                None => true,
+            };
+            if entry.kind == FileKind::Dir && can_descend {
+                self.pending = Some((entry.path.clone(), entry.depth));
+            }
+        }
+        Some(item)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn walk_paths(walk: Walk<'_>) -> Vec<PathBuf> {
+        walk.filter_map(|e| e.ok()).map(|e| e.path).collect()
+    }
+
+    #[test]
+    fn test_memory_fs_read_dir_lists_direct_children() {
+        let fs = MemoryFileSystem::new();
+        fs.add_file("/r/a/x.txt", 1)
+            .add_dir("/r/b")
+            .add_link("/r/c", "/r/b");
+
+        let entries = fs.read_dir(Path::new("/r")).unwrap();
+        let kinds: Vec<_> = entries.iter().map(|e| (e.file_name(), e.kind)).collect();
+
+        assert_eq!(
+            kinds,
+            vec![
+                (OsStr::new("a"), FileKind::Dir),
+                (OsStr::new("b"), FileKind::Dir),
+                (OsStr::new("c"), FileKind::Link),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_memory_fs_remove_respects_locks() {
+        let fs = MemoryFileSystem::new();
+        fs.add_file("/r/a/x.txt", 1).add_file("/r/b/y.txt", 1);
+        fs.lock("/r/b/y.txt");
+
+        fs.remove_dir_all(Path::new("/r/a")).unwrap();
+        assert!(!fs.exists("/r/a/x.txt"));
+
+        let err = fs.remove_dir_all(Path::new("/r/b")).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+        assert!(fs.exists("/r/b/y.txt"));
+    }
+
+    #[test]
+    fn test_walk_is_ordered_and_does_not_follow_links() {
+        let fs = MemoryFileSystem::new();
+        fs.add_file("/r/b/2.txt", 1)
+            .add_file("/r/a/1.txt", 1)
+            .add_link("/r/a/link", "/r/b");
+
+        let paths = walk_paths(Walk::new(&fs, Path::new("/r")));
+
+        let expected: Vec<PathBuf> = [
+            "/r",
+            "/r/a",
+            "/r/a/1.txt",
+            "/r/a/link",
+            "/r/b",
+            "/r/b/2.txt",
+        ]
+        .iter()
+        .map(PathBuf::from)
+        .collect();
+        assert_eq!(paths, expected);
+    }
+
+    #[test]
+    fn test_walk_skip_and_depth_limit() {
+        let fs = MemoryFileSystem::new();
+        fs.add_file("/r/skip/deep/x", 1)
+            .add_file("/r/keep/deep/y", 1);
+
+        let mut walk = Walk::new(&fs, Path::new("/r")).max_depth(2);
+        let mut paths = Vec::new();
+        while let Some(Ok(entry)) = walk.next() {
+            if entry.file_name() == "skip" {
+                walk.skip_current_dir();
+            }
+            paths.push(entry.path);
+        }
+
+        let expected: Vec<PathBuf> = ["/r", "/r/keep", "/r/keep/deep", "/r/skip"]
+            .iter()
+            .map(PathBuf::from)
+            .collect();
+        assert_eq!(paths, expected);
+    }
+
+    #[test]
+    fn test_walk_reports_unreadable_directories() {
+        let fs = MemoryFileSystem::new();
+        fs.add_file("/r/locked/x", 1).add_file("/r/open/y", 1);
+        fs.deny_read("/r/locked");
+
+        let errors: Vec<_> = Walk::new(&fs, Path::new("/r"))
+            .filter_map(|e| e.err())
+            .collect();
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].path, PathBuf::from("/r/locked"));
+        assert_eq!(errors[0].error.kind(), io::ErrorKind::PermissionDenied);
+    }
+
+    #[test]
+    fn test_std_fs_check_deletable_accepts_own_directory() {
+        let temp = TempDir::new().unwrap();
+        let dir = temp.path().join("node_modules");
+        std::fs::create_dir_all(&dir).unwrap();
+
+        assert_eq!(StdFileSystem.check_deletable(&dir), Ok(()));
+        assert!(StdFileSystem
+            .check_deletable(&temp.path().join("missing"))
+            .is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_std_fs_check_deletable_rejects_read_only_parent() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp = TempDir::new().unwrap();
+        let project = temp.path().join("project");
+        let dir = project.join("node_modules");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::set_permissions(&project, std::fs::Permissions::from_mode(0o555)).unwrap();
+
+        let checked = StdFileSystem.check_deletable(&dir);
+        let is_root = unsafe { libc::geteuid() } == 0;
+        std::fs::set_permissions(&project, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        // Root can delete anything, so there is nothing to observe
+        if is_root {
+            return;
+        }
+        assert_eq!(checked, Err("parent directory is not writable".to_string()));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_std_fs_removes_read_only_and_long_paths() {
+        let temp = TempDir::new().unwrap();
+        let junk = temp.path().join("node_modules");
+        // Well past MAX_PATH once joined
+        let deep = (0..12).fold(junk.clone(), |p, i| {
+            p.join(format!("{:02}{}", i, "x".repeat(24)))
+        });
+        std::fs::create_dir_all(os_path(&deep)).unwrap();
+
+        let file = os_path(&deep.join("index.js"));
+        std::fs::write(&file, b"x").unwrap();
+        let mut permissions = std::fs::metadata(&file).unwrap().permissions();
+        permissions.set_readonly(true);
+        std::fs::set_permissions(&file, permissions).unwrap();
+
+        StdFileSystem.remove_dir_all(&junk).unwrap();
+        assert!(!junk.exists());
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_std_fs_unlinks_a_linked_directory_instead_of_emptying_it() {
+        let temp = TempDir::new().unwrap();
+        let store = temp.path().join("store");
+        std::fs::create_dir(&store).unwrap();
+        std::fs::write(store.join("index.js"), b"x").unwrap();
+
+        // Junctions need no privileges, unlike directory symlinks
+        let junction = temp.path().join("node_modules");
+        let made = std::process::Command::new("cmd")
+            .args(["/C", "mklink", "/J"])
+            .arg(&junction)
+            .arg(&store)
+            .output()
+            .unwrap();
+        assert!(made.status.success());
+
+        StdFileSystem.remove_dir_all(&junction).unwrap();
+        assert!(std::fs::symlink_metadata(&junction).is_err());
+        assert!(store.join("index.js").exists());
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_verbatim_path() {
+        assert_eq!(
+            verbatim_path(Path::new(r"C:\proj\dist. ")),
+            PathBuf::from(r"\\?\C:\proj\dist. ")
+        );
+        assert_eq!(
+            verbatim_path(Path::new("C:/proj/./a/../target")),
+            PathBuf::from(r"\\?\C:\proj\target")
+        );
+        assert_eq!(
+            verbatim_path(Path::new(r"\\server\share\proj\target")),
+            PathBuf::from(r"\\?\UNC\server\share\proj\target")
+        );
+        assert_eq!(
+            verbatim_path(Path::new(r"\\?\C:\proj\target")),
+            PathBuf::from(r"\\?\C:\proj\target")
+        );
+    }
+}
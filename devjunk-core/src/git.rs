@@ -0,0 +1,184 @@
+//! Git working-tree status of the projects junk was found in
+//!
+//! Uses the `git` command line tool, so nothing is reported when git is not
+//! installed or a directory is not inside a repository.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Working-tree status of the repository an item belongs to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GitStatus {
+    /// Top-level directory of the repository
+    pub repo_root: PathBuf,
+    /// Whether there are staged, unstaged, or untracked (non-ignored) changes
+    ///
+    /// Junk directories that are merely untracked, like a `target` missing
+    /// from `.gitignore`, don't count.
+    pub has_uncommitted_changes: bool,
+    /// Commits not yet pushed to the upstream branch (None if there is no upstream)
+    pub unpushed_commits: Option<u32>,
+}
+
+impl GitStatus {
+    /// Whether the repository has work that exists nowhere else
+    pub fn is_dirty(&self) -> bool {
+        self.has_uncommitted_changes || self.unpushed_commits.is_some_and(|n| n > 0)
+    }
+}
+
+/// Looks up git status for many paths, querying each repository only once
+#[derive(Debug, Default)]
+pub(crate) struct GitStatusCache {
+    by_repo: HashMap<PathBuf, Option<GitStatus>>,
+    /// Junk directories, whose untracked contents aren't uncommitted work
+    junk: HashSet<PathBuf>,
+}
+
+impl GitStatusCache {
+    /// A cache that doesn't count untracked files in the `junk` directories as changes
+    pub(crate) fn new(junk: impl IntoIterator<Item = PathBuf>) -> Self {
+        Self {
+            by_repo: HashMap::new(),
+            junk: junk.into_iter().collect(),
+        }
+    }
+
+    /// Get the status of the repository containing `path`, if any
+    pub(crate) fn status_for(&mut self, path: &Path) -> Option<GitStatus> {
+        let repo_root = find_repo_root(path)?;
+        let junk = &self.junk;
+        self.by_repo
+            .entry(repo_root.clone())
+            .or_insert_with(|| query_status(&repo_root, junk))
+            .clone()
+    }
+}
+
+/// Walk up from `path` to the nearest directory containing `.git`
+///
+/// `.git` may be a file for worktrees and submodules, so either is accepted.
+fn find_repo_root(path: &Path) -> Option<PathBuf> {
+    path.ancestors()
+        .find(|dir| dir.join(".git").exists())
+        .map(Path::to_path_buf)
+}
+
+fn query_status(repo_root: &Path, junk: &HashSet<PathBuf>) -> Option<GitStatus> {
+    let porcelain = run_git(repo_root, &["status", "--porcelain", "-z"])?;
+
+    let unpushed_commits = run_git(repo_root, &["rev-list", "--count", "@{upstream}..HEAD"])
+        .and_then(|out| out.trim().parse().ok());
+
+    Some(GitStatus {
+        repo_root: repo_root.to_path_buf(),
+        has_uncommitted_changes: has_changes(&porcelain, repo_root, junk),
+        unpushed_commits,
+    })
+}
+
+/// Whether `git status --porcelain -z` lists anything besides untracked junk
+fn has_changes(porcelain: &str, repo_root: &Path, junk: &HashSet<PathBuf>) -> bool {
+    let mut entries = porcelain.split('\0').filter(|entry| !entry.is_empty());
+    while let Some(entry) = entries.next() {
+        let (status, path) = entry.split_at(entry.len().min(3));
+        // Renames and copies are followed by the path they came from
+        if status.starts_with(['R', 'C']) {
+            entries.next();
+        }
+        let untracked_junk = status == "?? "
+            && repo_root
+                .join(path.trim_end_matches('/'))
+                .ancestors()
+                .any(|dir| junk.contains(dir));
+        if !untracked_junk {
+            return true;
+        }
+    }
+    false
+}
+
+/// Run a git command in `dir`, returning stdout on success
+fn run_git(dir: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn git_available() -> bool {
+        Command::new("git").arg("--version").output().is_ok()
+    }
+
+    #[test]
+    fn test_status_distinguishes_ignored_and_untracked() {
+        if !git_available() {
+            return;
+        }
+
+        let temp = TempDir::new().unwrap();
+        let repo = temp.path();
+        run_git(repo, &["init", "-q"]).unwrap();
+        fs::write(repo.join(".gitignore"), "node_modules/\n").unwrap();
+        fs::create_dir_all(repo.join("node_modules")).unwrap();
+        fs::write(repo.join("node_modules").join("x.js"), "").unwrap();
+
+        let mut cache = GitStatusCache::default();
+        let status = cache.status_for(&repo.join("node_modules")).unwrap();
+
+        // Only .gitignore itself is untracked at this point
+        assert!(status.has_uncommitted_changes);
+        assert_eq!(status.unpushed_commits, None);
+        assert_eq!(
+            status.repo_root.canonicalize().unwrap(),
+            repo.canonicalize().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_untracked_junk_is_not_a_change() {
+        if !git_available() {
+            return;
+        }
+
+        let temp = TempDir::new().unwrap();
+        let repo = temp.path();
+        run_git(repo, &["init", "-q"]).unwrap();
+        fs::create_dir_all(repo.join("target/debug")).unwrap();
+        fs::write(repo.join("target/debug/app"), "").unwrap();
+
+        let status = GitStatusCache::new([repo.join("target")]).status_for(&repo.join("target"));
+        assert!(!status.unwrap().has_uncommitted_changes);
+
+        fs::write(repo.join("main.rs"), "").unwrap();
+        let status = GitStatusCache::new([repo.join("target")]).status_for(&repo.join("target"));
+        assert!(status.unwrap().has_uncommitted_changes);
+    }
+
+    #[test]
+    fn test_no_status_outside_repository() {
+        let temp = TempDir::new().unwrap();
+        // The temp dir may itself sit inside a repository on some machines
+        if find_repo_root(temp.path()).is_some() {
+            return;
+        }
+
+        let mut cache = GitStatusCache::default();
+        assert!(cache.status_for(temp.path()).is_none());
+    }
+}
@@ -0,0 +1,23 @@
+//! Compiling the user-supplied globs of scans and cleans
+
+use crate::error::{DevJunkError, Result};
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+
+/// Compile `patterns` into one set, where `*` never matches a path separator
+pub(crate) fn compile_globs(patterns: &[String]) -> Result<GlobSet> {
+    let mut set = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = GlobBuilder::new(pattern)
+            .literal_separator(true)
+            .build()
+            .map_err(|e| DevJunkError::InvalidGlob {
+                pattern: pattern.clone(),
+                reason: e.kind().to_string(),
+            })?;
+        set.add(glob);
+    }
+    set.build().map_err(|e| DevJunkError::InvalidGlob {
+        pattern: e.glob().unwrap_or_default().to_string(),
+        reason: e.kind().to_string(),
+    })
+}
@@ -0,0 +1,66 @@
+//! Handing a scan over from the CLI to the GUI
+//!
+//! `devjunk scan --open-gui` saves its result to a file and starts the GUI
+//! with [`OPEN_SCAN_ARG`] and the path of that file. The GUI takes the result
+//! from there, deleting the file so it is only shown once, and lists it ready
+//! to clean as if it had run the scan itself.
+
+use crate::error::Result;
+use crate::types::ScanResult;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Argument of the GUI followed by the path of a scan to show
+pub const OPEN_SCAN_ARG: &str = "--open-scan";
+
+/// The default file a scan is handed over in, under the user's local data directory
+pub fn default_handoff_path() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|dir| dir.join("devjunk").join("handoff.json"))
+}
+
+/// Save `result` at `path` for the GUI to take, creating its directory if needed
+pub fn save_handoff(path: &Path, result: &ScanResult) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let json = serde_json::to_vec(result).map_err(io::Error::other)?;
+    fs::write(path, json)?;
+    Ok(())
+}
+
+/// Read the scan saved at `path` and delete the file
+pub fn take_handoff(path: &Path) -> Result<ScanResult> {
+    let contents = fs::read_to_string(path)?;
+    fs::remove_file(path)?;
+    serde_json::from_str(&contents)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{JunkKind, ScanItem};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_handed_over_scan_is_taken_once() {
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("devjunk").join("handoff.json");
+        let mut result = ScanResult::new();
+        result.items.push(ScanItem::new(
+            PathBuf::from("/p/node_modules"),
+            JunkKind::NodeModules,
+            100,
+            3,
+        ));
+
+        save_handoff(&path, &result).unwrap();
+        let taken = take_handoff(&path).unwrap();
+
+        assert_eq!(taken.items.len(), 1);
+        assert_eq!(taken.items[0].path, PathBuf::from("/p/node_modules"));
+        assert!(!path.exists());
+        assert!(take_handoff(&path).is_err());
+    }
+}
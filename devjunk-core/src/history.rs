@@ -0,0 +1,391 @@
+//! Local history of scans and cleans, so reclaimed space can be reported over time
+//!
+//! Each scan or clean appends one line to `history.jsonl` under the user's
+//! local data directory. Lines that can't be read back, such as one cut short
+//! by a crash, are skipped. History always lives on the real filesystem.
+
+use crate::error::Result;
+use crate::types::{CleanResult, JunkKind, ScanResult};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// What a history record describes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoryEvent {
+    /// A scan, with the junk it found
+    Scan,
+    /// A clean, with the paths it deleted
+    Clean,
+}
+
+/// A junk directory found by a scan or deleted by a clean
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryItem {
+    /// Path of the junk directory
+    pub path: PathBuf,
+    /// Junk kind of the path, if known
+    pub kind: Option<JunkKind>,
+    /// Size found, or bytes freed
+    pub bytes: u64,
+}
+
+/// One line of the history
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryRecord {
+    /// When the scan or clean finished, in seconds since the Unix epoch
+    pub at: u64,
+    /// Whether this is a scan or a clean
+    pub event: HistoryEvent,
+    /// The junk found, or the paths deleted
+    pub items: Vec<HistoryItem>,
+}
+
+impl HistoryRecord {
+    /// Record the junk found by a scan that just finished
+    pub fn from_scan(result: &ScanResult) -> Self {
+        let items = result
+            .items
+            .iter()
+            .map(|item| HistoryItem {
+                path: item.path.clone(),
+                kind: Some(item.kind),
+                bytes: item.size_bytes,
+            })
+            .collect();
+        Self::now(HistoryEvent::Scan, items)
+    }
+
+    /// Record the paths deleted by a clean that just finished
+    pub fn from_clean(result: &CleanResult) -> Self {
+        let items = result
+            .deleted()
+            .map(|item| HistoryItem {
+                path: item.path.clone(),
+                kind: item.kind,
+                bytes: item.bytes_freed,
+            })
+            .collect();
+        Self::now(HistoryEvent::Clean, items)
+    }
+
+    fn now(event: HistoryEvent, items: Vec<HistoryItem>) -> Self {
+        let at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        Self {
+            at: at.as_secs(),
+            event,
+            items,
+        }
+    }
+
+    /// When the scan or clean finished
+    pub fn time(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(self.at)
+    }
+
+    /// Total size found, or bytes freed
+    pub fn total_bytes(&self) -> u64 {
+        self.items.iter().map(|item| item.bytes).sum()
+    }
+
+    /// Size found, or bytes freed, per junk kind, most first
+    ///
+    /// Items of an unknown kind are left out.
+    pub fn by_kind(&self) -> Vec<KindStats> {
+        let mut kinds: HashMap<JunkKind, u64> = HashMap::new();
+        for item in &self.items {
+            if let Some(kind) = item.kind {
+                *kinds.entry(kind).or_default() += item.bytes;
+            }
+        }
+        sorted_kinds(kinds)
+    }
+}
+
+/// Totals over a stretch of history
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct HistoryStats {
+    /// Number of scans recorded
+    pub scans: usize,
+    /// Number of cleans recorded
+    pub cleans: usize,
+    /// Total bytes freed by the cleans
+    pub bytes_freed: u64,
+    /// Bytes freed per junk kind, most first
+    pub by_kind: Vec<KindStats>,
+    /// Cleans and scans per project, most freed first
+    pub by_project: Vec<ProjectStats>,
+}
+
+/// Bytes freed of one junk kind
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct KindStats {
+    pub kind: JunkKind,
+    pub bytes_freed: u64,
+}
+
+/// History of one project, the directory holding the junk
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ProjectStats {
+    pub path: PathBuf,
+    /// Bytes freed by the cleans
+    pub bytes_freed: u64,
+    /// Number of cleans that deleted something here
+    pub cleans: usize,
+    /// Junk the most recent scan found here, less what was cleaned since
+    pub junk_bytes: Option<u64>,
+}
+
+/// The default history file, under the user's local data directory
+pub fn default_history_path() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|dir| dir.join("devjunk").join("history.jsonl"))
+}
+
+/// Append `record` to the history at `path`, creating it if needed
+pub fn record_history(path: &Path, record: &HistoryRecord) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut line = serde_json::to_vec(record).map_err(io::Error::other)?;
+    line.push(b'\n');
+    // A single write keeps concurrent runs from interleaving their lines
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?
+        .write_all(&line)?;
+    Ok(())
+}
+
+/// Read the history at `path`, oldest first; empty if there is none yet
+pub fn load_history(path: &Path) -> Result<Vec<HistoryRecord>> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let mut records: Vec<HistoryRecord> = contents
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect();
+    records.sort_by_key(|record| record.at);
+    Ok(records)
+}
+
+/// Add up the records made at or after `since` (all of them if None)
+///
+/// `records` must be oldest first, as returned by `load_history`.
+pub fn summarize_history(records: &[HistoryRecord], since: Option<SystemTime>) -> HistoryStats {
+    let mut stats = HistoryStats::default();
+    let mut kinds: HashMap<JunkKind, u64> = HashMap::new();
+    let mut projects: HashMap<PathBuf, ProjectStats> = HashMap::new();
+
+    for record in records {
+        if since.is_some_and(|since| record.time() < since) {
+            continue;
+        }
+
+        let mut per_project: HashMap<&Path, u64> = HashMap::new();
+        for item in &record.items {
+            let project = item.path.parent().unwrap_or(&item.path);
+            *per_project.entry(project).or_default() += item.bytes;
+        }
+
+        match record.event {
+            HistoryEvent::Scan => stats.scans += 1,
+            HistoryEvent::Clean => {
+                stats.cleans += 1;
+                stats.bytes_freed += record.total_bytes();
+                for kind in record.by_kind() {
+                    *kinds.entry(kind.kind).or_default() += kind.bytes_freed;
+                }
+            }
+        }
+
+        for (path, bytes) in per_project {
+            let project = projects
+                .entry(path.to_path_buf())
+                .or_insert_with(|| ProjectStats {
+                    path: path.to_path_buf(),
+                    bytes_freed: 0,
+                    cleans: 0,
+                    junk_bytes: None,
+                });
+            match record.event {
+                HistoryEvent::Scan => project.junk_bytes = Some(bytes),
+                HistoryEvent::Clean => {
+                    project.bytes_freed += bytes;
+                    project.cleans += 1;
+                    project.junk_bytes = project.junk_bytes.map(|junk| junk.saturating_sub(bytes));
+                }
+            }
+        }
+    }
+
+    stats.by_kind = sorted_kinds(kinds);
+    stats.by_project = projects.into_values().collect();
+    stats.by_project.sort_by(|a, b| {
+        b.bytes_freed
+            .cmp(&a.bytes_freed)
+            .then(b.junk_bytes.cmp(&a.junk_bytes))
+            .then(a.path.cmp(&b.path))
+    });
+    stats
+}
+
+fn sorted_kinds(kinds: HashMap<JunkKind, u64>) -> Vec<KindStats> {
+    let mut kinds: Vec<KindStats> = kinds
+        .into_iter()
+        .map(|(kind, bytes_freed)| KindStats { kind, bytes_freed })
+        .collect();
+    kinds.sort_by(|a, b| {
+        b.bytes_freed
+            .cmp(&a.bytes_freed)
+            .then(a.kind.id().cmp(b.kind.id()))
+    });
+    kinds
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn record(at: u64, event: HistoryEvent, items: &[(&str, JunkKind, u64)]) -> HistoryRecord {
+        HistoryRecord {
+            at,
+            event,
+            items: items
+                .iter()
+                .map(|&(path, kind, bytes)| HistoryItem {
+                    path: PathBuf::from(path),
+                    kind: Some(kind),
+                    bytes,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_history_round_trips_and_skips_torn_lines() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("devjunk").join("history.jsonl");
+        assert!(load_history(&path).unwrap().is_empty());
+
+        let first = record(
+            10,
+            HistoryEvent::Scan,
+            &[("/a/target", JunkKind::RustTarget, 5)],
+        );
+        let second = record(20, HistoryEvent::Clean, &[]);
+        record_history(&path, &first).unwrap();
+        record_history(&path, &second).unwrap();
+        // A crash in the middle of a write leaves a partial line behind
+        OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(br#"{"at":30,"ev"#)
+            .unwrap();
+
+        assert_eq!(load_history(&path).unwrap(), [first, second]);
+    }
+
+    #[test]
+    fn test_summary_adds_up_cleans_per_kind_and_project() {
+        let records = [
+            record(
+                100,
+                HistoryEvent::Clean,
+                &[("/old/node_modules", JunkKind::NodeModules, 1000)],
+            ),
+            record(
+                200,
+                HistoryEvent::Scan,
+                &[
+                    ("/a/target", JunkKind::RustTarget, 300),
+                    ("/b/node_modules", JunkKind::NodeModules, 50),
+                ],
+            ),
+            record(
+                300,
+                HistoryEvent::Clean,
+                &[
+                    ("/a/target", JunkKind::RustTarget, 300),
+                    ("/b/node_modules", JunkKind::NodeModules, 50),
+                ],
+            ),
+            record(
+                400,
+                HistoryEvent::Scan,
+                &[("/a/target", JunkKind::RustTarget, 20)],
+            ),
+        ];
+
+        let stats = summarize_history(&records, Some(UNIX_EPOCH + Duration::from_secs(150)));
+        assert_eq!((stats.scans, stats.cleans, stats.bytes_freed), (2, 1, 350));
+        assert_eq!(
+            stats.by_kind,
+            [
+                KindStats {
+                    kind: JunkKind::RustTarget,
+                    bytes_freed: 300
+                },
+                KindStats {
+                    kind: JunkKind::NodeModules,
+                    bytes_freed: 50
+                },
+            ]
+        );
+        assert_eq!(
+            stats.by_project[0],
+            ProjectStats {
+                path: PathBuf::from("/a"),
+                bytes_freed: 300,
+                cleans: 1,
+                junk_bytes: Some(20),
+            }
+        );
+        assert_eq!(stats.by_project.len(), 2);
+
+        assert_eq!(summarize_history(&records, None).bytes_freed, 1350);
+    }
+
+    #[test]
+    fn test_record_adds_up_each_kind() {
+        let mut clean = record(
+            100,
+            HistoryEvent::Clean,
+            &[
+                ("/a/node_modules", JunkKind::NodeModules, 10),
+                ("/b/target", JunkKind::RustTarget, 30),
+                ("/c/node_modules", JunkKind::NodeModules, 40),
+            ],
+        );
+        clean.items.push(HistoryItem {
+            path: PathBuf::from("/d/out"),
+            kind: None,
+            bytes: 99,
+        });
+
+        assert_eq!(
+            clean.by_kind(),
+            [
+                KindStats {
+                    kind: JunkKind::NodeModules,
+                    bytes_freed: 50
+                },
+                KindStats {
+                    kind: JunkKind::RustTarget,
+                    bytes_freed: 30
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,384 @@
+//! Write-ahead journal that lets an interrupted clean be reported and resumed
+//!
+//! Before anything is deleted, the plan is written to `<journal_dir>/<id>.jsonl`,
+//! and a line is appended as each path is moved aside or completes. The file is
+//! removed when the clean returns, so a journal left behind belongs to a clean
+//! that was killed part way through. Journals always live on the real filesystem.
+
+use crate::error::Result;
+use crate::types::{CleanItem, CleanOutcome, CleanPlan};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::warn;
+
+const JOURNAL_EXTENSION: &str = "jsonl";
+
+/// One line of a journal
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "event")]
+enum Record {
+    /// The clean is about to start; always the first line
+    Started {
+        plan: Box<CleanPlan>,
+        /// Directory relative plan paths are resolved against
+        cwd: PathBuf,
+        started_at: u64,
+    },
+    /// A directory of `path` was renamed to `to`, to be deleted later
+    Moved { path: PathBuf, to: PathBuf },
+    /// `path` is done with
+    Done {
+        path: PathBuf,
+        outcome: CleanOutcome,
+        bytes_freed: u64,
+    },
+}
+
+/// The journal of a running clean
+pub(crate) struct Journal {
+    /// None when the clean isn't journaled
+    file: Option<(PathBuf, File)>,
+}
+
+impl Journal {
+    /// Write the plan to a new journal in the plan's journal directory
+    ///
+    /// Dry runs and plans without a journal directory aren't journaled.
+    pub(crate) fn start(plan: &CleanPlan) -> Result<Self> {
+        let dir = match &plan.journal_dir {
+            Some(dir) if !plan.dry_run => dir,
+            _ => return Ok(Self { file: None }),
+        };
+
+        let started = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let record = Record::Started {
+            plan: Box::new(plan.clone()),
+            cwd: std::env::current_dir()?,
+            started_at: started.as_secs(),
+        };
+        // Plans with paths JSON can't represent are cleaned without a journal
+        let Ok(line) = serde_json::to_string(&record) else {
+            return Ok(Self { file: None });
+        };
+
+        fs::create_dir_all(dir)?;
+        let path = dir.join(format!(
+            "{}-{}.{}",
+            started.as_millis(),
+            std::process::id(),
+            JOURNAL_EXTENSION
+        ));
+        let mut file = OpenOptions::new()
+            .append(true)
+            .create_new(true)
+            .open(&path)?;
+        writeln!(file, "{}", line)?;
+        file.sync_data()?;
+
+        Ok(Self {
+            file: Some((path, file)),
+        })
+    }
+
+    /// Record that a directory of `path` was moved aside to `to`
+    pub(crate) fn moved(&mut self, path: &Path, to: &Path) {
+        self.append(&Record::Moved {
+            path: path.to_path_buf(),
+            to: to.to_path_buf(),
+        });
+    }
+
+    /// Record that a plan path is done with
+    pub(crate) fn done(&mut self, item: &CleanItem) {
+        self.append(&Record::Done {
+            path: item.path.clone(),
+            outcome: item.outcome,
+            bytes_freed: item.bytes_freed,
+        });
+    }
+
+    /// Remove the journal once the clean has returned
+    pub(crate) fn finish(self) {
+        if let Some((path, file)) = self.file {
+            drop(file);
+            let _ = fs::remove_file(path);
+        }
+    }
+
+    fn append(&mut self, record: &Record) {
+        let Some((_, file)) = &mut self.file else {
+            return;
+        };
+        // A journal that stops short only makes a later resume redo some work,
+        // so a failed write never stops the clean
+        if let Ok(line) = serde_json::to_string(record) {
+            if let Err(e) = writeln!(file, "{}", line).and_then(|_| file.sync_data()) {
+                warn!(error = %e, "could not write to the clean journal");
+            }
+        }
+    }
+}
+
+/// A clean that was killed before it finished, as recorded in its journal
+#[derive(Debug, Clone)]
+pub struct InterruptedClean {
+    /// The journal file
+    pub journal: PathBuf,
+    /// The plan that was being executed
+    pub plan: CleanPlan,
+    /// Directory the clean ran in, which relative plan paths are resolved against
+    pub cwd: PathBuf,
+    /// When the clean started (seconds since the Unix epoch)
+    pub started_at: u64,
+    /// Plan paths that were done with, in order, and how each ended
+    pub completed: Vec<(PathBuf, CleanOutcome)>,
+    /// Bytes the completed paths freed
+    pub bytes_freed: u64,
+    /// Directories that were moved aside but never finished, with where they were moved
+    pub moved: Vec<(PathBuf, PathBuf)>,
+}
+
+impl InterruptedClean {
+    /// Load a journal, or None if it doesn't start with a plan
+    fn load(journal: &Path) -> Result<Option<Self>> {
+        let contents = fs::read_to_string(journal)?;
+        let mut records = contents
+            .lines()
+            // The last line may have been cut short when the clean was killed
+            .filter_map(|line| serde_json::from_str::<Record>(line).ok());
+
+        let Some(Record::Started {
+            plan,
+            cwd,
+            started_at,
+        }) = records.next()
+        else {
+            return Ok(None);
+        };
+
+        let mut clean = Self {
+            journal: journal.to_path_buf(),
+            plan: *plan,
+            cwd,
+            started_at,
+            completed: Vec::new(),
+            bytes_freed: 0,
+            moved: Vec::new(),
+        };
+        for record in records {
+            match record {
+                Record::Moved { path, to } => clean.moved.push((path, to)),
+                Record::Done {
+                    path,
+                    outcome,
+                    bytes_freed,
+                } => {
+                    clean.moved.retain(|(moved, _)| !moved.starts_with(&path));
+                    clean.bytes_freed += bytes_freed;
+                    clean.completed.push((path, outcome));
+                }
+                Record::Started { .. } => {}
+            }
+        }
+        Ok(Some(clean))
+    }
+
+    /// Plan paths recorded as deleted
+    pub fn removed(&self) -> impl Iterator<Item = &Path> {
+        self.completed
+            .iter()
+            .filter(|(_, outcome)| *outcome == CleanOutcome::Deleted)
+            .map(|(path, _)| path.as_path())
+    }
+
+    /// Plan paths the clean never got to
+    ///
+    /// Paths moved aside aren't included, since their original location may
+    /// already hold a fresh build.
+    pub fn remaining(&self) -> Vec<PathBuf> {
+        let done: HashSet<&Path> = self
+            .completed
+            .iter()
+            .map(|(path, _)| path.as_path())
+            .chain(self.moved.iter().map(|(path, _)| path.as_path()))
+            .collect();
+        self.plan
+            .paths
+            .iter()
+            .filter(|path| !done.contains(path.as_path()))
+            .cloned()
+            .collect()
+    }
+
+    /// A plan that finishes the clean: the remaining paths and the leftovers moved aside
+    ///
+    /// Relative paths are resolved against the directory the clean ran in.
+    /// Paths that have since disappeared are skipped when the plan is executed.
+    pub fn resume_plan(&self) -> CleanPlan {
+        let absolute = |path: &Path| self.cwd.join(path);
+
+        let mut paths: Vec<PathBuf> = self.remaining().iter().map(|p| absolute(p)).collect();
+        paths.extend(self.moved.iter().map(|(_, to)| absolute(to)));
+
+        let mut plan = self.plan.clone();
+        plan.kinds = plan
+            .kinds
+            .iter()
+            .map(|(path, kind)| (absolute(path), *kind))
+            .collect::<BTreeMap<_, _>>();
+        plan.sizes = plan
+            .sizes
+            .iter()
+            .map(|(path, size)| (absolute(path), *size))
+            .collect();
+        plan.file_counts = plan
+            .file_counts
+            .iter()
+            .map(|(path, files)| (absolute(path), *files))
+            .collect();
+        plan.keep_globs = plan
+            .keep_globs
+            .iter()
+            .map(|(path, globs)| (absolute(path), globs.clone()))
+            .collect();
+        plan.roots = plan.roots.iter().map(|root| absolute(root)).collect();
+        plan.paths = paths;
+        plan
+    }
+
+    /// Delete the journal, once the clean has been resumed or is no longer wanted
+    pub fn discard(&self) -> Result<()> {
+        match fs::remove_file(&self.journal) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// The default directory for clean journals, under the user's local data directory
+pub fn default_journal_dir() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|dir| dir.join("devjunk").join("journal"))
+}
+
+/// List the cleans in `journal_dir` that were killed before they finished, oldest first
+pub fn interrupted_cleans(journal_dir: &Path) -> Result<Vec<InterruptedClean>> {
+    let entries = match fs::read_dir(journal_dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut cleans = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == JOURNAL_EXTENSION) {
+            cleans.extend(InterruptedClean::load(&path)?);
+        }
+    }
+    cleans.sort_by_key(|clean| clean.started_at);
+    Ok(cleans)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::CleanErrorKind;
+
+    fn plan_in(journal_dir: &Path) -> CleanPlan {
+        CleanPlan::new(
+            vec![
+                PathBuf::from("/r/a/node_modules"),
+                PathBuf::from("/r/b/node_modules"),
+                PathBuf::from("/r/c/node_modules"),
+            ],
+            false,
+        )
+        .with_journal_dir(journal_dir.to_path_buf())
+    }
+
+    #[test]
+    fn test_interrupted_clean_reports_progress() {
+        let dir = tempfile::tempdir().unwrap();
+        let plan = plan_in(dir.path());
+
+        let mut journal = Journal::start(&plan).unwrap();
+        journal.done(&CleanItem::deleted(plan.paths[0].clone(), None, 100, 2));
+        journal.done(&CleanItem::unclean(
+            plan.paths[1].clone(),
+            None,
+            CleanOutcome::Failed,
+            CleanErrorKind::InUse,
+            "busy",
+        ));
+        // Dropped without finishing, as if the process had been killed
+        drop(journal);
+
+        let cleans = interrupted_cleans(dir.path()).unwrap();
+        assert_eq!(cleans.len(), 1);
+        let clean = &cleans[0];
+        assert_eq!(
+            clean.removed().collect::<Vec<_>>(),
+            [plan.paths[0].as_path()]
+        );
+        assert_eq!(clean.bytes_freed, 100);
+        assert_eq!(clean.remaining(), [plan.paths[2].clone()]);
+        assert_eq!(clean.resume_plan().paths, [plan.paths[2].clone()]);
+
+        clean.discard().unwrap();
+        assert!(interrupted_cleans(dir.path()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_resume_finishes_directories_moved_aside() {
+        let dir = tempfile::tempdir().unwrap();
+        let plan = plan_in(dir.path());
+        let hidden = PathBuf::from("/r/a/.node_modules.devjunk-delete-1-0");
+
+        let mut journal = Journal::start(&plan).unwrap();
+        journal.moved(&plan.paths[0], &hidden);
+        drop(journal);
+
+        let clean = interrupted_cleans(dir.path()).unwrap().remove(0);
+        assert_eq!(clean.moved, [(plan.paths[0].clone(), hidden.clone())]);
+        // The original path may hold a fresh build by now, so only the leftover is deleted
+        assert_eq!(
+            clean.resume_plan().paths,
+            [plan.paths[1].clone(), plan.paths[2].clone(), hidden]
+        );
+    }
+
+    #[test]
+    fn test_ignores_a_cut_off_last_line() {
+        let dir = tempfile::tempdir().unwrap();
+        let plan = plan_in(dir.path());
+
+        let mut journal = Journal::start(&plan).unwrap();
+        journal.done(&CleanItem::deleted(plan.paths[0].clone(), None, 100, 2));
+        let (path, _) = journal.file.take().unwrap();
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        write!(file, r#"{{"event":"done","path":"/r/b/no"#).unwrap();
+
+        let clean = interrupted_cleans(dir.path()).unwrap().remove(0);
+        assert_eq!(clean.completed.len(), 1);
+        assert_eq!(clean.remaining().len(), 2);
+    }
+
+    #[test]
+    fn test_finished_cleans_leave_no_journal() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal = Journal::start(&plan_in(dir.path())).unwrap();
+        journal.finish();
+
+        assert!(interrupted_cleans(dir.path()).unwrap().is_empty());
+
+        let dry_run = CleanPlan::new(vec![PathBuf::from("/r/a/node_modules")], true)
+            .with_journal_dir(dir.path().to_path_buf());
+        assert!(Journal::start(&dry_run).unwrap().file.is_none());
+    }
+}
@@ -2,18 +2,101 @@
 //!
 //! This library provides the core functionality for:
 //! - Scanning directories to find development artifacts (node_modules, target, __pycache__, etc.)
+//! - Estimating a scan from directory names alone
 //! - Calculating sizes and file counts
 //! - Cleaning (deleting) selected directories with dry-run support
+//! - Archiving directories before deletion so they can be restored
+//! - Restoring directories from archives or the trash
+//! - Journaling cleans so an interrupted one can be resumed
+//! - Reading the config file shared by the CLI and the GUI
+//! - Keeping a local history of scans and cleans
+//! - Handing a scan over from the CLI to the GUI
+//! - Ranking projects by the junk they hold
+//! - Installing recurring cleans in the OS scheduler
+//! - Measuring the user-level caches of package managers and build tools
+//! - Telling whether macOS keeps protected folders from the scan
+//! - Listing the mounted volumes and how full they are
+//! - Suggesting the directories to scan on first run
 
+mod access;
+mod archive;
+mod caches;
+mod cargo;
 mod cleaner;
+mod config;
+mod control;
+mod delete;
+mod diff;
 mod error;
+mod filesystem;
+mod git;
+mod globs;
+mod handoff;
+mod history;
+mod journal;
+mod mounts;
+mod presets;
+mod priority;
+mod processes;
+mod projects;
+mod restore;
+mod roots;
+mod safety;
 mod scanner;
+mod schedule;
+mod space;
 mod types;
+mod units;
 
-pub use cleaner::{build_clean_plan, execute_clean};
+pub use access::{full_disk_access, full_disk_access_with_fs};
+pub use archive::{list_archives, restore_archive, ArchiveEntry};
+pub use caches::{global_caches, GlobalCache};
+pub use cargo::shared_target_dirs;
+pub use cleaner::{
+    apply_retention, build_clean_plan, execute_clean, execute_clean_with_cancel,
+    execute_clean_with_fs, execute_clean_with_progress, execute_clean_with_strategy, validate_plan,
+    validate_plan_with_fs, CleanProgress, CleanStage,
+};
+pub use config::{Config, GuiSettings, Theme, TypedConfirm, Workspace};
+pub use control::{CancelToken, ScanControl};
+pub use delete::{
+    dir_stats, ArchiveDelete, DeleteError, DeleteMode, DeleteStrategy, Deletion, PermanentDelete,
+    RenameDefer, TrashDelete,
+};
+pub use diff::{diff_plans, diff_scans, DiffEntry, ScanDiff};
 pub use error::{DevJunkError, Result};
-pub use scanner::{scan, scan_with_progress, ScanProgress};
-pub use types::{CleanPlan, CleanResult, JunkKind, ScanConfig, ScanItem, ScanResult};
+pub use filesystem::{FileKind, FileSystem, FsEntry, FsMetadata, MemoryFileSystem, StdFileSystem};
+pub use git::GitStatus;
+pub use handoff::{default_handoff_path, save_handoff, take_handoff, OPEN_SCAN_ARG};
+pub use history::{
+    default_history_path, load_history, record_history, summarize_history, HistoryEvent,
+    HistoryItem, HistoryRecord, HistoryStats, KindStats, ProjectStats,
+};
+pub use journal::{default_journal_dir, interrupted_cleans, InterruptedClean};
+pub use mounts::{list_mounts, MountInfo, MountKind};
+pub use priority::{enter_background_mode, set_worker_threads};
+pub use processes::{processes_using, ProcessHolder};
+pub use projects::{
+    group_by_project, group_by_project_with_fs, rank_projects, rank_projects_with_fs, KindTotal,
+    ProjectJunk,
+};
+pub use restore::{list_restorable, restore, trash_is_restorable, Restorable, RestoreSource};
+pub use roots::{suggest_roots, suggest_roots_with_fs, RootReason, SuggestedRoot};
+pub use scanner::{
+    estimate, estimate_with_fs, filter_scan_result, measure_children, measure_children_with_fs,
+    measure_dir, measure_dir_with_fs, remeasure_scan_result, remeasure_scan_result_with_fs, scan,
+    scan_with_control, scan_with_fs, scan_with_progress, EstimatedItem, ScanEstimate, ScanProgress,
+};
+pub use schedule::{
+    install_schedule, list_schedules, uninstall_schedule, Frequency, Schedule, ScheduleStatus,
+};
+pub use space::{free_space, list_volumes, SpaceProbe, Volume};
+pub use types::{
+    CleanErrorKind, CleanItem, CleanOptions, CleanOutcome, CleanPlan, CleanResult, CleanStrategy,
+    CustomPattern, DirSize, FreeSpace, JunkKind, KindSafety, PlanDrift, RetentionPolicy,
+    RetentionScope, ScanConfig, ScanConfigBuilder, ScanError, ScanErrorKind, ScanItem, ScanResult,
+};
+pub use units::{format_size, SizeUnits};
 
 #[cfg(test)]
 mod tests {
@@ -28,6 +111,43 @@ mod tests {
         assert!(config.max_depth.is_none());
     }
 
+    #[test]
+    fn test_scan_config_builder_rejects_configs_that_find_nothing() {
+        let fs = MemoryFileSystem::new();
+        fs.add_dir("/r/app").add_file("/r/notes.txt", 1);
+        let root = || ScanConfig::builder(vec![PathBuf::from("/r")]);
+        assert!(root().with_max_depth(3).build_with_fs(&fs).is_ok());
+
+        let fails = |builder: ScanConfigBuilder| builder.build_with_fs(&fs).unwrap_err();
+        assert!(matches!(
+            fails(ScanConfig::builder(Vec::new())),
+            DevJunkError::NoRoots
+        ));
+        assert!(matches!(
+            fails(ScanConfig::builder(vec![PathBuf::from("/missing")])),
+            DevJunkError::PathNotFound(_)
+        ));
+        assert!(matches!(
+            fails(ScanConfig::builder(vec![PathBuf::from("/r/notes.txt")])),
+            DevJunkError::NotADirectory(_)
+        ));
+        assert!(matches!(
+            fails(root().with_exclude_globs(vec!["**/[".to_string()])),
+            DevJunkError::InvalidGlob { .. }
+        ));
+        for builder in [
+            root().with_max_depth(0),
+            root().with_limit(0),
+            root().without_patterns(&JunkKind::all()),
+            ScanConfigBuilder::from(ScanConfig {
+                exclude_paths: vec![PathBuf::from("/r")],
+                ..ScanConfig::new(vec![PathBuf::from("/r/app")])
+            }),
+        ] {
+            assert!(matches!(fails(builder), DevJunkError::InvalidScanConfig(_)));
+        }
+    }
+
     #[test]
     fn test_junk_kind_patterns() {
         assert!(JunkKind::NodeModules.matches_name("node_modules"));
@@ -37,6 +157,66 @@ mod tests {
         assert!(JunkKind::PythonCache.matches_name("__pycache__"));
     }
 
+    #[test]
+    fn test_junk_kind_languages() {
+        assert_eq!(JunkKind::MypyCache.language(), Some("Python"));
+        assert_eq!(JunkKind::NextDir.language(), Some("JavaScript"));
+        assert_eq!(JunkKind::RustTarget.language(), Some("Rust"));
+        // Any project can have a build directory
+        assert_eq!(JunkKind::BuildDir.language(), None);
+    }
+
+    #[test]
+    fn test_junk_kind_ids_match_serialized_names() {
+        for kind in JunkKind::all().into_iter().chain([JunkKind::Custom]) {
+            let json = serde_json::to_string(&kind).unwrap();
+            assert_eq!(json, format!("\"{}\"", kind.id()));
+        }
+    }
+
+    #[test]
+    fn test_junk_kind_from_str() {
+        let parse = |name: &str| name.parse::<JunkKind>().ok();
+        assert_eq!(parse("node_modules"), Some(JunkKind::NodeModules));
+        assert_eq!(parse("nm"), Some(JunkKind::NodeModules));
+        assert_eq!(parse("Rust-Target"), Some(JunkKind::RustTarget));
+        assert_eq!(parse(".venv"), Some(JunkKind::PythonVenv));
+        // Typos and partial names are errors rather than matching nothing
+        assert_eq!(parse("node_modlues"), None);
+        assert_eq!(parse("py"), None);
+        assert_eq!(parse("custom"), None);
+
+        let mut names: Vec<_> = JunkKind::all()
+            .iter()
+            .flat_map(|kind| kind.aliases().iter().copied().chain([kind.id()]))
+            .collect();
+        let count = names.len();
+        names.sort_unstable();
+        names.dedup();
+        assert_eq!(names.len(), count, "every name must pick a single kind");
+    }
+
+    #[test]
+    fn test_junk_kind_safety() {
+        assert_eq!(JunkKind::NodeModules.safety(), KindSafety::Safe);
+        assert_eq!(JunkKind::RustTarget.safety(), KindSafety::Safe);
+        // Vendored Go dependencies are usually committed
+        assert_eq!(JunkKind::GoVendor.safety(), KindSafety::Caution);
+        assert_eq!(JunkKind::BuildDir.safety(), KindSafety::Caution);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_junk_kind_matches_os_names() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        assert!(JunkKind::BuildDir.matches_os_name(OsStr::new("build-debug")));
+        // Lossily this would be "build-\u{FFFD}" and match the build pattern
+        assert!(!JunkKind::BuildDir.matches_os_name(OsStr::from_bytes(b"build-\xff")));
+        assert_eq!(JunkKind::from_os_name(OsStr::from_bytes(b"\xfe\xff")), None);
+    }
+
     #[test]
     fn test_scan_result_summary() {
         let result = ScanResult {
@@ -46,18 +226,108 @@ mod tests {
                     kind: JunkKind::NodeModules,
                     size_bytes: 1000,
                     file_count: 50,
+                    root: PathBuf::from("/test"),
+                    last_modified: None,
+                    is_active: false,
+                    git: None,
+                    deletable: true,
+                    undeletable_reason: None,
+                    custom_name: None,
+                    custom_glob: None,
                 },
                 ScanItem {
                     path: PathBuf::from("/test/target"),
                     kind: JunkKind::RustTarget,
                     size_bytes: 2000,
                     file_count: 100,
+                    root: PathBuf::from("/test"),
+                    last_modified: None,
+                    is_active: false,
+                    git: None,
+                    deletable: true,
+                    undeletable_reason: None,
+                    custom_name: None,
+                    custom_glob: None,
                 },
             ],
+            ..Default::default()
         };
 
         assert_eq!(result.total_size_bytes(), 3000);
         assert_eq!(result.total_file_count(), 150);
         assert_eq!(result.item_count(), 2);
     }
+
+    #[test]
+    fn test_scan_result_groups() {
+        let item = |path: &str, kind| ScanItem::new(PathBuf::from(path), kind, 1, 1);
+        let mut custom = item("/b/.bazel-cache", JunkKind::Custom);
+        custom.custom_name = Some("Bazel cache".to_string());
+        let result = ScanResult {
+            items: vec![
+                item("/a/node_modules", JunkKind::NodeModules),
+                item("/b/node_modules", JunkKind::NodeModules),
+                item("/b/target", JunkKind::RustTarget),
+                custom,
+            ],
+            ..Default::default()
+        };
+
+        let projects: Vec<_> = result
+            .by_project()
+            .into_iter()
+            .map(|(project, items)| (project.to_path_buf(), items.len()))
+            .collect();
+        assert_eq!(
+            projects,
+            [(PathBuf::from("/a"), 1), (PathBuf::from("/b"), 3)]
+        );
+
+        let kinds: Vec<_> = result
+            .by_kind()
+            .into_iter()
+            .map(|(kind, items)| (kind, items.len()))
+            .collect();
+        assert_eq!(
+            kinds,
+            [("Bazel cache", 1), ("Node Modules", 2), ("Rust Target", 1)]
+        );
+    }
+
+    #[test]
+    fn test_scan_result_sorts() {
+        use std::time::{Duration, UNIX_EPOCH};
+
+        let item = |path: &str, kind, files, modified: Option<u64>| {
+            let mut item = ScanItem::new(PathBuf::from(path), kind, files * 10, files);
+            item.last_modified = modified.map(|secs| UNIX_EPOCH + Duration::from_secs(secs));
+            item
+        };
+        let mut result = ScanResult {
+            items: vec![
+                item("/b/target", JunkKind::RustTarget, 3, None),
+                item("/c/node", JunkKind::NodeModules, 1, Some(200)),
+                item("/a/node", JunkKind::NodeModules, 2, Some(100)),
+            ],
+            ..Default::default()
+        };
+        let paths = |result: &ScanResult| -> Vec<String> {
+            result
+                .items
+                .iter()
+                .map(|item| item.path.display().to_string())
+                .collect()
+        };
+
+        result.sort_by_path();
+        assert_eq!(paths(&result), ["/a/node", "/b/target", "/c/node"]);
+        result.sort_by_file_count();
+        assert_eq!(paths(&result), ["/b/target", "/a/node", "/c/node"]);
+        result.sort_by_age();
+        assert_eq!(paths(&result), ["/a/node", "/c/node", "/b/target"]);
+        result.sort_by_kind();
+        assert_eq!(paths(&result), ["/a/node", "/c/node", "/b/target"]);
+        result.sort_by_size();
+        assert_eq!(paths(&result), ["/b/target", "/a/node", "/c/node"]);
+    }
 }
@@ -0,0 +1,356 @@
+//! Mounted filesystem detection
+//!
+//! Used to keep scans from wandering onto network shares and removable
+//! media, where every `stat` can take orders of magnitude longer.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Broad classification of a mounted filesystem
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MountKind {
+    /// Local disk
+    Local,
+    /// Network filesystem (NFS, SMB, SSHFS and other FUSE mounts)
+    Network,
+    /// Removable media (USB sticks, SD cards, optical drives)
+    Removable,
+}
+
+/// A mounted filesystem
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MountInfo {
+    /// Directory the filesystem is mounted on
+    pub mount_point: PathBuf,
+    /// Filesystem type as reported by the OS (e.g. "nfs4", "smbfs")
+    pub fs_type: String,
+    /// Classification of the filesystem
+    pub kind: MountKind,
+}
+
+impl MountInfo {
+    /// Whether scans should avoid descending into this mount
+    pub fn is_slow(&self) -> bool {
+        self.kind != MountKind::Local
+    }
+}
+
+/// List the filesystems currently mounted on this machine
+///
+/// Returns an empty list if the mount table cannot be read.
+pub fn list_mounts() -> Vec<MountInfo> {
+    platform::list_mounts()
+}
+
+/// Whether `mount` belongs to the OS rather than holding the user's files
+///
+/// Kernel and memory filesystems have no place for projects, and the macOS
+/// system volumes share their space with `/`.
+pub(crate) fn is_system_mount(mount: &MountInfo) -> bool {
+    const SYSTEM_TYPES: &[&str] = &[
+        "proc",
+        "sysfs",
+        "devtmpfs",
+        "devpts",
+        "tmpfs",
+        "ramfs",
+        "securityfs",
+        "debugfs",
+        "tracefs",
+        "configfs",
+        "pstore",
+        "bpf",
+        "mqueue",
+        "hugetlbfs",
+        "fusectl",
+        "binfmt_misc",
+        "efivarfs",
+        "nsfs",
+        "autofs",
+        "rpc_pipefs",
+        "squashfs",
+        "overlay",
+        "devfs",
+        "nullfs",
+    ];
+
+    let fs_type = mount.fs_type.to_ascii_lowercase();
+    SYSTEM_TYPES.contains(&fs_type.as_str())
+        || fs_type.starts_with("cgroup")
+        || mount.mount_point.starts_with("/System/Volumes")
+        || mount.mount_point.starts_with("/proc")
+        || mount.mount_point.starts_with("/sys")
+}
+
+/// Classify a filesystem type name
+#[cfg_attr(windows, allow(dead_code))]
+fn classify_fs_type(fs_type: &str) -> MountKind {
+    const NETWORK_TYPES: &[&str] = &[
+        "nfs",
+        "nfs4",
+        "cifs",
+        "smbfs",
+        "smb3",
+        "afpfs",
+        "webdav",
+        "davfs",
+        "afs",
+        "9p",
+        "ceph",
+        "glusterfs",
+        "sshfs",
+        "ncpfs",
+    ];
+
+    let fs_type = fs_type.to_ascii_lowercase();
+    if NETWORK_TYPES.contains(&fs_type.as_str())
+        || fs_type.starts_with("fuse")
+        || fs_type.ends_with("fuse")
+    {
+        MountKind::Network
+    } else {
+        MountKind::Local
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::{classify_fs_type, is_removable_device, MountInfo, MountKind};
+    use std::fs;
+    use std::path::PathBuf;
+
+    pub fn list_mounts() -> Vec<MountInfo> {
+        let Ok(table) = fs::read_to_string("/proc/self/mounts") else {
+            return Vec::new();
+        };
+
+        table
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.split_whitespace();
+                let source = fields.next()?;
+                let mount_point = unescape(fields.next()?);
+                let fs_type = fields.next()?.to_string();
+
+                let mut kind = classify_fs_type(&fs_type);
+                if kind == MountKind::Local && is_removable_device(source) {
+                    kind = MountKind::Removable;
+                }
+
+                Some(MountInfo {
+                    mount_point: PathBuf::from(mount_point),
+                    fs_type,
+                    kind,
+                })
+            })
+            .collect()
+    }
+
+    /// Decode the octal escapes (`\040` for space, ...) used in /proc/self/mounts
+    fn unescape(field: &str) -> String {
+        let mut out = String::with_capacity(field.len());
+        let mut chars = field.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            if c == '\\' {
+                let code: String = chars.by_ref().take(3).collect();
+                match u8::from_str_radix(&code, 8) {
+                    Ok(byte) => out.push(byte as char),
+                    Err(_) => {
+                        out.push(c);
+                        out.push_str(&code);
+                    }
+                }
+            } else {
+                out.push(c);
+            }
+        }
+
+        out
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_unescape_mount_point() {
+            assert_eq!(unescape(r"/mnt/my\040share"), "/mnt/my share");
+            assert_eq!(unescape("/home"), "/home");
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::{classify_fs_type, MountInfo};
+    use std::path::PathBuf;
+    use std::process::Command;
+
+    /// Parse `mount` output: `<source> on <mount point> (<type>, <options>...)`
+    pub fn list_mounts() -> Vec<MountInfo> {
+        let Ok(output) = Command::new("/sbin/mount").output() else {
+            return Vec::new();
+        };
+
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| {
+                let (_, rest) = line.split_once(" on ")?;
+                let (mount_point, attrs) = rest.rsplit_once(" (")?;
+                let fs_type = attrs.split([',', ')']).next()?.trim().to_string();
+
+                Some(MountInfo {
+                    mount_point: PathBuf::from(mount_point),
+                    kind: classify_fs_type(&fs_type),
+                    fs_type,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use super::{MountInfo, MountKind};
+    use std::path::PathBuf;
+    use windows_sys::Win32::Storage::FileSystem::{GetDriveTypeW, GetLogicalDrives};
+    use windows_sys::Win32::System::WindowsProgramming::{
+        DRIVE_CDROM, DRIVE_REMOTE, DRIVE_REMOVABLE,
+    };
+
+    /// Enumerate drive letters and classify them with `GetDriveTypeW`
+    pub fn list_mounts() -> Vec<MountInfo> {
+        // SAFETY: GetLogicalDrives takes no arguments and returns a bitmask
+        let mask = unsafe { GetLogicalDrives() };
+
+        (0..26u8)
+            .filter(|bit| mask & (1 << bit) != 0)
+            .map(|bit| {
+                let root = format!("{}:\\", (b'A' + bit) as char);
+                let wide: Vec<u16> = root.encode_utf16().chain(Some(0)).collect();
+                // SAFETY: `wide` is a valid NUL-terminated UTF-16 string
+                let drive_type = unsafe { GetDriveTypeW(wide.as_ptr()) };
+
+                let (fs_type, kind) = match drive_type {
+                    DRIVE_REMOTE => ("remote", MountKind::Network),
+                    DRIVE_REMOVABLE => ("removable", MountKind::Removable),
+                    DRIVE_CDROM => ("cdrom", MountKind::Removable),
+                    _ => ("fixed", MountKind::Local),
+                };
+
+                MountInfo {
+                    mount_point: PathBuf::from(root),
+                    fs_type: fs_type.to_string(),
+                    kind,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+mod platform {
+    use super::MountInfo;
+
+    pub fn list_mounts() -> Vec<MountInfo> {
+        Vec::new()
+    }
+}
+
+/// Check whether a block device (e.g. `/dev/sdb1`) is flagged removable in sysfs
+#[cfg(target_os = "linux")]
+fn is_removable_device(source: &str) -> bool {
+    let Some(name) = source.strip_prefix("/dev/") else {
+        return false;
+    };
+
+    // Partitions don't carry the flag themselves; their parent disk does
+    let block = Path::new("/sys/class/block").join(name);
+    [block.join("removable"), block.join("../removable")]
+        .iter()
+        .filter_map(|p| std::fs::read_to_string(p).ok())
+        .any(|flag| flag.trim() == "1")
+}
+
+/// Find the slow (network/removable) mounts located strictly inside `root`
+///
+/// The returned paths are expressed relative to `root` as given, so they can
+/// be compared directly against walker entries without canonicalizing each one.
+pub(crate) fn slow_mounts_under(root: &Path, mounts: &[MountInfo]) -> Vec<(PathBuf, MountInfo)> {
+    let Ok(canonical_root) = root.canonicalize() else {
+        return Vec::new();
+    };
+
+    mounts
+        .iter()
+        .filter(|m| m.is_slow())
+        .filter_map(|m| {
+            let relative = m.mount_point.strip_prefix(&canonical_root).ok()?;
+            if relative.as_os_str().is_empty() {
+                // The root itself lives on this mount; the user asked for it
+                return None;
+            }
+            Some((root.join(relative), m.clone()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_classify_fs_type() {
+        assert_eq!(classify_fs_type("nfs4"), MountKind::Network);
+        assert_eq!(classify_fs_type("cifs"), MountKind::Network);
+        assert_eq!(classify_fs_type("fuse.sshfs"), MountKind::Network);
+        assert_eq!(classify_fs_type("ext4"), MountKind::Local);
+        assert_eq!(classify_fs_type("apfs"), MountKind::Local);
+    }
+
+    #[test]
+    fn test_system_mounts_are_not_for_files() {
+        let mount = |mount_point: &str, fs_type: &str| MountInfo {
+            mount_point: PathBuf::from(mount_point),
+            fs_type: fs_type.to_string(),
+            kind: MountKind::Local,
+        };
+        assert!(is_system_mount(&mount("/proc", "proc")));
+        assert!(is_system_mount(&mount("/sys/fs/cgroup", "cgroup2")));
+        assert!(is_system_mount(&mount("/snap/core/1", "squashfs")));
+        assert!(is_system_mount(&mount("/System/Volumes/VM", "apfs")));
+        assert!(!is_system_mount(&mount("/", "ext4")));
+        assert!(!is_system_mount(&mount("/Volumes/Backup", "apfs")));
+    }
+
+    #[test]
+    fn test_slow_mounts_under_root() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        let mounts = vec![
+            MountInfo {
+                mount_point: root.join("nas"),
+                fs_type: "nfs".to_string(),
+                kind: MountKind::Network,
+            },
+            MountInfo {
+                mount_point: root.join("disk"),
+                fs_type: "ext4".to_string(),
+                kind: MountKind::Local,
+            },
+            MountInfo {
+                mount_point: root.clone(),
+                fs_type: "nfs".to_string(),
+                kind: MountKind::Network,
+            },
+        ];
+
+        let slow = slow_mounts_under(&root, &mounts);
+
+        assert_eq!(slow.len(), 1);
+        assert_eq!(slow[0].0, root.join("nas"));
+    }
+}
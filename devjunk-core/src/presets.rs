@@ -0,0 +1,80 @@
+//! Built-in scan presets
+//!
+//! A whole home directory is mostly not code: photo libraries, mail stores,
+//! browser profiles and the trash are huge, slow to walk, and full of
+//! directories that merely look like junk (a `build` folder inside an app's
+//! data, say). The home preset skips those trees up front.
+
+use std::path::{Path, PathBuf};
+
+/// Default depth limit for the home preset
+///
+/// Deep enough for `~/go/src/github.com/org/repo/vendor`; junk directories
+/// themselves are never descended into, so this only bounds the search.
+pub(crate) const HOME_MAX_DEPTH: usize = 7;
+
+/// Trees under the home directory that never hold development projects
+const COMMON_EXCLUDES: &[&str] = &[
+    "Pictures",
+    "Music",
+    ".Trash",
+    ".cache",
+    ".mozilla",
+    ".thunderbird",
+    ".cargo/registry",
+    ".rustup",
+    ".npm",
+];
+
+#[cfg(target_os = "macos")]
+const PLATFORM_EXCLUDES: &[&str] = &[
+    // Mail, Photos, browser profiles, app containers and caches all live here
+    "Library",
+    "Movies",
+    "Applications",
+];
+
+#[cfg(windows)]
+const PLATFORM_EXCLUDES: &[&str] = &[
+    // Browser profiles, Outlook stores and caches all live here
+    "AppData", "Videos",
+    // Placeholder files would be downloaded just to be measured
+    "OneDrive",
+];
+
+#[cfg(not(any(target_os = "macos", windows)))]
+const PLATFORM_EXCLUDES: &[&str] = &[
+    "Videos",
+    ".local/share/Trash",
+    ".local/share/Steam",
+    ".steam",
+    ".config/google-chrome",
+    ".config/chromium",
+    ".config/BraveSoftware",
+    ".var/app",
+    "snap",
+];
+
+/// Paths under `home` the home preset leaves out of the scan
+pub(crate) fn home_excludes(home: &Path) -> Vec<PathBuf> {
+    COMMON_EXCLUDES
+        .iter()
+        .chain(PLATFORM_EXCLUDES)
+        .map(|rel| home.join(rel))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_home_excludes_are_under_home() {
+        let home = PathBuf::from("/home/dev");
+        let excludes = home_excludes(&home);
+
+        assert!(excludes.contains(&home.join("Pictures")));
+        assert!(excludes.contains(&home.join(".Trash")));
+        assert!(excludes.iter().all(|p| p.starts_with(&home)));
+    }
+}
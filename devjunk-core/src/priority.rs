@@ -0,0 +1,121 @@
+//! Running scans and cleans at background priority
+//!
+//! A large cleanup is mostly disk metadata traffic, which can make an
+//! interactive desktop or a shared CI node crawl. Background mode asks the OS
+//! to serve everything else first and keeps devjunk's own parallelism down.
+//! The number of worker threads can also be set on its own.
+
+use std::io;
+
+/// Worker threads used by scans and cleans in background mode
+const BACKGROUND_THREADS: usize = 1;
+
+/// Have scans and cleans use `threads` worker threads (0 = one per CPU)
+///
+/// Call this at startup, before the first scan or clean, since the worker
+/// count can only be set before the thread pool is first used. Background mode
+/// entered afterwards keeps this count instead of its single thread.
+pub fn set_worker_threads(threads: usize) -> io::Result<()> {
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build_global()
+        .map_err(io::Error::other)
+}
+
+/// Run the rest of this process at background CPU and disk priority
+///
+/// Uses the idle IO class and the lowest nice value on Linux, background mode
+/// on macOS and Windows and the lowest nice value on other Unixes. Scans and
+/// cleans are also limited to a single worker thread.
+///
+/// Call this at startup, before the first scan or clean: threads that already
+/// exist may keep their priority, and the worker limit can only be set before
+/// the thread pool is first used.
+pub fn enter_background_mode() -> io::Result<()> {
+    // Fails when the pool was already started, which only loses the throttling
+    let _ = rayon::ThreadPoolBuilder::new()
+        .num_threads(BACKGROUND_THREADS)
+        .build_global();
+
+    platform::enter_background_mode()
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use std::io;
+
+    const IOPRIO_WHO_PROCESS: libc::c_long = 1;
+    const IOPRIO_CLASS_IDLE: libc::c_long = 3;
+    const IOPRIO_CLASS_SHIFT: u32 = 13;
+
+    pub fn enter_background_mode() -> io::Result<()> {
+        // SAFETY: ioprio_set takes only integer arguments
+        let ioprio = unsafe {
+            libc::syscall(
+                libc::SYS_ioprio_set,
+                IOPRIO_WHO_PROCESS,
+                0,
+                IOPRIO_CLASS_IDLE << IOPRIO_CLASS_SHIFT,
+            )
+        };
+        if ioprio != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        super::lowest_nice()
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::io;
+
+    pub fn enter_background_mode() -> io::Result<()> {
+        // Throttles disk and network IO as well as CPU
+        // SAFETY: setpriority takes only integer arguments
+        if unsafe { libc::setpriority(libc::PRIO_DARWIN_PROCESS, 0, libc::PRIO_DARWIN_BG) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(unix, not(any(target_os = "linux", target_os = "macos"))))]
+mod platform {
+    pub fn enter_background_mode() -> std::io::Result<()> {
+        super::lowest_nice()
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::io;
+    use windows_sys::Win32::System::Threading::{
+        GetCurrentProcess, SetPriorityClass, PROCESS_MODE_BACKGROUND_BEGIN,
+    };
+
+    pub fn enter_background_mode() -> io::Result<()> {
+        // Lowers IO and memory priority as well as CPU
+        // SAFETY: GetCurrentProcess returns a pseudo handle that is always valid
+        if unsafe { SetPriorityClass(GetCurrentProcess(), PROCESS_MODE_BACKGROUND_BEGIN) } == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+mod platform {
+    pub fn enter_background_mode() -> std::io::Result<()> {
+        Err(std::io::ErrorKind::Unsupported.into())
+    }
+}
+
+/// Drop the CPU priority of this process to the lowest nice value
+#[cfg(all(unix, not(target_os = "macos")))]
+fn lowest_nice() -> io::Result<()> {
+    // SAFETY: setpriority takes only integer arguments
+    if unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, 19) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
@@ -0,0 +1,292 @@
+//! Detection of running processes that hold files open inside a directory
+//!
+//! Used to leave junk alone while a forgotten dev server or build still has
+//! it open, instead of failing half way through deleting it. Always looks at
+//! the real filesystem.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+
+/// A running process with files open inside a directory
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProcessHolder {
+    /// Process ID
+    pub pid: u32,
+    /// Process name as reported by the OS (e.g. "node")
+    pub name: String,
+}
+
+impl fmt::Display for ProcessHolder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (pid {})", self.name, self.pid)
+    }
+}
+
+/// List the other processes with files open inside `dir`, or running from it
+///
+/// Returns an empty list if `dir` doesn't exist or open files cannot be listed.
+pub fn processes_using(dir: &Path) -> Vec<ProcessHolder> {
+    let Ok(dir) = dir.canonicalize() else {
+        return Vec::new();
+    };
+    let mut holders = platform::processes_using(&dir);
+    holders.retain(|holder| holder.pid != std::process::id());
+    holders.sort_by_key(|holder| holder.pid);
+    holders.dedup_by_key(|holder| holder.pid);
+    holders
+}
+
+/// Describe the processes holding a directory, e.g. "in use by node (pid 1234)"
+pub(crate) fn in_use_message(holders: &[ProcessHolder]) -> String {
+    let names: Vec<String> = holders.iter().map(|holder| holder.to_string()).collect();
+    format!("in use by {}", names.join(", "))
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::ProcessHolder;
+    use std::fs;
+    use std::path::Path;
+
+    /// Look through each process's working directory, open files and mapped files in /proc
+    pub fn processes_using(dir: &Path) -> Vec<ProcessHolder> {
+        let Ok(procs) = fs::read_dir("/proc") else {
+            return Vec::new();
+        };
+
+        procs
+            .flatten()
+            .filter_map(|entry| {
+                let pid = entry.file_name().to_str()?.parse().ok()?;
+                let proc_dir = entry.path();
+                if !holds(&proc_dir, dir) {
+                    return None;
+                }
+                let name = fs::read_to_string(proc_dir.join("comm")).unwrap_or_default();
+                Some(ProcessHolder {
+                    pid,
+                    name: name.trim_end().to_string(),
+                })
+            })
+            .collect()
+    }
+
+    fn holds(proc_dir: &Path, dir: &Path) -> bool {
+        let under = |link: &Path| fs::read_link(link).is_ok_and(|target| target.starts_with(dir));
+
+        if under(&proc_dir.join("cwd")) {
+            return true;
+        }
+        // Processes of other users can't be looked into, so they never match
+        if let Ok(fds) = fs::read_dir(proc_dir.join("fd")) {
+            if fds.flatten().any(|fd| under(&fd.path())) {
+                return true;
+            }
+        }
+        // Shared libraries and native modules stay mapped after their file is closed
+        fs::read_to_string(proc_dir.join("maps")).is_ok_and(|maps| {
+            maps.lines()
+                .filter_map(|line| line.split_whitespace().nth(5))
+                .any(|path| Path::new(path).starts_with(dir))
+        })
+    }
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+mod platform {
+    use super::ProcessHolder;
+    use std::path::Path;
+    use std::process::Command;
+
+    /// Parse `lsof -F pcn` output, where each field is on its own line and starts
+    /// with its tag: `p<pid>`, then `c<command>` and an `n<name>` per open file
+    pub fn processes_using(dir: &Path) -> Vec<ProcessHolder> {
+        let Ok(output) = Command::new("lsof")
+            .args(["-n", "-w", "-F", "pcn"])
+            .output()
+        else {
+            return Vec::new();
+        };
+
+        let mut holders: Vec<ProcessHolder> = Vec::new();
+        let mut current: Option<ProcessHolder> = None;
+        for line in String::from_utf8_lossy(&output.stdout).lines() {
+            let (tag, value) = line.split_at(line.len().min(1));
+            match tag {
+                "p" => {
+                    current = value.parse().ok().map(|pid| ProcessHolder {
+                        pid,
+                        name: String::new(),
+                    })
+                }
+                "c" => {
+                    if let Some(holder) = &mut current {
+                        holder.name = value.to_string();
+                    }
+                }
+                "n" if Path::new(value).starts_with(dir) => {
+                    if let Some(holder) = current.take() {
+                        holders.push(holder);
+                    }
+                }
+                _ => {}
+            }
+        }
+        holders
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use super::ProcessHolder;
+    use crate::filesystem::{FileKind, StdFileSystem, Walk};
+    use std::os::windows::ffi::OsStrExt;
+    use std::path::Path;
+    use windows_sys::Win32::Foundation::{ERROR_MORE_DATA, ERROR_SUCCESS};
+    use windows_sys::Win32::System::RestartManager::{
+        RmEndSession, RmGetList, RmRegisterResources, RmStartSession, CCH_RM_SESSION_KEY,
+        RM_PROCESS_INFO,
+    };
+
+    /// Ask the Restart Manager which processes have the files under `dir` open
+    pub fn processes_using(dir: &Path) -> Vec<ProcessHolder> {
+        let files: Vec<Vec<u16>> = Walk::new(&StdFileSystem, dir)
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.kind == FileKind::File)
+            .map(|entry| {
+                entry
+                    .path
+                    .as_os_str()
+                    .encode_wide()
+                    .chain(Some(0))
+                    .collect()
+            })
+            .collect();
+        if files.is_empty() {
+            return Vec::new();
+        }
+        let names: Vec<*const u16> = files.iter().map(|file| file.as_ptr()).collect();
+
+        let mut session = 0;
+        let mut key = [0u16; CCH_RM_SESSION_KEY as usize + 1];
+        // SAFETY: both pointers are valid for writes; the key buffer has room for the NUL
+        if unsafe { RmStartSession(&mut session, 0, key.as_mut_ptr()) } != ERROR_SUCCESS {
+            return Vec::new();
+        }
+        let holders = list_holders(session, &names);
+        // SAFETY: the session was started above
+        unsafe { RmEndSession(session) };
+        holders
+    }
+
+    fn list_holders(session: u32, names: &[*const u16]) -> Vec<ProcessHolder> {
+        // SAFETY: every pointer in `names` is a NUL-terminated UTF-16 string that outlives the call
+        let registered = unsafe {
+            RmRegisterResources(
+                session,
+                names.len() as u32,
+                names.as_ptr(),
+                0,
+                std::ptr::null(),
+                0,
+                std::ptr::null(),
+            )
+        };
+        if registered != ERROR_SUCCESS {
+            return Vec::new();
+        }
+
+        // The list can grow between asking for its length and fetching it
+        let mut infos: Vec<RM_PROCESS_INFO> = Vec::new();
+        loop {
+            let mut needed = 0;
+            let mut count = infos.len() as u32;
+            let mut reasons = 0;
+            // SAFETY: `infos` has room for `count` entries
+            let status = unsafe {
+                RmGetList(
+                    session,
+                    &mut needed,
+                    &mut count,
+                    infos.as_mut_ptr(),
+                    &mut reasons,
+                )
+            };
+            match status {
+                ERROR_SUCCESS => {
+                    infos.truncate(count as usize);
+                    break;
+                }
+                // SAFETY: RM_PROCESS_INFO is plain data, for which all zeroes is valid
+                ERROR_MORE_DATA => infos.resize(needed as usize, unsafe { std::mem::zeroed() }),
+                _ => return Vec::new(),
+            }
+        }
+
+        infos
+            .iter()
+            .map(|info| {
+                let name = &info.strAppName;
+                let len = name.iter().position(|&c| c == 0).unwrap_or(name.len());
+                ProcessHolder {
+                    pid: info.Process.dwProcessId,
+                    name: String::from_utf16_lossy(&name[..len]),
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+mod platform {
+    use super::ProcessHolder;
+    use std::path::Path;
+
+    pub fn processes_using(_dir: &Path) -> Vec<ProcessHolder> {
+        Vec::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_in_use_message_names_each_process() {
+        let holders = [
+            ProcessHolder {
+                pid: 1234,
+                name: "node".to_string(),
+            },
+            ProcessHolder {
+                pid: 99,
+                name: "vite".to_string(),
+            },
+        ];
+        assert_eq!(
+            in_use_message(&holders),
+            "in use by node (pid 1234), vite (pid 99)"
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_finds_a_process_running_inside_a_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut child = std::process::Command::new("sleep")
+            .arg("30")
+            .current_dir(dir.path())
+            .spawn()
+            .unwrap();
+
+        let holders = processes_using(dir.path());
+        child.kill().unwrap();
+        child.wait().unwrap();
+
+        assert!(holders
+            .iter()
+            .any(|holder| holder.pid == child.id() && holder.name == "sleep"));
+        assert!(processes_using(&dir.path().join("missing")).is_empty());
+    }
+}
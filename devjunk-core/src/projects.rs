@@ -0,0 +1,167 @@
+//! Attributing junk to the projects it belongs to
+//!
+//! A project is the closest git checkout above a junk directory, up to the
+//! root it was found under, so the `node_modules` of every package in a
+//! monorepo count towards the monorepo. Junk outside any checkout belongs to
+//! the directory holding it.
+
+use crate::filesystem::{FileSystem, StdFileSystem};
+use crate::types::{ScanItem, ScanResult};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+/// All the junk found in one project
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ProjectJunk {
+    /// Directory of the project
+    pub project: PathBuf,
+    /// Total size in bytes of its junk
+    pub size_bytes: u64,
+    /// Total number of files in its junk
+    pub file_count: u64,
+    /// Number of junk directories
+    pub items: usize,
+    /// Totals per kind, largest first
+    pub kinds: Vec<KindTotal>,
+}
+
+/// The junk of one kind in a project
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct KindTotal {
+    /// Display name of the kind, or of the custom pattern
+    pub kind: String,
+    /// Number of junk directories
+    pub items: usize,
+    /// Total size in bytes
+    pub size_bytes: u64,
+}
+
+/// Add up the junk of `result` per project, the project with the most first
+pub fn rank_projects(result: &ScanResult) -> Vec<ProjectJunk> {
+    rank_projects_with_fs(result, &StdFileSystem)
+}
+
+/// Rank projects looking for checkouts on the given filesystem, like [`rank_projects`]
+pub fn rank_projects_with_fs(result: &ScanResult, fs: &dyn FileSystem) -> Vec<ProjectJunk> {
+    let mut ranked: Vec<ProjectJunk> = group_by_project_with_fs(result, fs)
+        .into_iter()
+        .map(|(project, items)| total(project, &items))
+        .collect();
+    // Stable, so equal sizes stay in path order
+    ranked.sort_by_key(|project| std::cmp::Reverse(project.size_bytes));
+    ranked
+}
+
+/// Group the items of `result` by the project they belong to, ordered by project
+///
+/// Unlike [`ScanResult::by_project`], which takes the directory holding each
+/// item, this looks for the git checkout above it.
+pub fn group_by_project(result: &ScanResult) -> BTreeMap<PathBuf, Vec<&ScanItem>> {
+    group_by_project_with_fs(result, &StdFileSystem)
+}
+
+/// Group items looking for checkouts on the given filesystem, like [`group_by_project`]
+pub fn group_by_project_with_fs<'a>(
+    result: &'a ScanResult,
+    fs: &dyn FileSystem,
+) -> BTreeMap<PathBuf, Vec<&'a ScanItem>> {
+    let mut checkouts: HashMap<PathBuf, bool> = HashMap::new();
+    let mut projects: BTreeMap<PathBuf, Vec<&ScanItem>> = BTreeMap::new();
+    for item in &result.items {
+        let project = project_of(item, fs, &mut checkouts);
+        projects.entry(project).or_default().push(item);
+    }
+    projects
+}
+
+/// The project `item` belongs to, remembering which directories are checkouts
+fn project_of(
+    item: &ScanItem,
+    fs: &dyn FileSystem,
+    checkouts: &mut HashMap<PathBuf, bool>,
+) -> PathBuf {
+    if let Some(git) = &item.git {
+        return git.repo_root.clone();
+    }
+    let holder = item.path.parent().unwrap_or(&item.path);
+    let within_root = |dir: &Path| item.root.as_os_str().is_empty() || dir.starts_with(&item.root);
+    for dir in holder.ancestors().take_while(|dir| within_root(dir)) {
+        let is_checkout = *checkouts
+            .entry(dir.to_path_buf())
+            .or_insert_with(|| fs.metadata(&dir.join(".git")).is_ok());
+        if is_checkout {
+            return dir.to_path_buf();
+        }
+    }
+    holder.to_path_buf()
+}
+
+fn total(project: PathBuf, items: &[&ScanItem]) -> ProjectJunk {
+    let mut kinds: BTreeMap<&str, KindTotal> = BTreeMap::new();
+    for item in items {
+        let kind = kinds.entry(item.kind_name()).or_insert_with(|| KindTotal {
+            kind: item.kind_name().to_string(),
+            items: 0,
+            size_bytes: 0,
+        });
+        kind.items += 1;
+        kind.size_bytes += item.size_bytes;
+    }
+    let mut kinds: Vec<KindTotal> = kinds.into_values().collect();
+    kinds.sort_by_key(|kind| std::cmp::Reverse(kind.size_bytes));
+
+    ProjectJunk {
+        project,
+        size_bytes: items.iter().map(|item| item.size_bytes).sum(),
+        file_count: items.iter().map(|item| item.file_count).sum(),
+        items: items.len(),
+        kinds,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filesystem::MemoryFileSystem;
+    use crate::types::JunkKind;
+
+    #[test]
+    fn test_monorepo_packages_count_towards_the_checkout() {
+        let fs = MemoryFileSystem::new();
+        fs.add_dir("/r/mono/.git").add_dir("/r/.git");
+        let root = PathBuf::from("/r/mono");
+        let item = |path: &str, kind, size| {
+            ScanItem::new(PathBuf::from(path), kind, size, 1).with_root(root.clone())
+        };
+        let result = ScanResult {
+            items: vec![
+                item("/r/mono/node_modules", JunkKind::NodeModules, 100),
+                item("/r/mono/packages/a/node_modules", JunkKind::NodeModules, 50),
+                item("/r/mono/packages/a/dist", JunkKind::DistDir, 400),
+                ScanItem::new(
+                    PathBuf::from("/s/solo/target"),
+                    JunkKind::RustTarget,
+                    300,
+                    3,
+                )
+                .with_root(PathBuf::from("/s")),
+            ],
+            ..Default::default()
+        };
+
+        let ranked = rank_projects_with_fs(&result, &fs);
+
+        assert_eq!(ranked.len(), 2);
+        assert_eq!(ranked[0].project, PathBuf::from("/r/mono"));
+        assert_eq!((ranked[0].size_bytes, ranked[0].items), (550, 3));
+        let kinds: Vec<_> = ranked[0]
+            .kinds
+            .iter()
+            .map(|kind| (kind.kind.as_str(), kind.items, kind.size_bytes))
+            .collect();
+        assert_eq!(kinds, [("Dist Dir", 1, 400), ("Node Modules", 2, 150)]);
+        // Outside a checkout, junk belongs to the directory holding it
+        assert_eq!(ranked[1].project, PathBuf::from("/s/solo"));
+    }
+}
@@ -0,0 +1,234 @@
+//! Finding what cleans archived or moved to the trash, and putting it back
+//!
+//! Archives are found through the manifests of the given archive directories.
+//! The trash can only be read on Windows and Linux; elsewhere only archives
+//! can be restored.
+
+use crate::archive::{list_archives, restore_archive};
+use crate::error::{DevJunkError, Result};
+use crate::types::JunkKind;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+/// Where a restorable directory is kept
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RestoreSource {
+    /// A tar.zst archive written by `--archive-to`
+    Archive,
+    /// The system trash
+    Trash,
+}
+
+/// A deleted directory that can be put back
+#[derive(Debug, Clone, Serialize)]
+pub struct Restorable {
+    /// Where the directory was before it was deleted
+    pub original_path: PathBuf,
+    /// When it was deleted, in seconds since the Unix epoch
+    pub deleted_at: u64,
+    /// Its size, if known; the trash doesn't record it
+    pub size_bytes: Option<u64>,
+    /// Whether it is in an archive or the trash
+    pub source: RestoreSource,
+    #[serde(skip)]
+    location: Location,
+}
+
+impl Restorable {
+    /// Whether this was deleted from `path`, ignoring `.` components
+    pub fn was_at(&self, path: &Path) -> bool {
+        let absolute =
+            |path: &Path| std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+        absolute(&self.original_path) == absolute(path)
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Location {
+    Archive {
+        dir: PathBuf,
+        id: String,
+    },
+    // Never built where the trash can't be listed
+    #[cfg_attr(
+        any(target_os = "macos", target_os = "ios", target_os = "android"),
+        allow(dead_code)
+    )]
+    Trash(trashed::Item),
+}
+
+/// Everything in `archive_dirs` and the trash that a clean deleted, newest first
+///
+/// Trashed entries are only listed if `cleaned` holds their original path or
+/// they are named like a built-in junk kind, so the rest of the trash stays out
+/// of it. Archive directories without a manifest are skipped, and so is a
+/// trash that can't be read.
+pub fn list_restorable(
+    archive_dirs: &[PathBuf],
+    cleaned: &HashSet<PathBuf>,
+) -> Result<Vec<Restorable>> {
+    let mut items = Vec::new();
+    for dir in archive_dirs {
+        items.extend(list_archives(dir)?.into_iter().map(|entry| Restorable {
+            original_path: entry.original_path,
+            deleted_at: entry.archived_at,
+            size_bytes: Some(entry.size_bytes),
+            source: RestoreSource::Archive,
+            location: Location::Archive {
+                dir: dir.clone(),
+                id: entry.id,
+            },
+        }));
+    }
+    let trashed = trashed::list().unwrap_or_else(|e| {
+        warn!(error = %e, "could not read the trash");
+        Vec::new()
+    });
+    items.extend(trashed.into_iter().filter(|item| {
+        cleaned.contains(&item.original_path)
+            || item
+                .original_path
+                .file_name()
+                .and_then(JunkKind::from_os_name)
+                .is_some()
+    }));
+
+    items.sort_by_key(|item| std::cmp::Reverse(item.deleted_at));
+    Ok(items)
+}
+
+/// Whether directories moved to the trash can be put back on this platform
+pub fn trash_is_restorable() -> bool {
+    cfg!(any(
+        windows,
+        all(
+            unix,
+            not(target_os = "macos"),
+            not(target_os = "ios"),
+            not(target_os = "android")
+        )
+    ))
+}
+
+/// Put `item` back where it was deleted from
+///
+/// Fails without touching anything if something already exists there.
+///
+/// # Returns
+/// The path the directory was restored to
+pub fn restore(item: &Restorable) -> Result<PathBuf> {
+    if std::fs::symlink_metadata(&item.original_path).is_ok() {
+        return Err(DevJunkError::RestoreTargetExists(
+            item.original_path.clone(),
+        ));
+    }
+    match &item.location {
+        Location::Archive { dir, id } => restore_archive(dir, id),
+        Location::Trash(trashed) => {
+            trashed::restore(trashed)?;
+            Ok(item.original_path.clone())
+        }
+    }
+}
+
+#[cfg(any(
+    windows,
+    all(
+        unix,
+        not(target_os = "macos"),
+        not(target_os = "ios"),
+        not(target_os = "android")
+    )
+))]
+mod trashed {
+    use super::{Location, Restorable, RestoreSource};
+    use crate::error::Result;
+    use std::io;
+
+    pub(super) type Item = trash::TrashItem;
+
+    /// Everything in the trash
+    pub(super) fn list() -> Result<Vec<Restorable>> {
+        let items = trash::os_limited::list().map_err(io::Error::other)?;
+        Ok(items
+            .into_iter()
+            .map(|item| Restorable {
+                original_path: item.original_path(),
+                deleted_at: u64::try_from(item.time_deleted).unwrap_or(0),
+                size_bytes: None,
+                source: RestoreSource::Trash,
+                location: Location::Trash(item),
+            })
+            .collect())
+    }
+
+    pub(super) fn restore(item: &Item) -> Result<()> {
+        trash::os_limited::restore_all([item.clone()]).map_err(io::Error::other)?;
+        Ok(())
+    }
+}
+
+/// The trash can't be listed here, so nothing in it is ever restorable
+#[cfg(not(any(
+    windows,
+    all(
+        unix,
+        not(target_os = "macos"),
+        not(target_os = "ios"),
+        not(target_os = "android")
+    )
+)))]
+mod trashed {
+    use super::Restorable;
+    use crate::error::Result;
+
+    #[derive(Debug, Clone)]
+    pub(super) enum Item {}
+
+    pub(super) fn list() -> Result<Vec<Restorable>> {
+        Ok(Vec::new())
+    }
+
+    pub(super) fn restore(item: &Item) -> Result<()> {
+        match *item {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::archive::archive_directory;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_archived_directory_is_listed_and_restored() {
+        let temp = TempDir::new().unwrap();
+        let target = temp.path().join("app").join("node_modules");
+        fs::create_dir_all(target.join("left-pad")).unwrap();
+        fs::write(target.join("left-pad").join("index.js"), "x").unwrap();
+        let archives = temp.path().join("archives");
+        archive_directory(&target, &archives, 1).unwrap();
+        fs::remove_dir_all(&target).unwrap();
+
+        let items = list_restorable(std::slice::from_ref(&archives), &HashSet::new()).unwrap();
+        let item = items
+            .iter()
+            .find(|item| item.source == RestoreSource::Archive)
+            .unwrap();
+        assert!(item.was_at(&target));
+        assert_eq!(item.size_bytes, Some(1));
+
+        assert_eq!(restore(item).unwrap(), target);
+        assert!(target.join("left-pad").join("index.js").exists());
+        // The directory is back, so restoring it again must not overwrite it
+        assert!(matches!(
+            restore(item),
+            Err(DevJunkError::RestoreTargetExists(_))
+        ));
+        assert!(list_archives(&archives).unwrap().is_empty());
+    }
+}
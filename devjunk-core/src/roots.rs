@@ -0,0 +1,202 @@
+//! Suggesting the directories to scan on first run
+//!
+//! Most developers keep their checkouts in one of a few well-known
+//! directories of their home (`~/dev`, `~/src`, `~/Projects`) or in Go's
+//! workspace. Those that exist are suggested, along with the other
+//! directories of the home directory holding several git checkouts a level or
+//! two down.
+
+use crate::filesystem::{FileKind, FileSystem, StdFileSystem};
+use crate::presets::home_excludes;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::path::{Path, PathBuf};
+
+/// Names of the home directory's folders commonly holding checkouts, in lowercase
+const WELL_KNOWN: &[&str] = &[
+    "dev",
+    "developer",
+    "src",
+    "source",
+    "code",
+    "projects",
+    "repos",
+    "git",
+    "github",
+    "workspace",
+    "work",
+];
+
+/// Checkouts a directory outside the well-known ones needs to be suggested
+const MIN_REPOS: usize = 3;
+
+/// A directory worth scanning
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SuggestedRoot {
+    pub path: PathBuf,
+    /// Why the directory is suggested
+    pub reason: RootReason,
+    /// Git checkouts found one or two levels down
+    pub repos: usize,
+}
+
+/// Why a directory is suggested for scanning
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RootReason {
+    /// It has a name developers commonly give their projects folder
+    WellKnown,
+    /// It is Go's workspace, `GOPATH` or `~/go`
+    GoPath,
+    /// It holds several git checkouts
+    Repositories,
+}
+
+/// Directories of the current user likely to hold projects, those with the most checkouts first
+pub fn suggest_roots() -> Vec<SuggestedRoot> {
+    let Some(home) = dirs::home_dir() else {
+        return Vec::new();
+    };
+    let gopath = env::var_os("GOPATH")
+        .and_then(|value| env::split_paths(&value).next())
+        .filter(|path| !path.as_os_str().is_empty());
+    suggest_roots_with_fs(&home, gopath.as_deref(), &StdFileSystem)
+}
+
+/// Suggest directories of `home` on the given filesystem, like [`suggest_roots`]
+///
+/// `gopath` is the first entry of `GOPATH`, if it is set.
+pub fn suggest_roots_with_fs(
+    home: &Path,
+    gopath: Option<&Path>,
+    fs: &dyn FileSystem,
+) -> Vec<SuggestedRoot> {
+    let excludes = home_excludes(home);
+    let mut suggested = Vec::new();
+    let mut suggest = |path: PathBuf, reason| {
+        let repos = count_repos(&path, fs);
+        suggested.push(SuggestedRoot {
+            path,
+            reason,
+            repos,
+        });
+    };
+
+    let gopath = gopath.map_or_else(|| home.join("go"), Path::to_path_buf);
+    if is_dir(&gopath, fs) {
+        suggest(gopath.clone(), RootReason::GoPath);
+    }
+    // Reading the home directory rather than trying each name finds `~/Dev` as
+    // well as `~/dev`, and only once on filesystems ignoring case
+    let mut entries = fs.read_dir(home).unwrap_or_default();
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+    for entry in entries {
+        let name = entry.file_name().to_string_lossy().to_lowercase();
+        if entry.kind != FileKind::Dir
+            || name.starts_with('.')
+            || entry.path == gopath
+            || excludes.contains(&entry.path)
+        {
+            continue;
+        }
+        if WELL_KNOWN.contains(&name.as_str()) {
+            suggest(entry.path, RootReason::WellKnown);
+        } else if count_repos(&entry.path, fs) >= MIN_REPOS {
+            suggest(entry.path, RootReason::Repositories);
+        }
+    }
+
+    // A scan of one suggestion already covers those inside it
+    let roots: Vec<PathBuf> = suggested.iter().map(|root| root.path.clone()).collect();
+    suggested.retain(|root| {
+        !roots
+            .iter()
+            .any(|other| other != &root.path && root.path.starts_with(other))
+    });
+    // Stable, so equal counts stay in the order found
+    suggested.sort_by_key(|root| std::cmp::Reverse(root.repos));
+    suggested
+}
+
+fn is_dir(path: &Path, fs: &dyn FileSystem) -> bool {
+    fs.metadata(path)
+        .is_ok_and(|meta| meta.kind == FileKind::Dir)
+}
+
+/// Git checkouts directly inside `dir` or one level further down
+fn count_repos(dir: &Path, fs: &dyn FileSystem) -> usize {
+    let is_repo = |path: &Path| fs.metadata(&path.join(".git")).is_ok();
+    let subdirs = |path: &Path| {
+        fs.read_dir(path)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|entry| entry.kind == FileKind::Dir)
+    };
+    subdirs(dir)
+        .map(|child| {
+            if is_repo(&child.path) {
+                1
+            } else {
+                subdirs(&child.path)
+                    .filter(|grandchild| is_repo(&grandchild.path))
+                    .count()
+            }
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filesystem::MemoryFileSystem;
+
+    #[test]
+    fn test_suggests_well_known_dirs_gopath_and_dirs_full_of_checkouts() {
+        let fs = MemoryFileSystem::new();
+        fs.add_dir("/home/me/Projects/app/.git")
+            .add_dir("/home/me/Projects/lib")
+            .add_dir("/home/me/go/src/github.com/me/tool/.git")
+            .add_dir("/home/me/clients/acme/site/.git")
+            .add_dir("/home/me/clients/acme/api/.git")
+            .add_dir("/home/me/clients/globex/.git")
+            .add_dir("/home/me/Documents/notes/.git")
+            .add_dir("/home/me/Pictures/a/.git")
+            .add_dir("/home/me/Pictures/b/.git")
+            .add_dir("/home/me/Pictures/c/.git")
+            .add_dir("/home/me/.config/x/.git");
+
+        let suggested = suggest_roots_with_fs(Path::new("/home/me"), None, &fs);
+
+        let found: Vec<_> = suggested
+            .iter()
+            .map(|root| (root.path.to_str().unwrap(), root.reason, root.repos))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("/home/me/clients", RootReason::Repositories, 3),
+                ("/home/me/Projects", RootReason::WellKnown, 1),
+                // Go's checkouts sit deeper than two levels
+                ("/home/me/go", RootReason::GoPath, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_suggestions_inside_another_are_left_out() {
+        let fs = MemoryFileSystem::new();
+        fs.add_dir("/home/me/dev/go/src").add_dir("/home/me/src");
+
+        let suggested = suggest_roots_with_fs(
+            Path::new("/home/me"),
+            Some(Path::new("/home/me/dev/go")),
+            &fs,
+        );
+
+        let paths: Vec<_> = suggested.iter().map(|root| root.path.clone()).collect();
+        assert_eq!(
+            paths,
+            [PathBuf::from("/home/me/dev"), PathBuf::from("/home/me/src")]
+        );
+    }
+}
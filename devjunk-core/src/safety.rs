@@ -0,0 +1,138 @@
+//! Last-line checks that keep the cleaner away from paths it must never delete
+
+use crate::error::{DevJunkError, Result};
+use crate::mounts;
+use crate::types::CleanPlan;
+use std::path::{Component, Path, PathBuf};
+
+/// Everything a path is checked against
+pub(crate) struct SafetyGuards {
+    /// The user's home directory, if known
+    home: Option<PathBuf>,
+    /// Directories other filesystems are mounted on
+    mount_points: Vec<PathBuf>,
+}
+
+impl SafetyGuards {
+    /// Guards for the current user and machine
+    pub(crate) fn current() -> Self {
+        Self {
+            home: dirs::home_dir(),
+            mount_points: mounts::list_mounts()
+                .into_iter()
+                .map(|m| m.mount_point)
+                .collect(),
+        }
+    }
+
+    /// Refuse the whole plan if any of its paths is unsafe to delete
+    ///
+    /// Paths must lie under one of the plan's scan roots, if it records any.
+    pub(crate) fn check_plan(&self, plan: &CleanPlan) -> Result<()> {
+        let roots: Vec<_> = plan.roots.iter().map(|root| absolute(root)).collect();
+        for path in &plan.paths {
+            self.check_path(&absolute(path), &roots)
+                .map_err(|reason| DevJunkError::unsafe_path(path.clone(), reason))?;
+        }
+        Ok(())
+    }
+
+    fn check_path(&self, path: &Path, roots: &[PathBuf]) -> std::result::Result<(), &'static str> {
+        // Covers `/`, drive roots like `C:\` and UNC shares, and `.`/`..`
+        if path.file_name().is_none() {
+            return Err("it is a filesystem root");
+        }
+        // `..` would let a path escape the roots it appears to be under
+        if path.components().any(|c| c == Component::ParentDir) {
+            return Err("it contains '..'");
+        }
+
+        if let Some(home) = &self.home {
+            if home == path {
+                return Err("it is your home directory");
+            }
+            if home.starts_with(path) {
+                return Err("it contains your home directory");
+            }
+        }
+
+        if self.mount_points.iter().any(|m| m == path) {
+            return Err("it is a mount point");
+        }
+
+        if !roots.is_empty() && !roots.iter().any(|root| path.starts_with(root)) {
+            return Err("it is not under any of the scanned directories");
+        }
+
+        Ok(())
+    }
+}
+
+/// Resolve a relative path against the current directory, without touching the filesystem
+fn absolute(path: &Path) -> PathBuf {
+    match std::env::current_dir() {
+        Ok(cwd) if !path.is_absolute() => cwd.join(path),
+        _ => path.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn guards() -> SafetyGuards {
+        SafetyGuards {
+            home: Some(PathBuf::from("/home/dev")),
+            mount_points: vec![PathBuf::from("/"), PathBuf::from("/mnt/data")],
+        }
+    }
+
+    fn check(path: &str, roots: &[&str]) -> std::result::Result<(), &'static str> {
+        let roots: Vec<_> = roots.iter().map(PathBuf::from).collect();
+        guards().check_path(Path::new(path), &roots)
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_refuses_catastrophic_paths() {
+        assert_eq!(check("/", &[]), Err("it is a filesystem root"));
+        assert_eq!(check("/home/dev", &[]), Err("it is your home directory"));
+        assert_eq!(check("/home", &[]), Err("it contains your home directory"));
+        assert_eq!(check("/mnt/data", &[]), Err("it is a mount point"));
+        assert_eq!(check("/home/dev/p/../../dev", &[]), Err("it contains '..'"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_requires_paths_under_scan_roots() {
+        let roots = ["/home/dev/code"];
+
+        assert_eq!(check("/home/dev/code/app/node_modules", &roots), Ok(()));
+        assert_eq!(check("/home/dev/code/target", &roots), Ok(()));
+        assert_eq!(
+            check("/home/dev/Documents/build", &roots),
+            Err("it is not under any of the scanned directories")
+        );
+        assert_eq!(check("/home/dev/Documents/build", &[]), Ok(()));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_refuses_drive_roots() {
+        assert_eq!(check(r"C:\", &[]), Err("it is a filesystem root"));
+        assert_eq!(
+            check(r"\\server\share\", &[]),
+            Err("it is a filesystem root")
+        );
+    }
+
+    #[test]
+    fn test_check_plan_reports_unsafe_path() {
+        let plan = CleanPlan::new(vec![PathBuf::from("/home/dev")], false);
+
+        assert!(matches!(
+            guards().check_plan(&plan),
+            Err(DevJunkError::UnsafePath { .. })
+        ));
+    }
+}
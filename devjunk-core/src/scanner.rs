@@ -1,14 +1,27 @@
 //! Directory scanning logic
 
+use crate::cargo;
+use crate::control::ScanControl;
 use crate::error::{DevJunkError, Result};
-use crate::types::{JunkKind, ScanConfig, ScanItem, ScanResult};
+use crate::filesystem::{FileKind, FileSystem, StdFileSystem, Walk, WalkEntry, WalkError};
+use crate::git::GitStatusCache;
+use crate::globs::compile_globs;
+use crate::history::{HistoryEvent, HistoryRecord};
+use crate::mounts::{self, MountInfo};
+use crate::types::{
+    CustomPattern, DirSize, JunkKind, ScanConfig, ScanError, ScanErrorKind, ScanItem, ScanResult,
+};
+use globset::GlobSet;
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
-use std::fs;
-use std::path::Path;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::cmp::{Ordering as CmpOrdering, Reverse};
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
-use walkdir::{DirEntry, WalkDir};
+use std::time::{Duration, Instant, SystemTime};
+use tracing::{debug, info, trace};
 
 /// Progress information during a scan operation
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -16,10 +29,127 @@ use walkdir::{DirEntry, WalkDir};
 pub struct ScanProgress {
     /// Current path being scanned
     pub current_path: String,
+    /// Junk directory currently being sized, if any
+    pub sizing_path: Option<String>,
     /// Number of junk items found so far
     pub items_found: usize,
     /// Number of directories scanned so far
     pub directories_scanned: usize,
+    /// Total size of junk items found so far
+    pub bytes_found: u64,
+    /// Time since the scan started, in milliseconds
+    pub elapsed_ms: u64,
+    /// Estimated time remaining, in milliseconds (None until enough is known)
+    pub eta_ms: Option<u64>,
+}
+
+/// What a quick pass over the roots found, before any of it is sized
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanEstimate {
+    /// Junk directories found by name
+    pub items: Vec<EstimatedItem>,
+    /// Number of directories walked
+    pub directories_scanned: usize,
+    /// Whether every root was walked before the time ran out
+    pub complete: bool,
+}
+
+/// A junk directory found by an estimate
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EstimatedItem {
+    pub path: PathBuf,
+    pub kind: JunkKind,
+    /// Scan root this item was found under
+    pub root: PathBuf,
+    /// Size an earlier scan found, if it found this directory
+    pub size_bytes: Option<u64>,
+}
+
+impl ScanEstimate {
+    /// Take the size of each item from the latest scan in `records` that found it
+    ///
+    /// `records` must be oldest first, as returned by `load_history`.
+    pub fn with_known_sizes(mut self, records: &[HistoryRecord]) -> Self {
+        let mut known: HashMap<&Path, u64> = HashMap::new();
+        for record in records {
+            if record.event == HistoryEvent::Scan {
+                for item in &record.items {
+                    known.insert(&item.path, item.bytes);
+                }
+            }
+        }
+        for item in &mut self.items {
+            item.size_bytes = known.get(item.path.as_path()).copied();
+        }
+        self
+    }
+
+    /// Total size of the items an earlier scan measured
+    pub fn known_bytes(&self) -> u64 {
+        self.items.iter().filter_map(|item| item.size_bytes).sum()
+    }
+
+    /// Number of items no earlier scan measured
+    pub fn unknown_count(&self) -> usize {
+        self.items
+            .iter()
+            .filter(|item| item.size_bytes.is_none())
+            .count()
+    }
+}
+
+/// Shared counters used to build ScanProgress snapshots
+struct ProgressTracker {
+    started: Instant,
+    dirs_scanned: AtomicUsize,
+    items_found: AtomicUsize,
+    bytes_found: AtomicU64,
+    /// Number of top-level entries across all roots, used for the ETA
+    top_level_total: usize,
+    /// Number of top-level entries reached so far
+    top_level_done: AtomicUsize,
+}
+
+impl ProgressTracker {
+    fn new(fs: &dyn FileSystem, roots: &[PathBuf]) -> Self {
+        let top_level_total = roots
+            .iter()
+            .filter_map(|root| fs.read_dir(root).ok())
+            .map(|entries| entries.len())
+            .sum();
+
+        Self {
+            started: Instant::now(),
+            dirs_scanned: AtomicUsize::new(0),
+            items_found: AtomicUsize::new(0),
+            bytes_found: AtomicU64::new(0),
+            top_level_total,
+            top_level_done: AtomicUsize::new(0),
+        }
+    }
+
+    fn snapshot(&self, current_path: &Path, sizing_path: Option<&Path>) -> ScanProgress {
+        let elapsed = self.started.elapsed();
+        let done = self.top_level_done.load(Ordering::Relaxed);
+
+        // Extrapolate from the share of top-level entries already walked
+        let eta_ms = if done > 0 && self.top_level_total > done {
+            let remaining = (self.top_level_total - done) as f64 / done as f64;
+            Some((elapsed.as_millis() as f64 * remaining) as u64)
+        } else {
+            None
+        };
+
+        ScanProgress {
+            current_path: current_path.display().to_string(),
+            sizing_path: sizing_path.map(|p| p.display().to_string()),
+            items_found: self.items_found.load(Ordering::Relaxed),
+            directories_scanned: self.dirs_scanned.load(Ordering::Relaxed),
+            bytes_found: self.bytes_found.load(Ordering::Relaxed),
+            elapsed_ms: elapsed.as_millis() as u64,
+            eta_ms,
+        }
+    }
 }
 
 /// Scan directories according to the given configuration
@@ -35,7 +165,7 @@ pub struct ScanProgress {
 /// use devjunk_core::{scan, ScanConfig};
 /// use std::path::PathBuf;
 ///
-/// let config = ScanConfig::new(vec![PathBuf::from(".")]);
+/// let config = ScanConfig::builder(vec![PathBuf::from(".")]).build().unwrap();
 /// let result = scan(&config).unwrap();
 /// println!("Found {} items", result.item_count());
 /// ```
@@ -55,232 +185,790 @@ pub fn scan_with_progress<F>(config: &ScanConfig, on_progress: F) -> Result<Scan
 where
     F: Fn(ScanProgress) + Send + Sync,
 {
-    // Validate roots exist
+    scan_with_control(config, &ScanControl::new(), on_progress)
+}
+
+/// Scan directories with progress callback and pause/resume control
+///
+/// # Arguments
+/// * `config` - Configuration specifying roots, patterns, and options
+/// * `control` - Handle the scan polls between entries; pausing it blocks the scan,
+///   cancelling it ends the scan with what was found so far
+/// * `on_progress` - Callback function called for each directory scanned
+///
+/// # Returns
+/// * `Result<ScanResult>` - The scan result containing all found junk items
+pub fn scan_with_control<F>(
+    config: &ScanConfig,
+    control: &ScanControl,
+    on_progress: F,
+) -> Result<ScanResult>
+where
+    F: Fn(ScanProgress) + Send + Sync,
+{
+    let ctx = ScanContext::new(config, &StdFileSystem, control)?;
+    scan_in_context(&ctx, on_progress)
+}
+
+/// Scan directories on the given filesystem
+///
+/// Behaves like [`scan`], but reads through `fs` instead of the real disk,
+/// which lets tests run against an in-memory tree.
+pub fn scan_with_fs(config: &ScanConfig, fs: &dyn FileSystem) -> Result<ScanResult> {
+    let control = ScanControl::new();
+    let ctx = ScanContext::new(config, fs, &control)?;
+    scan_in_context(&ctx, |_| {})
+}
+
+/// Find the junk directories under the roots by name alone, for a preview of a scan
+///
+/// Nothing is sized, so this takes a fraction of the time of a scan. The walk
+/// stops once `time_limit` has passed, and the estimate then only covers what
+/// was walked so far. Size and age thresholds of `config` are not applied.
+pub fn estimate(config: &ScanConfig, time_limit: Duration) -> Result<ScanEstimate> {
+    estimate_with_fs(config, &StdFileSystem, time_limit)
+}
+
+/// Estimate a scan on the given filesystem, like [`estimate`]
+pub fn estimate_with_fs(
+    config: &ScanConfig,
+    fs: &dyn FileSystem,
+    time_limit: Duration,
+) -> Result<ScanEstimate> {
+    let control = ScanControl::new();
+    let ctx = ScanContext::new(config, fs, &control)?;
+    let deadline = Instant::now() + time_limit;
+
     for root in &config.roots {
-        if !root.exists() {
-            return Err(DevJunkError::PathNotFound(root.clone()));
+        match fs.metadata(root) {
+            Err(_) => return Err(DevJunkError::PathNotFound(root.clone())),
+            Ok(meta) if meta.kind == FileKind::File => {
+                return Err(DevJunkError::NotADirectory(root.clone()));
+            }
+            Ok(_) => {}
         }
-        if !root.is_dir() {
-            return Err(DevJunkError::NotADirectory(root.clone()));
+    }
+    let mounts = if config.skip_network_mounts {
+        mounts::list_mounts()
+    } else {
+        Vec::new()
+    };
+
+    let estimates: Vec<ScanEstimate> = dedup_roots(&config.roots)
+        .par_iter()
+        .map(|root| estimate_root(root, &ctx, &mounts, deadline))
+        .collect();
+    let mut estimate = ScanEstimate {
+        items: Vec::new(),
+        directories_scanned: 0,
+        complete: true,
+    };
+    for root_estimate in estimates {
+        estimate.items.extend(root_estimate.items);
+        estimate.directories_scanned += root_estimate.directories_scanned;
+        estimate.complete &= root_estimate.complete;
+    }
+    estimate.items.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(estimate)
+}
+
+/// Measure the size and file count of any directory, junk or not
+///
+/// Uses the same parallel traversal a scan sizes junk directories with, and
+/// `path` may also be a single file. Links inside are counted by their own
+/// size and never followed; unreadable entries are listed in `errors`.
+pub fn measure_dir(path: &Path) -> Result<DirSize> {
+    measure_dir_with_fs(path, &StdFileSystem)
+}
+
+/// Measure a directory on the given filesystem, like [`measure_dir`]
+pub fn measure_dir_with_fs(path: &Path, fs: &dyn FileSystem) -> Result<DirSize> {
+    if fs.metadata(path).is_err() {
+        return Err(DevJunkError::PathNotFound(path.to_path_buf()));
+    }
+    let mut errors = Vec::new();
+    let stats = calculate_dir_stats(path, fs, &ScanControl::new(), &mut errors);
+    Ok(DirSize {
+        path: path.to_path_buf(),
+        size_bytes: stats.size,
+        file_count: stats.files,
+        last_modified: stats.newest,
+        errors,
+    })
+}
+
+/// Measure each entry directly inside a directory, the largest first
+///
+/// Breaks a junk directory down to show what makes it big. Files are
+/// measured like directories holding only themselves.
+pub fn measure_children(path: &Path) -> Result<Vec<DirSize>> {
+    measure_children_with_fs(path, &StdFileSystem)
+}
+
+/// Measure the entries of a directory on the given filesystem, like [`measure_children`]
+pub fn measure_children_with_fs(path: &Path, fs: &dyn FileSystem) -> Result<Vec<DirSize>> {
+    let metadata = fs
+        .metadata(path)
+        .map_err(|_| DevJunkError::PathNotFound(path.to_path_buf()))?;
+    if metadata.kind != FileKind::Dir {
+        return Err(DevJunkError::NotADirectory(path.to_path_buf()));
+    }
+    let entries = fs
+        .read_dir(path)
+        .map_err(|source| DevJunkError::TraversalError {
+            path: path.to_path_buf(),
+            source,
+        })?;
+
+    let mut children: Vec<DirSize> = entries
+        .iter()
+        .filter_map(|entry| measure_dir_with_fs(&entry.path, fs).ok())
+        .collect();
+    children.sort_by(|a, b| b.size_bytes.cmp(&a.size_bytes).then(a.path.cmp(&b.path)));
+    Ok(children)
+}
+
+/// Drop the items of a saved scan result that a scan with `config` would leave out
+///
+/// Only the filters that apply to a single item are checked: its kind or
+/// custom pattern, the excluded paths and globs, and the size and age
+/// thresholds. Roots, depth and hidden directories are not, since the saved
+/// result doesn't record where below its roots an item was found.
+pub fn filter_scan_result(result: &mut ScanResult, config: &ScanConfig) -> Result<()> {
+    let excludes = compile_globs(&config.exclude_globs)?;
+    result.items.retain(|item| {
+        let kind_wanted = match &item.custom_name {
+            Some(name) if item.kind == JunkKind::Custom => {
+                config.custom_patterns.iter().any(|p| &p.name == name)
+            }
+            _ => config.include_patterns.contains(&item.kind),
+        };
+        let excluded = config
+            .exclude_paths
+            .iter()
+            .any(|exc| item.path.starts_with(exc))
+            || item.path.ancestors().any(|dir| excludes.is_match(dir));
+        let big_enough = config
+            .min_size_bytes
+            .is_none_or(|min| item.size_bytes >= min);
+        let old_enough = config.min_age.is_none_or(|age| item.is_older_than(age));
+        kind_wanted && !excluded && big_enough && old_enough
+    });
+    Ok(())
+}
+
+/// Measure the items of a saved scan result again
+///
+/// Each item's size, file count and modification time are replaced with what
+/// is on disk now, and entries that can't be read are added to the result's
+/// errors. Items that no longer exist are dropped.
+///
+/// # Returns
+/// The paths of the dropped items
+pub fn remeasure_scan_result(result: &mut ScanResult) -> Vec<PathBuf> {
+    remeasure_scan_result_with_fs(result, &StdFileSystem)
+}
+
+/// Measure a saved scan result again on the given filesystem, like [`remeasure_scan_result`]
+pub fn remeasure_scan_result_with_fs(result: &mut ScanResult, fs: &dyn FileSystem) -> Vec<PathBuf> {
+    let mut missing = Vec::new();
+    let control = ScanControl::new();
+    let errors = &mut result.errors;
+    result.items.retain_mut(|item| {
+        if fs.metadata(&item.path).is_err() {
+            missing.push(item.path.clone());
+            return false;
+        }
+        let stats = calculate_dir_stats(&item.path, fs, &control, errors);
+        item.size_bytes = stats.size;
+        item.file_count = stats.files;
+        item.last_modified = stats.newest;
+        true
+    });
+    missing
+}
+
+/// Everything a scan reads from, passed down to the per-root walks
+struct ScanContext<'a> {
+    config: &'a ScanConfig,
+    fs: &'a dyn FileSystem,
+    control: &'a ScanControl,
+    /// The config's exclude globs
+    excludes: GlobSet,
+    /// The globs of the config's custom patterns, in the same order
+    custom: GlobSet,
+}
+
+impl<'a> ScanContext<'a> {
+    /// Compile the globs of `config`, which fails if any of them is invalid
+    fn new(
+        config: &'a ScanConfig,
+        fs: &'a dyn FileSystem,
+        control: &'a ScanControl,
+    ) -> Result<Self> {
+        let custom: Vec<String> = config
+            .custom_patterns
+            .iter()
+            .map(|pattern| pattern.glob.clone())
+            .collect();
+        Ok(Self {
+            config,
+            fs,
+            control,
+            excludes: compile_globs(&config.exclude_globs)?,
+            custom: compile_globs(&custom)?,
+        })
+    }
+
+    /// The junk kind of a directory named `name`, and the custom pattern it matched if any
+    ///
+    /// Built-in kinds win over custom patterns.
+    fn match_dir(&self, name: &OsStr) -> Option<(JunkKind, Option<&CustomPattern>)> {
+        if let Some(kind) = find_matching_kind(name, &self.config.include_patterns) {
+            return Some((kind, None));
+        }
+        let index = *self.custom.matches(name.to_str()?).first()?;
+        Some((JunkKind::Custom, Some(&self.config.custom_patterns[index])))
+    }
+}
+
+fn scan_in_context<F>(ctx: &ScanContext<'_>, on_progress: F) -> Result<ScanResult>
+where
+    F: Fn(ScanProgress) + Send + Sync,
+{
+    let config = ctx.config;
+    let started = Instant::now();
+
+    // Validate roots exist
+    for root in &config.roots {
+        match ctx.fs.metadata(root) {
+            Err(_) => return Err(DevJunkError::PathNotFound(root.clone())),
+            Ok(meta) if meta.kind == FileKind::File => {
+                return Err(DevJunkError::NotADirectory(root.clone()));
+            }
+            Ok(_) => {}
         }
     }
 
+    // Walk each location once, even if given twice or nested in another root
+    let roots = dedup_roots(&config.roots);
+    if roots.len() < config.roots.len() {
+        debug!(
+            dropped = config.roots.len() - roots.len(),
+            "dropped duplicate and nested roots"
+        );
+    }
+
     // Shared counters for progress tracking
-    let dirs_scanned = Arc::new(AtomicUsize::new(0));
-    let items_found = Arc::new(AtomicUsize::new(0));
+    let tracker = Arc::new(ProgressTracker::new(ctx.fs, &roots));
     let on_progress = Arc::new(on_progress);
 
-    // Collect all junk items from all roots in parallel
-    let items: Vec<ScanItem> = config
-        .roots
+    // Read the mount table once up front rather than per directory
+    let mounts = if config.skip_network_mounts {
+        mounts::list_mounts()
+    } else {
+        Vec::new()
+    };
+
+    // Scan all roots in parallel
+    let root_scans: Vec<RootScan> = roots
         .par_iter()
-        .flat_map(|root| {
+        .map(|root| {
             scan_root_with_progress(
                 root,
-                config,
-                Arc::clone(&dirs_scanned),
-                Arc::clone(&items_found),
+                ctx,
+                &mounts,
+                Arc::clone(&tracker),
                 Arc::clone(&on_progress),
             )
         })
         .collect();
 
-    let mut result = ScanResult { items };
+    info!(
+        roots = roots.len(),
+        directories = tracker.dirs_scanned.load(Ordering::Relaxed),
+        elapsed = ?started.elapsed(),
+        "walked roots"
+    );
+
+    let mut result = ScanResult::new();
+    for root_scan in root_scans {
+        result.items.extend(root_scan.items);
+        result.skipped_mounts.extend(root_scan.skipped_mounts);
+        result.errors.extend(root_scan.errors);
+    }
+    if ctx.control.is_cancelled() {
+        info!(items = result.items.len(), elapsed = ?started.elapsed(), "scan cancelled");
+        result.cancelled = true;
+        return Ok(result);
+    }
+
+    if config.include_shared_target_dirs && config.include_patterns.contains(&JunkKind::RustTarget)
+    {
+        let phase = Instant::now();
+        scan_shared_target_dirs(ctx, &mut result);
+        info!(elapsed = ?phase.elapsed(), "sized shared Cargo target directories");
+    }
+
+    // Symlinked roots can still reach the same directory by different paths
+    let mut seen = HashSet::new();
+    result
+        .items
+        .retain(|item| seen.insert(canonical_or_original(&item.path)));
     result.sort_by_size();
 
+    // Each root kept its own top N; trim the merged list to the overall top N
+    if let Some(limit) = config.limit {
+        result.items.truncate(limit);
+    }
+
+    // Only look up repositories for the items that made the cut
+    if config.check_git_status {
+        let phase = Instant::now();
+        let mut git_cache = GitStatusCache::new(result.items.iter().map(|item| item.path.clone()));
+        for item in &mut result.items {
+            let project_dir = item.path.parent().unwrap_or(&item.path);
+            item.git = git_cache.status_for(project_dir);
+        }
+        info!(elapsed = ?phase.elapsed(), "looked up git status");
+    }
+
+    info!(
+        items = result.items.len(),
+        errors = result.errors.len(),
+        elapsed = ?started.elapsed(),
+        "scan finished"
+    );
     Ok(result)
 }
 
+/// Add shared Cargo target directories to the result as RustTarget items
+///
+/// Each one is attributed to itself as root, since it usually lives outside
+/// the scanned roots.
+fn scan_shared_target_dirs(ctx: &ScanContext<'_>, result: &mut ScanResult) {
+    for dir in cargo::shared_target_dirs() {
+        if ctx
+            .config
+            .exclude_paths
+            .iter()
+            .any(|exc| dir.starts_with(exc))
+            || ctx.excludes.is_match(&dir)
+        {
+            continue;
+        }
+
+        let item = size_item(ctx, &dir, JunkKind::RustTarget, &mut result.errors);
+        if let Some(item) = item {
+            result.items.push(item.with_root(dir));
+        }
+    }
+}
+
+/// Size a junk directory and build its ScanItem
+///
+/// Returns None if the item falls below the configured size or age threshold.
+fn size_item(
+    ctx: &ScanContext<'_>,
+    path: &Path,
+    kind: JunkKind,
+    errors: &mut Vec<ScanError>,
+) -> Option<ScanItem> {
+    let config = ctx.config;
+    let stats = calculate_dir_stats(path, ctx.fs, ctx.control, errors);
+
+    // Drop items below the configured size threshold
+    if config.min_size_bytes.is_some_and(|min| stats.size < min) {
+        debug!(path = %path.display(), size = stats.size, "skipping junk below the minimum size");
+        return None;
+    }
+
+    let mut item = ScanItem::new(path.to_path_buf(), kind, stats.size, stats.files);
+    item.last_modified = stats.newest;
+    if config.min_age.is_some_and(|age| !item.is_older_than(age)) {
+        debug!(path = %path.display(), "skipping junk newer than the minimum age");
+        return None;
+    }
+    item.is_active = match (stats.newest, config.active_window) {
+        // Timestamps in the future count as active too
+        (Some(newest), Some(window)) => newest.elapsed().map_or(true, |age| age < window),
+        _ => false,
+    };
+
+    // Flag items that would only fail at clean time
+    if let Err(reason) = ctx.fs.check_deletable(path) {
+        item.deletable = false;
+        item.undeletable_reason = Some(reason);
+    }
+
+    Some(item)
+}
+
+/// Drop duplicate roots and roots nested inside another root
+///
+/// Comparison uses canonical paths; the surviving roots keep the form the
+/// caller gave them, in the original order.
+fn dedup_roots(roots: &[PathBuf]) -> Vec<PathBuf> {
+    let canonical: Vec<PathBuf> = roots.iter().map(|r| canonical_or_original(r)).collect();
+
+    roots
+        .iter()
+        .enumerate()
+        .filter(|&(i, _)| {
+            !canonical.iter().enumerate().any(|(j, other)| {
+                let covered = if canonical[i] == *other {
+                    j < i
+                } else {
+                    canonical[i].starts_with(other)
+                };
+                i != j && covered
+            })
+        })
+        .map(|(_, root)| root.clone())
+        .collect()
+}
+
+/// Canonicalize a path, falling back to the path itself if that fails
+fn canonical_or_original(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Collects items for one root, keeping only the largest when a limit is set
+struct ItemCollector {
+    limit: Option<usize>,
+    /// Min-heap on size so the smallest kept item is evicted first
+    heap: BinaryHeap<Reverse<BySize>>,
+}
+
+impl ItemCollector {
+    fn new(limit: Option<usize>) -> Self {
+        Self {
+            limit,
+            heap: BinaryHeap::new(),
+        }
+    }
+
+    fn push(&mut self, item: ScanItem) {
+        self.heap.push(Reverse(BySize(item)));
+        if self.limit.is_some_and(|limit| self.heap.len() > limit) {
+            self.heap.pop();
+        }
+    }
+
+    fn into_items(self) -> Vec<ScanItem> {
+        self.heap
+            .into_iter()
+            .map(|Reverse(BySize(item))| item)
+            .collect()
+    }
+}
+
+/// Orders items by size, then path for a stable tie-break
+struct BySize(ScanItem);
+
+impl PartialEq for BySize {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == CmpOrdering::Equal
+    }
+}
+
+impl Eq for BySize {}
+
+impl PartialOrd for BySize {
+    fn partial_cmp(&self, other: &Self) -> Option<CmpOrdering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BySize {
+    fn cmp(&self, other: &Self) -> CmpOrdering {
+        self.0
+            .size_bytes
+            .cmp(&other.0.size_bytes)
+            .then_with(|| other.0.path.cmp(&self.0.path))
+    }
+}
+
+/// Output of scanning a single root
+struct RootScan {
+    items: Vec<ScanItem>,
+    skipped_mounts: Vec<MountInfo>,
+    errors: Vec<ScanError>,
+}
+
 /// Scan a single root directory with progress reporting
 fn scan_root_with_progress<F>(
     root: &Path,
-    config: &ScanConfig,
-    dirs_scanned: Arc<AtomicUsize>,
-    items_found: Arc<AtomicUsize>,
+    ctx: &ScanContext<'_>,
+    mounts: &[MountInfo],
+    tracker: Arc<ProgressTracker>,
     on_progress: Arc<F>,
-) -> Vec<ScanItem>
+) -> RootScan
 where
     F: Fn(ScanProgress) + Send + Sync,
 {
-    let mut walker = WalkDir::new(root).follow_links(false);
+    let config = ctx.config;
+    let mut walk = Walk::new(ctx.fs, root);
 
     if let Some(depth) = config.max_depth {
-        walker = walker.max_depth(depth);
-    }
-
-    let mut items = Vec::new();
-    let mut skip_dirs: Vec<std::path::PathBuf> = Vec::new();
-
-    for entry in walker.into_iter().filter_entry(|e| {
-        // Skip hidden directories if not configured to include them
-        if !config.include_hidden && is_hidden(e) {
-            // But still allow scanning of hidden junk dirs like .venv
-            let name = e.file_name().to_string_lossy();
-            if !config
-                .include_patterns
-                .iter()
-                .any(|k| k.matches_name(&name))
-            {
-                return false;
-            }
+        walk = walk.max_depth(depth);
+    }
+
+    let mut items = ItemCollector::new(config.limit);
+    let slow_mounts = mounts::slow_mounts_under(root, mounts);
+    let mut skipped_mounts = Vec::new();
+    let mut errors = Vec::new();
+
+    while let Some(entry) = walk.next() {
+        ctx.control.wait_while_paused();
+        if ctx.control.is_cancelled() {
+            break;
         }
-        true
-    }) {
+
         let entry = match entry {
             Ok(e) => e,
-            Err(_) => continue, // Skip entries we can't read
+            Err(e) => {
+                // Record entries we can't read and keep going
+                errors.push(to_scan_error(e));
+                continue;
+            }
         };
 
-        // Skip if we're inside a previously found junk directory
-        if skip_dirs
-            .iter()
-            .any(|skip| entry.path().starts_with(skip))
-        {
+        if should_skip(ctx, &entry, &slow_mounts, &mut skipped_mounts) {
+            walk.skip_current_dir();
             continue;
         }
 
-        // Check if this entry is excluded
-        if config
-            .exclude_paths
-            .iter()
-            .any(|exc| entry.path().starts_with(exc))
-        {
-            continue;
+        // Reaching a new top-level entry means the previous one has been walked
+        if entry.depth == 1 {
+            tracker.top_level_done.fetch_add(1, Ordering::Relaxed);
         }
 
         // Only process directories
-        if !entry.file_type().is_dir() {
+        if entry.kind != FileKind::Dir {
             continue;
         }
 
         // Update progress for each directory
-        let current_dirs = dirs_scanned.fetch_add(1, Ordering::Relaxed) + 1;
-        let current_items = items_found.load(Ordering::Relaxed);
+        trace!(path = %entry.path.display(), "visiting directory");
+        tracker.dirs_scanned.fetch_add(1, Ordering::Relaxed);
+        on_progress(tracker.snapshot(&entry.path, None));
 
-        // Report progress
-        on_progress(ScanProgress {
-            current_path: entry.path().display().to_string(),
-            items_found: current_items,
-            directories_scanned: current_dirs,
-        });
+        // Check if this directory matches any junk pattern
+        if let Some((kind, custom)) = ctx.match_dir(entry.file_name()) {
+            // Found a junk directory; it is sized separately, so don't descend
+            walk.skip_current_dir();
+            let path = entry.path;
 
-        let name = entry.file_name().to_string_lossy();
+            // Calculate size and file count
+            on_progress(tracker.snapshot(&path, Some(&path)));
+            let Some(mut item) = size_item(ctx, &path, kind, &mut errors) else {
+                continue;
+            };
+            item.custom_name = custom.map(|pattern| pattern.name.clone());
+            item.custom_glob = custom.map(|pattern| pattern.glob.clone());
+            let size_bytes = item.size_bytes;
+            debug!(path = %path.display(), kind = kind.id(), size = size_bytes, "found junk");
+            items.push(item.with_root(root.to_path_buf()));
 
-        // Check if this directory matches any junk pattern
-        if let Some(kind) = find_matching_kind(&name, &config.include_patterns) {
-            // Found a junk directory, calculate its size
-            let path = entry.path().to_path_buf();
+            // Update counters and report progress
+            tracker.items_found.fetch_add(1, Ordering::Relaxed);
+            tracker.bytes_found.fetch_add(size_bytes, Ordering::Relaxed);
+            on_progress(tracker.snapshot(&path, None));
+        }
+    }
 
-            // Add to skip list so we don't descend into it
-            skip_dirs.push(path.clone());
+    RootScan {
+        items: items.into_items(),
+        skipped_mounts,
+        errors,
+    }
+}
 
-            // Calculate size and file count
-            let (size_bytes, file_count) = calculate_dir_stats(&path);
+/// Walk a single root for an estimate, until `deadline`
+fn estimate_root(
+    root: &Path,
+    ctx: &ScanContext<'_>,
+    mounts: &[MountInfo],
+    deadline: Instant,
+) -> ScanEstimate {
+    let mut walk = Walk::new(ctx.fs, root);
+    if let Some(depth) = ctx.config.max_depth {
+        walk = walk.max_depth(depth);
+    }
+    let slow_mounts = mounts::slow_mounts_under(root, mounts);
+    let mut estimate = ScanEstimate {
+        items: Vec::new(),
+        directories_scanned: 0,
+        complete: true,
+    };
 
-            items.push(ScanItem::new(path.clone(), kind, size_bytes, file_count));
+    while let Some(entry) = walk.next() {
+        if Instant::now() >= deadline {
+            estimate.complete = false;
+            break;
+        }
+        let Ok(entry) = entry else {
+            continue;
+        };
+        if should_skip(ctx, &entry, &slow_mounts, &mut Vec::new()) {
+            walk.skip_current_dir();
+            continue;
+        }
+        if entry.kind != FileKind::Dir {
+            continue;
+        }
 
-            // Update items found counter and report progress
-            let new_items = items_found.fetch_add(1, Ordering::Relaxed) + 1;
-            on_progress(ScanProgress {
-                current_path: path.display().to_string(),
-                items_found: new_items,
-                directories_scanned: current_dirs,
+        estimate.directories_scanned += 1;
+        if let Some((kind, _)) = ctx.match_dir(entry.file_name()) {
+            walk.skip_current_dir();
+            estimate.items.push(EstimatedItem {
+                path: entry.path,
+                kind,
+                root: root.to_path_buf(),
+                size_bytes: None,
             });
         }
     }
+    estimate
+}
+
+/// Whether the walk of a root should leave out `e` and everything below it
+///
+/// Slow mounts left out are added to `skipped_mounts`.
+fn should_skip(
+    ctx: &ScanContext<'_>,
+    e: &WalkEntry,
+    slow_mounts: &[(PathBuf, MountInfo)],
+    skipped_mounts: &mut Vec<MountInfo>,
+) -> bool {
+    let config = ctx.config;
+
+    // Never follow symlinks, junctions or other reparse points
+    if e.depth > 0 && e.kind == FileKind::Link {
+        trace!(path = %e.path.display(), "not following link");
+        return true;
+    }
+
+    // Don't descend into excluded trees at all
+    if config
+        .exclude_paths
+        .iter()
+        .any(|exc| e.path.starts_with(exc))
+        || ctx.excludes.is_match(&e.path)
+    {
+        debug!(path = %e.path.display(), "skipping excluded directory");
+        return true;
+    }
+
+    // Don't descend into network shares or removable media
+    if let Some((_, mount)) = slow_mounts.iter().find(|(p, _)| *p == e.path) {
+        debug!(path = %e.path.display(), fs_type = %mount.fs_type, "skipping slow mount");
+        skipped_mounts.push(mount.clone());
+        return true;
+    }
+
+    // Skip hidden directories if not configured to include them
+    if !config.include_hidden && is_hidden(e) {
+        // But still allow scanning of hidden junk dirs like .venv
+        if ctx.match_dir(e.file_name()).is_none() {
+            trace!(path = %e.path.display(), "skipping hidden directory");
+            return true;
+        }
+    }
+    false
+}
+
+/// Convert a walk error into a ScanError
+fn to_scan_error(err: WalkError) -> ScanError {
+    info!(path = %err.path.display(), error = %err.error, "could not read entry");
+    let kind = match err.error.kind() {
+        std::io::ErrorKind::PermissionDenied => ScanErrorKind::PermissionDenied,
+        std::io::ErrorKind::NotFound => ScanErrorKind::NotFound,
+        _ => ScanErrorKind::Other,
+    };
 
-    items
+    ScanError::new(err.path, kind, err.error.to_string())
 }
 
 /// Check if a directory entry is hidden (starts with '.')
-fn is_hidden(entry: &DirEntry) -> bool {
-    entry
-        .file_name()
-        .to_str()
-        .map(|s| s.starts_with('.'))
-        .unwrap_or(false)
+fn is_hidden(entry: &WalkEntry) -> bool {
+    // Lossy conversion keeps a leading '.' even if the rest isn't valid UTF-8
+    entry.file_name().to_string_lossy().starts_with('.')
 }
 
 /// Find the matching JunkKind for a directory name
-fn find_matching_kind(name: &str, patterns: &[JunkKind]) -> Option<JunkKind> {
-    patterns.iter().find(|k| k.matches_name(name)).copied()
+fn find_matching_kind(name: &OsStr, patterns: &[JunkKind]) -> Option<JunkKind> {
+    patterns.iter().find(|k| k.matches_os_name(name)).copied()
 }
 
 /// Calculate the total size and file count of a directory
-fn calculate_dir_stats(path: &Path) -> (u64, u64) {
-    let mut total_size: u64 = 0;
-    let mut file_count: u64 = 0;
-
-    // Use parallel iteration for large directories
-    let entries: Vec<_> = WalkDir::new(path)
-        .follow_links(false)
-        .into_iter()
-        .filter_map(|e| e.ok())
-        .collect();
-
-    // For small directories, sequential is faster
-    if entries.len() < 1000 {
-        for entry in entries {
-            if entry.file_type().is_file() {
-                file_count += 1;
-                if let Ok(metadata) = fs::metadata(entry.path()) {
-                    total_size += metadata.len();
+///
+/// Entries are streamed through a parallel fold, so memory use stays flat
+/// regardless of how many files the directory holds. Links are counted once,
+/// by their own size, and never followed. Unreadable entries are appended to
+/// `errors` and excluded from the totals.
+fn calculate_dir_stats(
+    path: &Path,
+    fs: &dyn FileSystem,
+    control: &ScanControl,
+    errors: &mut Vec<ScanError>,
+) -> DirStats {
+    let mut stats = Walk::new(fs, path)
+        .inspect(|_| control.wait_while_paused())
+        .take_while(|_| !control.is_cancelled())
+        .par_bridge()
+        .fold(DirStats::default, |mut stats, entry| {
+            match entry {
+                Ok(entry) if entry.kind != FileKind::Dir => {
+                    if let Ok(metadata) = fs.metadata(&entry.path) {
+                        stats.size += metadata.len;
+                        if entry.kind == FileKind::File {
+                            stats.newest = stats.newest.max(metadata.modified);
+                        }
+                    }
+                    stats.files += 1;
                 }
+                Ok(_) => {}
+                Err(e) => stats.errors.push(to_scan_error(e)),
             }
-        }
-    } else {
-        // Use parallel processing for large directories
-        let stats: (u64, u64) = entries
-            .par_iter()
-            .filter(|e| e.file_type().is_file())
-            .map(|entry| {
-                let size = fs::metadata(entry.path())
-                    .map(|m| m.len())
-                    .unwrap_or(0);
-                (size, 1u64)
-            })
-            .reduce(|| (0, 0), |a, b| (a.0 + b.0, a.1 + b.1));
+            stats
+        })
+        .reduce(DirStats::default, DirStats::merge);
 
-        total_size = stats.0;
-        file_count = stats.1;
-    }
+    errors.append(&mut stats.errors);
+    stats
+}
+
+/// Totals accumulated while sizing a directory
+#[derive(Default)]
+struct DirStats {
+    size: u64,
+    files: u64,
+    /// Modification time of the newest file
+    newest: Option<SystemTime>,
+    errors: Vec<ScanError>,
+}
 
-    (total_size, file_count)
+impl DirStats {
+    fn merge(mut self, other: Self) -> Self {
+        self.size += other.size;
+        self.files += other.files;
+        self.newest = self.newest.max(other.newest);
+        self.errors.extend(other.errors);
+        self
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::types::ScanConfigBuilder;
     use std::fs::{self, File};
     use std::io::Write;
+    use std::time::{Duration, SystemTime};
     use tempfile::TempDir;
 
-    /// Format bytes into human-readable string
-    fn format_size(bytes: u64) -> String {
-        const KB: u64 = 1024;
-        const MB: u64 = KB * 1024;
-        const GB: u64 = MB * 1024;
-
-        if bytes >= GB {
-            format!("{:.2} GB", bytes as f64 / GB as f64)
-        } else if bytes >= MB {
-            format!("{:.2} MB", bytes as f64 / MB as f64)
-        } else if bytes >= KB {
-            format!("{:.2} KB", bytes as f64 / KB as f64)
-        } else {
-            format!("{} B", bytes)
-        }
-    }
-
-    #[test]
-    fn test_format_size() {
-        assert_eq!(format_size(500), "500 B");
-        assert_eq!(format_size(1024), "1.00 KB");
-        assert_eq!(format_size(1536), "1.50 KB");
-        assert_eq!(format_size(1048576), "1.00 MB");
-        assert_eq!(format_size(1073741824), "1.00 GB");
-    }
-
     #[test]
     fn test_scan_finds_node_modules() {
         let temp = TempDir::new().unwrap();
@@ -292,7 +980,10 @@ mod tests {
         let mut file = File::create(&file_path).unwrap();
         file.write_all(b"console.log('test');").unwrap();
 
-        let config = ScanConfig::new(vec![temp.path().to_path_buf()]).with_hidden(true);
+        let config = ScanConfig::builder(vec![temp.path().to_path_buf()])
+            .with_hidden(true)
+            .build()
+            .unwrap();
         let result = scan(&config).unwrap();
 
         assert_eq!(result.item_count(), 1);
@@ -316,7 +1007,10 @@ mod tests {
         fs::create_dir_all(&pycache_path).unwrap();
         File::create(pycache_path.join("module.pyc")).unwrap();
 
-        let config = ScanConfig::new(vec![temp.path().to_path_buf()]).with_hidden(true);
+        let config = ScanConfig::builder(vec![temp.path().to_path_buf()])
+            .with_hidden(true)
+            .build()
+            .unwrap();
         let result = scan(&config).unwrap();
 
         assert_eq!(result.item_count(), 3);
@@ -326,4 +1020,643 @@ mod tests {
         assert!(kinds.contains(&JunkKind::RustTarget));
         assert!(kinds.contains(&JunkKind::PythonCache));
     }
+
+    #[test]
+    fn test_scan_skips_items_below_min_size() {
+        let temp = TempDir::new().unwrap();
+
+        let small = temp.path().join("proj1").join("__pycache__");
+        fs::create_dir_all(&small).unwrap();
+        File::create(small.join("a.pyc"))
+            .unwrap()
+            .write_all(&[0u8; 10])
+            .unwrap();
+
+        let large = temp.path().join("proj2").join("node_modules");
+        fs::create_dir_all(&large).unwrap();
+        File::create(large.join("index.js"))
+            .unwrap()
+            .write_all(&[0u8; 4096])
+            .unwrap();
+
+        let config = ScanConfig::builder(vec![temp.path().to_path_buf()])
+            .with_hidden(true)
+            .with_min_size(1024)
+            .build()
+            .unwrap();
+        let result = scan(&config).unwrap();
+
+        assert_eq!(result.item_count(), 1);
+        assert_eq!(result.items[0].kind, JunkKind::NodeModules);
+    }
+
+    #[test]
+    fn test_scan_skips_items_newer_than_min_age() {
+        use crate::filesystem::MemoryFileSystem;
+
+        let day = Duration::from_secs(24 * 60 * 60);
+        let fs = MemoryFileSystem::new();
+        fs.add_file("/r/old/node_modules/a.js", 10)
+            .add_file("/r/new/node_modules/b.js", 10)
+            .set_modified("/r/old/node_modules/a.js", SystemTime::now() - day * 90)
+            .set_modified("/r/old/node_modules", SystemTime::now() - day * 90)
+            .set_modified("/r/new/node_modules/b.js", SystemTime::now() - day);
+
+        let config = ScanConfig::builder(vec![PathBuf::from("/r")])
+            .with_skip_network_mounts(false)
+            .with_min_age(day * 30)
+            .build_with_fs(&fs)
+            .unwrap();
+        let result = scan_with_fs(&config, &fs).unwrap();
+
+        assert_eq!(result.item_count(), 1);
+        assert_eq!(result.items[0].path, PathBuf::from("/r/old/node_modules"));
+    }
+
+    #[test]
+    fn test_scan_applies_exclude_globs_and_custom_patterns() {
+        use crate::filesystem::MemoryFileSystem;
+
+        let fs = MemoryFileSystem::new();
+        fs.add_file("/r/app/node_modules/a.js", 10)
+            .add_file("/r/app/.bazel-cache-foo/b.o", 20)
+            .add_file("/r/work/critical/node_modules/c.js", 30);
+
+        let config = ScanConfig::builder(vec![PathBuf::from("/r")])
+            .with_skip_network_mounts(false)
+            .with_exclude_globs(vec!["**/work/critical/**".to_string()])
+            .with_custom_patterns(vec![CustomPattern::new("Bazel cache", ".bazel-cache-*")])
+            .build_with_fs(&fs)
+            .unwrap();
+        let result = scan_with_fs(&config, &fs).unwrap();
+
+        let found: Vec<_> = result
+            .items
+            .iter()
+            .map(|item| (item.path.clone(), item.kind, item.kind_name()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (
+                    PathBuf::from("/r/app/.bazel-cache-foo"),
+                    JunkKind::Custom,
+                    "Bazel cache"
+                ),
+                (
+                    PathBuf::from("/r/app/node_modules"),
+                    JunkKind::NodeModules,
+                    "Node Modules"
+                ),
+            ]
+        );
+
+        let mut invalid = config;
+        invalid.exclude_globs.push("[".to_string());
+        assert!(matches!(
+            scan_with_fs(&invalid, &fs),
+            Err(DevJunkError::InvalidGlob { .. })
+        ));
+    }
+
+    #[test]
+    fn test_scan_progress_reports_bytes_found() {
+        use std::sync::Mutex;
+
+        let temp = TempDir::new().unwrap();
+        let nm_path = temp.path().join("project").join("node_modules");
+        fs::create_dir_all(&nm_path).unwrap();
+        File::create(nm_path.join("index.js"))
+            .unwrap()
+            .write_all(&[0u8; 100])
+            .unwrap();
+
+        let last = Mutex::new(None);
+        let config = ScanConfig::builder(vec![temp.path().to_path_buf()])
+            .with_hidden(true)
+            .build()
+            .unwrap();
+        scan_with_progress(&config, |p| *last.lock().unwrap() = Some(p)).unwrap();
+
+        let last = last.into_inner().unwrap().unwrap();
+        assert_eq!(last.items_found, 1);
+        assert_eq!(last.bytes_found, 100);
+        assert!(last.sizing_path.is_none());
+    }
+
+    #[test]
+    fn test_scan_limit_keeps_largest_items() {
+        let temp = TempDir::new().unwrap();
+
+        for (project, size) in [("small", 10), ("medium", 500), ("large", 2000)] {
+            let nm_path = temp.path().join(project).join("node_modules");
+            fs::create_dir_all(&nm_path).unwrap();
+            File::create(nm_path.join("index.js"))
+                .unwrap()
+                .write_all(&vec![0u8; size])
+                .unwrap();
+        }
+
+        let config = ScanConfig::builder(vec![temp.path().to_path_buf()])
+            .with_hidden(true)
+            .with_limit(2)
+            .build()
+            .unwrap();
+        let result = scan(&config).unwrap();
+
+        let sizes: Vec<_> = result.items.iter().map(|i| i.size_bytes).collect();
+        assert_eq!(sizes, vec![2000, 500]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_scan_collects_unreadable_directories() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp = TempDir::new().unwrap();
+        let locked = temp.path().join("locked");
+        fs::create_dir_all(locked.join("inner")).unwrap();
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o000)).unwrap();
+
+        // Root can read anything, so there is nothing to observe
+        if fs::read_dir(&locked).is_ok() {
+            fs::set_permissions(&locked, fs::Permissions::from_mode(0o755)).unwrap();
+            return;
+        }
+
+        let config = ScanConfig::builder(vec![temp.path().to_path_buf()])
+            .with_hidden(true)
+            .build()
+            .unwrap();
+        let result = scan(&config).unwrap();
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o755)).unwrap();
+
+        assert!(result.has_errors());
+        assert_eq!(result.errors[0].path, locked);
+        assert_eq!(result.errors[0].kind, ScanErrorKind::PermissionDenied);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_scan_counts_links_once_without_following() {
+        let temp = TempDir::new().unwrap();
+
+        // A large directory outside the junk dir, linked from inside it
+        let store = temp.path().join("store");
+        fs::create_dir_all(&store).unwrap();
+        File::create(store.join("big.bin"))
+            .unwrap()
+            .write_all(&[0u8; 10_000])
+            .unwrap();
+
+        let nm_path = temp.path().join("project").join("node_modules");
+        fs::create_dir_all(&nm_path).unwrap();
+        File::create(nm_path.join("index.js"))
+            .unwrap()
+            .write_all(&[0u8; 100])
+            .unwrap();
+        std::os::unix::fs::symlink(&store, nm_path.join("linked")).unwrap();
+
+        let config = ScanConfig::builder(vec![temp.path().to_path_buf()])
+            .with_hidden(true)
+            .build()
+            .unwrap();
+        let result = scan(&config).unwrap();
+
+        assert_eq!(result.item_count(), 1);
+        assert_eq!(result.items[0].file_count, 2);
+        assert!(result.items[0].size_bytes < 10_000);
+    }
+
+    #[test]
+    fn test_scan_attributes_items_to_roots() {
+        let temp = TempDir::new().unwrap();
+        let root_a = temp.path().join("a");
+        let root_b = temp.path().join("b");
+        fs::create_dir_all(root_a.join("node_modules")).unwrap();
+        fs::create_dir_all(root_b.join("target")).unwrap();
+        fs::create_dir_all(root_b.join("dist")).unwrap();
+
+        let config = ScanConfig::builder(vec![root_a.clone(), root_b.clone()])
+            .with_hidden(true)
+            .build()
+            .unwrap();
+        let result = scan(&config).unwrap();
+        let groups = result.by_root();
+
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[root_a.as_path()].len(), 1);
+        assert_eq!(groups[root_b.as_path()].len(), 2);
+    }
+
+    #[test]
+    fn test_dedup_roots_drops_nested_and_duplicate_roots() {
+        let temp = TempDir::new().unwrap();
+        let parent = temp.path().to_path_buf();
+        let child = parent.join("project");
+        fs::create_dir_all(&child).unwrap();
+
+        let roots = vec![child.clone(), parent.clone(), parent.join(".")];
+        assert_eq!(dedup_roots(&roots), vec![parent]);
+    }
+
+    #[test]
+    fn test_scan_overlapping_roots_reports_items_once() {
+        let temp = TempDir::new().unwrap();
+        let project = temp.path().join("project");
+        fs::create_dir_all(project.join("node_modules")).unwrap();
+
+        let config = ScanConfig::builder(vec![
+            temp.path().to_path_buf(),
+            project,
+            temp.path().to_path_buf(),
+        ])
+        .with_hidden(true)
+        .build()
+        .unwrap();
+        let result = scan(&config).unwrap();
+
+        assert_eq!(result.item_count(), 1);
+        assert_eq!(result.items[0].root, temp.path());
+    }
+
+    #[test]
+    fn test_calculate_dir_stats_large_directory() {
+        let temp = TempDir::new().unwrap();
+        for dir in 0..15 {
+            let sub = temp.path().join(format!("pkg{}", dir));
+            fs::create_dir_all(&sub).unwrap();
+            for file in 0..100 {
+                File::create(sub.join(format!("{}.js", file)))
+                    .unwrap()
+                    .write_all(b"x")
+                    .unwrap();
+            }
+        }
+
+        let mut errors = Vec::new();
+        let stats = calculate_dir_stats(
+            temp.path(),
+            &StdFileSystem,
+            &ScanControl::new(),
+            &mut errors,
+        );
+
+        assert_eq!(stats.files, 1500);
+        assert_eq!(stats.size, 1500);
+        assert!(stats.newest.is_some());
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn test_scan_flags_recently_modified_items_as_active() {
+        let temp = TempDir::new().unwrap();
+        let nm_path = temp.path().join("project").join("node_modules");
+        fs::create_dir_all(&nm_path).unwrap();
+        File::create(nm_path.join("index.js")).unwrap();
+
+        let config = ScanConfig::builder(vec![temp.path().to_path_buf()])
+            .with_hidden(true)
+            .build()
+            .unwrap();
+        let result = scan(&config).unwrap();
+        assert!(result.items[0].is_active);
+        assert!(result.items[0].last_modified.is_some());
+
+        let config = ScanConfigBuilder::from(config)
+            .with_active_window(None)
+            .build()
+            .unwrap();
+        let result = scan(&config).unwrap();
+        assert!(!result.items[0].is_active);
+    }
+
+    #[test]
+    fn test_scan_attaches_git_status_only_when_requested() {
+        let temp = TempDir::new().unwrap();
+        let project = temp.path().join("project");
+        let nm_path = project.join("node_modules");
+        fs::create_dir_all(&nm_path).unwrap();
+        File::create(nm_path.join("index.js")).unwrap();
+        let initialized = std::process::Command::new("git")
+            .arg("init")
+            .arg("-q")
+            .arg(&project)
+            .status()
+            .is_ok_and(|s| s.success());
+        if !initialized {
+            return;
+        }
+
+        let builder = ScanConfig::builder(vec![temp.path().to_path_buf()]).with_hidden(true);
+        let result = scan(&builder.clone().build().unwrap()).unwrap();
+        assert!(result.items[0].git.is_none());
+
+        let config = builder.with_git_status(true).build().unwrap();
+        let result = scan(&config).unwrap();
+        let git = result.items[0].git.as_ref().unwrap();
+        // node_modules is untracked and not ignored, but it's the junk itself
+        assert!(!git.is_dirty());
+
+        File::create(project.join("package.json")).unwrap();
+        let result = scan(&config).unwrap();
+        assert!(result.items[0].git.as_ref().unwrap().is_dirty());
+    }
+
+    #[test]
+    fn test_home_preset_skips_non_dev_trees() {
+        let temp = TempDir::new().unwrap();
+        let home = temp.path().to_path_buf();
+        fs::create_dir_all(home.join("code").join("app").join("node_modules")).unwrap();
+        fs::create_dir_all(home.join("Pictures").join("album").join("build")).unwrap();
+
+        let config = ScanConfigBuilder::from(ScanConfig::for_home(home.clone()))
+            .with_hidden(true)
+            .build()
+            .unwrap();
+        let result = scan(&config).unwrap();
+
+        assert_eq!(result.items.len(), 1);
+        assert_eq!(
+            result.items[0].path,
+            home.join("code").join("app").join("node_modules")
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_scan_handles_non_utf8_names() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let temp = TempDir::new().unwrap();
+        let project = temp.path().join(OsStr::from_bytes(b"caf\xe9"));
+        fs::create_dir_all(project.join("node_modules")).unwrap();
+        fs::create_dir_all(temp.path().join(OsStr::from_bytes(b"build-\xff"))).unwrap();
+
+        let config = ScanConfig::builder(vec![temp.path().to_path_buf()])
+            .with_hidden(true)
+            .build()
+            .unwrap();
+        let result = scan(&config).unwrap();
+
+        assert_eq!(result.items.len(), 1);
+        assert_eq!(result.items[0].path, project.join("node_modules"));
+    }
+
+    #[test]
+    fn test_paused_scan_resumes_where_it_left_off() {
+        let temp = TempDir::new().unwrap();
+        let nm_path = temp.path().join("project").join("node_modules");
+        fs::create_dir_all(&nm_path).unwrap();
+        File::create(nm_path.join("index.js")).unwrap();
+
+        let control = ScanControl::new();
+        control.pause();
+
+        let config = ScanConfig::builder(vec![temp.path().to_path_buf()])
+            .with_hidden(true)
+            .build()
+            .unwrap();
+        let scan_thread = {
+            let control = control.clone();
+            std::thread::spawn(move || scan_with_control(&config, &control, |_| {}))
+        };
+
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        assert!(!scan_thread.is_finished());
+
+        control.resume();
+        let result = scan_thread.join().unwrap().unwrap();
+        assert_eq!(result.items.len(), 1);
+    }
+
+    #[test]
+    fn test_cancelled_scan_stops_while_paused() {
+        let temp = TempDir::new().unwrap();
+        let nm_path = temp.path().join("project").join("node_modules");
+        fs::create_dir_all(&nm_path).unwrap();
+        File::create(nm_path.join("index.js")).unwrap();
+
+        let control = ScanControl::new();
+        control.pause();
+
+        let config = ScanConfig::builder(vec![temp.path().to_path_buf()])
+            .with_hidden(true)
+            .build()
+            .unwrap();
+        let scan_thread = {
+            let control = control.clone();
+            std::thread::spawn(move || scan_with_control(&config, &control, |_| {}))
+        };
+
+        control.cancel();
+        let result = scan_thread.join().unwrap().unwrap();
+        assert!(result.cancelled);
+        assert!(result.items.is_empty());
+    }
+
+    #[test]
+    fn test_scan_with_memory_fs() {
+        use crate::filesystem::MemoryFileSystem;
+
+        let fs = MemoryFileSystem::new();
+        fs.add_file("/r/app/node_modules/a/index.js", 100)
+            .add_file("/r/app/node_modules/a/node_modules/b.js", 50)
+            .add_link("/r/app/node_modules/shared", "/r/lib")
+            .add_file("/r/lib/target/debug/lib.rlib", 400)
+            .add_file("/r/secret/target/x", 1)
+            .add_dir("/r/site/dist");
+        fs.deny_read("/r/secret");
+
+        let config = ScanConfig::builder(vec![PathBuf::from("/r")])
+            .with_skip_network_mounts(false)
+            .build_with_fs(&fs)
+            .unwrap();
+        let result = scan_with_fs(&config, &fs).unwrap();
+
+        let found: Vec<_> = result
+            .items
+            .iter()
+            .map(|i| (i.path.clone(), i.size_bytes, i.file_count, i.deletable))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (PathBuf::from("/r/lib/target"), 400, 1, true),
+                // Nested node_modules is part of the outer one; the link counts as a file
+                (PathBuf::from("/r/app/node_modules"), 150, 3, true),
+                (PathBuf::from("/r/site/dist"), 0, 0, true),
+            ]
+        );
+
+        assert_eq!(result.errors.len(), 1);
+        assert_eq!(result.errors[0].path, PathBuf::from("/r/secret"));
+        assert_eq!(result.errors[0].kind, ScanErrorKind::PermissionDenied);
+    }
+
+    #[test]
+    fn test_estimate_finds_junk_by_name_and_takes_known_sizes() {
+        use crate::filesystem::MemoryFileSystem;
+        use crate::history::HistoryItem;
+
+        let fs = MemoryFileSystem::new();
+        fs.add_file("/r/app/node_modules/a/node_modules/b.js", 50)
+            .add_file("/r/lib/target/debug/lib.rlib", 400)
+            .add_dir("/r/site/dist");
+
+        let config = ScanConfig::builder(vec![PathBuf::from("/r")])
+            .with_skip_network_mounts(false)
+            .build_with_fs(&fs)
+            .unwrap();
+        let estimate = estimate_with_fs(&config, &fs, Duration::from_secs(60)).unwrap();
+        assert!(estimate.complete);
+        let found: Vec<_> = estimate.items.iter().map(|i| i.path.clone()).collect();
+        assert_eq!(
+            found,
+            [
+                PathBuf::from("/r/app/node_modules"),
+                PathBuf::from("/r/lib/target"),
+                PathBuf::from("/r/site/dist"),
+            ]
+        );
+
+        let scanned = |at, bytes| HistoryRecord {
+            at,
+            event: HistoryEvent::Scan,
+            items: vec![HistoryItem {
+                path: PathBuf::from("/r/lib/target"),
+                kind: Some(JunkKind::RustTarget),
+                bytes,
+            }],
+        };
+        let estimate = estimate.with_known_sizes(&[scanned(1, 100), scanned(2, 300)]);
+        assert_eq!(estimate.known_bytes(), 300);
+        assert_eq!(estimate.unknown_count(), 2);
+
+        let timed_out = estimate_with_fs(&config, &fs, Duration::ZERO).unwrap();
+        assert!(!timed_out.complete);
+        assert!(timed_out.items.is_empty());
+    }
+
+    #[test]
+    fn test_measure_dir_sizes_any_directory() {
+        use crate::filesystem::MemoryFileSystem;
+
+        let fs = MemoryFileSystem::new();
+        fs.add_file("/home/me/photos/a.jpg", 300)
+            .add_file("/home/me/photos/2025/b.jpg", 200)
+            .add_file("/home/me/photos/private/c.jpg", 1);
+        fs.deny_read("/home/me/photos/private");
+
+        let size = measure_dir_with_fs(Path::new("/home/me/photos"), &fs).unwrap();
+        assert_eq!((size.size_bytes, size.file_count), (500, 2));
+        assert_eq!(size.errors.len(), 1);
+
+        let file = measure_dir_with_fs(Path::new("/home/me/photos/a.jpg"), &fs).unwrap();
+        assert_eq!((file.size_bytes, file.file_count), (300, 1));
+        assert!(matches!(
+            measure_dir_with_fs(Path::new("/home/me/missing"), &fs),
+            Err(DevJunkError::PathNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_measure_children_lists_the_largest_first() {
+        use crate::filesystem::MemoryFileSystem;
+
+        let fs = MemoryFileSystem::new();
+        fs.add_file("/r/target/debug/deps/a.rlib", 700)
+            .add_file("/r/target/debug/build/b.o", 100)
+            .add_file("/r/target/release/app", 300)
+            .add_file("/r/target/.rustc_info.json", 5);
+
+        let children = measure_children_with_fs(Path::new("/r/target"), &fs).unwrap();
+        assert_eq!(children[0].file_count, 2);
+        let sizes: Vec<_> = children
+            .iter()
+            .map(|child| (child.path.to_str().unwrap(), child.size_bytes))
+            .collect();
+        assert_eq!(
+            sizes,
+            [
+                ("/r/target/debug", 800),
+                ("/r/target/release", 300),
+                ("/r/target/.rustc_info.json", 5),
+            ]
+        );
+        assert!(matches!(
+            measure_children_with_fs(Path::new("/r/target/release/app"), &fs),
+            Err(DevJunkError::NotADirectory(_))
+        ));
+    }
+
+    #[test]
+    fn test_saved_scan_result_is_filtered_and_remeasured() {
+        use crate::filesystem::MemoryFileSystem;
+
+        let fs = MemoryFileSystem::new();
+        fs.add_file("/r/app/node_modules/index.js", 40)
+            .add_file("/r/app/node_modules/extra.js", 60)
+            .add_file("/r/keep/node_modules/index.js", 10)
+            .add_file("/r/app/Cargo.toml", 1)
+            .add_file("/r/app/target/debug/app", 5);
+        let config = ScanConfig::builder(vec![PathBuf::from("/r")])
+            .with_skip_network_mounts(false)
+            .build_with_fs(&fs)
+            .unwrap();
+        let mut result = scan_with_fs(&config, &fs).unwrap();
+        fs.add_file("/r/app/node_modules/late.js", 900);
+        fs.remove_dir_all(Path::new("/r/app/target")).unwrap();
+
+        let config = ScanConfigBuilder::from(config)
+            .with_patterns(vec![JunkKind::NodeModules, JunkKind::RustTarget])
+            .with_exclude_globs(vec!["**/keep".to_string()])
+            .with_min_size(5)
+            .build_with_fs(&fs)
+            .unwrap();
+        filter_scan_result(&mut result, &config).unwrap();
+        let mut paths: Vec<_> = result.items.iter().map(|i| i.path.clone()).collect();
+        paths.sort();
+        assert_eq!(
+            paths,
+            [
+                PathBuf::from("/r/app/node_modules"),
+                PathBuf::from("/r/app/target")
+            ]
+        );
+
+        let missing = remeasure_scan_result_with_fs(&mut result, &fs);
+        assert_eq!(missing, [PathBuf::from("/r/app/target")]);
+        assert_eq!(result.items.len(), 1);
+        assert_eq!(
+            (result.items[0].size_bytes, result.items[0].file_count),
+            (1000, 3)
+        );
+    }
+
+    #[test]
+    fn test_scan_with_memory_fs_flags_locked_items() {
+        use crate::filesystem::MemoryFileSystem;
+
+        let fs = MemoryFileSystem::new();
+        fs.add_file("/r/app/node_modules/index.js", 1);
+        fs.lock("/r/app/node_modules");
+
+        let config = ScanConfig::builder(vec![PathBuf::from("/r")])
+            .with_skip_network_mounts(false)
+            .build_with_fs(&fs)
+            .unwrap();
+        let result = scan_with_fs(&config, &fs).unwrap();
+
+        assert!(!result.items[0].deletable);
+        assert_eq!(
+            result.items[0].undeletable_reason.as_deref(),
+            Some("directory is locked")
+        );
+        assert!(matches!(
+            scan_with_fs(&ScanConfig::new(vec![PathBuf::from("/missing")]), &fs),
+            Err(DevJunkError::PathNotFound(_))
+        ));
+    }
 }
@@ -0,0 +1,578 @@
+//! Recurring cleans run by the OS scheduler
+//!
+//! A schedule becomes a systemd user timer on Linux, a launchd agent on macOS
+//! and a Task Scheduler task on Windows, each running a program with fixed
+//! arguments. The installed schedules are also recorded in `schedules.json`
+//! under the user's local data directory, so they can be listed without
+//! parsing each scheduler's own format back.
+
+use crate::error::{DevJunkError, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+const REGISTRY_FILE: &str = "schedules.json";
+
+/// How often a schedule runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Frequency {
+    /// Every day
+    Daily,
+    /// Every Sunday
+    Weekly,
+    /// On the first day of every month
+    Monthly,
+}
+
+/// A program the OS scheduler runs on a recurring basis
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Schedule {
+    /// Name telling the schedules apart; letters, digits, `-` and `_`
+    pub name: String,
+    /// How often it runs
+    pub frequency: Frequency,
+    /// Hour of the day it runs at, in local time
+    pub hour: u8,
+    /// Minute of the hour it runs at
+    pub minute: u8,
+    /// Program to run, usually the devjunk binary
+    pub program: PathBuf,
+    /// Arguments to run it with
+    pub args: Vec<String>,
+}
+
+impl Schedule {
+    /// Run `program` with `args` at 03:00 local time with the given frequency
+    pub fn new(
+        name: impl Into<String>,
+        frequency: Frequency,
+        program: impl Into<PathBuf>,
+        args: Vec<String>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            frequency,
+            hour: 3,
+            minute: 0,
+            program: program.into(),
+            args,
+        }
+    }
+
+    /// Run at `hour:minute` local time instead
+    pub fn with_time(mut self, hour: u8, minute: u8) -> Self {
+        self.hour = hour;
+        self.minute = minute;
+        self
+    }
+
+    fn validate(&self) -> Result<()> {
+        let name_ok = !self.name.is_empty()
+            && self
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !name_ok {
+            return Err(DevJunkError::InvalidSchedule(format!(
+                "name '{}' may only use letters, digits, '-' and '_'",
+                self.name
+            )));
+        }
+        if self.hour > 23 || self.minute > 59 {
+            return Err(DevJunkError::InvalidSchedule(format!(
+                "{:02}:{:02} is not a time of day",
+                self.hour, self.minute
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// A recorded schedule and whether the OS scheduler still has it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ScheduleStatus {
+    #[serde(flatten)]
+    pub schedule: Schedule,
+    /// Name of the timer, agent or task in the OS scheduler
+    pub job: String,
+    /// Whether the job is still installed
+    pub installed: bool,
+}
+
+/// Install `schedule` in the OS scheduler, replacing one of the same name
+pub fn install_schedule(schedule: &Schedule) -> Result<()> {
+    schedule.validate()?;
+    let registry = registry_path()?;
+    platform::install(schedule)?;
+
+    let mut schedules = load_registry(&registry)?;
+    schedules.retain(|s| s.name != schedule.name);
+    schedules.push(schedule.clone());
+    save_registry(&registry, &schedules)
+}
+
+/// Remove the schedule called `name` from the OS scheduler
+///
+/// # Returns
+/// Whether there was such a schedule
+pub fn uninstall_schedule(name: &str) -> Result<bool> {
+    let registry = registry_path()?;
+    let mut schedules = load_registry(&registry)?;
+    let recorded = schedules.iter().any(|s| s.name == name);
+    let installed = platform::is_installed(name);
+    if installed {
+        platform::uninstall(name)?;
+    }
+    if recorded {
+        schedules.retain(|s| s.name != name);
+        save_registry(&registry, &schedules)?;
+    }
+    Ok(recorded || installed)
+}
+
+/// The recorded schedules, by name
+pub fn list_schedules() -> Result<Vec<ScheduleStatus>> {
+    let mut schedules = load_registry(&registry_path()?)?;
+    schedules.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(schedules
+        .into_iter()
+        .map(|schedule| ScheduleStatus {
+            job: platform::job_name(&schedule.name),
+            installed: platform::is_installed(&schedule.name),
+            schedule,
+        })
+        .collect())
+}
+
+fn registry_path() -> Result<PathBuf> {
+    dirs::data_local_dir()
+        .map(|dir| dir.join("devjunk").join(REGISTRY_FILE))
+        .ok_or_else(|| DevJunkError::Scheduler("no local data directory to record it in".into()))
+}
+
+fn load_registry(path: &Path) -> Result<Vec<Schedule>> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    serde_json::from_str(&contents)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e).into())
+}
+
+/// Write the registry through a temporary file so a crash never leaves it half written
+fn save_registry(path: &Path, schedules: &[Schedule]) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let tmp = path.with_extension("json.tmp");
+    let json = serde_json::to_vec_pretty(schedules).map_err(io::Error::other)?;
+    fs::write(&tmp, json)?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+/// Run a scheduler command, turning a failure into its error output
+#[cfg(any(target_os = "linux", target_os = "macos", windows))]
+fn run(program: &str, args: &[&str]) -> Result<()> {
+    let output = std::process::Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| DevJunkError::Scheduler(format!("could not run {}: {}", program, e)))?;
+    if !output.status.success() {
+        return Err(DevJunkError::Scheduler(format!(
+            "`{} {}` failed: {}",
+            program,
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+use systemd as platform;
+
+#[cfg(target_os = "macos")]
+use launchd as platform;
+
+#[cfg(windows)]
+use schtasks as platform;
+
+/// Systemd user timers, each starting a oneshot service
+#[cfg(any(target_os = "linux", test))]
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+mod systemd {
+    use super::{run, Frequency, Schedule};
+    use crate::error::{DevJunkError, Result};
+    use std::fs;
+    use std::io;
+    use std::path::PathBuf;
+
+    pub fn job_name(name: &str) -> String {
+        format!("devjunk-{}.timer", name)
+    }
+
+    pub fn install(schedule: &Schedule) -> Result<()> {
+        let dir = unit_dir()?;
+        fs::create_dir_all(&dir)?;
+        let service = dir.join(format!("devjunk-{}.service", schedule.name));
+        let timer = dir.join(job_name(&schedule.name));
+        fs::write(&service, service_unit(schedule))?;
+        fs::write(&timer, timer_unit(schedule))?;
+        let enabled = run("systemctl", &["--user", "daemon-reload"]).and_then(|()| {
+            run(
+                "systemctl",
+                &["--user", "enable", "--now", &job_name(&schedule.name)],
+            )
+        });
+        // Without a user session bus the units would only pile up unused
+        if enabled.is_err() {
+            let _ = fs::remove_file(&service);
+            let _ = fs::remove_file(&timer);
+        }
+        enabled
+    }
+
+    pub fn uninstall(name: &str) -> Result<()> {
+        let dir = unit_dir()?;
+        // A timer that already stopped or was never loaded only needs its files gone
+        let _ = run(
+            "systemctl",
+            &["--user", "disable", "--now", &job_name(name)],
+        );
+        for extension in ["timer", "service"] {
+            match fs::remove_file(dir.join(format!("devjunk-{}.{}", name, extension))) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+                _ => {}
+            }
+        }
+        run("systemctl", &["--user", "daemon-reload"])
+    }
+
+    pub fn is_installed(name: &str) -> bool {
+        unit_dir().is_ok_and(|dir| dir.join(job_name(name)).exists())
+    }
+
+    fn unit_dir() -> Result<PathBuf> {
+        dirs::config_dir()
+            .map(|dir| dir.join("systemd").join("user"))
+            .ok_or_else(|| DevJunkError::Scheduler("no config directory for systemd units".into()))
+    }
+
+    pub(super) fn service_unit(schedule: &Schedule) -> String {
+        let command: Vec<String> = std::iter::once(schedule.program.to_string_lossy().into_owned())
+            .chain(schedule.args.iter().cloned())
+            .map(|arg| quote(&arg))
+            .collect();
+        format!(
+            "[Unit]\nDescription=devjunk schedule {}\n\n[Service]\nType=oneshot\nExecStart={}\n",
+            schedule.name,
+            command.join(" ")
+        )
+    }
+
+    pub(super) fn timer_unit(schedule: &Schedule) -> String {
+        let time = format!("{:02}:{:02}:00", schedule.hour, schedule.minute);
+        let calendar = match schedule.frequency {
+            Frequency::Daily => format!("*-*-* {}", time),
+            Frequency::Weekly => format!("Sun *-*-* {}", time),
+            Frequency::Monthly => format!("*-*-01 {}", time),
+        };
+        // Persistent catches up on a run missed while the machine was off
+        format!(
+            "[Unit]\nDescription=devjunk schedule {}\n\n[Timer]\nOnCalendar={}\nPersistent=true\n\n[Install]\nWantedBy=timers.target\n",
+            schedule.name, calendar
+        )
+    }
+
+    /// Quote an `ExecStart` word so neither specifiers nor variables are expanded
+    fn quote(arg: &str) -> String {
+        let escaped = arg
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('%', "%%")
+            .replace('$', "$$");
+        format!("\"{}\"", escaped)
+    }
+}
+
+/// Launchd agents in the user's LaunchAgents directory
+#[cfg(any(target_os = "macos", test))]
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+mod launchd {
+    use super::{run, Frequency, Schedule};
+    use crate::error::{DevJunkError, Result};
+    use std::fs;
+    use std::path::PathBuf;
+
+    pub fn job_name(name: &str) -> String {
+        format!("com.devjunk.schedule.{}", name)
+    }
+
+    pub fn install(schedule: &Schedule) -> Result<()> {
+        let path = plist_path(&schedule.name)?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let path_arg = path.to_string_lossy();
+        // Loading an agent that is already loaded fails, so replace it
+        let _ = run("launchctl", &["unload", &path_arg]);
+        fs::write(&path, plist(schedule))?;
+        run("launchctl", &["load", "-w", &path_arg])
+    }
+
+    pub fn uninstall(name: &str) -> Result<()> {
+        let path = plist_path(name)?;
+        let _ = run("launchctl", &["unload", "-w", &path.to_string_lossy()]);
+        fs::remove_file(&path)?;
+        Ok(())
+    }
+
+    pub fn is_installed(name: &str) -> bool {
+        plist_path(name).is_ok_and(|path| path.exists())
+    }
+
+    fn plist_path(name: &str) -> Result<PathBuf> {
+        let home = dirs::home_dir()
+            .ok_or_else(|| DevJunkError::Scheduler("no home directory for launch agents".into()))?;
+        Ok(home
+            .join("Library")
+            .join("LaunchAgents")
+            .join(format!("{}.plist", job_name(name))))
+    }
+
+    pub(super) fn plist(schedule: &Schedule) -> String {
+        let arguments: String = std::iter::once(schedule.program.to_string_lossy().into_owned())
+            .chain(schedule.args.iter().cloned())
+            .map(|arg| format!("\t\t<string>{}</string>\n", escape(&arg)))
+            .collect();
+        let day = match schedule.frequency {
+            Frequency::Daily => "",
+            Frequency::Weekly => "\t\t<key>Weekday</key>\n\t\t<integer>0</integer>\n",
+            Frequency::Monthly => "\t\t<key>Day</key>\n\t\t<integer>1</integer>\n",
+        };
+        let calendar = format!(
+            "{}\t\t<key>Hour</key>\n\t\t<integer>{}</integer>\n\t\t<key>Minute</key>\n\t\t<integer>{}</integer>\n",
+            day, schedule.hour, schedule.minute
+        );
+        format!(
+            concat!(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+                "<!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n",
+                "<plist version=\"1.0\">\n<dict>\n",
+                "\t<key>Label</key>\n\t<string>{}</string>\n",
+                "\t<key>ProgramArguments</key>\n\t<array>\n{}\t</array>\n",
+                "\t<key>StartCalendarInterval</key>\n\t<dict>\n{}\t</dict>\n",
+                "</dict>\n</plist>\n"
+            ),
+            job_name(&schedule.name),
+            arguments,
+            calendar
+        )
+    }
+
+    fn escape(text: &str) -> String {
+        text.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+    }
+}
+
+/// Tasks in a `devjunk` folder of the Windows Task Scheduler
+#[cfg(any(windows, test))]
+#[cfg_attr(not(windows), allow(dead_code))]
+mod schtasks {
+    use super::{run, Frequency, Schedule};
+    use crate::error::Result;
+
+    pub fn job_name(name: &str) -> String {
+        format!("devjunk\\{}", name)
+    }
+
+    pub fn install(schedule: &Schedule) -> Result<()> {
+        let args = create_args(schedule);
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        run("schtasks", &args)
+    }
+
+    pub fn uninstall(name: &str) -> Result<()> {
+        run("schtasks", &["/Delete", "/F", "/TN", &job_name(name)])
+    }
+
+    pub fn is_installed(name: &str) -> bool {
+        std::process::Command::new("schtasks")
+            .args(["/Query", "/TN", &job_name(name)])
+            .output()
+            .is_ok_and(|output| output.status.success())
+    }
+
+    pub(super) fn create_args(schedule: &Schedule) -> Vec<String> {
+        let command: Vec<String> = std::iter::once(schedule.program.to_string_lossy().into_owned())
+            .chain(schedule.args.iter().cloned())
+            .map(|arg| quote(&arg))
+            .collect();
+        let (period, day) = match schedule.frequency {
+            Frequency::Daily => ("DAILY", None),
+            Frequency::Weekly => ("WEEKLY", Some("SUN")),
+            Frequency::Monthly => ("MONTHLY", Some("1")),
+        };
+        let mut args: Vec<String> = ["/Create", "/F", "/TN"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        args.push(job_name(&schedule.name));
+        args.extend(["/SC".to_string(), period.to_string()]);
+        if let Some(day) = day {
+            args.extend(["/D".to_string(), day.to_string()]);
+        }
+        args.extend([
+            "/ST".to_string(),
+            format!("{:02}:{:02}", schedule.hour, schedule.minute),
+            "/TR".to_string(),
+            command.join(" "),
+        ]);
+        args
+    }
+
+    /// Quote an argument the way the C runtime splits a command line
+    fn quote(arg: &str) -> String {
+        if !arg.is_empty() && !arg.contains([' ', '\t', '"']) {
+            return arg.to_string();
+        }
+        let mut quoted = String::from("\"");
+        let mut backslashes = 0;
+        for c in arg.chars() {
+            match c {
+                '\\' => backslashes += 1,
+                '"' => {
+                    // Backslashes before a quote escape each other, then the quote
+                    quoted.push_str(&"\\".repeat(backslashes * 2 + 1));
+                    backslashes = 0;
+                }
+                _ => {
+                    quoted.push_str(&"\\".repeat(backslashes));
+                    backslashes = 0;
+                }
+            }
+            if c != '\\' {
+                quoted.push(c);
+            }
+        }
+        quoted.push_str(&"\\".repeat(backslashes * 2));
+        quoted.push('"');
+        quoted
+    }
+}
+
+/// No scheduler is supported here, so nothing can be installed
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+mod platform {
+    use super::Schedule;
+    use crate::error::{DevJunkError, Result};
+
+    pub fn job_name(name: &str) -> String {
+        name.to_string()
+    }
+
+    pub fn install(_schedule: &Schedule) -> Result<()> {
+        Err(DevJunkError::Scheduler(
+            "scheduling is only supported on Linux, macOS and Windows".into(),
+        ))
+    }
+
+    pub fn uninstall(_name: &str) -> Result<()> {
+        Ok(())
+    }
+
+    pub fn is_installed(_name: &str) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn schedule(frequency: Frequency) -> Schedule {
+        Schedule::new(
+            "weekly-clean",
+            frequency,
+            "/opt/dev junk/devjunk",
+            vec!["clean".into(), "/home/me/100%".into(), "--yes".into()],
+        )
+        .with_time(4, 30)
+    }
+
+    #[test]
+    fn test_invalid_schedules_are_rejected() {
+        assert!(schedule(Frequency::Daily).validate().is_ok());
+        let bad_name = Schedule {
+            name: "../clean".into(),
+            ..schedule(Frequency::Daily)
+        };
+        assert!(matches!(
+            bad_name.validate(),
+            Err(DevJunkError::InvalidSchedule(_))
+        ));
+        let bad_time = schedule(Frequency::Daily).with_time(24, 0);
+        assert!(matches!(
+            bad_time.validate(),
+            Err(DevJunkError::InvalidSchedule(_))
+        ));
+    }
+
+    #[test]
+    fn test_systemd_units_quote_arguments_and_set_the_calendar() {
+        let service = systemd::service_unit(&schedule(Frequency::Weekly));
+        assert!(service.contains(
+            "ExecStart=\"/opt/dev junk/devjunk\" \"clean\" \"/home/me/100%%\" \"--yes\""
+        ));
+
+        let calendar = |frequency| {
+            systemd::timer_unit(&schedule(frequency))
+                .lines()
+                .find_map(|line| line.strip_prefix("OnCalendar=").map(str::to_string))
+                .unwrap()
+        };
+        assert_eq!(calendar(Frequency::Daily), "*-*-* 04:30:00");
+        assert_eq!(calendar(Frequency::Weekly), "Sun *-*-* 04:30:00");
+        assert_eq!(calendar(Frequency::Monthly), "*-*-01 04:30:00");
+    }
+
+    #[test]
+    fn test_launchd_and_task_scheduler_jobs() {
+        let plist = launchd::plist(&schedule(Frequency::Monthly));
+        assert!(plist.contains("<string>com.devjunk.schedule.weekly-clean</string>"));
+        assert!(plist.contains("<string>/opt/dev junk/devjunk</string>"));
+        assert!(plist.contains("<key>Day</key>\n\t\t<integer>1</integer>"));
+        assert!(!plist.contains("Weekday"));
+
+        let args = schtasks::create_args(&schedule(Frequency::Weekly));
+        let value = |flag: &str| {
+            let at = args.iter().position(|arg| arg == flag).unwrap();
+            args[at + 1].as_str()
+        };
+        assert_eq!(value("/TN"), "devjunk\\weekly-clean");
+        assert_eq!(value("/D"), "SUN");
+        assert_eq!(value("/ST"), "04:30");
+        assert_eq!(
+            value("/TR"),
+            "\"/opt/dev junk/devjunk\" clean /home/me/100% --yes"
+        );
+    }
+
+    #[test]
+    fn test_registry_round_trips() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("devjunk").join(REGISTRY_FILE);
+        assert!(load_registry(&path).unwrap().is_empty());
+
+        let schedules = [schedule(Frequency::Daily), schedule(Frequency::Monthly)];
+        save_registry(&path, &schedules).unwrap();
+        assert_eq!(load_registry(&path).unwrap(), schedules);
+    }
+}
@@ -0,0 +1,243 @@
+//! Free space on the filesystems being cleaned
+//!
+//! The apparent size of a directory can be far from what deleting it gives
+//! back: hardlinked files (pnpm stores, Cargo's hardlinked outputs) free nothing
+//! while another link remains, and compressed or deduplicated filesystems free
+//! less than the file sizes add up to. Measuring free space around a clean
+//! shows what was actually reclaimed.
+
+use crate::mounts::{self, MountKind};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// A mounted volume and how full it is
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Volume {
+    /// Directory the volume is mounted on
+    pub mount_point: PathBuf,
+    /// Filesystem type as reported by the OS (e.g. "ext4", "apfs")
+    pub fs_type: String,
+    /// Local disk, network share or removable media
+    pub kind: MountKind,
+    /// Size of the volume in bytes
+    pub total_bytes: u64,
+    /// Bytes available to the current user
+    pub free_bytes: u64,
+}
+
+impl Volume {
+    /// Share of the volume the current user can no longer write to, from 0.0 to 1.0
+    pub fn used_fraction(&self) -> f64 {
+        if self.total_bytes == 0 {
+            return 0.0;
+        }
+        1.0 - self.free_bytes.min(self.total_bytes) as f64 / self.total_bytes as f64
+    }
+}
+
+/// Bytes available to the current user on the filesystem holding `path`
+pub fn free_space(path: &Path) -> io::Result<u64> {
+    platform::space(path).map(|(_, free)| free)
+}
+
+/// The volumes files can be kept on, by mount point
+///
+/// Filesystems of the OS itself, like `/proc` or the macOS system volumes,
+/// are left out, and so are volumes whose size can't be read. A volume
+/// mounted in several places is listed once.
+pub fn list_volumes() -> Vec<Volume> {
+    let mut seen = HashSet::new();
+    let mut volumes: Vec<Volume> = mounts::list_mounts()
+        .into_iter()
+        .filter(|mount| !mounts::is_system_mount(mount))
+        .filter_map(|mount| {
+            let (total_bytes, free_bytes) = platform::space(&mount.mount_point).ok()?;
+            let device = platform::volume_of(&mount.mount_point)?;
+            (total_bytes > 0 && seen.insert(device)).then_some(Volume {
+                mount_point: mount.mount_point,
+                fs_type: mount.fs_type,
+                kind: mount.kind,
+                total_bytes,
+                free_bytes,
+            })
+        })
+        .collect();
+    volumes.sort_by(|a, b| a.mount_point.cmp(&b.mount_point));
+    volumes
+}
+
+/// The filesystems holding a set of paths, to watch their free space grow as the paths are deleted
+#[derive(Debug, Clone)]
+pub struct SpaceProbe {
+    probes: Vec<PathBuf>,
+}
+
+impl SpaceProbe {
+    /// Probe the filesystems holding `paths`, before any of them is deleted
+    pub fn new(paths: &[PathBuf]) -> Self {
+        Self {
+            probes: volume_probes(paths),
+        }
+    }
+
+    /// Free bytes across the filesystems, or None if there are none or one can't be measured
+    pub fn free_bytes(&self) -> Option<u64> {
+        total_free_space(&self.probes).filter(|_| !self.probes.is_empty())
+    }
+}
+
+/// One directory on each distinct filesystem holding `paths`
+///
+/// The parents of the paths are used, since the paths themselves are about to be deleted.
+pub(crate) fn volume_probes(paths: &[PathBuf]) -> Vec<PathBuf> {
+    let mut seen = HashSet::new();
+    paths
+        .iter()
+        .filter_map(|path| path.parent())
+        .filter_map(|parent| {
+            let volume = platform::volume_of(parent)?;
+            seen.insert(volume).then(|| parent.to_path_buf())
+        })
+        .collect()
+}
+
+/// Total free bytes across the given probes, or None if any of them can't be measured
+pub(crate) fn total_free_space(probes: &[PathBuf]) -> Option<u64> {
+    probes.iter().map(|probe| free_space(probe).ok()).sum()
+}
+
+#[cfg(unix)]
+mod platform {
+    use std::ffi::CString;
+    use std::io;
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::fs::MetadataExt;
+    use std::path::Path;
+
+    /// Total and available bytes of the filesystem holding `path`
+    pub fn space(path: &Path) -> io::Result<(u64, u64)> {
+        let c_path = CString::new(path.as_os_str().as_bytes())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        // SAFETY: statvfs is plain data, for which all zeroes is valid
+        let mut stats: libc::statvfs = unsafe { std::mem::zeroed() };
+        // SAFETY: c_path is a valid NUL-terminated string and stats is valid for writes
+        if unsafe { libc::statvfs(c_path.as_ptr(), &mut stats) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // The field widths differ between platforms
+        #[allow(clippy::unnecessary_cast)]
+        let block = stats.f_frsize as u64;
+        #[allow(clippy::unnecessary_cast)]
+        Ok((stats.f_blocks as u64 * block, stats.f_bavail as u64 * block))
+    }
+
+    /// The device the filesystem holding `path` is on
+    pub fn volume_of(path: &Path) -> Option<String> {
+        Some(std::fs::metadata(path).ok()?.dev().to_string())
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::io;
+    use std::os::windows::ffi::OsStrExt;
+    use std::path::Path;
+    use windows_sys::Win32::Storage::FileSystem::{GetDiskFreeSpaceExW, GetVolumePathNameW};
+
+    fn wide(path: &Path) -> Vec<u16> {
+        path.as_os_str().encode_wide().chain(Some(0)).collect()
+    }
+
+    /// Total and available bytes of the volume holding `path`
+    pub fn space(path: &Path) -> io::Result<(u64, u64)> {
+        let path = wide(path);
+        let mut available = 0;
+        let mut total = 0;
+        // SAFETY: `path` is NUL-terminated and the last output may be null
+        let ok = unsafe {
+            GetDiskFreeSpaceExW(
+                path.as_ptr(),
+                &mut available,
+                &mut total,
+                std::ptr::null_mut(),
+            )
+        };
+        if ok == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok((total, available))
+    }
+
+    /// The mount point of the volume holding `path`, e.g. `C:\`
+    pub fn volume_of(path: &Path) -> Option<String> {
+        let path = wide(path);
+        let mut volume = [0u16; 261];
+        // SAFETY: `path` is NUL-terminated and `volume` has room for the given length
+        let ok =
+            unsafe { GetVolumePathNameW(path.as_ptr(), volume.as_mut_ptr(), volume.len() as u32) };
+        if ok == 0 {
+            return None;
+        }
+        let len = volume.iter().position(|&c| c == 0).unwrap_or(volume.len());
+        Some(String::from_utf16_lossy(&volume[..len]))
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+mod platform {
+    use std::io;
+    use std::path::Path;
+
+    pub fn space(_path: &Path) -> io::Result<(u64, u64)> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    pub fn volume_of(_path: &Path) -> Option<String> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_probes_each_filesystem_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = [
+            dir.path().join("a/node_modules"),
+            dir.path().join("b/node_modules"),
+        ];
+        for path in &paths {
+            std::fs::create_dir_all(path).unwrap();
+        }
+
+        let probes = volume_probes(&paths);
+        assert_eq!(probes, [dir.path().join("a")]);
+        assert!(total_free_space(&probes).is_some_and(|free| free > 0));
+        assert!(SpaceProbe::new(&paths).free_bytes().is_some());
+        assert_eq!(SpaceProbe::new(&[]).free_bytes(), None);
+    }
+
+    #[test]
+    fn test_used_fraction_of_a_volume() {
+        let volume = |total_bytes, free_bytes| Volume {
+            mount_point: PathBuf::from("/"),
+            fs_type: "ext4".to_string(),
+            kind: MountKind::Local,
+            total_bytes,
+            free_bytes,
+        };
+        assert_eq!(volume(1000, 250).used_fraction(), 0.75);
+        assert_eq!(volume(1000, 1000).used_fraction(), 0.0);
+        assert_eq!(volume(0, 0).used_fraction(), 0.0);
+    }
+
+    #[test]
+    fn test_unmeasurable_paths_have_no_total() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(total_free_space(&[dir.path().join("missing")]).is_none());
+    }
+}
@@ -1,8 +1,19 @@
 //! Domain types for devjunk-core
 
+use crate::delete::DeleteMode;
+use crate::error::DevJunkError;
+use crate::filesystem::{FileKind, FileSystem, StdFileSystem};
+use crate::git::GitStatus;
+use crate::globs::compile_globs;
+use crate::mounts::MountInfo;
+use crate::presets;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashMap};
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, SystemTime};
 
 /// Configuration for scanning directories
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -13,10 +24,28 @@ pub struct ScanConfig {
     pub include_patterns: Vec<JunkKind>,
     /// Patterns to exclude (paths matching these will be skipped)
     pub exclude_paths: Vec<PathBuf>,
+    /// Globs matched against full paths, e.g. `**/work/critical-project/**`; matching trees are skipped
+    pub exclude_globs: Vec<String>,
+    /// User-defined directory names to report as junk, besides `include_patterns`
+    pub custom_patterns: Vec<CustomPattern>,
     /// Maximum depth to scan (None = unlimited)
     pub max_depth: Option<usize>,
     /// Whether to include hidden files/directories in scan
     pub include_hidden: bool,
+    /// Minimum size for an item to be reported (None = no threshold)
+    pub min_size_bytes: Option<u64>,
+    /// Only report items whose newest file is at least this old (None = any age)
+    pub min_age: Option<Duration>,
+    /// Whether to skip network and removable mounts found inside the roots
+    pub skip_network_mounts: bool,
+    /// Keep only the N largest items (None = keep everything)
+    pub limit: Option<usize>,
+    /// Also report shared Cargo target directories (CARGO_TARGET_DIR etc.)
+    pub include_shared_target_dirs: bool,
+    /// Items modified more recently than this are flagged as active (None = never)
+    pub active_window: Option<Duration>,
+    /// Look up the git status of each item's enclosing repository
+    pub check_git_status: bool,
 }
 
 impl Default for ScanConfig {
@@ -25,8 +54,17 @@ impl Default for ScanConfig {
             roots: Vec::new(),
             include_patterns: JunkKind::all(),
             exclude_paths: Vec::new(),
+            exclude_globs: Vec::new(),
+            custom_patterns: Vec::new(),
             max_depth: None,
             include_hidden: false,
+            min_size_bytes: None,
+            min_age: None,
+            skip_network_mounts: true,
+            limit: None,
+            include_shared_target_dirs: false,
+            active_window: Some(Duration::from_secs(24 * 60 * 60)),
+            check_git_status: false,
         }
     }
 }
@@ -40,23 +78,227 @@ impl ScanConfig {
         }
     }
 
+    /// Preset for scanning the current user's whole home directory
+    ///
+    /// Returns None if the home directory can't be determined.
+    pub fn home() -> Option<Self> {
+        dirs::home_dir().map(Self::for_home)
+    }
+
+    /// Preset for scanning a whole home directory
+    ///
+    /// Skips photo libraries, mail stores, browser profiles, the trash and
+    /// similar non-development trees, and limits the search depth.
+    pub fn for_home(home: PathBuf) -> Self {
+        Self {
+            exclude_paths: presets::home_excludes(&home),
+            max_depth: Some(presets::HOME_MAX_DEPTH),
+            ..Self::new(vec![home])
+        }
+    }
+
+    /// Start a builder from the given root paths
+    pub fn builder(roots: Vec<PathBuf>) -> ScanConfigBuilder {
+        Self::new(roots).into()
+    }
+}
+
+/// Builder for a [`ScanConfig`] that checks it before a scan runs
+///
+/// A config with no roots, a root that doesn't exist, or options that
+/// leave nothing to find would otherwise scan without finding anything.
+/// [`build`](Self::build) turns those into errors instead.
+///
+/// # Example
+/// ```no_run
+/// use devjunk_core::ScanConfig;
+/// use std::path::PathBuf;
+///
+/// let config = ScanConfig::builder(vec![PathBuf::from(".")])
+///     .with_max_depth(5)
+///     .with_hidden(true)
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct ScanConfigBuilder {
+    config: ScanConfig,
+}
+
+impl From<ScanConfig> for ScanConfigBuilder {
+    fn from(config: ScanConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl ScanConfigBuilder {
     /// Builder method to set max depth
     pub fn with_max_depth(mut self, depth: usize) -> Self {
-        self.max_depth = Some(depth);
+        self.config.max_depth = Some(depth);
         self
     }
 
     /// Builder method to include hidden files
     pub fn with_hidden(mut self, include: bool) -> Self {
-        self.include_hidden = include;
+        self.config.include_hidden = include;
+        self
+    }
+
+    /// Builder method to set the minimum reported item size
+    pub fn with_min_size(mut self, bytes: u64) -> Self {
+        self.config.min_size_bytes = Some(bytes);
+        self
+    }
+
+    /// Builder method to only report items untouched for at least `age`
+    ///
+    /// Items whose modification time can't be read are never reported.
+    pub fn with_min_age(mut self, age: Duration) -> Self {
+        self.config.min_age = Some(age);
+        self
+    }
+
+    /// Builder method to control skipping of network/removable mounts
+    pub fn with_skip_network_mounts(mut self, skip: bool) -> Self {
+        self.config.skip_network_mounts = skip;
+        self
+    }
+
+    /// Builder method to keep only the N largest items
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.config.limit = Some(limit);
+        self
+    }
+
+    /// Builder method to include shared Cargo target directories
+    pub fn with_shared_target_dirs(mut self, include: bool) -> Self {
+        self.config.include_shared_target_dirs = include;
+        self
+    }
+
+    /// Builder method to set the window within which items count as active
+    pub fn with_active_window(mut self, window: Option<Duration>) -> Self {
+        self.config.active_window = window;
+        self
+    }
+
+    /// Builder method to enable git status lookups for each item
+    pub fn with_git_status(mut self, check: bool) -> Self {
+        self.config.check_git_status = check;
         self
     }
 
     /// Builder method to set include patterns
     pub fn with_patterns(mut self, patterns: Vec<JunkKind>) -> Self {
-        self.include_patterns = patterns;
+        self.config.include_patterns = patterns;
+        self
+    }
+
+    /// Builder method to stop looking for the given kinds
+    pub fn without_patterns(mut self, patterns: &[JunkKind]) -> Self {
+        self.config
+            .include_patterns
+            .retain(|kind| !patterns.contains(kind));
         self
     }
+
+    /// Builder method to also skip every path matching one of `globs`
+    pub fn with_exclude_globs(mut self, globs: Vec<String>) -> Self {
+        self.config.exclude_globs.extend(globs);
+        self
+    }
+
+    /// Builder method to also report directories matching user-defined patterns
+    pub fn with_custom_patterns(mut self, patterns: Vec<CustomPattern>) -> Self {
+        self.config.custom_patterns.extend(patterns);
+        self
+    }
+
+    /// Check the config and return it
+    ///
+    /// # Errors
+    /// * [`DevJunkError::NoRoots`] if there is nothing to scan
+    /// * [`DevJunkError::PathNotFound`] or [`DevJunkError::NotADirectory`]
+    ///   for a root that can't be scanned
+    /// * [`DevJunkError::InvalidGlob`] for an exclude or custom pattern glob
+    ///   that can't be parsed
+    /// * [`DevJunkError::InvalidScanConfig`] for options under which the
+    ///   scan can't find anything
+    pub fn build(self) -> Result<ScanConfig, DevJunkError> {
+        self.build_with_fs(&StdFileSystem)
+    }
+
+    /// Check the config against the given filesystem and return it, like [`build`](Self::build)
+    pub fn build_with_fs(self, fs: &dyn FileSystem) -> Result<ScanConfig, DevJunkError> {
+        let config = self.config;
+        if config.roots.is_empty() {
+            return Err(DevJunkError::NoRoots);
+        }
+        for root in &config.roots {
+            match fs.metadata(root) {
+                Err(_) => return Err(DevJunkError::PathNotFound(root.clone())),
+                Ok(meta) if meta.kind == FileKind::File => {
+                    return Err(DevJunkError::NotADirectory(root.clone()));
+                }
+                Ok(_) => {}
+            }
+            if let Some(excluded) = config
+                .exclude_paths
+                .iter()
+                .find(|exc| root.starts_with(exc))
+            {
+                return Err(DevJunkError::InvalidScanConfig(format!(
+                    "{} is inside the excluded {}",
+                    root.display(),
+                    excluded.display()
+                )));
+            }
+        }
+
+        if config.max_depth == Some(0) {
+            return Err(DevJunkError::InvalidScanConfig(
+                "a max depth of 0 doesn't look below the roots".into(),
+            ));
+        }
+        if config.limit == Some(0) {
+            return Err(DevJunkError::InvalidScanConfig(
+                "a limit of 0 keeps none of the items".into(),
+            ));
+        }
+        if config.include_patterns.is_empty() && config.custom_patterns.is_empty() {
+            return Err(DevJunkError::InvalidScanConfig(
+                "no junk kinds to look for".into(),
+            ));
+        }
+
+        compile_globs(&config.exclude_globs)?;
+        let custom: Vec<String> = config
+            .custom_patterns
+            .iter()
+            .map(|pattern| pattern.glob.clone())
+            .collect();
+        compile_globs(&custom)?;
+        Ok(config)
+    }
+}
+
+/// A user-defined kind of junk directory, reported as [`JunkKind::Custom`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CustomPattern {
+    /// Name shown for matching directories, e.g. "Bazel cache"
+    pub name: String,
+    /// Glob matched against directory names, e.g. `.bazel-cache-*`
+    pub glob: String,
+}
+
+impl CustomPattern {
+    /// Create a new CustomPattern
+    pub fn new(name: impl Into<String>, glob: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            glob: glob.into(),
+        }
+    }
 }
 
 /// Types of development junk directories
@@ -89,10 +331,12 @@ pub enum JunkKind {
     NextDir,
     /// .nuxt directory (Nuxt.js)
     NuxtDir,
+    /// Directory matched by a user-defined CustomPattern
+    Custom,
 }
 
 impl JunkKind {
-    /// Returns all known junk kinds
+    /// Returns all built-in junk kinds
     pub fn all() -> Vec<Self> {
         vec![
             Self::PythonVenv,
@@ -112,7 +356,7 @@ impl JunkKind {
     }
 
     /// Returns the directory name patterns for this junk kind as anchored regex strings
-    pub fn patterns(&self) -> &[&str] {
+    pub fn patterns(&self) -> &'static [&'static str] {
         match self {
             Self::PythonVenv => &[r"^\.venv$", r"^venv$"],
             Self::PythonTox => &[r"^\.tox$"],
@@ -127,6 +371,8 @@ impl JunkKind {
             Self::GoVendor => &[r"^vendor$"],
             Self::NextDir => &[r"^\.next$"],
             Self::NuxtDir => &[r"^\.nuxt$"],
+            // Matched through the scan's CustomPatterns instead
+            Self::Custom => &[],
         }
     }
 
@@ -139,11 +385,136 @@ impl JunkKind {
         })
     }
 
+    /// Check if a directory name, as read from the filesystem, matches this junk kind
+    ///
+    /// Names that aren't valid Unicode never match; converting them lossily
+    /// could make e.g. `build-\xff` look like a build directory.
+    pub fn matches_os_name(&self, name: &OsStr) -> bool {
+        name.to_str().is_some_and(|name| self.matches_name(name))
+    }
+
     /// Try to identify the junk kind from a directory name
     pub fn from_name(name: &str) -> Option<Self> {
         Self::all().into_iter().find(|kind| kind.matches_name(name))
     }
 
+    /// Try to identify the junk kind from a directory name read from the filesystem
+    pub fn from_os_name(name: &OsStr) -> Option<Self> {
+        Self::all()
+            .into_iter()
+            .find(|kind| kind.matches_os_name(name))
+    }
+
+    /// Files the tool that creates this kind of directory always leaves inside it
+    ///
+    /// At least one of them must be present for a directory to be confirmed
+    /// as this kind; empty for kinds that have no such file.
+    pub fn markers(&self) -> &'static [&'static str] {
+        match self {
+            Self::PythonVenv => &["pyvenv.cfg"],
+            Self::PytestCache => &["CACHEDIR.TAG", "README.md"],
+            Self::RustTarget => &["CACHEDIR.TAG", ".rustc_info.json"],
+            _ => &[],
+        }
+    }
+
+    /// Language or ecosystem whose tools create this kind of directory
+    ///
+    /// None for the generic names, which projects in any language use.
+    pub fn language(&self) -> Option<&'static str> {
+        match self {
+            Self::PythonVenv
+            | Self::PythonTox
+            | Self::PythonCache
+            | Self::MypyCache
+            | Self::PytestCache => Some("Python"),
+            Self::NodeModules | Self::NextDir | Self::NuxtDir => Some("JavaScript"),
+            Self::RustTarget => Some("Rust"),
+            Self::GoVendor => Some("Go"),
+            Self::BuildDir | Self::DistDir | Self::OutDir | Self::Custom => None,
+        }
+    }
+
+    /// How sure it is that deleting a directory of this kind loses nothing
+    pub fn safety(&self) -> KindSafety {
+        match self {
+            Self::PythonTox
+            | Self::PythonCache
+            | Self::MypyCache
+            | Self::PytestCache
+            | Self::NodeModules
+            | Self::RustTarget
+            | Self::NextDir
+            | Self::NuxtDir => KindSafety::Safe,
+            // Venvs can hold packages installed by hand, vendor is often
+            // checked in, and the generic names are also used for real files
+            Self::PythonVenv
+            | Self::BuildDir
+            | Self::DistDir
+            | Self::OutDir
+            | Self::GoVendor
+            | Self::Custom => KindSafety::Caution,
+        }
+    }
+
+    /// What brings a deleted directory of this kind back
+    pub fn rebuild_hint(&self) -> &'static str {
+        match self {
+            Self::PythonVenv => "recreate it with `python -m venv` and reinstall the dependencies",
+            Self::PythonTox => "run tox",
+            Self::PythonCache => "recreated whenever Python runs",
+            Self::MypyCache => "recreated on the next mypy run",
+            Self::PytestCache => "recreated on the next pytest run",
+            Self::NodeModules => "run npm install, or your package manager's install",
+            Self::RustTarget => "run cargo build",
+            Self::BuildDir | Self::DistDir | Self::OutDir => "run the project's build",
+            Self::GoVendor => "run go mod vendor",
+            Self::NextDir => "run next build or next dev",
+            Self::NuxtDir => "run nuxt build or nuxt dev",
+            Self::Custom => "run whatever created it",
+        }
+    }
+
+    /// Stable identifier, as used in JSON output and the config file
+    pub fn id(&self) -> &'static str {
+        match self {
+            Self::PythonVenv => "python_venv",
+            Self::PythonTox => "python_tox",
+            Self::PythonCache => "python_cache",
+            Self::MypyCache => "mypy_cache",
+            Self::PytestCache => "pytest_cache",
+            Self::NodeModules => "node_modules",
+            Self::RustTarget => "rust_target",
+            Self::BuildDir => "build_dir",
+            Self::DistDir => "dist_dir",
+            Self::OutDir => "out_dir",
+            Self::GoVendor => "go_vendor",
+            Self::NextDir => "next_dir",
+            Self::NuxtDir => "nuxt_dir",
+            Self::Custom => "custom",
+        }
+    }
+
+    /// Other names the kind goes by on the command line, e.g. `nm` for node_modules
+    pub fn aliases(&self) -> &'static [&'static str] {
+        match self {
+            Self::PythonVenv => &["venv", ".venv"],
+            Self::PythonTox => &["tox", ".tox"],
+            Self::PythonCache => &["pycache", "__pycache__"],
+            Self::MypyCache => &["mypy", ".mypy_cache"],
+            Self::PytestCache => &["pytest", ".pytest_cache"],
+            Self::NodeModules => &["nm", "node", "npm"],
+            Self::RustTarget => &["rust", "cargo", "target"],
+            Self::BuildDir => &["build"],
+            Self::DistDir => &["dist"],
+            Self::OutDir => &["out"],
+            Self::GoVendor => &["go", "vendor"],
+            Self::NextDir => &["next", "nextjs", ".next"],
+            Self::NuxtDir => &["nuxt", "nuxtjs", ".nuxt"],
+            Self::Custom => &[],
+        }
+    }
+
     /// Human-readable display name
     pub fn display_name(&self) -> &'static str {
         match self {
@@ -160,10 +531,37 @@ impl JunkKind {
             Self::GoVendor => "Go Vendor",
             Self::NextDir => "Next.js",
             Self::NuxtDir => "Nuxt.js",
+            Self::Custom => "Custom",
         }
     }
 }
 
+/// How safe a junk kind is to delete
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KindSafety {
+    /// Only ever holds what its tool recreates from the project
+    Safe,
+    /// Usually junk, but may hold files that can't be recreated
+    Caution,
+}
+
+/// Parses a built-in kind from its id or one of its aliases
+///
+/// Case is ignored and dashes count as underscores, so `Rust-Target` works
+/// too. Custom patterns have no kind name of their own.
+impl FromStr for JunkKind {
+    type Err = DevJunkError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        let normalized = name.trim().to_ascii_lowercase().replace('-', "_");
+        Self::all()
+            .into_iter()
+            .find(|kind| kind.id() == normalized || kind.aliases().contains(&normalized.as_str()))
+            .ok_or_else(|| DevJunkError::UnknownKind(name.to_string()))
+    }
+}
+
 impl std::fmt::Display for JunkKind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.display_name())
@@ -181,6 +579,35 @@ pub struct ScanItem {
     pub size_bytes: u64,
     /// Total number of files
     pub file_count: u64,
+    /// Scan root this item was found under (empty if unknown)
+    #[serde(default)]
+    pub root: PathBuf,
+    /// Modification time of the newest file inside
+    #[serde(default)]
+    pub last_modified: Option<SystemTime>,
+    /// Whether the newest file is within the configured active window,
+    /// i.e. the directory was probably built recently and is still in use
+    #[serde(default)]
+    pub is_active: bool,
+    /// Status of the enclosing git repository (None if not checked or not in a repo)
+    #[serde(default)]
+    pub git: Option<GitStatus>,
+    /// Whether the current user looks able to delete this directory
+    #[serde(default = "default_true")]
+    pub deletable: bool,
+    /// Why the directory can't be deleted, if it can't
+    #[serde(default)]
+    pub undeletable_reason: Option<String>,
+    /// Name of the CustomPattern that matched, for `Custom` items
+    #[serde(default)]
+    pub custom_name: Option<String>,
+    /// Glob of the CustomPattern that matched, for `Custom` items
+    #[serde(default)]
+    pub custom_glob: Option<String>,
+}
+
+fn default_true() -> bool {
+    true
 }
 
 impl ScanItem {
@@ -191,15 +618,105 @@ impl ScanItem {
             kind,
             size_bytes,
             file_count,
+            root: PathBuf::new(),
+            last_modified: None,
+            is_active: false,
+            git: None,
+            deletable: true,
+            undeletable_reason: None,
+            custom_name: None,
+            custom_glob: None,
+        }
+    }
+
+    /// Builder method to record the scan root this item was found under
+    pub fn with_root(mut self, root: PathBuf) -> Self {
+        self.root = root;
+        self
+    }
+
+    /// Display name of the item's kind, or of the custom pattern it matched
+    pub fn kind_name(&self) -> &str {
+        self.custom_name
+            .as_deref()
+            .unwrap_or_else(|| self.kind.display_name())
+    }
+
+    /// Whether the newest file inside was modified at least `age` ago
+    ///
+    /// Items with an unknown modification time are never considered old.
+    pub fn is_older_than(&self, age: Duration) -> bool {
+        self.last_modified
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+            .is_some_and(|elapsed| elapsed >= age)
+    }
+}
+
+/// Category of a non-fatal scan error
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScanErrorKind {
+    /// The current user is not allowed to read the path
+    PermissionDenied,
+    /// The path disappeared while the scan was running
+    NotFound,
+    /// A symlink loop was detected
+    Loop,
+    /// Any other IO failure
+    Other,
+}
+
+/// A path that could not be read during a scan
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScanError {
+    /// Path that could not be read
+    pub path: PathBuf,
+    /// Category of the failure
+    pub kind: ScanErrorKind,
+    /// Human-readable cause
+    pub cause: String,
+}
+
+impl ScanError {
+    /// Create a new ScanError
+    pub fn new(path: PathBuf, kind: ScanErrorKind, cause: impl Into<String>) -> Self {
+        Self {
+            path,
+            kind,
+            cause: cause.into(),
         }
     }
 }
 
+/// Size of any directory or file, whether it is junk or not
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DirSize {
+    /// The directory that was measured
+    pub path: PathBuf,
+    /// Total size in bytes
+    pub size_bytes: u64,
+    /// Total number of files
+    pub file_count: u64,
+    /// Modification time of the newest file inside
+    pub last_modified: Option<SystemTime>,
+    /// Entries that could not be read and are left out of the totals
+    pub errors: Vec<ScanError>,
+}
+
 /// Result of a scan operation
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ScanResult {
     /// All discovered junk items
     pub items: Vec<ScanItem>,
+    /// Network/removable mounts that were not descended into
+    #[serde(default)]
+    pub skipped_mounts: Vec<MountInfo>,
+    /// Paths that could not be read; the scan continued without them
+    #[serde(default)]
+    pub errors: Vec<ScanError>,
+    /// Whether the scan was cancelled; the items are those found until then
+    #[serde(default)]
+    pub cancelled: bool,
 }
 
 impl ScanResult {
@@ -223,15 +740,123 @@ impl ScanResult {
         self.items.len()
     }
 
+    /// Whether any paths could not be read
+    pub fn has_errors(&self) -> bool {
+        !self.errors.is_empty()
+    }
+
+    /// Group items by the scan root they were found under, ordered by root
+    pub fn by_root(&self) -> BTreeMap<&Path, Vec<&ScanItem>> {
+        let mut groups: BTreeMap<&Path, Vec<&ScanItem>> = BTreeMap::new();
+        for item in &self.items {
+            groups.entry(item.root.as_path()).or_default().push(item);
+        }
+        groups
+    }
+
+    /// Group items by project, the directory holding each one, ordered by project
+    pub fn by_project(&self) -> BTreeMap<&Path, Vec<&ScanItem>> {
+        let mut groups: BTreeMap<&Path, Vec<&ScanItem>> = BTreeMap::new();
+        for item in &self.items {
+            let project = item.path.parent().unwrap_or(&item.path);
+            groups.entry(project).or_default().push(item);
+        }
+        groups
+    }
+
+    /// Group items by kind name, ordered by name
+    ///
+    /// Each custom pattern forms a group of its own.
+    pub fn by_kind(&self) -> BTreeMap<&str, Vec<&ScanItem>> {
+        let mut groups: BTreeMap<&str, Vec<&ScanItem>> = BTreeMap::new();
+        for item in &self.items {
+            groups.entry(item.kind_name()).or_default().push(item);
+        }
+        groups
+    }
+
     /// Sort items by size (largest first)
     pub fn sort_by_size(&mut self) {
-        self.items.sort_by(|a, b| b.size_bytes.cmp(&a.size_bytes));
+        self.items
+            .sort_by_key(|item| std::cmp::Reverse(item.size_bytes));
     }
 
     /// Sort items by path
     pub fn sort_by_path(&mut self) {
         self.items.sort_by(|a, b| a.path.cmp(&b.path));
     }
+
+    /// Sort items by kind name, keeping the current order within a kind
+    pub fn sort_by_kind(&mut self) {
+        self.items.sort_by(|a, b| a.kind_name().cmp(b.kind_name()));
+    }
+
+    /// Sort items by last modification (least recently modified first)
+    ///
+    /// Items whose modification time is unknown go last.
+    pub fn sort_by_age(&mut self) {
+        self.items
+            .sort_by_key(|item| (item.last_modified.is_none(), item.last_modified));
+    }
+
+    /// Sort items by file count (most files first)
+    pub fn sort_by_file_count(&mut self) {
+        self.items
+            .sort_by_key(|item| std::cmp::Reverse(item.file_count));
+    }
+}
+
+/// How a junk directory is cleaned
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CleanStrategy {
+    /// Delete the whole directory
+    #[default]
+    Remove,
+    /// Delete only the intermediate outputs of a Rust target directory
+    /// (`incremental`, `deps`, `build` and `.fingerprint` in each profile),
+    /// keeping the final binaries and libraries
+    SweepRustTarget,
+}
+
+/// Which directories a retention policy ranks against each other
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RetentionScope {
+    /// All directories of the kind found under the same scan root
+    #[default]
+    Root,
+    /// Directories of the kind that sit side by side in the same project
+    Project,
+}
+
+/// Keep the most recently modified directories of a kind and clean the rest
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetentionPolicy {
+    /// Kind of junk the policy applies to
+    pub kind: JunkKind,
+    /// How many of the newest directories to keep in each group
+    pub keep: usize,
+    /// How directories are grouped
+    #[serde(default)]
+    pub scope: RetentionScope,
+}
+
+impl RetentionPolicy {
+    /// Keep the `keep` newest directories of `kind` under each scan root
+    pub fn new(kind: JunkKind, keep: usize) -> Self {
+        Self {
+            kind,
+            keep,
+            scope: RetentionScope::default(),
+        }
+    }
+
+    /// Group the directories with the given scope
+    pub fn with_scope(mut self, scope: RetentionScope) -> Self {
+        self.scope = scope;
+        self
+    }
 }
 
 /// Plan for cleaning (deleting) junk directories
@@ -241,31 +866,412 @@ pub struct CleanPlan {
     pub paths: Vec<PathBuf>,
     /// Whether this is a dry run (no actual deletion)
     pub dry_run: bool,
+    /// How each directory is removed
+    #[serde(default)]
+    pub delete_mode: DeleteMode,
+    /// Scan roots the paths were found under; when set, nothing outside them is deleted
+    #[serde(default)]
+    pub roots: Vec<PathBuf>,
+    /// Kind of each path, where known from the scan
+    #[serde(default)]
+    pub kinds: BTreeMap<PathBuf, JunkKind>,
+    /// Glob each `Custom` path was matched by, where known from the scan
+    #[serde(default)]
+    pub custom_globs: BTreeMap<PathBuf, String>,
+    /// Size in bytes of each path, where known from the scan
+    #[serde(default)]
+    pub sizes: BTreeMap<PathBuf, u64>,
+    /// Number of files in each path, where known from the scan
+    #[serde(default)]
+    pub file_counts: BTreeMap<PathBuf, u64>,
+    /// Strategy for each kind of junk (kinds not listed are removed outright)
+    #[serde(default)]
+    pub strategies: HashMap<JunkKind, CleanStrategy>,
+    /// Directory to keep a journal of the clean in, so it can be resumed if interrupted
+    #[serde(default)]
+    pub journal_dir: Option<PathBuf>,
+    /// Leave directories alone while a running process has files open in them
+    #[serde(default)]
+    pub skip_in_use: bool,
+    /// Globs, relative to each path, of contents to keep when the path is cleaned
+    #[serde(default)]
+    pub keep_globs: BTreeMap<PathBuf, Vec<String>>,
+    /// Maximum number of directories removed at once (None for one per CPU)
+    #[serde(default)]
+    pub concurrency: Option<usize>,
+    /// Check that each path with a recorded kind still is that kind of junk before deleting it
+    #[serde(default = "default_verify_before_delete")]
+    pub verify_before_delete: bool,
+}
+
+fn default_verify_before_delete() -> bool {
+    true
 }
 
 impl CleanPlan {
     /// Create a new CleanPlan
     pub fn new(paths: Vec<PathBuf>, dry_run: bool) -> Self {
-        Self { paths, dry_run }
+        Self {
+            paths,
+            dry_run,
+            delete_mode: DeleteMode::default(),
+            roots: Vec::new(),
+            kinds: BTreeMap::new(),
+            custom_globs: BTreeMap::new(),
+            sizes: BTreeMap::new(),
+            file_counts: BTreeMap::new(),
+            strategies: HashMap::new(),
+            journal_dir: None,
+            skip_in_use: false,
+            keep_globs: BTreeMap::new(),
+            concurrency: None,
+            verify_before_delete: true,
+        }
+    }
+
+    /// Remove directories with the given delete mode
+    pub fn with_delete_mode(mut self, mode: DeleteMode) -> Self {
+        self.delete_mode = mode;
+        self
+    }
+
+    /// Journal the clean in `dir` (see `interrupted_cleans`)
+    pub fn with_journal_dir(mut self, dir: PathBuf) -> Self {
+        self.journal_dir = Some(dir);
+        self
+    }
+
+    /// Skip directories that running processes have files open in (see `processes_using`)
+    pub fn with_skip_in_use(mut self, skip: bool) -> Self {
+        self.skip_in_use = skip;
+        self
+    }
+
+    /// Keep whatever matches `globs` inside `path`, deleting only the rest of it
+    ///
+    /// Globs are matched against paths relative to `path`, and `*` doesn't
+    /// cross directory separators: `release/myapp` keeps one binary of a Rust
+    /// target directory, `**/*.exe` keeps every executable and `.bin` keeps a
+    /// whole directory.
+    pub fn with_keep_globs(mut self, path: PathBuf, globs: Vec<String>) -> Self {
+        self.keep_globs.insert(path, globs);
+        self
+    }
+
+    /// Record the size and file count of `path`, so the clean needn't walk it to measure them
+    pub fn with_known_size(mut self, path: PathBuf, bytes: u64, files: u64) -> Self {
+        self.sizes.insert(path.clone(), bytes);
+        self.file_counts.insert(path, files);
+        self
+    }
+
+    /// Only allow deleting paths under these scan roots
+    pub fn with_roots(mut self, roots: Vec<PathBuf>) -> Self {
+        self.roots = roots;
+        self
+    }
+
+    /// Clean every path of the given kind with `strategy`
+    pub fn with_strategy(mut self, kind: JunkKind, strategy: CleanStrategy) -> Self {
+        self.strategies.insert(kind, strategy);
+        self
+    }
+
+    /// The strategy used for `path`
+    ///
+    /// Paths without a recorded kind are identified by their directory name.
+    pub fn strategy_for(&self, path: &Path) -> CleanStrategy {
+        self.kind_of(path)
+            .and_then(|kind| self.strategies.get(&kind).copied())
+            .unwrap_or_default()
+    }
+
+    /// The junk kind of `path`, as scanned or else as identified by its directory name
+    pub fn kind_of(&self, path: &Path) -> Option<JunkKind> {
+        self.kinds
+            .get(path)
+            .copied()
+            .or_else(|| path.file_name().and_then(JunkKind::from_os_name))
+    }
+
+    /// The recorded size and file count of `path`, if both are known
+    pub fn known_size(&self, path: &Path) -> Option<(u64, u64)> {
+        Some((*self.sizes.get(path)?, *self.file_counts.get(path)?))
     }
 
     /// Number of paths in the plan
     pub fn count(&self) -> usize {
         self.paths.len()
     }
+
+    /// Total size of the paths whose size was recorded
+    pub fn total_size_bytes(&self) -> u64 {
+        self.paths
+            .iter()
+            .filter_map(|path| self.sizes.get(path))
+            .sum()
+    }
+}
+
+/// Options for cleaning the directories selected from a scan
+///
+/// Marked `non_exhaustive` so options can be added without breaking callers;
+/// construct it with [`CleanOptions::new`] and the `with_*` methods.
+///
+/// # Example
+/// ```
+/// use devjunk_core::{CleanOptions, DeleteMode};
+/// use std::time::Duration;
+///
+/// let options = CleanOptions::new()
+///     .with_dry_run(true)
+///     .with_delete_mode(DeleteMode::Trash)
+///     .with_min_age(Duration::from_secs(7 * 24 * 60 * 60));
+/// assert!(options.verify_before_delete);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct CleanOptions {
+    /// Only report what would be deleted
+    pub dry_run: bool,
+    /// How each directory is removed
+    pub delete_mode: DeleteMode,
+    /// Maximum number of directories removed at once (None for one per CPU)
+    pub concurrency: Option<usize>,
+    /// Only clean directories whose newest file is at least this old
+    pub min_age: Option<Duration>,
+    /// Globs of contents to keep inside every directory (see `CleanPlan::with_keep_globs`)
+    pub keep_globs: Vec<String>,
+    /// Check that each directory still is the junk it was scanned as before deleting it
+    pub verify_before_delete: bool,
+}
+
+impl Default for CleanOptions {
+    fn default() -> Self {
+        Self {
+            dry_run: false,
+            delete_mode: DeleteMode::default(),
+            concurrency: None,
+            min_age: None,
+            keep_globs: Vec::new(),
+            verify_before_delete: true,
+        }
+    }
+}
+
+impl CleanOptions {
+    /// Options for a real, permanent clean of everything selected
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only report what would be deleted
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Remove directories with the given delete mode
+    pub fn with_delete_mode(mut self, mode: DeleteMode) -> Self {
+        self.delete_mode = mode;
+        self
+    }
+
+    /// Remove at most `concurrency` directories at once
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = Some(concurrency);
+        self
+    }
+
+    /// Leave out directories with files newer than `min_age`
+    pub fn with_min_age(mut self, min_age: Duration) -> Self {
+        self.min_age = Some(min_age);
+        self
+    }
+
+    /// Keep contents matching `globs` inside every directory
+    pub fn with_keep_globs(mut self, globs: Vec<String>) -> Self {
+        self.keep_globs = globs;
+        self
+    }
+
+    /// Whether to check each directory's identity again right before deleting it
+    pub fn with_verify_before_delete(mut self, verify: bool) -> Self {
+        self.verify_before_delete = verify;
+        self
+    }
+}
+
+/// How a path in a saved plan no longer matches what was planned
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "drift")]
+pub enum PlanDrift {
+    /// The path no longer exists
+    Missing,
+    /// The path no longer looks like the junk it was planned as
+    ChangedIdentity { reason: String },
+    /// The path holds a different number of bytes than when it was planned
+    SizeChanged { planned: u64, actual: u64 },
+}
+
+impl std::fmt::Display for PlanDrift {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Missing => f.write_str("no longer exists"),
+            Self::ChangedIdentity { reason } => f.write_str(reason),
+            Self::SizeChanged { planned, actual } => {
+                write!(f, "size changed from {} to {} bytes", planned, actual)
+            }
+        }
+    }
+}
+
+/// What happened to a path during a clean
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CleanOutcome {
+    /// The path was deleted (or would be, in a dry run)
+    Deleted,
+    /// Deleting the path failed (or was predicted to, in a dry run)
+    Failed,
+    /// The path was left alone
+    Skipped,
+}
+
+/// Why a path failed to delete or was skipped
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CleanErrorKind {
+    /// The current user is not allowed to delete something inside the path
+    PermissionDenied,
+    /// A file inside the path is held open by another process
+    InUse,
+    /// Part of the path disappeared while it was being deleted
+    NotFound,
+    /// The path could not be archived, so it was not deleted
+    ArchiveFailed,
+    /// The path no longer looked like the junk it was scanned as
+    ChangedIdentity,
+    /// The clean was cancelled before it reached the path
+    Cancelled,
+    /// Any other failure
+    Other,
+}
+
+impl std::fmt::Display for CleanErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let text = match self {
+            Self::PermissionDenied => "permission denied",
+            Self::InUse => "in use",
+            Self::NotFound => "not found",
+            Self::ArchiveFailed => "archive failed",
+            Self::ChangedIdentity => "no longer junk",
+            Self::Cancelled => "cancelled",
+            Self::Other => "other error",
+        };
+        f.write_str(text)
+    }
+}
+
+/// The record of one plan path in a CleanResult
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CleanItem {
+    /// Path from the plan
+    pub path: PathBuf,
+    /// Junk kind of the path, if known
+    pub kind: Option<JunkKind>,
+    /// What happened to the path
+    pub outcome: CleanOutcome,
+    /// Bytes freed (or that would be, in a dry run)
+    pub bytes_freed: u64,
+    /// Files removed (or that would be, in a dry run)
+    pub files_removed: u64,
+    /// Time spent on the path, in milliseconds
+    pub elapsed_ms: u64,
+    /// Category of the failure or reason for skipping
+    pub error_kind: Option<CleanErrorKind>,
+    /// Human-readable failure or reason for skipping
+    pub error: Option<String>,
+}
+
+impl CleanItem {
+    /// A path that was deleted
+    pub fn deleted(
+        path: PathBuf,
+        kind: Option<JunkKind>,
+        bytes_freed: u64,
+        files_removed: u64,
+    ) -> Self {
+        Self {
+            path,
+            kind,
+            outcome: CleanOutcome::Deleted,
+            bytes_freed,
+            files_removed,
+            elapsed_ms: 0,
+            error_kind: None,
+            error: None,
+        }
+    }
+
+    /// A path that failed to delete or was skipped
+    pub fn unclean(
+        path: PathBuf,
+        kind: Option<JunkKind>,
+        outcome: CleanOutcome,
+        error_kind: CleanErrorKind,
+        error: impl Into<String>,
+    ) -> Self {
+        Self {
+            path,
+            kind,
+            outcome,
+            bytes_freed: 0,
+            files_removed: 0,
+            elapsed_ms: 0,
+            error_kind: Some(error_kind),
+            error: Some(error.into()),
+        }
+    }
+
+    /// Record how long the path took
+    pub fn with_elapsed(mut self, elapsed: Duration) -> Self {
+        self.elapsed_ms = elapsed.as_millis() as u64;
+        self
+    }
 }
 
 /// Result of a clean operation
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct CleanResult {
-    /// Successfully deleted paths
-    pub deleted: Vec<PathBuf>,
-    /// Paths that failed to delete, with error messages
-    pub failed: Vec<(PathBuf, String)>,
+    /// What happened to each path the clean handled, in the order they completed
+    pub items: Vec<CleanItem>,
     /// Total bytes freed
     pub bytes_freed: u64,
     /// Whether this was a dry run
     pub was_dry_run: bool,
+    /// Whether the clean was cancelled; paths it never reached are skipped
+    #[serde(default)]
+    pub cancelled: bool,
+    /// Free space measured on the cleaned filesystems, when it could be
+    #[serde(default)]
+    pub free_space: Option<FreeSpace>,
+}
+
+/// Free space on the cleaned filesystems, measured before and after a clean
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FreeSpace {
+    /// Free bytes before anything was deleted
+    pub before: u64,
+    /// Free bytes once the clean finished
+    pub after: u64,
+}
+
+impl FreeSpace {
+    /// How much free space grew; negative if other programs wrote more than was freed
+    pub fn reclaimed(&self) -> i64 {
+        self.after as i64 - self.before as i64
+    }
 }
 
 impl CleanResult {
@@ -277,18 +1283,88 @@ impl CleanResult {
         }
     }
 
+    /// Add the record of a path, counting the bytes it freed
+    pub fn push(&mut self, item: CleanItem) {
+        self.bytes_freed += item.bytes_freed;
+        self.items.push(item);
+    }
+
+    /// Items with the given outcome
+    pub fn with_outcome(&self, outcome: CleanOutcome) -> impl Iterator<Item = &CleanItem> {
+        self.items
+            .iter()
+            .filter(move |item| item.outcome == outcome)
+    }
+
+    /// Successfully deleted items
+    pub fn deleted(&self) -> impl Iterator<Item = &CleanItem> {
+        self.with_outcome(CleanOutcome::Deleted)
+    }
+
+    /// Items that failed to delete
+    pub fn failed(&self) -> impl Iterator<Item = &CleanItem> {
+        self.with_outcome(CleanOutcome::Failed)
+    }
+
+    /// Items that were left alone
+    pub fn skipped(&self) -> impl Iterator<Item = &CleanItem> {
+        self.with_outcome(CleanOutcome::Skipped)
+    }
+
     /// Number of successfully deleted items
     pub fn deleted_count(&self) -> usize {
-        self.deleted.len()
+        self.deleted().count()
     }
 
     /// Number of failed items
     pub fn failed_count(&self) -> usize {
-        self.failed.len()
+        self.failed().count()
+    }
+
+    /// Number of items that were left alone
+    pub fn skipped_count(&self) -> usize {
+        self.skipped().count()
+    }
+
+    /// Total files removed
+    pub fn files_removed(&self) -> u64 {
+        self.items.iter().map(|item| item.files_removed).sum()
     }
 
     /// Whether all operations succeeded
     pub fn is_success(&self) -> bool {
-        self.failed.is_empty()
+        self.failed().next().is_none()
+    }
+
+    /// Bytes freed per junk kind, largest first
+    ///
+    /// Items of unknown kind are left out.
+    pub fn bytes_freed_by_kind(&self) -> Vec<(JunkKind, u64)> {
+        let mut totals: HashMap<JunkKind, u64> = HashMap::new();
+        for item in self.deleted() {
+            if let Some(kind) = item.kind {
+                *totals.entry(kind).or_default() += item.bytes_freed;
+            }
+        }
+        let mut totals: Vec<_> = totals.into_iter().collect();
+        totals.sort_by(|a, b| {
+            b.1.cmp(&a.1)
+                .then_with(|| a.0.display_name().cmp(b.0.display_name()))
+        });
+        totals
+    }
+
+    /// Number of failed items per error category, most common first
+    pub fn failures_by_kind(&self) -> Vec<(CleanErrorKind, usize)> {
+        let mut counts: HashMap<CleanErrorKind, usize> = HashMap::new();
+        for kind in self.failed().filter_map(|item| item.error_kind) {
+            *counts.entry(kind).or_default() += 1;
+        }
+        let mut counts: Vec<_> = counts.into_iter().collect();
+        counts.sort_by(|a, b| {
+            b.1.cmp(&a.1)
+                .then_with(|| a.0.to_string().cmp(&b.0.to_string()))
+        });
+        counts
     }
 }
@@ -0,0 +1,58 @@
+//! Formatting byte counts for people to read
+
+/// The base sizes are counted in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SizeUnits {
+    /// Powers of 1024, which most disk tools report
+    #[default]
+    Binary,
+    /// Powers of 1000 (SI), which macOS Finder and drive makers use
+    Si,
+}
+
+impl SizeUnits {
+    fn base(self) -> u64 {
+        match self {
+            Self::Binary => 1024,
+            Self::Si => 1000,
+        }
+    }
+}
+
+/// Format bytes into human-readable string, e.g. `1.50 GB`
+pub fn format_size(bytes: u64, units: SizeUnits) -> String {
+    let kb = units.base();
+    let mb = kb * kb;
+    let gb = mb * kb;
+
+    if bytes >= gb {
+        format!("{:.2} GB", bytes as f64 / gb as f64)
+    } else if bytes >= mb {
+        format!("{:.2} MB", bytes as f64 / mb as f64)
+    } else if bytes >= kb {
+        format!("{:.2} KB", bytes as f64 / kb as f64)
+    } else {
+        format!("{} B", bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(500, SizeUnits::Binary), "500 B");
+        assert_eq!(format_size(1024, SizeUnits::Binary), "1.00 KB");
+        assert_eq!(format_size(1536, SizeUnits::Binary), "1.50 KB");
+        assert_eq!(format_size(1048576, SizeUnits::Binary), "1.00 MB");
+        assert_eq!(format_size(1073741824, SizeUnits::Binary), "1.00 GB");
+    }
+
+    #[test]
+    fn test_format_size_in_si_units() {
+        assert_eq!(format_size(999, SizeUnits::Si), "999 B");
+        assert_eq!(format_size(1500, SizeUnits::Si), "1.50 KB");
+        assert_eq!(format_size(1073741824, SizeUnits::Si), "1.07 GB");
+    }
+}
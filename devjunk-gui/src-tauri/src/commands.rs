@@ -1,37 +1,185 @@
 //! Tauri commands for the DevJunk GUI
 
-use crate::dto::{CleanResultDto, JunkKindDto, ScanResultDto};
-use devjunk_core::{build_clean_plan, execute_clean, scan, scan_with_progress, JunkKind, ScanConfig, ScanProgress};
-use std::path::PathBuf;
+use crate::dto::{
+    can_undo, custom_pattern_dtos, delete_mode_dto, parse_delete_mode, path_strings,
+    workspace_dtos, CleanFailureDto, CleanHistoryDto, CleanResultDto, ConfigDto, CustomPatternDto,
+    DiskAccessDto, DiskFreeDto, ItemBreakdownDto, JunkKindDto, NewScheduleDto, PathCheckDto,
+    ResultPageDto, ScanEstimateDto, ScanResultDto, ScheduleDto, SettingsDto, SuggestedRootDto,
+    TreemapNodeDto, UndoResultDto, VolumeDto, WorkspaceDto,
+};
+use crate::export::{self, ExportFormat};
+use crate::query::{self, ResultFilter, ResultSort};
+use crate::reveal::reveal;
+use crate::tray::BackgroundScans;
+use crate::treemap::{treemap, TreemapLayout};
+use devjunk_core::{
+    build_clean_plan, default_history_path, estimate, execute_clean_with_cancel, full_disk_access,
+    install_schedule, list_restorable, list_volumes, load_history, measure_children,
+    record_history, restore, scan_with_control, uninstall_schedule, CancelToken, CleanPlan,
+    CleanProgress, CleanResult, Config, DeleteMode, HistoryRecord, JunkKind, RestoreSource,
+    ScanConfig, ScanConfigBuilder, ScanControl, ScanProgress, ScanResult, Schedule, SpaceProbe,
+    Theme,
+};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::Arc;
-use tauri::{command, AppHandle, Emitter};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{command, AppHandle, Emitter, State};
+use tauri_plugin_autostart::ManagerExt;
+use tauri_plugin_dialog::DialogExt;
+use tauri_plugin_notification::NotificationExt;
 
-/// Scan the given paths for development junk directories
-#[command]
-pub async fn scan_paths(app: AppHandle, paths: Vec<String>) -> Result<ScanResultDto, String> {
-    // Convert string paths to PathBuf
-    let roots: Vec<PathBuf> = paths.iter().map(PathBuf::from).collect();
+/// Managed state holding the control handle of the running scan, and the
+/// result of the last one for cleans to be planned from
+#[derive(Default)]
+pub struct ScanState {
+    control: Mutex<Option<ScanControl>>,
+    last: Mutex<Option<(u64, ScanResult)>>,
+    next_id: AtomicU64,
+}
+
+impl ScanState {
+    fn set(&self, control: Option<ScanControl>) {
+        *self.control.lock().unwrap_or_else(|e| e.into_inner()) = control;
+    }
+
+    /// Keep `result` as the last scan, returning the id cleans refer to it by
+    pub fn cache(&self, result: ScanResult) -> u64 {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        *self.last.lock().unwrap_or_else(|e| e.into_inner()) = Some((id, result));
+        id
+    }
 
-    // Validate paths exist
-    for root in &roots {
-        if !root.exists() {
-            return Err(format!("Path does not exist: {}", root.display()));
+    /// The result of the scan with id `scan_id`, if it is still the last one
+    fn cached(&self, scan_id: u64) -> Result<ScanResult, String> {
+        self.with_cached(scan_id, ScanResult::clone)
+    }
+
+    /// Run `f` on the result of the scan with id `scan_id`, without copying it
+    fn with_cached<T>(&self, scan_id: u64, f: impl FnOnce(&ScanResult) -> T) -> Result<T, String> {
+        let guard = self.last.lock().unwrap_or_else(|e| e.into_inner());
+        match guard.as_ref() {
+            Some((id, result)) if *id == scan_id => Ok(f(result)),
+            _ => Err("These results are out of date; scan again before cleaning".to_string()),
         }
-        if !root.is_dir() {
-            return Err(format!("Path is not a directory: {}", root.display()));
+    }
+
+    fn last(&self) -> Option<ScanResultDto> {
+        let guard = self.last.lock().unwrap_or_else(|e| e.into_inner());
+        guard
+            .as_ref()
+            .map(|(id, result)| ScanResultDto::new(result, *id))
+    }
+
+    fn with_control(&self, f: impl FnOnce(&ScanControl)) -> Result<(), String> {
+        let guard = self.control.lock().unwrap_or_else(|e| e.into_inner());
+        let control = guard.as_ref().ok_or("No scan is running")?;
+        f(control);
+        Ok(())
+    }
+}
+
+/// Managed state holding the cancel token of the running clean, and what
+/// the last clean moved to the trash or archived so it can be undone
+#[derive(Default)]
+pub struct CleanState {
+    cancel: Mutex<Option<CancelToken>>,
+    undoable: Mutex<Option<UndoableClean>>,
+}
+
+impl CleanState {
+    fn set(&self, cancel: Option<CancelToken>) {
+        *self.cancel.lock().unwrap_or_else(|e| e.into_inner()) = cancel;
+    }
+
+    fn set_undoable(&self, clean: Option<UndoableClean>) {
+        *self.undoable.lock().unwrap_or_else(|e| e.into_inner()) = clean;
+    }
+}
+
+/// The directories a clean moved to the trash or archived
+struct UndoableClean {
+    paths: Vec<PathBuf>,
+    source: RestoreSource,
+    archive_dir: Option<PathBuf>,
+    /// When the clean started, in seconds since the Unix epoch
+    started_at: u64,
+}
+
+impl UndoableClean {
+    /// What `result` deleted in `mode` that can be put back, if anything
+    fn new(result: &CleanResult, mode: &DeleteMode, started_at: u64) -> Option<Self> {
+        if result.was_dry_run || result.deleted_count() == 0 || !can_undo(mode) {
+            return None;
+        }
+        let (source, archive_dir) = match mode {
+            DeleteMode::Archive { dir } => (RestoreSource::Archive, Some(dir.clone())),
+            _ => (RestoreSource::Trash, None),
+        };
+        Some(Self {
+            paths: result.deleted().map(|item| item.path.clone()).collect(),
+            source,
+            archive_dir,
+            started_at,
+        })
+    }
+
+    /// Put each directory back from where the clean moved it
+    fn undo(&self) -> Result<UndoResultDto, String> {
+        let cleaned: HashSet<PathBuf> = self.paths.iter().cloned().collect();
+        let archive_dirs: Vec<PathBuf> = self.archive_dir.iter().cloned().collect();
+        let items = list_restorable(&archive_dirs, &cleaned)
+            .map_err(|e| format!("Restore error: {}", e))?;
+
+        let mut result = UndoResultDto::default();
+        for path in &self.paths {
+            // Newest first, so an older deletion of the same path is left alone
+            let item = items.iter().find(|item| {
+                item.source == self.source
+                    && item.deleted_at >= self.started_at
+                    && item.was_at(path)
+            });
+            let restored = match item {
+                Some(item) => restore(item).map_err(|e| e.to_string()),
+                None => Err("It is no longer in the trash or the archive".to_string()),
+            };
+            match restored {
+                Ok(restored) => result.restored.push(restored.display().to_string()),
+                Err(error) => result.failed.push(CleanFailureDto {
+                    path: path.display().to_string(),
+                    error,
+                }),
+            }
         }
+        result.restored_count = result.restored.len();
+        result.failed_count = result.failed.len();
+        Ok(result)
     }
+}
 
-    // Build config and scan
-    let config = ScanConfig::new(roots);
+/// Scan the given paths for development junk directories
+#[command]
+pub async fn scan_paths(
+    app: AppHandle,
+    state: State<'_, ScanState>,
+    paths: Vec<String>,
+) -> Result<ScanResultDto, String> {
+    let config = scan_config(&load_config()?, &paths)?;
 
     // Throttle progress events to avoid flooding (emit at most every 50ms)
     let last_emit = Arc::new(AtomicU64::new(0));
 
+    // Make the scan pausable from pause_scan/resume_scan and stoppable from cancel_scan
+    let control = ScanControl::new();
+    state.set(Some(control.clone()));
+
     // Run scan in blocking task to not block the async runtime
+    let events = app.clone();
     let result = tokio::task::spawn_blocking(move || {
-        scan_with_progress(&config, |progress: ScanProgress| {
+        scan_with_control(&config, &control, |progress: ScanProgress| {
             let now = std::time::SystemTime::now()
                 .duration_since(std::time::UNIX_EPOCH)
                 .unwrap()
@@ -44,51 +192,241 @@ pub async fn scan_paths(app: AppHandle, paths: Vec<String>) -> Result<ScanResult
             }
         })
     })
+    .await;
+    state.set(None);
+
+    let result = result
+        .map_err(|e| format!("Task join error: {}", e))?
+        .map_err(|e| format!("Scan error: {}", e))?;
+    if result.cancelled {
+        let _ = events.emit("scan-cancelled", ());
+        return Err("Scan cancelled".to_string());
+    }
+    add_to_history(HistoryRecord::from_scan(&result));
+
+    let scan_id = state.cache(result.clone());
+    Ok(ScanResultDto::new(&result, scan_id))
+}
+
+/// How long an estimate walks before settling for what it found
+const ESTIMATE_TIME_LIMIT: Duration = Duration::from_secs(2);
+
+/// Quickly find the junk under the given paths by name, for a preview while they are scanned
+///
+/// Sizes come from earlier scans in the history, for the directories they found.
+#[command]
+pub async fn estimate_paths(paths: Vec<String>) -> Result<ScanEstimateDto, String> {
+    let roots: Vec<PathBuf> = paths.iter().map(PathBuf::from).collect();
+    let config = ScanConfigBuilder::from(load_config()?.scan_config(ScanConfig::new(roots)))
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    let estimate = tokio::task::spawn_blocking(move || {
+        let records = default_history_path()
+            .and_then(|path| load_history(&path).ok())
+            .unwrap_or_default();
+        estimate(&config, ESTIMATE_TIME_LIMIT).map(|estimate| estimate.with_known_sizes(&records))
+    })
     .await
     .map_err(|e| format!("Task join error: {}", e))?
     .map_err(|e| format!("Scan error: {}", e))?;
+    Ok(ScanEstimateDto::from(&estimate))
+}
 
-    Ok(ScanResultDto::from(&result))
+/// Items listed per page when no page size is given
+const RESULTS_PAGE_SIZE: usize = 100;
+
+/// One page of the items of the scan with id `scan_id`, filtered and sorted
+///
+/// Pages count from 0. Without a filter every item is listed, largest first
+/// without a sort.
+#[command]
+pub fn query_results(
+    scans: State<'_, ScanState>,
+    scan_id: u64,
+    filter: Option<ResultFilter>,
+    sort: Option<ResultSort>,
+    page: Option<usize>,
+    page_size: Option<usize>,
+) -> Result<ResultPageDto, String> {
+    scans.with_cached(scan_id, |result| {
+        query::query(
+            result,
+            &filter.unwrap_or_default(),
+            sort.unwrap_or_default(),
+            page.unwrap_or(0),
+            page_size.unwrap_or(RESULTS_PAGE_SIZE),
+        )
+    })
 }
 
-/// Clean (delete) the specified paths
+/// The scan with id `scan_id` nested for a treemap, by project without a `layout`
 #[command]
-pub async fn clean_paths(paths: Vec<String>, dry_run: bool) -> Result<CleanResultDto, String> {
-    // Convert string paths to PathBuf
-    let path_bufs: Vec<PathBuf> = paths.iter().map(PathBuf::from).collect();
+pub async fn get_treemap(
+    scans: State<'_, ScanState>,
+    scan_id: u64,
+    layout: Option<TreemapLayout>,
+) -> Result<TreemapNodeDto, String> {
+    let result = scans.cached(scan_id)?;
+    // Finding the projects looks for checkouts on disk
+    tokio::task::spawn_blocking(move || treemap(&result, layout.unwrap_or_default()))
+        .await
+        .map_err(|e| format!("Task join error: {}", e))
+}
 
-    // We need to do a quick scan to get the ScanResult structure
-    // In a real implementation, we might cache the scan result
-    // For now, we create a minimal scan result
-    let roots: Vec<PathBuf> = path_bufs
-        .iter()
-        .filter_map(|p| p.parent().map(|pp| pp.to_path_buf()))
-        .collect::<std::collections::HashSet<_>>()
-        .into_iter()
-        .collect();
+/// Pause the running scan
+#[command]
+pub fn pause_scan(state: State<'_, ScanState>) -> Result<(), String> {
+    state.with_control(ScanControl::pause)
+}
 
-    let config = ScanConfig::new(if roots.is_empty() {
-        path_bufs.clone()
-    } else {
-        roots
-    });
+/// Resume a paused scan
+#[command]
+pub fn resume_scan(state: State<'_, ScanState>) -> Result<(), String> {
+    state.with_control(ScanControl::resume)
+}
 
-    let scan_result = tokio::task::spawn_blocking(move || scan(&config))
-        .await
+/// Stop the running scan, paused or not, dropping what it found so far
+#[command]
+pub fn cancel_scan(state: State<'_, ScanState>) -> Result<(), String> {
+    state.with_control(ScanControl::cancel)
+}
+
+/// Build the config of a scan of `paths` from the shared config file
+///
+/// Fails on paths that can't be scanned.
+fn scan_config(user_config: &Config, paths: &[String]) -> Result<ScanConfig, String> {
+    let roots: Vec<PathBuf> = paths.iter().map(PathBuf::from).collect();
+    ScanConfigBuilder::from(user_config.scan_config(ScanConfig::new(roots)))
+        .with_git_status(user_config.gui.git_status)
+        .build()
+        .map_err(|e| e.to_string())
+}
+
+/// Time between `disk-free` events while a clean runs
+const DISK_FREE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Clean (delete) the specified paths, as found by the scan with id `scan_id`
+///
+/// `delete_mode` is "permanent", "trash", "archive" or "renameDefer", or the
+/// configured mode when None. Archiving goes to the configured archive directory.
+/// While a real clean runs, `disk-free` events report the free space of the
+/// filesystems being cleaned.
+#[command]
+pub async fn clean_paths(
+    app: AppHandle,
+    scans: State<'_, ScanState>,
+    state: State<'_, CleanState>,
+    scan_id: u64,
+    paths: Vec<String>,
+    dry_run: bool,
+    delete_mode: Option<String>,
+) -> Result<CleanResultDto, String> {
+    // Plan from the items the user saw rather than scanning again
+    let scan_result = scans.cached(scan_id)?;
+    let plan = clean_plan(&load_config()?, &scan_result, &paths, dry_run, delete_mode)?;
+    let delete_mode = plan.delete_mode.clone();
+
+    // Make the clean stoppable from cancel_clean
+    let cancel = CancelToken::new();
+    state.set(Some(cancel.clone()));
+    let started_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+
+    // Measure the filesystems before anything on them is deleted
+    let probe = (!plan.dry_run).then(|| SpaceProbe::new(&plan.paths));
+    let before = probe.as_ref().and_then(SpaceProbe::free_bytes);
+
+    // Execute the clean, reporting each path as it starts and finishes, and
+    // the free space growing meanwhile
+    let clean_result = tokio::task::spawn_blocking(move || {
+        let (done, finished) = mpsc::channel::<()>();
+        thread::scope(|scope| {
+            if let (Some(probe), Some(before)) = (&probe, before) {
+                let app = &app;
+                scope.spawn(move || report_free_space(app, probe, before, &finished));
+            }
+            let result = execute_clean_with_cancel(&plan, &cancel, |progress: CleanProgress| {
+                let _ = app.emit("clean-progress", &progress);
+            });
+            drop(done);
+            result
+        })
+    })
+    .await;
+    state.set(None);
+
+    let clean_result = clean_result
         .map_err(|e| format!("Task join error: {}", e))?
-        .map_err(|e| format!("Scan error: {}", e))?;
+        .map_err(|e| format!("Clean error: {}", e))?;
+    if !clean_result.was_dry_run {
+        add_to_history(HistoryRecord::from_clean(&clean_result));
+        state.set_undoable(UndoableClean::new(&clean_result, &delete_mode, started_at));
+    }
+
+    Ok(CleanResultDto::new(&clean_result, &delete_mode))
+}
 
-    // Build clean plan with selected paths
+/// Plan the clean of the selected `paths` of `scan_result` with the configured options
+///
+/// `delete_mode` overrides the configured mode, as in `clean_paths`.
+fn clean_plan(
+    user_config: &Config,
+    scan_result: &ScanResult,
+    paths: &[String],
+    dry_run: bool,
+    delete_mode: Option<String>,
+) -> Result<CleanPlan, String> {
     let selection: Vec<PathBuf> = paths.iter().map(PathBuf::from).collect();
-    let plan = build_clean_plan(&scan_result, &selection, dry_run);
+    let mut options = user_config.clean_options().with_dry_run(dry_run);
+    if let Some(mode) = delete_mode {
+        let (_, archive_dir) = delete_mode_dto(&user_config.delete_mode);
+        let mode = parse_delete_mode(&mode, archive_dir)?;
+        options = options.with_delete_mode(mode);
+    }
+    build_clean_plan(scan_result, &selection, &options).map_err(|e| format!("Clean error: {}", e))
+}
+
+/// Emit `disk-free` every [`DISK_FREE_INTERVAL`] until the clean is `finished`, then once more
+fn report_free_space(app: &AppHandle, probe: &SpaceProbe, before: u64, finished: &Receiver<()>) {
+    loop {
+        if let Some(free) = probe.free_bytes() {
+            let _ = app.emit("disk-free", DiskFreeDto::new(before, free));
+        }
+        if finished.recv_timeout(DISK_FREE_INTERVAL) != Err(RecvTimeoutError::Timeout) {
+            break;
+        }
+    }
+    if let Some(free) = probe.free_bytes() {
+        let _ = app.emit("disk-free", DiskFreeDto::new(before, free));
+    }
+}
+
+/// Stop the running clean after the directory being deleted
+#[command]
+pub fn cancel_clean(state: State<'_, CleanState>) -> Result<(), String> {
+    let guard = state.cancel.lock().unwrap_or_else(|e| e.into_inner());
+    let cancel = guard.as_ref().ok_or("No clean is running")?;
+    cancel.cancel();
+    Ok(())
+}
 
-    // Execute the clean
-    let clean_result = tokio::task::spawn_blocking(move || execute_clean(&plan))
+/// Put back what the last clean moved to the trash or archived
+///
+/// Only the last clean of this session can be undone, and only once.
+#[command]
+pub async fn undo_last_clean(state: State<'_, CleanState>) -> Result<UndoResultDto, String> {
+    let clean = state
+        .undoable
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .take()
+        .ok_or("There is no clean to undo")?;
+    tokio::task::spawn_blocking(move || clean.undo())
         .await
         .map_err(|e| format!("Task join error: {}", e))?
-        .map_err(|e| format!("Clean error: {}", e))?;
-
-    Ok(CleanResultDto::from(&clean_result))
 }
 
 /// Get list of all supported junk kinds
@@ -97,6 +435,430 @@ pub fn get_junk_kinds() -> Vec<JunkKindDto> {
     JunkKind::all().into_iter().map(JunkKindDto::from).collect()
 }
 
+/// Read the config file shared with the CLI
+#[command]
+pub fn get_config() -> Result<ConfigDto, String> {
+    Ok(ConfigDto::from(&load_config()?))
+}
+
+/// Replace the config file shared with the CLI
+#[command]
+pub fn save_config(config: ConfigDto) -> Result<(), String> {
+    write_config(&Config::try_from(config)?)
+}
+
+/// Read the preferences the GUI keeps between launches
+#[command]
+pub fn get_settings() -> Result<SettingsDto, String> {
+    Ok(SettingsDto::from(&load_config()?))
+}
+
+/// Save the preferences the GUI keeps between launches, leaving the rest of the config file alone
+#[command]
+pub fn set_settings(app: AppHandle, settings: SettingsDto) -> Result<(), String> {
+    let mut config = load_config()?;
+    settings.apply_to(&mut config)?;
+    write_config(&config)?;
+    apply_theme(&app, config.gui.theme);
+    Ok(())
+}
+
+/// Switch the windows to `theme`, which the frontend's styles follow
+pub fn apply_theme(app: &AppHandle, theme: Theme) {
+    app.set_theme(match theme {
+        Theme::System => None,
+        Theme::Light => Some(tauri::Theme::Light),
+        Theme::Dark => Some(tauri::Theme::Dark),
+    });
+}
+
+/// The result of the last scan, from the window or in the background, if any
+#[command]
+pub fn get_last_scan(state: State<'_, ScanState>) -> Option<ScanResultDto> {
+    state.last()
+}
+
+/// Save the scan with id `scan_id` to a file the user picks, in `format`
+///
+/// Returns the path written to, or None if the user closed the dialog.
+#[command]
+pub async fn export_results(
+    app: AppHandle,
+    scans: State<'_, ScanState>,
+    format: ExportFormat,
+    scan_id: u64,
+) -> Result<Option<String>, String> {
+    let contents = export::render(&scans.cached(scan_id)?, format)?;
+
+    // The dialog waits for the user, so keep it off the async runtime
+    let picked = tokio::task::spawn_blocking(move || {
+        app.dialog()
+            .file()
+            .set_title("Export scan results")
+            .set_file_name(format!("devjunk-scan.{}", format.extension()))
+            .add_filter(format.filter_name(), &[format.extension()])
+            .blocking_save_file()
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?;
+    let Some(picked) = picked else {
+        return Ok(None);
+    };
+
+    let path = picked
+        .into_path()
+        .map_err(|e| format!("Can't save there: {}", e))?;
+    std::fs::write(&path, contents)
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(Some(path.display().to_string()))
+}
+
+/// Minutes between scans of the configured roots in the tray, or None if they are off
+#[command]
+pub fn get_background_scan() -> Result<Option<u64>, String> {
+    Ok(load_config()?.gui.background_scan_minutes)
+}
+
+/// Scan the configured roots every `minutes` while the app runs in the tray, or stop with None
+#[command]
+pub fn set_background_scan(
+    app: AppHandle,
+    scans: State<'_, BackgroundScans>,
+    minutes: Option<u64>,
+) -> Result<(), String> {
+    let mut config = load_config()?;
+    config.gui.background_scan_minutes = minutes;
+    write_config(&config)?;
+    if minutes.is_some() {
+        // Ask now rather than when the first scan finds something
+        let _ = app.notification().request_permission();
+    }
+    scans.set_interval(minutes);
+    Ok(())
+}
+
+/// Whether the app starts with the OS, in the tray
+#[command]
+pub fn get_autostart(app: AppHandle) -> Result<bool, String> {
+    app.autolaunch()
+        .is_enabled()
+        .map_err(|e| format!("Autostart error: {}", e))
+}
+
+/// Start the app at login, or no longer
+///
+/// The login item is registered with the OS (a launch agent on macOS, the Run
+/// key on Windows, an autostart entry on Linux) rather than kept in the config.
+/// Started that way, the app stays in the tray when background scans are on.
+#[command]
+pub fn set_autostart(app: AppHandle, enabled: bool) -> Result<(), String> {
+    let autolaunch = app.autolaunch();
+    let set = if enabled {
+        autolaunch.enable()
+    } else {
+        autolaunch.disable()
+    };
+    set.map_err(|e| format!("Autostart error: {}", e))
+}
+
+/// The saved workspaces, in the order they were created
+#[command]
+pub fn list_workspaces() -> Result<Vec<WorkspaceDto>, String> {
+    Ok(workspace_dtos(&load_config()?.gui.workspaces))
+}
+
+/// Save `paths` as the workspace called `name`, replacing one with that name
+///
+/// Returns the workspaces as they are now.
+#[command]
+pub fn create_workspace(name: String, paths: Vec<String>) -> Result<Vec<WorkspaceDto>, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("A workspace needs a name".to_string());
+    }
+    if paths.is_empty() {
+        return Err("A workspace needs at least one path".to_string());
+    }
+
+    let mut config = load_config()?;
+    config
+        .gui
+        .set_workspace(WorkspaceDto { name, paths }.into());
+    write_config(&config)?;
+    Ok(workspace_dtos(&config.gui.workspaces))
+}
+
+/// Delete the workspace called `name`, returning the workspaces that are left
+#[command]
+pub fn delete_workspace(name: String) -> Result<Vec<WorkspaceDto>, String> {
+    let mut config = load_config()?;
+    if !config.gui.remove_workspace(&name) {
+        return Err(format!("No workspace is called {}", name));
+    }
+    write_config(&config)?;
+    Ok(workspace_dtos(&config.gui.workspaces))
+}
+
+/// The custom patterns scans match directory names against, besides the built-in kinds
+#[command]
+pub fn get_custom_patterns() -> Result<Vec<CustomPatternDto>, String> {
+    Ok(custom_pattern_dtos(&load_config()?.custom_patterns))
+}
+
+/// Match directories named like `glob` as junk called `name`, replacing a pattern with that name
+///
+/// Returns the custom patterns as they are now.
+#[command]
+pub fn add_custom_pattern(name: String, glob: String) -> Result<Vec<CustomPatternDto>, String> {
+    let name = name.trim().to_string();
+    let glob = glob.trim().to_string();
+    if name.is_empty() {
+        return Err("A custom pattern needs a name".to_string());
+    }
+    if glob.is_empty() {
+        return Err("A custom pattern needs a glob to match directory names".to_string());
+    }
+
+    let mut config = load_config()?;
+    config
+        .set_custom_pattern(CustomPatternDto { name, glob }.into())
+        .map_err(|e| e.to_string())?;
+    write_config(&config)?;
+    Ok(custom_pattern_dtos(&config.custom_patterns))
+}
+
+/// Delete the custom pattern called `name`, returning the ones that are left
+#[command]
+pub fn remove_custom_pattern(name: String) -> Result<Vec<CustomPatternDto>, String> {
+    let mut config = load_config()?;
+    if !config.remove_custom_pattern(&name) {
+        return Err(format!("No custom pattern is called {}", name));
+    }
+    write_config(&config)?;
+    Ok(custom_pattern_dtos(&config.custom_patterns))
+}
+
+/// The directories every scan leaves out, as `devjunk ignore` adds them
+#[command]
+pub fn get_ignored_paths() -> Result<Vec<String>, String> {
+    Ok(path_strings(&load_config()?.ignore))
+}
+
+/// Leave `path` out of every scan from now on, like `devjunk ignore add`
+///
+/// Returns the ignored directories as they are now.
+#[command]
+pub fn add_ignored_path(path: String) -> Result<Vec<String>, String> {
+    let path = path.trim();
+    if path.is_empty() {
+        return Err("Choose a directory to ignore".to_string());
+    }
+    let path = std::path::absolute(path).map_err(|e| format!("Invalid path {}: {}", path, e))?;
+
+    let mut config = load_config()?;
+    if config.add_ignore(path) {
+        write_config(&config)?;
+    }
+    Ok(path_strings(&config.ignore))
+}
+
+/// Scan `path` again, returning the directories that are still ignored
+#[command]
+pub fn remove_ignored_path(path: String) -> Result<Vec<String>, String> {
+    let mut config = load_config()?;
+    if !config.remove_ignore(Path::new(&path)) {
+        return Err(format!("{} isn't ignored", path));
+    }
+    write_config(&config)?;
+    Ok(path_strings(&config.ignore))
+}
+
+/// The recurring cleans installed from the GUI or `devjunk schedule`, by name
+#[command]
+pub async fn list_schedules() -> Result<Vec<ScheduleDto>, String> {
+    // Asking the OS scheduler about each job runs a command, so keep it off the main thread
+    let schedules = tokio::task::spawn_blocking(devjunk_core::list_schedules)
+        .await
+        .map_err(|e| format!("Task join error: {}", e))?
+        .map_err(|e| format!("Schedule error: {}", e))?;
+    Ok(schedules.iter().map(ScheduleDto::from).collect())
+}
+
+/// Have the OS scheduler run a clean, replacing the schedule of the same name
+///
+/// Scheduled cleans run the devjunk CLI, so it must be installed next to the
+/// GUI or on the PATH. Returns the schedules as they are now.
+#[command]
+pub async fn create_schedule(schedule: NewScheduleDto) -> Result<Vec<ScheduleDto>, String> {
+    let args = schedule.clean_args()?;
+    let program = cli_program()?;
+    let schedule = Schedule::new(schedule.name, schedule.frequency, program, args)
+        .with_time(schedule.hour, schedule.minute);
+    tokio::task::spawn_blocking(move || install_schedule(&schedule))
+        .await
+        .map_err(|e| format!("Task join error: {}", e))?
+        .map_err(|e| format!("Schedule error: {}", e))?;
+    list_schedules().await
+}
+
+/// Remove the schedule called `name` from the OS scheduler, returning the ones left
+#[command]
+pub async fn delete_schedule(name: String) -> Result<Vec<ScheduleDto>, String> {
+    let removed = tokio::task::spawn_blocking({
+        let name = name.clone();
+        move || uninstall_schedule(&name)
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+    .map_err(|e| format!("Schedule error: {}", e))?;
+    if !removed {
+        return Err(format!("No schedule is called {}", name));
+    }
+    list_schedules().await
+}
+
+/// The devjunk CLI, next to the GUI's own binary or else on the PATH
+fn cli_program() -> Result<PathBuf, String> {
+    let name = format!("devjunk{}", std::env::consts::EXE_SUFFIX);
+    let beside_gui = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.join(&name)));
+    let on_path = std::env::var_os("PATH")
+        .into_iter()
+        .flat_map(|path| std::env::split_paths(&path).collect::<Vec<_>>())
+        .map(|dir| dir.join(&name));
+    beside_gui
+        .into_iter()
+        .chain(on_path)
+        .find(|program| program.is_file())
+        .ok_or_else(|| {
+            "Scheduled cleans need the devjunk command line tool; install it first".to_string()
+        })
+}
+
+/// Record a scan or clean for `devjunk stats`, carrying on if it can't be written
+pub fn add_to_history(record: HistoryRecord) {
+    if let Some(path) = default_history_path() {
+        let _ = record_history(&path, &record);
+    }
+}
+
+/// The cleans recorded in the history, of the last `days` or all of them
+///
+/// A `days` reaching back further than the clock goes counts as all of them.
+#[command]
+pub fn get_clean_history(days: Option<u64>) -> Result<CleanHistoryDto, String> {
+    let records = match default_history_path() {
+        Some(path) => load_history(&path).map_err(|e| format!("History error: {}", e))?,
+        None => Vec::new(),
+    };
+    let since = days
+        .and_then(|days| days.checked_mul(24 * 60 * 60))
+        .and_then(|secs| SystemTime::now().checked_sub(Duration::from_secs(secs)));
+    Ok(CleanHistoryDto::new(&records, since))
+}
+
+/// Read the shared config file, or the defaults if there is none
+pub fn load_config() -> Result<Config, String> {
+    Config::load_default().map_err(|e| format!("Config error: {}", e))
+}
+
+/// Save `config` as the shared config file
+fn write_config(config: &Config) -> Result<(), String> {
+    let path = Config::default_path().ok_or("Could not determine the config directory")?;
+    config
+        .save(&path)
+        .map_err(|e| format!("Failed to save the config file: {}", e))
+}
+
+/// Entries shown in a breakdown when the frontend doesn't ask for a number
+const BREAKDOWN_ENTRIES: usize = 10;
+
+/// Break a junk directory down into its largest files and subdirectories
+#[command]
+pub async fn get_item_breakdown(
+    path: String,
+    limit: Option<usize>,
+) -> Result<ItemBreakdownDto, String> {
+    // Measuring a large directory takes a while, so keep it off the async runtime
+    let children = tokio::task::spawn_blocking(move || measure_children(&PathBuf::from(path)))
+        .await
+        .map_err(|e| format!("Task join error: {}", e))?
+        .map_err(|e| format!("Measure error: {}", e))?;
+
+    Ok(ItemBreakdownDto::new(
+        &children,
+        limit.unwrap_or(BREAKDOWN_ENTRIES),
+    ))
+}
+
+/// System Settings pane where macOS grants Full Disk Access
+const DISK_ACCESS_SETTINGS: &str =
+    "x-apple.systempreferences:com.apple.preference.security?Privacy_AllFiles";
+
+/// Whether the scan with id `scan_id` missed paths because macOS denied access
+///
+/// Without an id, or once the scan is out of date, only the access of the app is checked.
+#[command]
+pub fn check_disk_access(scans: State<'_, ScanState>, scan_id: Option<u64>) -> DiskAccessDto {
+    let result = scan_id.and_then(|id| scans.cached(id).ok());
+    DiskAccessDto::new(full_disk_access(), result.as_ref())
+}
+
+/// Open the System Settings pane granting Full Disk Access
+#[command]
+pub fn open_disk_access_settings() -> Result<(), String> {
+    if !cfg!(target_os = "macos") {
+        return Err("Full Disk Access is only a setting on macOS".to_string());
+    }
+    std::process::Command::new("open")
+        .arg(DISK_ACCESS_SETTINGS)
+        .spawn()
+        .map_err(|e| format!("Could not open System Settings: {}", e))?;
+    Ok(())
+}
+
+/// The mounted volumes with their size and free space, for the dashboard
+#[command]
+pub async fn get_volumes() -> Result<Vec<VolumeDto>, String> {
+    // Measuring a network share can take a while
+    let volumes = tokio::task::spawn_blocking(list_volumes)
+        .await
+        .map_err(|e| format!("Task join error: {}", e))?;
+    Ok(VolumeDto::list(&volumes, dirs::home_dir().as_deref()))
+}
+
+/// Directories likely to hold the user's projects, to offer on first run, most checkouts first
+#[command]
+pub async fn suggest_roots() -> Result<Vec<SuggestedRootDto>, String> {
+    // Counting checkouts reads a couple of levels of the home directory
+    let roots = tokio::task::spawn_blocking(devjunk_core::suggest_roots)
+        .await
+        .map_err(|e| format!("Task join error: {}", e))?;
+    Ok(roots.iter().map(SuggestedRootDto::from).collect())
+}
+
+/// Show a junk directory in the file manager, so it can be checked before deleting it
+#[command]
+pub fn reveal_path(path: String) -> Result<(), String> {
+    let path_buf = PathBuf::from(&path);
+
+    if !path_buf.exists() {
+        return Err(format!("Path does not exist: {}", path));
+    }
+
+    reveal(&path_buf).map_err(|e| format!("Could not open the file manager: {}", e))
+}
+
+/// Check a batch of paths to scan at once, such as the folders of a drop
+///
+/// Besides whether each exists and is a directory, paths repeating or inside
+/// another of the batch are flagged.
+#[command]
+pub fn validate_paths(paths: Vec<String>) -> Vec<PathCheckDto> {
+    PathCheckDto::check_all(&paths)
+}
+
 /// Validate that a path exists and is a directory
 #[command]
 pub fn validate_path(path: String) -> Result<bool, String> {
@@ -112,3 +874,40 @@ pub fn validate_path(path: String) -> Result<bool, String> {
 
     Ok(true)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use devjunk_core::{execute_clean, scan};
+    use std::fs;
+    use tempfile::TempDir;
+
+    // Only Linux finds the config file through XDG_CONFIG_HOME
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_added_custom_pattern_directories_are_cleaned() {
+        let home = TempDir::new().unwrap();
+        std::env::set_var("XDG_CONFIG_HOME", home.path().join("config"));
+        let project = home.path().join("project");
+        let cache = project.join(".bazel-cache-x1");
+        fs::create_dir_all(&cache).unwrap();
+        fs::write(cache.join("out.o"), b"object").unwrap();
+        fs::write(project.join("main.c"), b"int main;").unwrap();
+
+        add_custom_pattern("Bazel cache".to_string(), ".bazel-cache-*".to_string()).unwrap();
+        let user_config = load_config().unwrap();
+        let roots = vec![project.display().to_string()];
+        let scanned = scan(&scan_config(&user_config, &roots).unwrap()).unwrap();
+        let found: Vec<_> = scanned.items.iter().map(|item| item.path.clone()).collect();
+        assert_eq!(found, vec![cache.clone()]);
+
+        let selection = path_strings(&found);
+        let permanent = Some("permanent".to_string());
+        let plan = clean_plan(&user_config, &scanned, &selection, false, permanent).unwrap();
+        let result = execute_clean(&plan).unwrap();
+
+        assert_eq!(result.deleted().count(), 1);
+        assert!(!cache.exists());
+        assert!(project.join("main.c").exists());
+    }
+}
@@ -3,8 +3,15 @@
 //! These types are used for serializing data between
 //! the Rust backend and the TypeScript frontend.
 
-use devjunk_core::{CleanResult, JunkKind, ScanItem, ScanResult};
+use devjunk_core::{
+    summarize_history, trash_is_restorable, CleanItem, CleanResult, Config, CustomPattern,
+    DeleteMode, DirSize, Frequency, GuiSettings, HistoryEvent, HistoryRecord, JunkKind, KindSafety,
+    KindStats, MountKind, RootReason, ScanError, ScanErrorKind, ScanEstimate, ScanItem, ScanResult,
+    ScheduleStatus, SizeUnits, SuggestedRoot, Theme, TypedConfirm, Volume, Workspace,
+};
 use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 /// DTO for a single scanned junk item
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -16,12 +23,28 @@ pub struct ScanItemDto {
     pub kind: String,
     /// Human-readable kind name
     pub kind_display: String,
+    /// Whether deleting the kind can lose files that can't be recreated
+    pub safety: KindSafety,
+    /// What brings the directory back once deleted, e.g. "run cargo build"
+    pub rebuild_hint: String,
     /// Total size in bytes
     pub size_bytes: u64,
     /// Human-readable size string
     pub size_display: String,
     /// Total number of files
     pub file_count: u64,
+    /// Scan root this item was found under
+    pub root: String,
+    /// Modification time of the newest file inside (ms since Unix epoch)
+    pub last_modified_ms: Option<u64>,
+    /// Whether the directory was built recently and is probably still in use
+    pub is_active: bool,
+    /// Whether the project's git repository has uncommitted or unpushed work
+    pub repo_dirty: bool,
+    /// Whether the current user looks able to delete the directory
+    pub deletable: bool,
+    /// Why the directory can't be deleted, if it can't
+    pub undeletable_reason: Option<String>,
 }
 
 impl From<&ScanItem> for ScanItemDto {
@@ -29,10 +52,21 @@ impl From<&ScanItem> for ScanItemDto {
         Self {
             path: item.path.display().to_string(),
             kind: format!("{:?}", item.kind).to_lowercase(),
-            kind_display: item.kind.display_name().to_string(),
+            kind_display: item.kind_name().to_string(),
+            safety: item.kind.safety(),
+            rebuild_hint: item.kind.rebuild_hint().to_string(),
             size_bytes: item.size_bytes,
             size_display: format_size(item.size_bytes),
             file_count: item.file_count,
+            root: item.root.display().to_string(),
+            last_modified_ms: item
+                .last_modified
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_millis() as u64),
+            is_active: item.is_active,
+            repo_dirty: item.git.as_ref().is_some_and(|g| g.is_dirty()),
+            deletable: item.deletable,
+            undeletable_reason: item.undeletable_reason.clone(),
         }
     }
 }
@@ -51,16 +85,501 @@ pub struct ScanResultDto {
     pub total_file_count: u64,
     /// Number of items
     pub item_count: usize,
+    /// Network/removable mount points that were not scanned
+    pub skipped_mounts: Vec<String>,
+    /// Paths that could not be read during the scan
+    pub errors: Vec<ScanErrorDto>,
+    /// Id to clean the items of this scan by with `clean_paths`
+    pub scan_id: u64,
 }
 
-impl From<&ScanResult> for ScanResultDto {
-    fn from(result: &ScanResult) -> Self {
+impl ScanResultDto {
+    /// Convert the result of the scan with id `scan_id`
+    pub fn new(result: &ScanResult, scan_id: u64) -> Self {
         Self {
+            scan_id,
             items: result.items.iter().map(ScanItemDto::from).collect(),
             total_size_bytes: result.total_size_bytes(),
             total_size_display: format_size(result.total_size_bytes()),
             total_file_count: result.total_file_count(),
             item_count: result.item_count(),
+            skipped_mounts: result
+                .skipped_mounts
+                .iter()
+                .map(|m| m.mount_point.display().to_string())
+                .collect(),
+            errors: result.errors.iter().map(ScanErrorDto::from).collect(),
+        }
+    }
+}
+
+/// DTO for one page of the items of a scan that match a filter
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResultPageDto {
+    pub items: Vec<ScanItemDto>,
+    /// Page number, counting from 0
+    pub page: usize,
+    pub page_size: usize,
+    /// Number of pages the matching items fill
+    pub page_count: usize,
+    /// Number of items matching the filter, on all pages
+    pub match_count: usize,
+    /// Total size of the items matching the filter
+    pub match_size_bytes: u64,
+    pub match_size_display: String,
+}
+
+/// DTO for a rectangle of the treemap, holding the smaller ones drawn inside it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TreemapNodeDto {
+    /// Label of the rectangle; empty for the whole scan
+    pub name: String,
+    /// Directory the node stands for; None for kinds and the whole scan
+    pub path: Option<String>,
+    /// Kind id (snake_case string) of a kind or junk directory
+    pub kind: Option<String>,
+    /// Total size in bytes of the junk the node holds
+    pub size_bytes: u64,
+    /// Human-readable size string
+    pub size_display: String,
+    /// Number of junk directories in the node, or 1 for a junk directory
+    pub item_count: usize,
+    /// Nodes inside this one, largest first; empty for junk directories
+    pub children: Vec<TreemapNodeDto>,
+}
+
+impl TreemapNodeDto {
+    /// A junk directory, labelled `name`
+    pub fn item(item: &ScanItem, name: String) -> Self {
+        Self {
+            name,
+            path: Some(item.path.display().to_string()),
+            kind: Some(format!("{:?}", item.kind).to_lowercase()),
+            size_bytes: item.size_bytes,
+            size_display: format_size(item.size_bytes),
+            item_count: 1,
+            children: Vec::new(),
+        }
+    }
+
+    /// A node adding up `children`
+    pub fn group(
+        name: String,
+        path: Option<String>,
+        kind: Option<String>,
+        mut children: Vec<TreemapNodeDto>,
+    ) -> Self {
+        // Stable, so equal sizes keep the order they were given in
+        children.sort_by_key(|child| std::cmp::Reverse(child.size_bytes));
+        let size_bytes = children.iter().map(|child| child.size_bytes).sum();
+        Self {
+            name,
+            path,
+            kind,
+            size_bytes,
+            size_display: format_size(size_bytes),
+            item_count: children.iter().map(|child| child.item_count).sum(),
+            children,
+        }
+    }
+}
+
+/// DTO for a path that could not be read during a scan
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanErrorDto {
+    pub path: String,
+    /// Error category (snake_case string)
+    pub kind: String,
+    pub cause: String,
+}
+
+impl From<&ScanError> for ScanErrorDto {
+    fn from(error: &ScanError) -> Self {
+        Self {
+            path: error.path.display().to_string(),
+            kind: format!("{:?}", error.kind).to_lowercase(),
+            cause: error.cause.clone(),
+        }
+    }
+}
+
+/// DTO for what takes up the space inside a junk directory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ItemBreakdownDto {
+    /// The largest entries directly inside, largest first
+    pub entries: Vec<BreakdownEntryDto>,
+    /// Number of smaller entries left out
+    pub other_count: usize,
+    /// Total size in bytes of the entries left out
+    pub other_size_bytes: u64,
+    /// Human-readable size of the entries left out
+    pub other_size_display: String,
+}
+
+impl ItemBreakdownDto {
+    /// Keep the `limit` largest of `children`, which are sorted largest first
+    pub fn new(children: &[DirSize], limit: usize) -> Self {
+        let (shown, others) = children.split_at(limit.min(children.len()));
+        let other_size_bytes = others.iter().map(|child| child.size_bytes).sum();
+        Self {
+            entries: shown.iter().map(BreakdownEntryDto::from).collect(),
+            other_count: others.len(),
+            other_size_bytes,
+            other_size_display: format_size(other_size_bytes),
+        }
+    }
+}
+
+/// DTO for one file or subdirectory in a breakdown
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BreakdownEntryDto {
+    /// File or directory name
+    pub name: String,
+    /// Full path
+    pub path: String,
+    pub size_bytes: u64,
+    pub size_display: String,
+    pub file_count: u64,
+}
+
+impl From<&DirSize> for BreakdownEntryDto {
+    fn from(child: &DirSize) -> Self {
+        Self {
+            name: child
+                .path
+                .file_name()
+                .unwrap_or(child.path.as_os_str())
+                .to_string_lossy()
+                .into_owned(),
+            path: child.path.display().to_string(),
+            size_bytes: child.size_bytes,
+            size_display: format_size(child.size_bytes),
+            file_count: child.file_count,
+        }
+    }
+}
+
+/// DTO for a quick estimate of what a scan will find
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanEstimateDto {
+    /// Number of junk directories found by name
+    pub item_count: usize,
+    /// Total size of the directories earlier scans measured
+    pub known_bytes: u64,
+    pub known_display: String,
+    /// Number of directories no earlier scan measured, so not in `known_bytes`
+    pub unknown_count: usize,
+    /// Junk directories found per kind, most first
+    pub by_kind: Vec<EstimateKindDto>,
+    pub directories_scanned: usize,
+    /// Whether the whole of the paths was walked, rather than only what time allowed
+    pub complete: bool,
+}
+
+impl From<&ScanEstimate> for ScanEstimateDto {
+    fn from(estimate: &ScanEstimate) -> Self {
+        let mut by_kind: Vec<EstimateKindDto> = Vec::new();
+        for item in &estimate.items {
+            let kind = format!("{:?}", item.kind).to_lowercase();
+            let index = match by_kind.iter().position(|entry| entry.kind == kind) {
+                Some(index) => index,
+                None => {
+                    by_kind.push(EstimateKindDto {
+                        kind,
+                        kind_display: item.kind.display_name().to_string(),
+                        count: 0,
+                        known_bytes: 0,
+                    });
+                    by_kind.len() - 1
+                }
+            };
+            by_kind[index].count += 1;
+            by_kind[index].known_bytes += item.size_bytes.unwrap_or(0);
+        }
+        by_kind.sort_by(|a, b| {
+            b.count
+                .cmp(&a.count)
+                .then(b.known_bytes.cmp(&a.known_bytes))
+        });
+
+        Self {
+            item_count: estimate.items.len(),
+            known_bytes: estimate.known_bytes(),
+            known_display: format_size(estimate.known_bytes()),
+            unknown_count: estimate.unknown_count(),
+            by_kind,
+            directories_scanned: estimate.directories_scanned,
+            complete: estimate.complete,
+        }
+    }
+}
+
+/// DTO for the junk of one kind an estimate found
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EstimateKindDto {
+    /// Type of junk (snake_case string)
+    pub kind: String,
+    /// Human-readable kind name
+    pub kind_display: String,
+    pub count: usize,
+    /// Total size of the ones earlier scans measured
+    pub known_bytes: u64,
+}
+
+/// DTO for whether the OS kept a scan from reading some paths
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiskAccessDto {
+    /// Whether the app has Full Disk Access on macOS (None elsewhere, or if it can't be told)
+    pub full_disk_access: Option<bool>,
+    /// Paths the scan was not allowed to read
+    pub denied_paths: Vec<String>,
+    /// Whether results are missing for lack of Full Disk Access, so the user should grant it
+    pub restricted: bool,
+}
+
+impl DiskAccessDto {
+    /// Check the access of the app, and what `result` could not read, if there is one
+    pub fn new(full_disk_access: Option<bool>, result: Option<&ScanResult>) -> Self {
+        let denied_paths: Vec<String> = result
+            .map(|result| result.errors.as_slice())
+            .unwrap_or_default()
+            .iter()
+            .filter(|error| error.kind == ScanErrorKind::PermissionDenied)
+            .map(|error| error.path.display().to_string())
+            .collect();
+        Self {
+            full_disk_access,
+            restricted: full_disk_access == Some(false) && !denied_paths.is_empty(),
+            denied_paths,
+        }
+    }
+}
+
+/// Whether a path can be added to the paths to scan
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PathStatus {
+    Ok,
+    NotFound,
+    NotADirectory,
+    /// The same directory as an earlier path
+    Duplicate,
+    /// Inside another of the paths, which scans it already
+    Nested,
+}
+
+/// DTO for the check of one of a batch of paths
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PathCheckDto {
+    pub path: String,
+    pub status: PathStatus,
+    /// The path this one duplicates or is nested in
+    pub covered_by: Option<String>,
+}
+
+impl PathCheckDto {
+    /// Check each of `paths` on its own and against the others
+    ///
+    /// Paths are compared once symlinks are resolved, like scan roots are.
+    pub fn check_all(paths: &[String]) -> Vec<Self> {
+        let dirs: Vec<Option<PathBuf>> = paths
+            .iter()
+            .map(|path| std::fs::canonicalize(path).ok().filter(|dir| dir.is_dir()))
+            .collect();
+        paths
+            .iter()
+            .enumerate()
+            .map(|(i, path)| {
+                let check = |status, covered_by: Option<usize>| Self {
+                    path: path.clone(),
+                    status,
+                    covered_by: covered_by.map(|j| paths[j].clone()),
+                };
+                let Some(dir) = &dirs[i] else {
+                    if Path::new(path).exists() {
+                        return check(PathStatus::NotADirectory, None);
+                    }
+                    return check(PathStatus::NotFound, None);
+                };
+                let others = || {
+                    dirs.iter()
+                        .enumerate()
+                        .filter_map(|(j, other)| Some((j, other.as_ref()?)))
+                        .filter(move |&(j, _)| j != i)
+                };
+                if let Some((j, _)) = others().find(|&(j, other)| other == dir && j < i) {
+                    check(PathStatus::Duplicate, Some(j))
+                } else if let Some((j, _)) =
+                    others().find(|&(_, other)| other != dir && dir.starts_with(other))
+                {
+                    check(PathStatus::Nested, Some(j))
+                } else {
+                    check(PathStatus::Ok, None)
+                }
+            })
+            .collect()
+    }
+}
+
+/// DTO for a mounted volume and how full it is
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VolumeDto {
+    pub mount_point: String,
+    pub fs_type: String,
+    /// "local", "network" or "removable"
+    pub kind: MountKind,
+    pub total_bytes: u64,
+    pub total_display: String,
+    pub free_bytes: u64,
+    pub free_display: String,
+    /// Share of the volume in use, from 0 to 100
+    pub used_percent: u8,
+    /// Where to scan the volume from: the home directory on the volume holding it
+    pub suggested_root: String,
+}
+
+impl VolumeDto {
+    /// Describe each of `volumes`, suggesting `home` as the root of the one holding it
+    pub fn list(volumes: &[Volume], home: Option<&Path>) -> Vec<Self> {
+        // Mounts can nest, so the home directory is on the deepest one above it
+        let home_volume = home.and_then(|home| {
+            volumes
+                .iter()
+                .filter(|volume| home.starts_with(&volume.mount_point))
+                .max_by_key(|volume| volume.mount_point.components().count())
+        });
+        volumes
+            .iter()
+            .map(|volume| {
+                let suggested_root = match home {
+                    Some(home) if home_volume == Some(volume) => home,
+                    _ => &volume.mount_point,
+                };
+                Self {
+                    mount_point: volume.mount_point.display().to_string(),
+                    fs_type: volume.fs_type.clone(),
+                    kind: volume.kind,
+                    total_bytes: volume.total_bytes,
+                    total_display: format_size(volume.total_bytes),
+                    free_bytes: volume.free_bytes,
+                    free_display: format_size(volume.free_bytes),
+                    used_percent: (volume.used_fraction() * 100.0).round() as u8,
+                    suggested_root: suggested_root.display().to_string(),
+                }
+            })
+            .collect()
+    }
+}
+
+/// DTO for a directory the first-run screen suggests scanning
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SuggestedRootDto {
+    pub path: String,
+    /// "well_known", "go_path" or "repositories"
+    pub reason: RootReason,
+    /// Git checkouts found one or two levels down
+    pub repos: usize,
+}
+
+impl From<&SuggestedRoot> for SuggestedRootDto {
+    fn from(root: &SuggestedRoot) -> Self {
+        Self {
+            path: root.path.display().to_string(),
+            reason: root.reason,
+            repos: root.repos,
+        }
+    }
+}
+
+/// DTO for the cleans in the history, for charting space reclaimed over time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CleanHistoryDto {
+    /// Cleans that deleted something, oldest first
+    pub cleans: Vec<CleanRecordDto>,
+    /// Total bytes freed by the cleans
+    pub bytes_freed: u64,
+    pub bytes_freed_display: String,
+    /// Bytes freed per junk kind over all the cleans, most first
+    pub by_kind: Vec<KindBytesDto>,
+}
+
+impl CleanHistoryDto {
+    /// Collect the cleans of `records`, which are oldest first, made at or after `since`
+    pub fn new(records: &[HistoryRecord], since: Option<SystemTime>) -> Self {
+        let stats = summarize_history(records, since);
+        let cleans = records
+            .iter()
+            .filter(|record| record.event == HistoryEvent::Clean && !record.items.is_empty())
+            .filter(|record| since.is_none_or(|since| record.time() >= since))
+            .map(CleanRecordDto::from)
+            .collect();
+        Self {
+            cleans,
+            bytes_freed: stats.bytes_freed,
+            bytes_freed_display: format_size(stats.bytes_freed),
+            by_kind: stats.by_kind.iter().map(KindBytesDto::from).collect(),
+        }
+    }
+}
+
+/// DTO for one clean in the history
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CleanRecordDto {
+    /// When the clean finished (ms since Unix epoch)
+    pub at_ms: u64,
+    /// Number of directories deleted
+    pub deleted_count: usize,
+    pub bytes_freed: u64,
+    pub bytes_freed_display: String,
+    /// Bytes freed per junk kind, most first
+    pub by_kind: Vec<KindBytesDto>,
+}
+
+impl From<&HistoryRecord> for CleanRecordDto {
+    fn from(record: &HistoryRecord) -> Self {
+        let bytes_freed = record.total_bytes();
+        Self {
+            at_ms: record.at * 1000,
+            deleted_count: record.items.len(),
+            bytes_freed,
+            bytes_freed_display: format_size(bytes_freed),
+            by_kind: record.by_kind().iter().map(KindBytesDto::from).collect(),
+        }
+    }
+}
+
+/// DTO for the bytes freed of one junk kind
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KindBytesDto {
+    /// Type of junk (snake_case string)
+    pub kind: String,
+    /// Human-readable kind name
+    pub kind_display: String,
+    pub bytes_freed: u64,
+    pub bytes_freed_display: String,
+}
+
+impl From<&KindStats> for KindBytesDto {
+    fn from(stats: &KindStats) -> Self {
+        Self {
+            kind: format!("{:?}", stats.kind).to_lowercase(),
+            kind_display: stats.kind.display_name().to_string(),
+            bytes_freed: stats.bytes_freed,
+            bytes_freed_display: format_size(stats.bytes_freed),
         }
     }
 }
@@ -77,17 +596,33 @@ pub struct CleanResultDto {
     pub failed: Vec<CleanFailureDto>,
     /// Number of failed items
     pub failed_count: usize,
+    /// Paths left alone because they no longer looked like junk or the clean was cancelled
+    pub skipped: Vec<CleanFailureDto>,
+    /// Number of skipped items
+    pub skipped_count: usize,
     /// Total bytes freed
     pub bytes_freed: u64,
     /// Human-readable bytes freed
     pub bytes_freed_display: String,
     /// Whether this was a dry run
     pub was_dry_run: bool,
+    /// Whether the clean was cancelled before every path was handled
+    pub cancelled: bool,
+    /// How much free space grew on the cleaned filesystems, when it could be measured
+    pub measured_bytes_freed: Option<i64>,
+    /// Human-readable measured free space change, e.g. "+1.20 GB"
+    pub measured_bytes_freed_display: Option<String>,
     /// Whether all operations succeeded
     pub is_success: bool,
+    /// How the directories were deleted: "permanent", "trash", "archive" or "renameDefer"
+    pub delete_mode: String,
+    /// Where the directories were archived, for the "archive" delete mode
+    pub archive_dir: Option<String>,
+    /// Whether `undo_last_clean` can put the deleted directories back
+    pub undoable: bool,
 }
 
-/// DTO for a failed clean operation
+/// DTO for a failed or skipped clean operation
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CleanFailureDto {
@@ -95,28 +630,83 @@ pub struct CleanFailureDto {
     pub error: String,
 }
 
-impl From<&CleanResult> for CleanResultDto {
-    fn from(result: &CleanResult) -> Self {
+impl From<&CleanItem> for CleanFailureDto {
+    fn from(item: &CleanItem) -> Self {
         Self {
-            deleted: result.deleted.iter().map(|p| p.display().to_string()).collect(),
-            deleted_count: result.deleted_count(),
-            failed: result
-                .failed
-                .iter()
-                .map(|(path, error)| CleanFailureDto {
-                    path: path.display().to_string(),
-                    error: error.clone(),
-                })
+            path: item.path.display().to_string(),
+            error: item.error.clone().unwrap_or_default(),
+        }
+    }
+}
+
+impl CleanResultDto {
+    /// Describe `result`, a clean that deleted with `delete_mode`
+    pub fn new(result: &CleanResult, delete_mode: &DeleteMode) -> Self {
+        let undoable = !result.was_dry_run && result.deleted_count() > 0 && can_undo(delete_mode);
+        let (delete_mode, archive_dir) = delete_mode_dto(delete_mode);
+        Self {
+            deleted: result
+                .deleted()
+                .map(|item| item.path.display().to_string())
                 .collect(),
+            deleted_count: result.deleted_count(),
+            failed: result.failed().map(CleanFailureDto::from).collect(),
             failed_count: result.failed_count(),
+            skipped: result.skipped().map(CleanFailureDto::from).collect(),
+            skipped_count: result.skipped_count(),
             bytes_freed: result.bytes_freed,
             bytes_freed_display: format_size(result.bytes_freed),
             was_dry_run: result.was_dry_run,
+            cancelled: result.cancelled,
+            measured_bytes_freed: result.free_space.map(|space| space.reclaimed()),
+            measured_bytes_freed_display: result.free_space.map(|space| {
+                let sign = if space.reclaimed() < 0 { "-" } else { "+" };
+                format!("{}{}", sign, format_size(space.reclaimed().unsigned_abs()))
+            }),
             is_success: result.is_success(),
+            delete_mode: delete_mode.to_string(),
+            archive_dir,
+            undoable,
+        }
+    }
+}
+
+/// DTO for the free space on the filesystems being cleaned, sent while a clean runs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiskFreeDto {
+    pub free_bytes: u64,
+    pub free_display: String,
+    /// How much free space grew since the clean started; 0 if other programs wrote more
+    pub reclaimed_bytes: u64,
+    pub reclaimed_display: String,
+}
+
+impl DiskFreeDto {
+    /// Free space of `free_bytes`, after `before` when the clean started
+    pub fn new(before: u64, free_bytes: u64) -> Self {
+        let reclaimed_bytes = free_bytes.saturating_sub(before);
+        Self {
+            free_bytes,
+            free_display: format_size(free_bytes),
+            reclaimed_bytes,
+            reclaimed_display: format_size(reclaimed_bytes),
         }
     }
 }
 
+/// DTO for what undoing a clean put back
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UndoResultDto {
+    /// Restored paths
+    pub restored: Vec<String>,
+    pub restored_count: usize,
+    /// Paths that could not be restored, with the reason
+    pub failed: Vec<CleanFailureDto>,
+    pub failed_count: usize,
+}
+
 /// DTO for junk kind information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -124,6 +714,12 @@ pub struct JunkKindDto {
     pub id: String,
     pub display_name: String,
     pub patterns: Vec<String>,
+    /// Language or ecosystem of the kind; None for generic names like `build`
+    pub language: Option<String>,
+    /// "safe", or "caution" when it may hold files that can't be recreated
+    pub safety: KindSafety,
+    /// What brings a deleted directory of the kind back
+    pub rebuild_hint: String,
 }
 
 impl From<JunkKind> for JunkKindDto {
@@ -132,23 +728,348 @@ impl From<JunkKind> for JunkKindDto {
             id: format!("{:?}", kind).to_lowercase(),
             display_name: kind.display_name().to_string(),
             patterns: kind.patterns().iter().map(|s| s.to_string()).collect(),
+            language: kind.language().map(str::to_string),
+            safety: kind.safety(),
+            rebuild_hint: kind.rebuild_hint().to_string(),
         }
     }
 }
 
-/// Format bytes into human-readable string
-fn format_size(bytes: u64) -> String {
-    const KB: u64 = 1024;
-    const MB: u64 = KB * 1024;
-    const GB: u64 = MB * 1024;
-
-    if bytes >= GB {
-        format!("{:.2} GB", bytes as f64 / GB as f64)
-    } else if bytes >= MB {
-        format!("{:.2} MB", bytes as f64 / MB as f64)
-    } else if bytes >= KB {
-        format!("{:.2} KB", bytes as f64 / KB as f64)
-    } else {
-        format!("{} B", bytes)
+/// DTO for a user-defined junk pattern
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomPatternDto {
+    /// Name shown for matching directories
+    pub name: String,
+    /// Glob matched against directory names
+    pub glob: String,
+}
+
+impl From<&CustomPattern> for CustomPatternDto {
+    fn from(pattern: &CustomPattern) -> Self {
+        Self {
+            name: pattern.name.clone(),
+            glob: pattern.glob.clone(),
+        }
+    }
+}
+
+impl From<CustomPatternDto> for CustomPattern {
+    fn from(dto: CustomPatternDto) -> Self {
+        CustomPattern::new(dto.name, dto.glob)
     }
 }
+
+/// DTOs for `patterns`, in the same order
+pub fn custom_pattern_dtos(patterns: &[CustomPattern]) -> Vec<CustomPatternDto> {
+    patterns.iter().map(CustomPatternDto::from).collect()
+}
+
+/// DTO for a named set of directories scanned together
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceDto {
+    pub name: String,
+    pub paths: Vec<String>,
+}
+
+impl From<&Workspace> for WorkspaceDto {
+    fn from(workspace: &Workspace) -> Self {
+        Self {
+            name: workspace.name.clone(),
+            paths: path_strings(&workspace.paths),
+        }
+    }
+}
+
+impl From<WorkspaceDto> for Workspace {
+    fn from(dto: WorkspaceDto) -> Self {
+        Self {
+            name: dto.name,
+            paths: dto.paths.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+/// DTOs for `workspaces`, in the same order
+pub fn workspace_dtos(workspaces: &[Workspace]) -> Vec<WorkspaceDto> {
+    workspaces.iter().map(WorkspaceDto::from).collect()
+}
+
+/// DTO for a recurring clean installed in the OS scheduler
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduleDto {
+    pub name: String,
+    /// "daily", "weekly" (on Sundays) or "monthly" (on the 1st)
+    pub frequency: Frequency,
+    /// Time of day it runs at, in local time
+    pub hour: u8,
+    pub minute: u8,
+    /// The devjunk command it runs, e.g. "clean /home/me/src --older-than 30d --yes"
+    pub command: String,
+    /// Name of the timer, agent or task in the OS scheduler
+    pub job: String,
+    /// Whether the OS scheduler still has the job
+    pub installed: bool,
+}
+
+impl From<&ScheduleStatus> for ScheduleDto {
+    fn from(status: &ScheduleStatus) -> Self {
+        let schedule = &status.schedule;
+        Self {
+            name: schedule.name.clone(),
+            frequency: schedule.frequency,
+            hour: schedule.hour,
+            minute: schedule.minute,
+            command: schedule.args.join(" "),
+            job: status.job.clone(),
+            installed: status.installed,
+        }
+    }
+}
+
+/// DTO for a recurring clean to install
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewScheduleDto {
+    /// Letters, digits, `-` and `_`; replaces the schedule of the same name
+    pub name: String,
+    pub frequency: Frequency,
+    pub hour: u8,
+    pub minute: u8,
+    /// Directories to clean, as absolute paths
+    pub paths: Vec<String>,
+    /// Ids of the junk kinds to clean, as in JunkKindDto (None = all)
+    pub kinds: Option<Vec<String>>,
+    /// Only clean directories untouched for this many days
+    pub older_than_days: Option<u64>,
+    /// Only report what would be deleted
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+impl NewScheduleDto {
+    /// The arguments of the `devjunk clean` the schedule runs
+    pub fn clean_args(&self) -> Result<Vec<String>, String> {
+        if self.paths.is_empty() {
+            return Err("A scheduled clean needs at least one path".into());
+        }
+        let mut args = vec!["clean".to_string()];
+        for path in &self.paths {
+            // Scheduled cleans run in no particular directory
+            if !Path::new(path).is_absolute() {
+                return Err(format!("{} is not an absolute path", path));
+            }
+            args.push(path.clone());
+        }
+        for kind in parse_kinds(self.kinds.clone())?.unwrap_or_default() {
+            args.extend(["--kind".to_string(), kind.id().to_string()]);
+        }
+        if let Some(days) = self.older_than_days {
+            args.extend(["--older-than".to_string(), format!("{}d", days)]);
+        }
+        // Nobody is there to confirm it
+        args.push(if self.dry_run { "--dry-run" } else { "--yes" }.to_string());
+        Ok(args)
+    }
+}
+
+/// DTO for the config file shared with the CLI
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigDto {
+    /// Directories to scan when none are given
+    pub roots: Vec<String>,
+    /// Globs of paths never to scan or clean
+    pub exclude: Vec<String>,
+    /// Directories never to scan or clean, with everything below them
+    pub ignore: Vec<String>,
+    /// Ids of the junk kinds to look for, as in JunkKindDto (None = all)
+    pub kinds: Option<Vec<String>>,
+    /// Extra directory names to treat as junk
+    pub custom_patterns: Vec<CustomPatternDto>,
+    /// "permanent", "trash", "archive" or "renameDefer"
+    pub delete_mode: String,
+    /// Where directories are archived, for the "archive" delete mode
+    pub archive_dir: Option<String>,
+    /// Maximum number of directories deleted at once (None = one per CPU)
+    pub concurrency: Option<usize>,
+    /// Worker threads for scanning and deleting, applied on the next start (None = one per CPU)
+    pub threads: Option<usize>,
+    /// Size in bytes from which the CLI has a clean confirmed by typing its size
+    pub typed_confirm_bytes: u64,
+    /// Number of directories from which the CLI has a clean confirmed by typing
+    pub typed_confirm_dirs: usize,
+    /// "system", "light" or "dark"
+    pub theme: Theme,
+    /// Minutes between scans of the roots in the tray (None = never)
+    pub background_scan_minutes: Option<u64>,
+    /// Look up the git status of each item's repository while scanning
+    pub git_status: bool,
+    /// Named sets of directories to scan together
+    pub workspaces: Vec<WorkspaceDto>,
+}
+
+impl From<&Config> for ConfigDto {
+    fn from(config: &Config) -> Self {
+        let (delete_mode, archive_dir) = delete_mode_dto(&config.delete_mode);
+        Self {
+            roots: path_strings(&config.roots),
+            exclude: config.exclude.clone(),
+            ignore: path_strings(&config.ignore),
+            kinds: kind_ids(&config.kinds),
+            custom_patterns: custom_pattern_dtos(&config.custom_patterns),
+            delete_mode: delete_mode.to_string(),
+            archive_dir,
+            concurrency: config.concurrency,
+            threads: config.threads,
+            typed_confirm_bytes: config.typed_confirm.min_bytes,
+            typed_confirm_dirs: config.typed_confirm.min_dirs,
+            theme: config.gui.theme,
+            background_scan_minutes: config.gui.background_scan_minutes,
+            git_status: config.gui.git_status,
+            workspaces: workspace_dtos(&config.gui.workspaces),
+        }
+    }
+}
+
+impl TryFrom<ConfigDto> for Config {
+    type Error = String;
+
+    fn try_from(dto: ConfigDto) -> Result<Self, String> {
+        let delete_mode = parse_delete_mode(&dto.delete_mode, dto.archive_dir)?;
+        let kinds = parse_kinds(dto.kinds)?;
+
+        Ok(Self {
+            roots: dto.roots.into_iter().map(Into::into).collect(),
+            exclude: dto.exclude,
+            ignore: dto.ignore.into_iter().map(Into::into).collect(),
+            kinds,
+            custom_patterns: dto.custom_patterns.into_iter().map(Into::into).collect(),
+            delete_mode,
+            concurrency: dto.concurrency,
+            threads: dto.threads,
+            typed_confirm: TypedConfirm {
+                min_bytes: dto.typed_confirm_bytes,
+                min_dirs: dto.typed_confirm_dirs,
+            },
+            gui: GuiSettings {
+                theme: dto.theme,
+                background_scan_minutes: dto.background_scan_minutes,
+                git_status: dto.git_status,
+                workspaces: dto.workspaces.into_iter().map(Into::into).collect(),
+            },
+        })
+    }
+}
+
+/// DTO for the preferences the GUI keeps between launches
+///
+/// A part of the shared config file; saving it leaves the rest of the file as it is.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingsDto {
+    /// Directories to scan when none are given
+    pub roots: Vec<String>,
+    /// Ids of the junk kinds to look for, as in JunkKindDto (None = all)
+    pub kinds: Option<Vec<String>>,
+    /// Globs of paths never to scan or clean
+    pub exclude: Vec<String>,
+    /// "permanent", "trash", "archive" or "renameDefer"
+    pub delete_mode: String,
+    /// Where directories are archived, for the "archive" delete mode
+    pub archive_dir: Option<String>,
+    /// "system", "light" or "dark"
+    pub theme: Theme,
+    /// Look up the git status of each item's repository while scanning
+    pub git_status: bool,
+}
+
+impl From<&Config> for SettingsDto {
+    fn from(config: &Config) -> Self {
+        let (delete_mode, archive_dir) = delete_mode_dto(&config.delete_mode);
+        Self {
+            roots: path_strings(&config.roots),
+            kinds: kind_ids(&config.kinds),
+            exclude: config.exclude.clone(),
+            delete_mode: delete_mode.to_string(),
+            archive_dir,
+            theme: config.gui.theme,
+            git_status: config.gui.git_status,
+        }
+    }
+}
+
+impl SettingsDto {
+    /// Replace the settings of `config` with these
+    pub fn apply_to(self, config: &mut Config) -> Result<(), String> {
+        config.delete_mode = parse_delete_mode(&self.delete_mode, self.archive_dir)?;
+        config.kinds = parse_kinds(self.kinds)?;
+        config.roots = self.roots.into_iter().map(Into::into).collect();
+        config.exclude = self.exclude;
+        config.gui.theme = self.theme;
+        config.gui.git_status = self.git_status;
+        Ok(())
+    }
+}
+
+/// `paths` as the frontend shows them
+pub fn path_strings(paths: &[PathBuf]) -> Vec<String> {
+    paths.iter().map(|p| p.display().to_string()).collect()
+}
+
+fn kind_ids(kinds: &Option<Vec<JunkKind>>) -> Option<Vec<String>> {
+    kinds
+        .as_ref()
+        .map(|kinds| kinds.iter().map(|&k| JunkKindDto::from(k).id).collect())
+}
+
+fn parse_kinds(ids: Option<Vec<String>>) -> Result<Option<Vec<JunkKind>>, String> {
+    ids.map(|ids| {
+        ids.iter()
+            .map(|id| {
+                JunkKind::all()
+                    .into_iter()
+                    .find(|&kind| JunkKindDto::from(kind).id == *id)
+                    .ok_or_else(|| format!("Unknown junk kind: {}", id))
+            })
+            .collect::<Result<Vec<_>, String>>()
+    })
+    .transpose()
+}
+
+/// The delete mode name and archive directory the frontend uses
+pub fn delete_mode_dto(mode: &DeleteMode) -> (&'static str, Option<String>) {
+    match mode {
+        DeleteMode::Permanent => ("permanent", None),
+        DeleteMode::Trash => ("trash", None),
+        DeleteMode::Archive { dir } => ("archive", Some(dir.display().to_string())),
+        DeleteMode::RenameDefer => ("renameDefer", None),
+    }
+}
+
+/// Whether directories deleted in `mode` can be put back
+pub fn can_undo(mode: &DeleteMode) -> bool {
+    match mode {
+        DeleteMode::Trash => trash_is_restorable(),
+        DeleteMode::Archive { .. } => true,
+        DeleteMode::Permanent | DeleteMode::RenameDefer => false,
+    }
+}
+
+pub fn parse_delete_mode(mode: &str, archive_dir: Option<String>) -> Result<DeleteMode, String> {
+    match (mode, archive_dir) {
+        ("permanent", _) => Ok(DeleteMode::Permanent),
+        ("trash", _) => Ok(DeleteMode::Trash),
+        ("archive", Some(dir)) => Ok(DeleteMode::Archive { dir: dir.into() }),
+        ("archive", None) => Err("The archive delete mode needs a directory".into()),
+        ("renameDefer", _) => Ok(DeleteMode::RenameDefer),
+        (other, _) => Err(format!("Unknown delete mode: {}", other)),
+    }
+}
+
+/// Format bytes the way the frontend shows sizes, in powers of 1024
+fn format_size(bytes: u64) -> String {
+    devjunk_core::format_size(bytes, SizeUnits::Binary)
+}
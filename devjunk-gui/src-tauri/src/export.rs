@@ -0,0 +1,122 @@
+//! Writing scan results to a file to share
+//!
+//! JSON is the document `devjunk scan --format json` prints and CSV has the
+//! same columns as `--format csv`, so an export can go through the same
+//! scripts as the CLI's output. Markdown is meant for pasting into an issue
+//! or a chat, with sizes written for people.
+
+use devjunk_core::{ScanItem, ScanResult};
+use serde::Deserialize;
+use std::time::SystemTime;
+
+/// Largest directories listed in a Markdown export
+const MARKDOWN_ITEMS: usize = 50;
+
+/// File format of an export
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ExportFormat {
+    Json,
+    Csv,
+    Markdown,
+}
+
+impl ExportFormat {
+    /// Extension of files in this format
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Csv => "csv",
+            Self::Markdown => "md",
+        }
+    }
+
+    /// Name of the format in the save dialog's file type filter
+    pub fn filter_name(self) -> &'static str {
+        match self {
+            Self::Json => "JSON",
+            Self::Csv => "CSV",
+            Self::Markdown => "Markdown",
+        }
+    }
+}
+
+/// The contents of a file holding `result` in `format`
+pub fn render(result: &ScanResult, format: ExportFormat) -> Result<Vec<u8>, String> {
+    match format {
+        ExportFormat::Json => {
+            let mut json = serde_json::to_vec_pretty(result).map_err(|e| e.to_string())?;
+            json.push(b'\n');
+            Ok(json)
+        }
+        ExportFormat::Csv => csv(result).map_err(|e| e.to_string()),
+        ExportFormat::Markdown => Ok(markdown(result).into_bytes()),
+    }
+}
+
+fn csv(result: &ScanResult) -> csv::Result<Vec<u8>> {
+    let mut csv = csv::Writer::from_writer(Vec::new());
+    csv.write_record([
+        "path",
+        "kind",
+        "kind_name",
+        "size_bytes",
+        "file_count",
+        "last_modified",
+        "is_active",
+        "deletable",
+    ])?;
+    for item in &result.items {
+        csv.write_record([
+            item.path.display().to_string(),
+            item.kind.id().to_string(),
+            item.kind_name().to_string(),
+            item.size_bytes.to_string(),
+            item.file_count.to_string(),
+            item.last_modified.map(rfc3339).unwrap_or_default(),
+            item.is_active.to_string(),
+            item.deletable.to_string(),
+        ])?;
+    }
+    csv.into_inner().map_err(|e| e.into_error().into())
+}
+
+/// Totals, then the largest directories
+fn markdown(result: &ScanResult) -> String {
+    let mut md = format!(
+        "## devjunk scan\n\n{} junk directories, {}.\n\n",
+        result.items.len(),
+        format_size(result.total_size_bytes())
+    );
+    if result.items.is_empty() {
+        return md;
+    }
+
+    let mut items: Vec<&ScanItem> = result.items.iter().collect();
+    items.sort_by_key(|item| std::cmp::Reverse(item.size_bytes));
+    md.push_str("| Path | Type | Size | Files |\n| --- | --- | ---: | ---: |\n");
+    for item in items.iter().take(MARKDOWN_ITEMS) {
+        md.push_str(&format!(
+            "| `{}` | {} | {} | {} |\n",
+            item.path.display().to_string().replace('|', "\\|"),
+            item.kind_name(),
+            format_size(item.size_bytes),
+            item.file_count
+        ));
+    }
+    if items.len() > MARKDOWN_ITEMS {
+        md.push_str(&format!(
+            "\n...and {} smaller directories.\n",
+            items.len() - MARKDOWN_ITEMS
+        ));
+    }
+    md
+}
+
+fn rfc3339(time: SystemTime) -> String {
+    humantime::format_rfc3339_seconds(time).to_string()
+}
+
+fn format_size(bytes: u64) -> String {
+    devjunk_core::format_size(bytes, devjunk_core::SizeUnits::Binary)
+}
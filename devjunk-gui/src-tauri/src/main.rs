@@ -4,18 +4,123 @@
 
 mod commands;
 mod dto;
+mod export;
+mod query;
+mod reveal;
+mod tray;
+mod treemap;
 
-use commands::{clean_paths, get_junk_kinds, scan_paths, validate_path};
+use commands::{
+    add_custom_pattern, add_ignored_path, apply_theme, cancel_clean, cancel_scan,
+    check_disk_access, clean_paths, create_schedule, create_workspace, delete_schedule,
+    delete_workspace, estimate_paths, export_results, get_autostart, get_background_scan,
+    get_clean_history, get_config, get_custom_patterns, get_ignored_paths, get_item_breakdown,
+    get_junk_kinds, get_last_scan, get_settings, get_treemap, get_volumes, list_schedules,
+    list_workspaces, open_disk_access_settings, pause_scan, query_results, remove_custom_pattern,
+    remove_ignored_path, resume_scan, reveal_path, save_config, scan_paths, set_autostart,
+    set_background_scan, set_settings, suggest_roots, undo_last_clean, validate_path,
+    validate_paths, CleanState, ScanState,
+};
+use devjunk_core::{set_worker_threads, take_handoff, Config, ScanResult, OPEN_SCAN_ARG};
+use std::path::Path;
+use tauri::{Manager, WindowEvent};
+use tauri_plugin_autostart::MacosLauncher;
+use tray::BackgroundScans;
+
+/// The scan `devjunk scan --open-gui` started the app with, if any
+fn handed_over_scan() -> Option<ScanResult> {
+    let path = std::env::args_os()
+        .skip_while(|arg| arg != OPEN_SCAN_ARG)
+        .nth(1)?;
+    take_handoff(Path::new(&path)).ok()
+}
 
 fn main() {
+    // The thread pool can only be sized before the first scan uses it
+    let config = Config::load_default().unwrap_or_default();
+    if let Some(threads) = config.threads {
+        let _ = set_worker_threads(threads);
+    }
+
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_autostart::init(
+            MacosLauncher::LaunchAgent,
+            Some(vec![tray::AUTOSTART_ARG]),
+        ))
+        .manage(ScanState::default())
+        .manage(CleanState::default())
+        .setup(move |app| {
+            apply_theme(app.handle(), config.gui.theme);
+            // The window lists a scan handed over by the CLI as the last one
+            if let Some(result) = handed_over_scan() {
+                app.state::<ScanState>().cache(result);
+            }
+            let scans = tray::setup(app.handle(), config.gui.background_scan_minutes)?;
+            // Started at login, the app only shows up in the tray
+            let autostarted = std::env::args().any(|arg| arg == tray::AUTOSTART_ARG);
+            if autostarted && scans.is_enabled() {
+                if let Some(window) = app.get_webview_window("main") {
+                    let _ = window.hide();
+                }
+            }
+            app.manage(scans);
+            Ok(())
+        })
+        .on_window_event(|window, event| {
+            // With background scans on, the window only goes to the tray
+            if let WindowEvent::CloseRequested { api, .. } = event {
+                if window.state::<BackgroundScans>().is_enabled() {
+                    api.prevent_close();
+                    let _ = window.hide();
+                }
+            }
+        })
         .invoke_handler(tauri::generate_handler![
             scan_paths,
+            query_results,
+            get_treemap,
+            pause_scan,
+            resume_scan,
+            cancel_scan,
             clean_paths,
+            cancel_clean,
+            undo_last_clean,
             get_junk_kinds,
+            get_item_breakdown,
+            get_config,
+            save_config,
+            get_settings,
+            set_settings,
+            get_background_scan,
+            set_background_scan,
+            get_autostart,
+            set_autostart,
+            get_last_scan,
+            export_results,
+            list_workspaces,
+            create_workspace,
+            delete_workspace,
+            get_custom_patterns,
+            add_custom_pattern,
+            remove_custom_pattern,
+            get_ignored_paths,
+            add_ignored_path,
+            remove_ignored_path,
+            list_schedules,
+            create_schedule,
+            delete_schedule,
+            reveal_path,
+            check_disk_access,
+            open_disk_access_settings,
+            estimate_paths,
+            get_volumes,
+            suggest_roots,
+            get_clean_history,
             validate_path,
+            validate_paths,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,127 @@
+//! Filtering, sorting and paging the results of a scan
+//!
+//! A scan can find tens of thousands of directories, more than the webview
+//! lists smoothly. The table asks for one page of the cached result at a
+//! time instead, filtered and sorted here, with the same orders as
+//! `devjunk scan --sort`.
+
+use crate::dto::{ResultPageDto, ScanItemDto};
+use devjunk_core::{ScanItem, ScanResult};
+use serde::Deserialize;
+
+/// Which items of a scan to list
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ResultFilter {
+    /// Text the path must contain, ignoring case
+    pub text: Option<String>,
+    /// Kinds to list, by id; all of them when empty
+    pub kinds: Vec<String>,
+    /// Smallest size to list
+    pub min_size_bytes: Option<u64>,
+    /// Leave out the directories that can't be deleted
+    pub deletable_only: bool,
+}
+
+impl ResultFilter {
+    fn matches(&self, item: &ScanItem, text: Option<&str>) -> bool {
+        text.is_none_or(|text| {
+            item.path
+                .display()
+                .to_string()
+                .to_lowercase()
+                .contains(text)
+        }) && (self.kinds.is_empty() || self.kinds.contains(&kind_id(item)))
+            && self.min_size_bytes.is_none_or(|min| item.size_bytes >= min)
+            && (!self.deletable_only || item.deletable)
+    }
+}
+
+/// Order to list the items of a scan in
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ResultSort {
+    pub key: SortKey,
+    /// Reverse the order given by `key`
+    pub reverse: bool,
+}
+
+/// What items are ordered by
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SortKey {
+    /// Largest first
+    #[default]
+    Size,
+    /// By path
+    Path,
+    /// By junk kind, largest first within each
+    Kind,
+    /// Least recently modified first
+    Age,
+    /// Most files first
+    Files,
+}
+
+/// The items of `result` on page `page` of `page_size` items, after filtering and sorting
+pub fn query(
+    result: &ScanResult,
+    filter: &ResultFilter,
+    sort: ResultSort,
+    page: usize,
+    page_size: usize,
+) -> ResultPageDto {
+    let text = filter
+        .text
+        .as_deref()
+        .map(str::trim)
+        .filter(|text| !text.is_empty())
+        .map(str::to_lowercase);
+    let mut matching = ScanResult::new();
+    matching.items = result
+        .items
+        .iter()
+        .filter(|item| filter.matches(item, text.as_deref()))
+        .cloned()
+        .collect();
+
+    // Sorting by kind keeps the size order within a kind
+    matching.sort_by_size();
+    match sort.key {
+        SortKey::Size => {}
+        SortKey::Path => matching.sort_by_path(),
+        SortKey::Kind => matching.sort_by_kind(),
+        SortKey::Age => matching.sort_by_age(),
+        SortKey::Files => matching.sort_by_file_count(),
+    }
+    if sort.reverse {
+        matching.items.reverse();
+    }
+
+    let page_size = page_size.max(1);
+    let match_size_bytes = matching.total_size_bytes();
+    ResultPageDto {
+        items: matching
+            .items
+            .iter()
+            .skip(page.saturating_mul(page_size))
+            .take(page_size)
+            .map(ScanItemDto::from)
+            .collect(),
+        page,
+        page_size,
+        page_count: matching.items.len().div_ceil(page_size),
+        match_count: matching.items.len(),
+        match_size_bytes,
+        match_size_display: format_size(match_size_bytes),
+    }
+}
+
+/// The id the GUI knows the kind of `item` by
+fn kind_id(item: &ScanItem) -> String {
+    format!("{:?}", item.kind).to_lowercase()
+}
+
+fn format_size(bytes: u64) -> String {
+    devjunk_core::format_size(bytes, devjunk_core::SizeUnits::Binary)
+}
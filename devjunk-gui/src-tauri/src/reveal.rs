@@ -0,0 +1,76 @@
+//! Showing a directory in the platform's file manager
+//!
+//! The file manager opens the folder holding the directory with the
+//! directory itself selected, where the platform supports it, so users can
+//! look inside before deleting it.
+
+use std::io;
+use std::path::Path;
+
+/// Open the folder containing `path` in Finder, Explorer or the file manager
+pub fn reveal(path: &Path) -> io::Result<()> {
+    platform::reveal(path)
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::io;
+    use std::path::Path;
+    use std::process::Command;
+
+    pub fn reveal(path: &Path) -> io::Result<()> {
+        Command::new("open").arg("-R").arg(path).spawn()?;
+        Ok(())
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::io;
+    use std::os::windows::process::CommandExt;
+    use std::path::Path;
+    use std::process::Command;
+
+    pub fn reveal(path: &Path) -> io::Result<()> {
+        // Explorer parses its own command line, and wants the path quoted after the comma
+        Command::new("explorer")
+            .raw_arg(format!("/select,\"{}\"", path.display()))
+            .spawn()?;
+        Ok(())
+    }
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+mod platform {
+    use std::io;
+    use std::path::Path;
+    use std::process::{Command, Stdio};
+    use tauri::Url;
+
+    pub fn reveal(path: &Path) -> io::Result<()> {
+        // File managers implementing the freedesktop FileManager1 interface
+        // select the item; the others can only open its parent
+        if let Ok(uri) = Url::from_file_path(path) {
+            let shown = Command::new("dbus-send")
+                .args([
+                    "--session",
+                    "--dest=org.freedesktop.FileManager1",
+                    "--type=method_call",
+                    "/org/freedesktop/FileManager1",
+                    "org.freedesktop.FileManager1.ShowItems",
+                ])
+                .arg(format!("array:string:{}", uri))
+                .arg("string:")
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status();
+            if shown.is_ok_and(|status| status.success()) {
+                return Ok(());
+            }
+        }
+
+        let parent = path.parent().unwrap_or(path);
+        Command::new("xdg-open").arg(parent).spawn()?;
+        Ok(())
+    }
+}
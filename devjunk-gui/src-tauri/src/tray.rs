@@ -0,0 +1,164 @@
+//! Tray icon and scans in the background
+//!
+//! While background scans are switched on, closing the window only hides it
+//! and the configured roots are scanned on the set interval. A scan that finds
+//! junk raises a notification, and its result becomes the last scan, so the
+//! window opened from the notification or the tray shows it ready to clean.
+
+use crate::commands::{add_to_history, load_config, ScanState};
+use crate::dto::ScanResultDto;
+use devjunk_core::{format_size, scan, HistoryRecord, ScanConfig, ScanConfigBuilder, SizeUnits};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use tauri::menu::{Menu, MenuItem};
+use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_notification::NotificationExt;
+
+/// Argument the login item starts the app with, to open in the tray
+pub const AUTOSTART_ARG: &str = "--autostart";
+
+/// What the background scan thread is told
+enum Message {
+    /// Scan on this interval from now on, or no longer when None
+    Every(Option<Duration>),
+    /// Scan right away
+    ScanNow,
+}
+
+/// Managed state for talking to the background scan thread
+pub struct BackgroundScans {
+    sender: Mutex<Sender<Message>>,
+    enabled: AtomicBool,
+}
+
+impl BackgroundScans {
+    /// Scan every `minutes` from now on, or stop scanning when None
+    pub fn set_interval(&self, minutes: Option<u64>) {
+        self.enabled.store(minutes.is_some(), Ordering::Relaxed);
+        self.send(Message::Every(minutes.map(every)));
+    }
+
+    /// Whether closing the window should keep the app running in the tray
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    fn send(&self, message: Message) {
+        let sender = self.sender.lock().unwrap_or_else(|e| e.into_inner());
+        let _ = sender.send(message);
+    }
+}
+
+/// Add the tray icon and start the background scan thread, scanning every `minutes`
+pub fn setup(app: &AppHandle, minutes: Option<u64>) -> tauri::Result<BackgroundScans> {
+    let open = MenuItem::with_id(app, "open", "Open DevJunk", true, None::<&str>)?;
+    let scan_now = MenuItem::with_id(app, "scan", "Scan now", true, None::<&str>)?;
+    let quit = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
+    let menu = Menu::with_items(app, &[&open, &scan_now, &quit])?;
+
+    let mut tray = TrayIconBuilder::with_id("main")
+        .tooltip("DevJunk")
+        .menu(&menu)
+        .show_menu_on_left_click(false)
+        .on_menu_event(|app, event| match event.id.as_ref() {
+            "open" => show_window(app),
+            "scan" => app.state::<BackgroundScans>().send(Message::ScanNow),
+            "quit" => app.exit(0),
+            _ => {}
+        })
+        .on_tray_icon_event(|tray, event| {
+            if let TrayIconEvent::Click {
+                button: MouseButton::Left,
+                button_state: MouseButtonState::Up,
+                ..
+            } = event
+            {
+                show_window(tray.app_handle());
+            }
+        });
+    if let Some(icon) = app.default_window_icon() {
+        tray = tray.icon(icon.clone());
+    }
+    tray.build(app)?;
+
+    // Clicking a notification brings up the results it announced
+    let handle = app.clone();
+    let _ = app.notification().on_action(move |_| show_window(&handle));
+
+    let (sender, receiver) = mpsc::channel();
+    let handle = app.clone();
+    thread::spawn(move || {
+        let mut interval = minutes.map(every);
+        loop {
+            let message = match interval {
+                Some(interval) => receiver.recv_timeout(interval),
+                None => receiver.recv().map_err(|_| RecvTimeoutError::Disconnected),
+            };
+            match message {
+                Ok(Message::Every(every)) => interval = every,
+                Ok(Message::ScanNow) | Err(RecvTimeoutError::Timeout) => scan_roots(&handle),
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+        }
+    });
+
+    Ok(BackgroundScans {
+        sender: Mutex::new(sender),
+        enabled: AtomicBool::new(minutes.is_some()),
+    })
+}
+
+/// Bring the window back from the tray
+pub fn show_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+fn every(minutes: u64) -> Duration {
+    Duration::from_secs(minutes.max(1) * 60)
+}
+
+/// Scan the configured roots, keep the result as the last scan and announce what it found
+fn scan_roots(app: &AppHandle) {
+    let Ok(config) = load_config() else {
+        return;
+    };
+    // Without roots there is nothing to pick for the user
+    if config.roots.is_empty() {
+        return;
+    }
+    let Ok(config) = ScanConfigBuilder::from(config.scan_config(ScanConfig::new(Vec::new())))
+        .with_git_status(config.gui.git_status)
+        .build()
+    else {
+        return;
+    };
+    let Ok(result) = scan(&config) else {
+        return;
+    };
+    add_to_history(HistoryRecord::from_scan(&result));
+
+    let scan_id = app.state::<ScanState>().cache(result.clone());
+    let _ = app.emit(
+        "background-scan-finished",
+        ScanResultDto::new(&result, scan_id),
+    );
+    if !result.items.is_empty() {
+        let _ = app
+            .notification()
+            .builder()
+            .title("DevJunk")
+            .body(format!(
+                "{} of junk found",
+                format_size(result.total_size_bytes(), SizeUnits::Binary)
+            ))
+            .show();
+    }
+}
@@ -0,0 +1,133 @@
+//! Nesting the results of a scan for the treemap
+//!
+//! The treemap draws each node as a rectangle sized by its junk, with its
+//! children inside. Nodes come in one of two layouts: projects holding kinds
+//! holding junk directories, or the directories from each scan root down to
+//! the junk, where a directory holding only one other is drawn together with it.
+
+use crate::dto::TreemapNodeDto;
+use devjunk_core::{group_by_project, ScanItem, ScanResult};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+/// How the nodes of the treemap nest
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TreemapLayout {
+    /// Projects, then kinds, then junk directories
+    #[default]
+    Project,
+    /// Scan roots, then the directories below them
+    Path,
+}
+
+/// The whole of `result` as one node, nested in `layout`
+///
+/// Grouping by project looks for git checkouts, so this reads the disk.
+pub fn treemap(result: &ScanResult, layout: TreemapLayout) -> TreemapNodeDto {
+    let children = match layout {
+        TreemapLayout::Project => group_by_project(result)
+            .into_iter()
+            .map(|(project, items)| project_node(&project, &items))
+            .collect(),
+        TreemapLayout::Path => result
+            .by_root()
+            .into_iter()
+            .map(|(root, items)| {
+                let mut dir = Dir::default();
+                for item in items {
+                    dir.insert(item, root);
+                }
+                dir.into_node(root.to_path_buf(), root.to_path_buf())
+            })
+            .collect(),
+    };
+    TreemapNodeDto::group(String::new(), None, None, children)
+}
+
+/// A project holding its junk per kind
+fn project_node(project: &Path, items: &[&ScanItem]) -> TreemapNodeDto {
+    let mut kinds: BTreeMap<&str, Vec<&ScanItem>> = BTreeMap::new();
+    for item in items {
+        kinds.entry(item.kind_name()).or_default().push(item);
+    }
+    let kinds = kinds
+        .into_iter()
+        .map(|(name, items)| {
+            let kind = format!("{:?}", items[0].kind).to_lowercase();
+            let items = items
+                .iter()
+                .map(|item| {
+                    // Within a project, the path tells apart directories of the same kind
+                    let name = item.path.strip_prefix(project).unwrap_or(&item.path);
+                    TreemapNodeDto::item(item, name.display().to_string())
+                })
+                .collect();
+            TreemapNodeDto::group(name.to_string(), None, Some(kind), items)
+        })
+        .collect();
+    TreemapNodeDto::group(
+        label(project),
+        Some(project.display().to_string()),
+        None,
+        kinds,
+    )
+}
+
+/// A directory on the way from a scan root to junk
+#[derive(Default)]
+struct Dir<'a> {
+    dirs: BTreeMap<OsString, Dir<'a>>,
+    items: Vec<&'a ScanItem>,
+}
+
+impl<'a> Dir<'a> {
+    /// Add `item` below this directory, which is `root`
+    fn insert(&mut self, item: &'a ScanItem, root: &Path) {
+        let relative = item.path.strip_prefix(root).unwrap_or(&item.path);
+        let mut dir = self;
+        if let Some(parent) = relative.parent() {
+            for component in parent.components() {
+                dir = dir
+                    .dirs
+                    .entry(component.as_os_str().to_os_string())
+                    .or_default();
+            }
+        }
+        dir.items.push(item);
+    }
+
+    /// The node of this directory, at `path` and labelled `name`
+    fn into_node(mut self, name: PathBuf, path: PathBuf) -> TreemapNodeDto {
+        if self.items.is_empty() && self.dirs.len() == 1 {
+            if let Some((child, dir)) = self.dirs.pop_first() {
+                return dir.into_node(name.join(&child), path.join(&child));
+            }
+        }
+
+        let dirs = self
+            .dirs
+            .into_iter()
+            .map(|(child, dir)| dir.into_node(PathBuf::from(&child), path.join(&child)));
+        let items = self
+            .items
+            .into_iter()
+            .map(|item| TreemapNodeDto::item(item, label(&item.path)));
+        TreemapNodeDto::group(
+            name.display().to_string(),
+            Some(path.display().to_string()),
+            None,
+            dirs.chain(items).collect(),
+        )
+    }
+}
+
+/// Last component of `path`, or all of it when it has none
+fn label(path: &Path) -> String {
+    path.file_name().map_or_else(
+        || path.display().to_string(),
+        |name| name.to_string_lossy().into_owned(),
+    )
+}
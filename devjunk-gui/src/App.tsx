@@ -1,8 +1,10 @@
+import { useEffect } from "react";
 import { useTranslation } from "react-i18next";
 import { PathInput } from "./components/PathInput";
 import { ScanTable } from "./components/ScanTable";
 import { ActionBar } from "./components/ActionBar";
 import { useScanStore } from "./store/scanStore";
+import { formatDuration, formatSize } from "./utils/format";
 import "./App.css";
 
 const SUPPORTED_LANGUAGES = [
@@ -16,12 +18,44 @@ const SUPPORTED_LANGUAGES = [
  * Layout:
  * - Header: App title and language selector
  * - Top: Path input section
- * - Middle: Scan results table (with progress overlay during scan)
+ * - Middle: Scan results table (with progress overlay during scan or clean)
  * - Bottom: Summary and action buttons
  */
 function App() {
   const { t, i18n } = useTranslation();
-  const { error, clearError, isScanning, scanProgress } = useScanStore();
+  const {
+    error,
+    clearError,
+    isScanning,
+    isPaused,
+    scanProgress,
+    scanEstimate,
+    pauseScan,
+    resumeScan,
+    cancelScan,
+    isCleaning,
+    isCancelling,
+    cleanProgress,
+    diskFree,
+    cancelClean,
+    loadSettings,
+    loadWorkspaces,
+    loadSuggestedRoots,
+    listenForBackgroundScans,
+  } = useScanStore();
+
+  useEffect(() => {
+    loadSettings();
+    loadWorkspaces();
+    loadSuggestedRoots();
+  }, [loadSettings, loadWorkspaces, loadSuggestedRoots]);
+
+  useEffect(() => {
+    const unlisten = listenForBackgroundScans();
+    return () => {
+      unlisten.then((stop) => stop());
+    };
+  }, [listenForBackgroundScans]);
 
   const handleLanguageChange = (e: React.ChangeEvent<HTMLSelectElement>) => {
     i18n.changeLanguage(e.target.value);
@@ -64,15 +98,88 @@ function App() {
       {isScanning && scanProgress && (
         <div className="scan-progress-bar">
           <div className="progress-info">
-            <span className="progress-label">{t("scanProgress.scanning")}:</span>
-            <span className="progress-path" title={scanProgress.currentPath}>
-              {scanProgress.currentPath}
+            <span className="progress-label">
+              {isPaused ? t("scanProgress.paused") : t("scanProgress.scanning")}:
+            </span>
+            <span
+              className="progress-path"
+              title={scanProgress.sizingPath ?? scanProgress.currentPath}
+            >
+              {scanProgress.sizingPath ?? scanProgress.currentPath}
             </span>
+            <button
+              className="btn-secondary progress-toggle"
+              onClick={() => (isPaused ? resumeScan() : pauseScan())}
+            >
+              {isPaused ? t("scanProgress.resume") : t("scanProgress.pause")}
+            </button>
+            <button className="btn-secondary progress-toggle" onClick={cancelScan}>
+              {t("scanProgress.cancel")}
+            </button>
           </div>
           <div className="progress-stats">
             <span>{scanProgress.directoriesScanned.toLocaleString()} {t("scanProgress.directoriesScanned")}</span>
             <span className="separator">•</span>
             <span>{scanProgress.itemsFound} {t("scanProgress.itemsFound")}</span>
+            <span className="separator">•</span>
+            <span>{formatSize(scanProgress.bytesFound)}</span>
+            <span className="separator">•</span>
+            <span>
+              {formatDuration(scanProgress.elapsedMs)} {t("scanProgress.elapsed")}
+            </span>
+            {scanProgress.etaMs !== null && (
+              <>
+                <span className="separator">•</span>
+                <span>
+                  ~{formatDuration(scanProgress.etaMs)} {t("scanProgress.remaining")}
+                </span>
+              </>
+            )}
+          </div>
+          {scanEstimate && scanEstimate.itemCount > 0 && (
+            <div className="progress-estimate">
+              {scanEstimate.knownBytes > 0
+                ? t("scanProgress.estimate", {
+                    count: scanEstimate.itemCount,
+                    size: scanEstimate.knownDisplay,
+                  })
+                : t("scanProgress.estimateCount", { count: scanEstimate.itemCount })}
+            </div>
+          )}
+        </div>
+      )}
+
+      {isCleaning && cleanProgress && (
+        <div className="scan-progress-bar">
+          <div className="progress-info">
+            <span className="progress-label">{t("cleanProgress.cleaning")}:</span>
+            <span className="progress-path" title={cleanProgress.currentPath}>
+              {cleanProgress.currentPath}
+            </span>
+            <button
+              className="btn-secondary progress-toggle"
+              onClick={cancelClean}
+              disabled={isCancelling}
+            >
+              {isCancelling ? t("cleanProgress.cancelling") : t("cleanProgress.cancel")}
+            </button>
+          </div>
+          <div className="progress-stats">
+            <span>
+              {cleanProgress.pathsDone} / {cleanProgress.pathsTotal} {t("cleanProgress.directories")}
+            </span>
+            <span className="separator">•</span>
+            <span>{cleanProgress.filesRemoved.toLocaleString()} {t("cleanProgress.filesRemoved")}</span>
+            <span className="separator">•</span>
+            <span>{formatSize(cleanProgress.bytesFreed)} {t("cleanProgress.freed")}</span>
+            {diskFree && (
+              <>
+                <span className="separator">•</span>
+                <span title={t("cleanProgress.diskFree", { size: diskFree.freeDisplay })}>
+                  {t("cleanProgress.reclaimed", { size: diskFree.reclaimedDisplay })}
+                </span>
+              </>
+            )}
           </div>
         </div>
       )}
@@ -1,14 +1,19 @@
+import { useState } from "react";
 import { useTranslation } from "react-i18next";
 import { useScanStore } from "../store/scanStore";
+import { formatSize } from "../utils/format";
+import type { DeleteMode, ExportFormat } from "../types";
 
 /**
  * ActionBar component for displaying summary and action buttons
  *
  * Features:
  * - Shows selected items count and total size
+ * - Tells what brings back each kind of directory selected
  * - Dry-run button for preview
- * - Delete button for actual deletion
- * - Shows clean results
+ * - Delete button for actual deletion, in the delete mode picked
+ * - Shows clean results, with an undo for trashed or archived directories
+ * - Exports the scan results to share them
  */
 export function ActionBar() {
   const { t } = useTranslation();
@@ -19,7 +24,23 @@ export function ActionBar() {
     isCleaning,
     cleanResult,
     clearCleanResult,
+    undoResult,
+    undoLastClean,
+    settings,
+    exportResults,
   } = useScanStore();
+  const [exportedTo, setExportedTo] = useState<string | null>(null);
+
+  const handleExport = async (format: ExportFormat) => {
+    setExportedTo(await exportResults(format));
+  };
+  // Until one is picked, clean in the configured mode
+  const [pickedMode, setPickedMode] = useState<DeleteMode | null>(null);
+  const deleteMode = pickedMode ?? settings?.deleteMode ?? "permanent";
+  // The configured archive directory is the only one a clean can archive into
+  const modes: DeleteMode[] = settings?.archiveDir
+    ? ["permanent", "trash", "archive", "renameDefer"]
+    : ["permanent", "trash", "renameDefer"];
 
   // Show cleanResult even if scanResult is empty/null
   if ((!scanResult || scanResult.items.length === 0) && !cleanResult) {
@@ -38,6 +59,10 @@ export function ActionBar() {
     (sum, item) => sum + item.fileCount,
     0
   );
+  // One note per kind, so deleting makes clear what rebuilding costs
+  const rebuildNotes = [
+    ...new Map(selectedItems.map((item) => [item.kindDisplay, item])).values(),
+  ];
 
   return (
     <div className="action-bar">
@@ -67,21 +92,62 @@ export function ActionBar() {
           </div>
 
           <div className="action-buttons">
+            <select
+              value=""
+              onChange={(e) => handleExport(e.target.value as ExportFormat)}
+              aria-label={t("actionBar.export")}
+            >
+              <option value="" disabled>
+                {t("actionBar.export")}
+              </option>
+              <option value="json">JSON</option>
+              <option value="csv">CSV</option>
+              <option value="markdown">Markdown</option>
+            </select>
             <button
-              onClick={() => cleanSelected(true)}
+              onClick={() => cleanSelected(true, deleteMode)}
               disabled={isCleaning || selectedPaths.size === 0}
               className="btn-secondary"
             >
               {isCleaning ? t("actionBar.processing") : t("actionBar.dryRun")}
             </button>
+            <select
+              value={deleteMode}
+              onChange={(e) => setPickedMode(e.target.value as DeleteMode)}
+              disabled={isCleaning}
+              aria-label={t("actionBar.deleteMode")}
+            >
+              {modes.map((mode) => (
+                <option key={mode} value={mode}>
+                  {t(`actionBar.modes.${mode}`)}
+                </option>
+              ))}
+            </select>
             <button
-              onClick={() => cleanSelected(false)}
+              onClick={() => cleanSelected(false, deleteMode)}
               disabled={isCleaning || selectedPaths.size === 0}
               className="btn-danger"
             >
               {isCleaning ? t("actionBar.deleting") : t("actionBar.deleteSelected")}
             </button>
           </div>
+          {rebuildNotes.length > 0 && (
+            <ul className="rebuild-notes">
+              {rebuildNotes.map((item) => (
+                <li key={item.kindDisplay}>
+                  <strong>{item.kindDisplay}</strong>: {item.rebuildHint}
+                  {item.safety === "caution" && (
+                    <span className="rebuild-caution"> ⚠️ {t("actionBar.mayLoseFiles")}</span>
+                  )}
+                </li>
+              ))}
+            </ul>
+          )}
+          {exportedTo && (
+            <div className="export-notice" title={exportedTo}>
+              {t("actionBar.exported", { path: exportedTo })}
+            </div>
+          )}
         </>
       )}
 
@@ -101,14 +167,67 @@ export function ActionBar() {
           </div>
           <div className="clean-result-body">
             <p>
-              {cleanResult.wasDryRun ? t("actionBar.wouldDelete") : t("actionBar.deleted")}:{" "}
+              {cleanResult.wasDryRun
+                ? t(`actionBar.wouldClean.${cleanResult.deleteMode}`)
+                : t(`actionBar.cleaned.${cleanResult.deleteMode}`, {
+                    dir: cleanResult.archiveDir,
+                  })}
+              :{" "}
               <strong>{cleanResult.deletedCount}</strong> {t("actionBar.directories")} (
               {cleanResult.bytesFreedDisplay})
             </p>
+            {cleanResult.measuredBytesFreedDisplay && (
+              <p>
+                {t("actionBar.freeSpace")}: <strong>{cleanResult.measuredBytesFreedDisplay}</strong>
+              </p>
+            )}
+            {cleanResult.undoable && (
+              <button className="btn-secondary undo-button" onClick={undoLastClean}>
+                {t("actionBar.undo")}
+              </button>
+            )}
+            {undoResult && (
+              <p>
+                ♻️ {t("actionBar.restored")}: <strong>{undoResult.restoredCount}</strong>{" "}
+                {t("actionBar.directories")}
+              </p>
+            )}
+            {undoResult && undoResult.failedCount > 0 && (
+              <div className="clean-failures">
+                <p>
+                  ❌ {t("actionBar.restoreFailed")}: <strong>{undoResult.failedCount}</strong>{" "}
+                  {t("actionBar.directories")}
+                </p>
+                <ul>
+                  {undoResult.failed.map((f) => (
+                    <li key={f.path}>
+                      {f.path}: {f.error}
+                    </li>
+                  ))}
+                </ul>
+              </div>
+            )}
+            {cleanResult.cancelled && <p>⏹️ {t("actionBar.cancelled")}</p>}
+            {cleanResult.skippedCount > 0 && (
+              <div className="clean-failures">
+                <p>
+                  ⚠️ {t("actionBar.skipped")}: <strong>{cleanResult.skippedCount}</strong>{" "}
+                  {t("actionBar.directories")}
+                </p>
+                <ul>
+                  {cleanResult.skipped.map((s) => (
+                    <li key={s.path}>
+                      {s.path}: {s.error}
+                    </li>
+                  ))}
+                </ul>
+              </div>
+            )}
             {cleanResult.failedCount > 0 && (
               <div className="clean-failures">
                 <p>
-                  ❌ {t("actionBar.failed")}: <strong>{cleanResult.failedCount}</strong>{" "}
+                  ❌ {cleanResult.wasDryRun ? t("actionBar.wouldFail") : t("actionBar.failed")}:{" "}
+                  <strong>{cleanResult.failedCount}</strong>{" "}
                   {t("actionBar.directories")}
                 </p>
                 <ul>
@@ -126,19 +245,3 @@ export function ActionBar() {
     </div>
   );
 }
-
-function formatSize(bytes: number): string {
-  const KB = 1024;
-  const MB = KB * 1024;
-  const GB = MB * 1024;
-
-  if (bytes >= GB) {
-    return `${(bytes / GB).toFixed(2)} GB`;
-  } else if (bytes >= MB) {
-    return `${(bytes / MB).toFixed(2)} MB`;
-  } else if (bytes >= KB) {
-    return `${(bytes / KB).toFixed(2)} KB`;
-  } else {
-    return `${bytes} B`;
-  }
-}
@@ -10,22 +10,46 @@ import { useScanStore } from "../store/scanStore";
  * - Text input for path entry
  * - Native folder picker dialog
  * - List of added paths with remove buttons
+ * - Saved workspaces, to scan a usual set of paths in one click
+ * - Suggested project folders while there are no paths yet
  * - Scan button to start scanning
  */
 export function PathInput() {
   const { t } = useTranslation();
   const [inputValue, setInputValue] = useState("");
-  const { paths, addPath, removePath, clearPaths, startScan, isScanning } = useScanStore();
+  const [workspaceName, setWorkspaceName] = useState("");
+  const {
+    paths,
+    addPaths,
+    removePath,
+    clearPaths,
+    startScan,
+    isScanning,
+    workspaces,
+    saveWorkspace,
+    deleteWorkspace,
+    openWorkspace,
+    suggestedRoots,
+  } = useScanStore();
 
   const handleSubmit = (e: FormEvent) => {
     e.preventDefault();
     const trimmed = inputValue.trim();
     if (trimmed) {
-      addPath(trimmed);
+      addPaths([trimmed]);
       setInputValue("");
     }
   };
 
+  const handleSaveWorkspace = (e: FormEvent) => {
+    e.preventDefault();
+    const trimmed = workspaceName.trim();
+    if (trimmed) {
+      saveWorkspace(trimmed);
+      setWorkspaceName("");
+    }
+  };
+
   const handleBrowse = async () => {
     try {
       const selected = await open({
@@ -36,7 +60,7 @@ export function PathInput() {
 
       if (selected) {
         const selectedPaths = Array.isArray(selected) ? selected : [selected];
-        selectedPaths.forEach((p) => addPath(p));
+        addPaths(selectedPaths);
       }
     } catch {
       // User cancelled or error occurred
@@ -96,6 +120,65 @@ export function PathInput() {
               </li>
             ))}
           </ul>
+          <form onSubmit={handleSaveWorkspace} className="workspace-form">
+            <input
+              type="text"
+              value={workspaceName}
+              onChange={(e) => setWorkspaceName(e.target.value)}
+              placeholder={t("pathInput.workspaceName")}
+              disabled={isScanning}
+              className="path-text-input"
+            />
+            <button type="submit" disabled={isScanning || !workspaceName.trim()}>
+              {t("pathInput.saveWorkspace")}
+            </button>
+          </form>
+        </div>
+      )}
+
+      {paths.length === 0 && suggestedRoots.length > 0 && (
+        <div className="workspace-list suggested-roots">
+          <span>{t("pathInput.suggested")}:</span>
+          {suggestedRoots.map((root) => (
+            <button
+              key={root.path}
+              onClick={() => addPaths([root.path])}
+              disabled={isScanning}
+              title={root.path}
+            >
+              {root.path}
+              {root.repos > 0 && (
+                <span className="suggested-repos">
+                  {t("pathInput.suggestedRepos", { count: root.repos })}
+                </span>
+              )}
+            </button>
+          ))}
+        </div>
+      )}
+
+      {workspaces.length > 0 && (
+        <div className="workspace-list">
+          <span>{t("pathInput.workspaces")}:</span>
+          {workspaces.map((workspace) => (
+            <span key={workspace.name} className="workspace-chip">
+              <button
+                onClick={() => openWorkspace(workspace.name)}
+                disabled={isScanning}
+                title={workspace.paths.join("\n")}
+              >
+                {workspace.name}
+              </button>
+              <button
+                onClick={() => deleteWorkspace(workspace.name)}
+                disabled={isScanning}
+                className="remove-btn"
+                aria-label={t("pathInput.deleteWorkspace", { name: workspace.name })}
+              >
+                ×
+              </button>
+            </span>
+          ))}
         </div>
       )}
 
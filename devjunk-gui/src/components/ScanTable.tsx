@@ -1,3 +1,4 @@
+import { Fragment } from "react";
 import { useTranslation } from "react-i18next";
 import { useScanStore } from "../store/scanStore";
 
@@ -9,6 +10,10 @@ import { useScanStore } from "../store/scanStore";
  * - Checkboxes for selection
  * - Sortable columns (TODO: implement sorting)
  * - Human-readable sizes
+ * - Show in the file manager, to check a directory before deleting it
+ * - Ignore a directory in every scan from now on
+ * - Expandable breakdown of the largest contents of a directory
+ * - Guidance to System Settings when macOS kept the scan out of folders
  */
 export function ScanTable() {
   const { t } = useTranslation();
@@ -18,6 +23,11 @@ export function ScanTable() {
     toggleSelection,
     selectAll,
     deselectAll,
+    revealPath,
+    ignorePath,
+    expandedPath,
+    breakdown,
+    toggleBreakdown,
     isScanning,
   } = useScanStore();
 
@@ -41,20 +51,37 @@ export function ScanTable() {
   if (scanResult.items.length === 0) {
     return (
       <div className="scan-table-empty">
+        <DiskAccessNotice />
         <p>{t("scanTable.noJunk")}</p>
       </div>
     );
   }
 
-  const allSelected = scanResult.items.every((item) =>
-    selectedPaths.has(item.path)
-  );
+  // Items the user can't delete are never selectable
+  const selectableItems = scanResult.items.filter((item) => item.deletable);
+  const allSelected =
+    selectableItems.length > 0 &&
+    selectableItems.every((item) => selectedPaths.has(item.path));
   const someSelected = scanResult.items.some((item) =>
     selectedPaths.has(item.path)
   );
 
   return (
     <div className="scan-table-container">
+      <DiskAccessNotice />
+      {scanResult.skippedMounts.length > 0 && (
+        <div className="scan-table-notice" title={scanResult.skippedMounts.join("\n")}>
+          {t("scanTable.skippedMounts", { count: scanResult.skippedMounts.length })}
+        </div>
+      )}
+      {scanResult.errors.length > 0 && (
+        <div
+          className="scan-table-notice"
+          title={scanResult.errors.map((e) => `${e.path}: ${e.cause}`).join("\n")}
+        >
+          {t("scanTable.unreadablePaths", { count: scanResult.errors.length })}
+        </div>
+      )}
       <table className="scan-table">
         <thead>
           <tr>
@@ -77,33 +104,143 @@ export function ScanTable() {
         </thead>
         <tbody>
           {scanResult.items.map((item) => (
-            <tr
-              key={item.path}
-              className={selectedPaths.has(item.path) ? "selected" : ""}
-              onClick={() => toggleSelection(item.path)}
-            >
-              <td className="checkbox-col">
-                <input
-                  type="checkbox"
-                  checked={selectedPaths.has(item.path)}
-                  onChange={() => toggleSelection(item.path)}
-                  onClick={(e) => e.stopPropagation()}
-                />
-              </td>
-              <td className="path-col" title={item.path}>
-                {item.path}
-              </td>
-              <td className="kind-col">
-                <span className={`kind-badge kind-${item.kind}`}>
-                  {item.kindDisplay}
-                </span>
-              </td>
-              <td className="size-col">{item.sizeDisplay}</td>
-              <td className="count-col">{item.fileCount.toLocaleString()}</td>
-            </tr>
+            <Fragment key={item.path}>
+              <tr
+                className={
+                  !item.deletable
+                    ? "undeletable"
+                    : selectedPaths.has(item.path)
+                      ? "selected"
+                      : ""
+                }
+                title={
+                  item.deletable
+                    ? undefined
+                    : t("scanTable.undeletable", { reason: item.undeletableReason })
+                }
+                onClick={() => item.deletable && toggleSelection(item.path)}
+              >
+                <td className="checkbox-col">
+                  <input
+                    type="checkbox"
+                    checked={selectedPaths.has(item.path)}
+                    disabled={!item.deletable}
+                    onChange={() => toggleSelection(item.path)}
+                    onClick={(e) => e.stopPropagation()}
+                  />
+                </td>
+                <td className="path-col" title={item.path}>
+                  <button
+                    className="expand-button"
+                    title={t("scanTable.breakdown")}
+                    aria-label={t("scanTable.breakdown")}
+                    aria-expanded={expandedPath === item.path}
+                    onClick={(e) => {
+                      e.stopPropagation();
+                      toggleBreakdown(item.path);
+                    }}
+                  >
+                    {expandedPath === item.path ? "▾" : "▸"}
+                  </button>
+                  <button
+                    className="reveal-button"
+                    title={t("scanTable.reveal")}
+                    aria-label={t("scanTable.reveal")}
+                    onClick={(e) => {
+                      e.stopPropagation();
+                      revealPath(item.path);
+                    }}
+                  >
+                    📂
+                  </button>
+                  <button
+                    className="reveal-button"
+                    title={t("scanTable.ignore")}
+                    aria-label={t("scanTable.ignore")}
+                    onClick={(e) => {
+                      e.stopPropagation();
+                      ignorePath(item.path);
+                    }}
+                  >
+                    🚫
+                  </button>
+                  {item.path}
+                </td>
+                <td className="kind-col">
+                  <span className={`kind-badge kind-${item.kind}`}>
+                    {item.kindDisplay}
+                  </span>
+                  {item.isActive && (
+                    <span className="active-badge" title={t("scanTable.activeHint")}>
+                      {t("scanTable.active")}
+                    </span>
+                  )}
+                  {item.repoDirty && (
+                    <span className="active-badge" title={t("scanTable.repoDirtyHint")}>
+                      {t("scanTable.repoDirty")}
+                    </span>
+                  )}
+                </td>
+                <td className="size-col">{item.sizeDisplay}</td>
+                <td className="count-col">{item.fileCount.toLocaleString()}</td>
+              </tr>
+              {expandedPath === item.path && (
+                <tr className="breakdown-row">
+                  <td></td>
+                  <td colSpan={4}>
+                    {breakdown ? (
+                      <ul className="breakdown-list">
+                        {breakdown.entries.map((entry) => (
+                          <li key={entry.path}>
+                            <span className="breakdown-name" title={entry.path}>
+                              {entry.name}
+                            </span>
+                            <span className="breakdown-size">{entry.sizeDisplay}</span>
+                            <span className="breakdown-files">
+                              {t("scanTable.breakdownFiles", { count: entry.fileCount })}
+                            </span>
+                          </li>
+                        ))}
+                        {breakdown.otherCount > 0 && (
+                          <li className="breakdown-other">
+                            {t("scanTable.breakdownOther", {
+                              count: breakdown.otherCount,
+                              size: breakdown.otherSizeDisplay,
+                            })}
+                          </li>
+                        )}
+                      </ul>
+                    ) : (
+                      <span className="breakdown-loading">
+                        {t("scanTable.breakdownLoading")}
+                      </span>
+                    )}
+                  </td>
+                </tr>
+              )}
+            </Fragment>
           ))}
         </tbody>
       </table>
     </div>
   );
 }
+
+/** Tells the user to grant Full Disk Access when macOS denied the scan folders */
+function DiskAccessNotice() {
+  const { t } = useTranslation();
+  const { diskAccess, openDiskAccessSettings } = useScanStore();
+
+  if (!diskAccess?.restricted) {
+    return null;
+  }
+
+  return (
+    <div className="scan-table-notice" title={diskAccess.deniedPaths.join("\n")}>
+      {t("scanTable.diskAccess", { count: diskAccess.deniedPaths.length })}{" "}
+      <button className="btn-secondary" onClick={openDiskAccessSettings}>
+        {t("scanTable.openDiskAccessSettings")}
+      </button>
+    </div>
+  );
+}
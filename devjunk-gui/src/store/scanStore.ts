@@ -12,17 +12,63 @@
 import { create } from "zustand";
 import { invoke } from "@tauri-apps/api/core";
 import { listen, type UnlistenFn } from "@tauri-apps/api/event";
-import type { AppStore, ScanResult, CleanResult, ScanProgress } from "../types";
+import { formatSize } from "../utils/format";
+import type {
+  AppStore,
+  ScanResult,
+  CleanHistory,
+  CleanResult,
+  CustomPattern,
+  ScanProgress,
+  CleanProgress,
+  DeleteMode,
+  DiskAccess,
+  DiskFree,
+  ExportFormat,
+  ItemBreakdown,
+  NewSchedule,
+  PathCheck,
+  ResultFilter,
+  ResultPage,
+  ResultSort,
+  ScanEstimate,
+  Schedule,
+  Settings,
+  SuggestedRoot,
+  TreemapLayout,
+  TreemapNode,
+  UndoResult,
+  Volume,
+  Workspace,
+} from "../types";
 
 const initialState = {
   paths: [] as string[],
+  settings: null as Settings | null,
+  workspaces: [] as Workspace[],
+  customPatterns: [] as CustomPattern[],
+  ignoredPaths: [] as string[],
+  autostart: null as boolean | null,
+  schedules: [] as Schedule[],
+  diskAccess: null as DiskAccess | null,
+  volumes: [] as Volume[],
+  suggestedRoots: [] as SuggestedRoot[],
+  cleanHistory: null as CleanHistory | null,
   scanResult: null as ScanResult | null,
   scanProgress: null as ScanProgress | null,
+  scanEstimate: null as ScanEstimate | null,
   selectedPaths: new Set<string>(),
+  expandedPath: null as string | null,
+  breakdown: null as ItemBreakdown | null,
   isScanning: false,
+  isPaused: false,
   isCleaning: false,
+  isCancelling: false,
+  cleanProgress: null as CleanProgress | null,
+  diskFree: null as DiskFree | null,
   error: null as string | null,
   cleanResult: null as CleanResult | null,
+  undoResult: null as UndoResult | null,
 };
 
 export const useScanStore = create<AppStore>((set, get) => ({
@@ -35,6 +81,38 @@ export const useScanStore = create<AppStore>((set, get) => ({
     }
   },
 
+  addPaths: async (newPaths: string[]) => {
+    const { paths } = get();
+    let checks: PathCheck[];
+    try {
+      // One round trip for the lot, checked against the paths already added
+      checks = await invoke<PathCheck[]>("validate_paths", {
+        paths: [...paths, ...newPaths],
+      });
+    } catch (e) {
+      set({ error: typeof e === "string" ? e : "Failed to check the paths" });
+      return;
+    }
+
+    const added = checks.slice(paths.length);
+    const rejected = added
+      .filter((check) => check.status !== "ok")
+      .map((check) => {
+        switch (check.status) {
+          case "notFound":
+            return `${check.path} (does not exist)`;
+          case "notADirectory":
+            return `${check.path} (not a directory)`;
+          default:
+            return `${check.path} (already covered by ${check.coveredBy})`;
+        }
+      });
+    set({
+      paths: [...paths, ...added.filter((check) => check.status === "ok").map((check) => check.path)],
+      error: rejected.length > 0 ? `Not added: ${rejected.join(", ")}` : null,
+    });
+  },
+
   removePath: (path: string) => {
     const { paths, selectedPaths } = get();
     const newSelected = new Set(selectedPaths);
@@ -54,6 +132,212 @@ export const useScanStore = create<AppStore>((set, get) => ({
     set({ paths: [], scanResult: null, selectedPaths: new Set() });
   },
 
+  loadSettings: async () => {
+    try {
+      const settings = await invoke<Settings>("get_settings");
+      const { paths } = get();
+      set({ settings, ...(paths.length === 0 ? { paths: settings.roots } : {}) });
+    } catch (e) {
+      set({ error: typeof e === "string" ? e : "Failed to load settings" });
+    }
+  },
+
+  saveSettings: async (settings: Settings) => {
+    try {
+      await invoke("set_settings", { settings });
+      set({ settings });
+    } catch (e) {
+      set({ error: typeof e === "string" ? e : "Failed to save settings" });
+    }
+  },
+
+  setBackgroundScan: async (minutes: number | null) => {
+    try {
+      await invoke("set_background_scan", { minutes });
+    } catch (e) {
+      set({ error: typeof e === "string" ? e : "Failed to schedule background scans" });
+    }
+  },
+
+  loadAutostart: async () => {
+    try {
+      const autostart = await invoke<boolean>("get_autostart");
+      set({ autostart });
+    } catch (e) {
+      set({ error: typeof e === "string" ? e : "Failed to check autostart" });
+    }
+  },
+
+  setAutostart: async (enabled: boolean) => {
+    try {
+      await invoke("set_autostart", { enabled });
+      set({ autostart: enabled });
+    } catch (e) {
+      set({ error: typeof e === "string" ? e : "Failed to change autostart" });
+    }
+  },
+
+  loadCustomPatterns: async () => {
+    try {
+      const customPatterns = await invoke<CustomPattern[]>("get_custom_patterns");
+      set({ customPatterns });
+    } catch (e) {
+      set({ error: typeof e === "string" ? e : "Failed to load custom patterns" });
+    }
+  },
+
+  addCustomPattern: async (name: string, glob: string) => {
+    try {
+      const customPatterns = await invoke<CustomPattern[]>("add_custom_pattern", { name, glob });
+      set({ customPatterns });
+    } catch (e) {
+      set({ error: typeof e === "string" ? e : "Failed to save the custom pattern" });
+    }
+  },
+
+  removeCustomPattern: async (name: string) => {
+    try {
+      const customPatterns = await invoke<CustomPattern[]>("remove_custom_pattern", { name });
+      set({ customPatterns });
+    } catch (e) {
+      set({ error: typeof e === "string" ? e : "Failed to delete the custom pattern" });
+    }
+  },
+
+  loadIgnoredPaths: async () => {
+    try {
+      const ignoredPaths = await invoke<string[]>("get_ignored_paths");
+      set({ ignoredPaths });
+    } catch (e) {
+      set({ error: typeof e === "string" ? e : "Failed to load the ignored directories" });
+    }
+  },
+
+  ignorePath: async (path: string) => {
+    try {
+      const ignoredPaths = await invoke<string[]>("add_ignored_path", { path });
+      const { scanResult, selectedPaths, expandedPath } = get();
+      const ignored = scanResult?.items.find((item) => item.path === path);
+      if (!scanResult || !ignored) {
+        set({ ignoredPaths });
+        return;
+      }
+      // The next scan leaves it out anyway, so take it out of these results now
+      const totalSizeBytes = scanResult.totalSizeBytes - ignored.sizeBytes;
+      const selected = new Set(selectedPaths);
+      selected.delete(path);
+      set({
+        ignoredPaths,
+        scanResult: {
+          ...scanResult,
+          items: scanResult.items.filter((item) => item !== ignored),
+          itemCount: scanResult.itemCount - 1,
+          totalSizeBytes,
+          totalSizeDisplay: formatSize(totalSizeBytes),
+          totalFileCount: scanResult.totalFileCount - ignored.fileCount,
+        },
+        selectedPaths: selected,
+        ...(expandedPath === path ? { expandedPath: null, breakdown: null } : {}),
+      });
+    } catch (e) {
+      set({ error: typeof e === "string" ? e : "Failed to ignore the directory" });
+    }
+  },
+
+  unignorePath: async (path: string) => {
+    try {
+      const ignoredPaths = await invoke<string[]>("remove_ignored_path", { path });
+      set({ ignoredPaths });
+    } catch (e) {
+      set({ error: typeof e === "string" ? e : "Failed to stop ignoring the directory" });
+    }
+  },
+
+  loadWorkspaces: async () => {
+    try {
+      const workspaces = await invoke<Workspace[]>("list_workspaces");
+      set({ workspaces });
+    } catch (e) {
+      set({ error: typeof e === "string" ? e : "Failed to load workspaces" });
+    }
+  },
+
+  saveWorkspace: async (name: string) => {
+    try {
+      const workspaces = await invoke<Workspace[]>("create_workspace", {
+        name,
+        paths: get().paths,
+      });
+      set({ workspaces });
+    } catch (e) {
+      set({ error: typeof e === "string" ? e : "Failed to save the workspace" });
+    }
+  },
+
+  deleteWorkspace: async (name: string) => {
+    try {
+      const workspaces = await invoke<Workspace[]>("delete_workspace", { name });
+      set({ workspaces });
+    } catch (e) {
+      set({ error: typeof e === "string" ? e : "Failed to delete the workspace" });
+    }
+  },
+
+  openWorkspace: async (name: string) => {
+    const workspace = get().workspaces.find((w) => w.name === name);
+    if (!workspace) {
+      return;
+    }
+    set({ paths: workspace.paths, error: null });
+    await get().startScan();
+  },
+
+  loadSchedules: async () => {
+    try {
+      const schedules = await invoke<Schedule[]>("list_schedules");
+      set({ schedules });
+    } catch (e) {
+      set({ error: typeof e === "string" ? e : "Failed to load schedules" });
+    }
+  },
+
+  createSchedule: async (schedule: NewSchedule) => {
+    try {
+      const schedules = await invoke<Schedule[]>("create_schedule", { schedule });
+      set({ schedules });
+    } catch (e) {
+      set({ error: typeof e === "string" ? e : "Failed to schedule the clean" });
+    }
+  },
+
+  deleteSchedule: async (name: string) => {
+    try {
+      const schedules = await invoke<Schedule[]>("delete_schedule", { name });
+      set({ schedules });
+    } catch (e) {
+      set({ error: typeof e === "string" ? e : "Failed to remove the schedule" });
+    }
+  },
+
+  listenForBackgroundScans: async () => {
+    // A scan that finished while the window was closed is the last one
+    const last = await invoke<ScanResult | null>("get_last_scan").catch(() => null);
+    if (last && !get().scanResult) {
+      set({ scanResult: last, selectedPaths: new Set(), expandedPath: null, breakdown: null });
+    }
+    return listen<ScanResult>("background-scan-finished", (event) => {
+      // Leave the results alone while the user is working with them
+      if (!get().isScanning && !get().isCleaning) {
+        set({
+          scanResult: event.payload,
+          selectedPaths: new Set(),
+          expandedPath: null,
+          breakdown: null,
+        });
+      }
+    });
+  },
+
   startScan: async () => {
     const { paths } = get();
     if (paths.length === 0) {
@@ -61,7 +345,18 @@ export const useScanStore = create<AppStore>((set, get) => ({
       return;
     }
 
-    set({ isScanning: true, error: null, scanResult: null, scanProgress: null, selectedPaths: new Set() });
+    set({ isScanning: true, isPaused: false, error: null, scanResult: null, scanProgress: null, scanEstimate: null, selectedPaths: new Set(), expandedPath: null, breakdown: null });
+
+    // A quick look by name alone gives a preview while the scan sizes everything
+    invoke<ScanEstimate>("estimate_paths", { paths })
+      .then((scanEstimate) => {
+        if (get().isScanning) {
+          set({ scanEstimate });
+        }
+      })
+      .catch(() => {
+        // The scan reports the same errors
+      });
 
     // Set up progress listener
     let unlisten: UnlistenFn | null = null;
@@ -73,19 +368,175 @@ export const useScanStore = create<AppStore>((set, get) => ({
       // Progress listening failed, continue without it
     }
 
+    // A cancelled scan fails too, but that's no error to show
+    let cancelled = false;
+    let unlistenCancel: UnlistenFn | null = null;
+    try {
+      unlistenCancel = await listen("scan-cancelled", () => {
+        cancelled = true;
+      });
+    } catch {
+      // Cancellation listening failed, the scan still stops
+    }
+
     try {
       const result = await invoke<ScanResult>("scan_paths", { paths });
-      set({ scanResult: result, isScanning: false, scanProgress: null });
+      set({ scanResult: result, isScanning: false, isPaused: false, scanProgress: null, scanEstimate: null });
+      // An empty result may only mean macOS didn't let the scan in
+      get().checkDiskAccess();
     } catch (e) {
       set({
-        error: typeof e === "string" ? e : "Failed to scan paths",
+        error: cancelled ? null : typeof e === "string" ? e : "Failed to scan paths",
         isScanning: false,
+        isPaused: false,
         scanProgress: null,
+        scanEstimate: null,
       });
     } finally {
       if (unlisten) {
         unlisten();
       }
+      if (unlistenCancel) {
+        unlistenCancel();
+      }
+    }
+  },
+
+  pauseScan: async () => {
+    try {
+      await invoke("pause_scan");
+      set({ isPaused: true });
+    } catch {
+      // The scan finished before it could be paused
+    }
+  },
+
+  resumeScan: async () => {
+    try {
+      await invoke("resume_scan");
+    } catch {
+      // The scan is no longer running
+    }
+    set({ isPaused: false });
+  },
+
+  cancelScan: async () => {
+    try {
+      await invoke("cancel_scan");
+    } catch {
+      // The scan finished before it could be cancelled
+    }
+  },
+
+  toggleBreakdown: async (path: string) => {
+    if (get().expandedPath === path) {
+      set({ expandedPath: null, breakdown: null });
+      return;
+    }
+    set({ expandedPath: path, breakdown: null });
+    try {
+      const breakdown = await invoke<ItemBreakdown>("get_item_breakdown", { path });
+      // Another item may have been expanded while this one was measured
+      if (get().expandedPath === path) {
+        set({ breakdown });
+      }
+    } catch (e) {
+      set({
+        expandedPath: null,
+        error: typeof e === "string" ? e : "Failed to measure the directory",
+      });
+    }
+  },
+
+  checkDiskAccess: async () => {
+    const scanId = get().scanResult?.scanId ?? null;
+    try {
+      const diskAccess = await invoke<DiskAccess>("check_disk_access", { scanId });
+      set({ diskAccess });
+    } catch {
+      // Not knowing only leaves out the guidance
+    }
+  },
+
+  openDiskAccessSettings: async () => {
+    try {
+      await invoke("open_disk_access_settings");
+    } catch (e) {
+      set({ error: typeof e === "string" ? e : "Failed to open System Settings" });
+    }
+  },
+
+  queryResults: async (
+    filter: ResultFilter,
+    sort: ResultSort,
+    page: number,
+    pageSize?: number
+  ) => {
+    const scanId = get().scanResult?.scanId;
+    if (scanId === undefined) {
+      return null;
+    }
+    try {
+      return await invoke<ResultPage>("query_results", {
+        scanId,
+        filter,
+        sort,
+        page,
+        pageSize: pageSize ?? null,
+      });
+    } catch (e) {
+      set({ error: typeof e === "string" ? e : "Failed to list the results" });
+      return null;
+    }
+  },
+
+  getTreemap: async (layout?: TreemapLayout) => {
+    const scanId = get().scanResult?.scanId;
+    if (scanId === undefined) {
+      return null;
+    }
+    try {
+      return await invoke<TreemapNode>("get_treemap", { scanId, layout: layout ?? null });
+    } catch (e) {
+      set({ error: typeof e === "string" ? e : "Failed to lay out the treemap" });
+      return null;
+    }
+  },
+
+  loadVolumes: async () => {
+    try {
+      const volumes = await invoke<Volume[]>("get_volumes");
+      set({ volumes });
+    } catch (e) {
+      set({ error: typeof e === "string" ? e : "Failed to list the volumes" });
+    }
+  },
+
+  loadSuggestedRoots: async () => {
+    try {
+      const suggestedRoots = await invoke<SuggestedRoot[]>("suggest_roots");
+      set({ suggestedRoots });
+    } catch (e) {
+      set({ error: typeof e === "string" ? e : "Failed to look for project folders" });
+    }
+  },
+
+  loadCleanHistory: async (days?: number) => {
+    try {
+      const cleanHistory = await invoke<CleanHistory>("get_clean_history", {
+        days: days ?? null,
+      });
+      set({ cleanHistory });
+    } catch (e) {
+      set({ error: typeof e === "string" ? e : "Failed to read the clean history" });
+    }
+  },
+
+  revealPath: async (path: string) => {
+    try {
+      await invoke("reveal_path", { path });
+    } catch (e) {
+      set({ error: typeof e === "string" ? e : "Failed to open the file manager" });
     }
   },
 
@@ -103,7 +554,11 @@ export const useScanStore = create<AppStore>((set, get) => ({
   selectAll: () => {
     const { scanResult } = get();
     if (scanResult) {
-      const allPaths = new Set(scanResult.items.map((item) => item.path));
+      const allPaths = new Set(
+        scanResult.items
+          .filter((item) => item.deletable)
+          .map((item) => item.path)
+      );
       set({ selectedPaths: allPaths });
     }
   },
@@ -112,23 +567,46 @@ export const useScanStore = create<AppStore>((set, get) => ({
     set({ selectedPaths: new Set() });
   },
 
-  cleanSelected: async (dryRun: boolean) => {
-    const { selectedPaths } = get();
-    if (selectedPaths.size === 0) {
+  cleanSelected: async (dryRun: boolean, deleteMode?: DeleteMode) => {
+    const { selectedPaths, scanResult } = get();
+    if (selectedPaths.size === 0 || !scanResult) {
       set({ error: "No items selected for cleaning." });
       return;
     }
 
-    set({ isCleaning: true, error: null, cleanResult: null });
+    set({ isCleaning: true, isCancelling: false, error: null, cleanResult: null, undoResult: null, cleanProgress: null, diskFree: null });
+
+    // Set up progress listeners
+    let unlistenClean: UnlistenFn | null = null;
+    let unlistenDiskFree: UnlistenFn | null = null;
+    try {
+      unlistenClean = await listen<CleanProgress>("clean-progress", (event) => {
+        set({ cleanProgress: event.payload });
+      });
+      unlistenDiskFree = await listen<DiskFree>("disk-free", (event) => {
+        set({ diskFree: event.payload });
+      });
+    } catch {
+      // Progress listening failed, continue without it
+    }
 
     try {
       const paths = Array.from(selectedPaths);
-      const result = await invoke<CleanResult>("clean_paths", { paths, dryRun });
+      const result = await invoke<CleanResult>("clean_paths", {
+        scanId: scanResult.scanId,
+        paths,
+        dryRun,
+        // Without a mode the configured one is used
+        deleteMode: deleteMode ?? null,
+      });
       
       // Set clean result first so user can see it
       set({
         cleanResult: result,
         isCleaning: false,
+        isCancelling: false,
+        cleanProgress: null,
+        diskFree: null,
         // Clear selection if actual deletion was successful
         ...((!dryRun && result.isSuccess) ? { selectedPaths: new Set() } : {}),
       });
@@ -138,7 +616,7 @@ export const useScanStore = create<AppStore>((set, get) => ({
         const { paths: scanPaths } = get();
         if (scanPaths.length > 0) {
           // Inline re-scan logic to preserve cleanResult
-          set({ isScanning: true, scanProgress: null });
+          set({ isScanning: true, isPaused: false, scanProgress: null });
           
           let unlisten: UnlistenFn | null = null;
           try {
@@ -165,7 +643,26 @@ export const useScanStore = create<AppStore>((set, get) => ({
       set({
         error: typeof e === "string" ? e : "Failed to clean paths",
         isCleaning: false,
+        isCancelling: false,
+        cleanProgress: null,
+        diskFree: null,
       });
+    } finally {
+      if (unlistenClean) {
+        unlistenClean();
+      }
+      if (unlistenDiskFree) {
+        unlistenDiskFree();
+      }
+    }
+  },
+
+  cancelClean: async () => {
+    try {
+      await invoke("cancel_clean");
+      set({ isCancelling: true });
+    } catch {
+      // The clean finished before it could be cancelled
     }
   },
 
@@ -173,8 +670,43 @@ export const useScanStore = create<AppStore>((set, get) => ({
     set({ error: null });
   },
 
+  exportResults: async (format: ExportFormat) => {
+    const { scanResult } = get();
+    if (!scanResult) {
+      return null;
+    }
+    try {
+      return await invoke<string | null>("export_results", {
+        format,
+        scanId: scanResult.scanId,
+      });
+    } catch (e) {
+      set({ error: typeof e === "string" ? e : "Failed to export the results" });
+      return null;
+    }
+  },
+
   clearCleanResult: () => {
-    set({ cleanResult: null });
+    set({ cleanResult: null, undoResult: null });
+  },
+
+  undoLastClean: async () => {
+    try {
+      const undoResult = await invoke<UndoResult>("undo_last_clean");
+      const { cleanResult } = get();
+      set({
+        undoResult,
+        // A clean can only be undone once
+        cleanResult: cleanResult && { ...cleanResult, undoable: false },
+      });
+    } catch (e) {
+      set({ error: typeof e === "string" ? e : "Failed to undo the clean" });
+      return;
+    }
+    // The restored directories are junk to be found again
+    if (get().paths.length > 0) {
+      await get().startScan();
+    }
   },
 
   setScanProgress: (progress: ScanProgress | null) => {
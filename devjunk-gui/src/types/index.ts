@@ -3,6 +3,8 @@
  * These types mirror the Rust DTO types
  */
 
+import type { UnlistenFn } from "@tauri-apps/api/event";
+
 /** A single scanned junk item */
 export interface ScanItem {
   /** Full path to the junk directory */
@@ -11,12 +13,28 @@ export interface ScanItem {
   kind: string;
   /** Human-readable kind name */
   kindDisplay: string;
+  /** Whether deleting the kind can lose files that can't be recreated */
+  safety: KindSafety;
+  /** What brings the directory back once deleted, e.g. "run cargo build" */
+  rebuildHint: string;
   /** Total size in bytes */
   sizeBytes: number;
   /** Human-readable size string */
   sizeDisplay: string;
   /** Total number of files */
   fileCount: number;
+  /** Scan root this item was found under */
+  root: string;
+  /** Modification time of the newest file inside (ms since Unix epoch) */
+  lastModifiedMs: number | null;
+  /** Whether the directory was built recently and is probably still in use */
+  isActive: boolean;
+  /** Whether the project's git repository has uncommitted or unpushed work */
+  repoDirty: boolean;
+  /** Whether the current user looks able to delete the directory */
+  deletable: boolean;
+  /** Why the directory can't be deleted, if it can't */
+  undeletableReason: string | null;
 }
 
 /** Result of a scan operation */
@@ -31,19 +49,85 @@ export interface ScanResult {
   totalFileCount: number;
   /** Number of items */
   itemCount: number;
+  /** Network/removable mount points that were not scanned */
+  skippedMounts: string[];
+  /** Paths that could not be read during the scan */
+  errors: ScanError[];
+  /** Id to clean the items of this scan by */
+  scanId: number;
+}
+
+/** One file or subdirectory inside a junk directory */
+export interface BreakdownEntry {
+  name: string;
+  path: string;
+  sizeBytes: number;
+  sizeDisplay: string;
+  fileCount: number;
+}
+
+/** What takes up the space inside a junk directory */
+export interface ItemBreakdown {
+  /** The largest entries directly inside, largest first */
+  entries: BreakdownEntry[];
+  /** Number of smaller entries left out */
+  otherCount: number;
+  otherSizeBytes: number;
+  otherSizeDisplay: string;
+}
+
+/** A path that could not be read during a scan */
+export interface ScanError {
+  path: string;
+  /** Error category */
+  kind: string;
+  cause: string;
 }
 
 /** Progress information during a scan operation */
 export interface ScanProgress {
   /** Current path being scanned */
   currentPath: string;
+  /** Junk directory currently being sized, if any */
+  sizingPath: string | null;
   /** Number of junk items found so far */
   itemsFound: number;
   /** Number of directories scanned so far */
   directoriesScanned: number;
+  /** Total size of junk items found so far */
+  bytesFound: number;
+  /** Time since the scan started, in milliseconds */
+  elapsedMs: number;
+  /** Estimated time remaining, in milliseconds */
+  etaMs: number | null;
+}
+
+/** Progress information during a clean operation */
+export interface CleanProgress {
+  /** Path being deleted */
+  currentPath: string;
+  /** Stage the current path has reached */
+  stage: "started" | "moved" | "finished" | "failed" | "skipped";
+  /** Number of plan paths handled so far */
+  pathsDone: number;
+  /** Number of paths in the plan */
+  pathsTotal: number;
+  /** Total bytes freed so far */
+  bytesFreed: number;
+  /** Number of files removed so far */
+  filesRemoved: number;
 }
 
-/** A failed clean operation */
+/** Free space on the filesystems being cleaned, reported while a clean runs */
+export interface DiskFree {
+  freeBytes: number;
+  freeDisplay: string;
+  /** How much free space grew since the clean started */
+  reclaimedBytes: number;
+  reclaimedDisplay: string;
+}
+
+/** A failed or skipped clean operation */
 export interface CleanFailure {
   path: string;
   error: string;
@@ -59,53 +143,450 @@ export interface CleanResult {
   failed: CleanFailure[];
   /** Number of failed items */
   failedCount: number;
+  /** Paths left alone because they no longer looked like junk or the clean was cancelled */
+  skipped: CleanFailure[];
+  /** Number of skipped items */
+  skippedCount: number;
   /** Total bytes freed */
   bytesFreed: number;
   /** Human-readable bytes freed */
   bytesFreedDisplay: string;
   /** Whether this was a dry run */
   wasDryRun: boolean;
+  /** Whether the clean was cancelled before every path was handled */
+  cancelled: boolean;
+  /** How much free space grew on the cleaned filesystems, when it could be measured */
+  measuredBytesFreed: number | null;
+  /** Human-readable measured free space change, e.g. "+1.20 GB" */
+  measuredBytesFreedDisplay: string | null;
   /** Whether all operations succeeded */
   isSuccess: boolean;
+  /** How the directories were deleted */
+  deleteMode: DeleteMode;
+  /** Where the directories were archived, for the "archive" delete mode */
+  archiveDir: string | null;
+  /** Whether `undoLastClean` can put the deleted directories back */
+  undoable: boolean;
+}
+
+/** What undoing a clean put back */
+export interface UndoResult {
+  restored: string[];
+  restoredCount: number;
+  /** Paths that could not be restored, with the reason */
+  failed: CleanFailure[];
+  failedCount: number;
 }
 
+/** Whether the OS kept a scan from reading some paths */
+export interface DiskAccess {
+  /** Whether the app has Full Disk Access on macOS (null elsewhere, or if it can't be told) */
+  fullDiskAccess: boolean | null;
+  /** Paths the scan was not allowed to read */
+  deniedPaths: string[];
+  /** Whether results are missing for lack of Full Disk Access */
+  restricted: boolean;
+}
+
+/** Junk of one kind a quick estimate found */
+export interface EstimateKind {
+  kind: string;
+  kindDisplay: string;
+  count: number;
+  /** Total size of the ones earlier scans measured */
+  knownBytes: number;
+}
+
+/** A quick estimate of what a scan will find, from directory names alone */
+export interface ScanEstimate {
+  itemCount: number;
+  /** Total size of the directories earlier scans measured */
+  knownBytes: number;
+  knownDisplay: string;
+  /** Number of directories no earlier scan measured */
+  unknownCount: number;
+  /** Junk directories found per kind, most first */
+  byKind: EstimateKind[];
+  directoriesScanned: number;
+  /** Whether all of the paths were walked, rather than only what time allowed */
+  complete: boolean;
+}
+
+/** Whether a path can be added to the paths to scan */
+export type PathStatus = "ok" | "notFound" | "notADirectory" | "duplicate" | "nested";
+
+/** The check of one of a batch of paths */
+export interface PathCheck {
+  path: string;
+  status: PathStatus;
+  /** The path this one duplicates or is nested in */
+  coveredBy: string | null;
+}
+
+/** Which items of a scan `queryResults` lists */
+export interface ResultFilter {
+  /** Text the path must contain, ignoring case */
+  text?: string;
+  /** Kinds to list, by id; all of them when empty */
+  kinds?: string[];
+  minSizeBytes?: number;
+  /** Leave out the directories that can't be deleted */
+  deletableOnly?: boolean;
+}
+
+/** Order `queryResults` lists the items of a scan in */
+export interface ResultSort {
+  /** Largest, least recently modified or most files first; by path or kind */
+  key: "size" | "path" | "kind" | "age" | "files";
+  reverse?: boolean;
+}
+
+/** One page of the items of a scan that match a filter */
+export interface ResultPage {
+  items: ScanItem[];
+  /** Page number, counting from 0 */
+  page: number;
+  pageSize: number;
+  pageCount: number;
+  /** Number of items matching the filter, on all pages */
+  matchCount: number;
+  matchSizeBytes: number;
+  matchSizeDisplay: string;
+}
+
+/** How `getTreemap` nests the junk of a scan */
+export type TreemapLayout = "project" | "path";
+
+/** A rectangle of the treemap, holding the smaller ones drawn inside it */
+export interface TreemapNode {
+  /** Label of the rectangle; empty for the whole scan */
+  name: string;
+  /** Directory the node stands for; null for kinds and the whole scan */
+  path: string | null;
+  /** Kind id of a kind or junk directory */
+  kind: string | null;
+  sizeBytes: number;
+  sizeDisplay: string;
+  /** Number of junk directories in the node, or 1 for a junk directory */
+  itemCount: number;
+  /** Nodes inside this one, largest first; empty for junk directories */
+  children: TreemapNode[];
+}
+
+/** A mounted volume and how full it is */
+export interface Volume {
+  mountPoint: string;
+  fsType: string;
+  kind: "local" | "network" | "removable";
+  totalBytes: number;
+  totalDisplay: string;
+  freeBytes: number;
+  freeDisplay: string;
+  /** Share of the volume in use, from 0 to 100 */
+  usedPercent: number;
+  /** Where to scan the volume from: the home directory on the volume holding it */
+  suggestedRoot: string;
+}
+
+/** A directory suggested for scanning on first run */
+export interface SuggestedRoot {
+  path: string;
+  /** A usual name for a projects folder, Go's workspace, or holding several checkouts */
+  reason: "well_known" | "go_path" | "repositories";
+  /** Git checkouts found one or two levels down */
+  repos: number;
+}
+
+/** Bytes freed of one junk kind */
+export interface KindBytes {
+  kind: string;
+  kindDisplay: string;
+  bytesFreed: number;
+  bytesFreedDisplay: string;
+}
+
+/** One clean in the history */
+export interface CleanRecord {
+  /** When the clean finished (ms since Unix epoch) */
+  atMs: number;
+  deletedCount: number;
+  bytesFreed: number;
+  bytesFreedDisplay: string;
+  /** Bytes freed per junk kind, most first */
+  byKind: KindBytes[];
+}
+
+/** The cleans in the history, for charting space reclaimed over time */
+export interface CleanHistory {
+  /** Cleans that deleted something, oldest first */
+  cleans: CleanRecord[];
+  bytesFreed: number;
+  bytesFreedDisplay: string;
+  /** Bytes freed per junk kind over all the cleans, most first */
+  byKind: KindBytes[];
+}
+
+/** File format scan results are exported in */
+export type ExportFormat = "json" | "csv" | "markdown";
+
+/** How a clean deletes directories */
+export type DeleteMode = "permanent" | "trash" | "archive" | "renameDefer";
+
 /** Information about a junk kind */
 export interface JunkKind {
   id: string;
   displayName: string;
   patterns: string[];
+  /** Language or ecosystem of the kind; null for generic names like `build` */
+  language: string | null;
+  safety: KindSafety;
+  /** What brings a deleted directory of the kind back */
+  rebuildHint: string;
+}
+
+/** "caution" when a kind may hold files that can't be recreated */
+export type KindSafety = "safe" | "caution";
+
+/** A user-defined junk pattern */
+export interface CustomPattern {
+  /** Name shown for matching directories */
+  name: string;
+  /** Glob matched against directory names */
+  glob: string;
+}
+
+/** The config file shared with the CLI */
+export interface Config {
+  /** Directories to scan when none are given */
+  roots: string[];
+  /** Globs of paths never to scan or clean */
+  exclude: string[];
+  /** Directories never to scan or clean, with everything below them */
+  ignore: string[];
+  /** Ids of the junk kinds to look for (null = all) */
+  kinds: string[] | null;
+  /** Extra directory names to treat as junk */
+  customPatterns: CustomPattern[];
+  /** How directories are deleted */
+  deleteMode: DeleteMode;
+  /** Where directories are archived, for the "archive" delete mode */
+  archiveDir: string | null;
+  /** Maximum number of directories deleted at once (null = one per CPU) */
+  concurrency: number | null;
+  /** Worker threads for scanning and deleting, applied on the next start (null = one per CPU) */
+  threads: number | null;
+  /** Size in bytes from which the CLI has a clean confirmed by typing its size */
+  typedConfirmBytes: number;
+  /** Number of directories from which the CLI has a clean confirmed by typing */
+  typedConfirmDirs: number;
+  /** Color theme of the window */
+  theme: Theme;
+  /** Minutes between scans of the roots in the tray (null = never) */
+  backgroundScanMinutes: number | null;
+  /** Look up the git status of each item's repository while scanning */
+  gitStatus: boolean;
+  /** Named sets of directories to scan together */
+  workspaces: Workspace[];
+}
+
+/** How often a scheduled clean runs */
+export type Frequency = "daily" | "weekly" | "monthly";
+
+/** A recurring clean installed in the OS scheduler */
+export interface Schedule {
+  name: string;
+  /** "weekly" runs on Sundays, "monthly" on the 1st */
+  frequency: Frequency;
+  /** Time of day it runs at, in local time */
+  hour: number;
+  minute: number;
+  /** The devjunk command it runs */
+  command: string;
+  /** Name of the timer, agent or task in the OS scheduler */
+  job: string;
+  /** Whether the OS scheduler still has the job */
+  installed: boolean;
+}
+
+/** A recurring clean to install */
+export interface NewSchedule {
+  /** Letters, digits, "-" and "_"; replaces the schedule of the same name */
+  name: string;
+  frequency: Frequency;
+  hour: number;
+  minute: number;
+  /** Directories to clean, as absolute paths */
+  paths: string[];
+  /** Ids of the junk kinds to clean (null = all) */
+  kinds: string[] | null;
+  /** Only clean directories untouched for this many days */
+  olderThanDays: number | null;
+  /** Only report what would be deleted */
+  dryRun?: boolean;
+}
+
+/** A named set of directories, like "Work repos", scanned together */
+export interface Workspace {
+  name: string;
+  paths: string[];
+}
+
+/** Color theme of the window; "system" follows the OS */
+export type Theme = "system" | "light" | "dark";
+
+/** Preferences kept between launches, part of the shared config file */
+export interface Settings {
+  /** Directories to scan when none are given */
+  roots: string[];
+  /** Ids of the junk kinds to look for (null = all) */
+  kinds: string[] | null;
+  /** Globs of paths never to scan or clean */
+  exclude: string[];
+  /** How directories are deleted */
+  deleteMode: DeleteMode;
+  /** Where directories are archived, for the "archive" delete mode */
+  archiveDir: string | null;
+  /** Color theme of the window */
+  theme: Theme;
+  /** Look up the git status of each item's repository while scanning */
+  gitStatus: boolean;
 }
 
 /** Application state */
 export interface AppState {
   /** Paths to scan */
   paths: string[];
+  /** Preferences loaded from the config file, once they are */
+  settings: Settings | null;
+  /** Saved workspaces */
+  workspaces: Workspace[];
+  /** Directory names matched as junk besides the built-in kinds, once they are loaded */
+  customPatterns: CustomPattern[];
+  /** Directories every scan leaves out, once they are loaded */
+  ignoredPaths: string[];
+  /** Whether the app starts at login, once it is known */
+  autostart: boolean | null;
+  /** Recurring cleans in the OS scheduler, once they are loaded */
+  schedules: Schedule[];
+  /** Whether the last scan missed paths it wasn't allowed to read */
+  diskAccess: DiskAccess | null;
+  /** Mounted volumes and their free space, once they are loaded */
+  volumes: Volume[];
+  /** Directories likely to hold projects, offered while there are no paths to scan */
+  suggestedRoots: SuggestedRoot[];
+  /** Past cleans, once they are loaded */
+  cleanHistory: CleanHistory | null;
   /** Current scan result */
   scanResult: ScanResult | null;
+  /** Quick estimate of the running scan, until it finishes */
+  scanEstimate: ScanEstimate | null;
   /** Current scan progress */
   scanProgress: ScanProgress | null;
   /** Selected paths for deletion */
   selectedPaths: Set<string>;
+  /** Item whose contents are broken down below its row */
+  expandedPath: string | null;
+  /** Breakdown of the expanded item, once it is measured */
+  breakdown: ItemBreakdown | null;
   /** Whether a scan is in progress */
   isScanning: boolean;
+  /** Whether the running scan is paused */
+  isPaused: boolean;
   /** Whether a clean is in progress */
   isCleaning: boolean;
+  /** Whether the running clean has been asked to stop */
+  isCancelling: boolean;
+  /** Current clean progress */
+  cleanProgress: CleanProgress | null;
+  /** Free space on the filesystems being cleaned, while a real clean runs */
+  diskFree: DiskFree | null;
   /** Error message if any */
   error: string | null;
   /** Last clean result */
   cleanResult: CleanResult | null;
+  /** What undoing the last clean put back */
+  undoResult: UndoResult | null;
 }
 
 /** Actions for the store */
 export interface AppActions {
   /** Add a path to scan */
   addPath: (path: string) => void;
+  /** Check a batch of paths, such as picked folders, and add the ones that aren't covered yet */
+  addPaths: (paths: string[]) => Promise<void>;
   /** Remove a path */
   removePath: (path: string) => void;
   /** Clear all paths */
   clearPaths: () => void;
+  /** Load the saved preferences, starting with their roots as the paths to scan */
+  loadSettings: () => Promise<void>;
+  /** Save preferences for the next launch */
+  saveSettings: (settings: Settings) => Promise<void>;
+  /** Scan the roots every so many minutes from the tray, or stop with null */
+  setBackgroundScan: (minutes: number | null) => Promise<void>;
+  /** Find out whether the app starts at login */
+  loadAutostart: () => Promise<void>;
+  /** Start the app in the tray at login, or no longer */
+  setAutostart: (enabled: boolean) => Promise<void>;
+  /** Load the custom patterns from the config file */
+  loadCustomPatterns: () => Promise<void>;
+  /** Match directories named like `glob` as junk called `name`, replacing a pattern with that name */
+  addCustomPattern: (name: string, glob: string) => Promise<void>;
+  /** Delete the custom pattern called `name` */
+  removeCustomPattern: (name: string) => Promise<void>;
+  /** Load the directories every scan leaves out */
+  loadIgnoredPaths: () => Promise<void>;
+  /** Leave `path` out of every scan from now on, and out of the current results */
+  ignorePath: (path: string) => Promise<void>;
+  /** Scan `path` again from the next scan on */
+  unignorePath: (path: string) => Promise<void>;
+  /** Load the saved workspaces */
+  loadWorkspaces: () => Promise<void>;
+  /** Save the paths to scan as a workspace, replacing one with the same name */
+  saveWorkspace: (name: string) => Promise<void>;
+  /** Delete a saved workspace */
+  deleteWorkspace: (name: string) => Promise<void>;
+  /** Scan the paths of a saved workspace */
+  openWorkspace: (name: string) => Promise<void>;
+  /** Load the recurring cleans */
+  loadSchedules: () => Promise<void>;
+  /** Install a recurring clean, replacing one with the same name */
+  createSchedule: (schedule: NewSchedule) => Promise<void>;
+  /** Remove a recurring clean */
+  deleteSchedule: (name: string) => Promise<void>;
+  /** Show the results of scans run in the background as they finish; returns the unlisten function */
+  listenForBackgroundScans: () => Promise<UnlistenFn>;
   /** Start scanning */
   startScan: () => Promise<void>;
+  /** Pause the running scan */
+  pauseScan: () => Promise<void>;
+  /** Resume a paused scan */
+  resumeScan: () => Promise<void>;
+  /** Stop the running scan, dropping what it found so far */
+  cancelScan: () => Promise<void>;
+  /** Show or hide the breakdown of an item's largest contents */
+  toggleBreakdown: (path: string) => Promise<void>;
+  /** Check whether macOS kept the last scan from reading protected folders */
+  checkDiskAccess: () => Promise<void>;
+  /** Open the System Settings pane granting Full Disk Access */
+  openDiskAccessSettings: () => Promise<void>;
+  /** Fetch one page of the current scan's items, filtered and sorted on the backend */
+  queryResults: (
+    filter: ResultFilter,
+    sort: ResultSort,
+    page: number,
+    pageSize?: number
+  ) => Promise<ResultPage | null>;
+  /** Nest the current scan's junk for a treemap, by project then kind or by directory */
+  getTreemap: (layout?: TreemapLayout) => Promise<TreemapNode | null>;
+  /** Load the mounted volumes and their free space */
+  loadVolumes: () => Promise<void>;
+  /** Look for the directories likely to hold the user's projects */
+  loadSuggestedRoots: () => Promise<void>;
+  /** Load the cleans of the last `days`, or all of them */
+  loadCleanHistory: (days?: number) => Promise<void>;
+  /** Show an item in the file manager */
+  revealPath: (path: string) => Promise<void>;
   /** Toggle selection of a path */
   toggleSelection: (path: string) => void;
   /** Select all items */
@@ -113,11 +594,17 @@ export interface AppActions {
   /** Deselect all items */
   deselectAll: () => void;
   /** Clean selected paths */
-  cleanSelected: (dryRun: boolean) => Promise<void>;
+  cleanSelected: (dryRun: boolean, deleteMode?: DeleteMode) => Promise<void>;
+  /** Stop the running clean after the current directory */
+  cancelClean: () => Promise<void>;
   /** Clear error */
   clearError: () => void;
   /** Clear clean result */
   clearCleanResult: () => void;
+  /** Put back what the last clean moved to the trash or archived, then scan again */
+  undoLastClean: () => Promise<void>;
+  /** Save the scan results to a file the user picks; resolves to its path, or null if cancelled */
+  exportResults: (format: ExportFormat) => Promise<string | null>;
   /** Set scan progress */
   setScanProgress: (progress: ScanProgress | null) => void;
   /** Reset state */